@@ -0,0 +1,112 @@
+//! Computes $\mathsf{Commit}^\mathsf{ivk}_\mathsf{rivk}(\mathsf{ak}, \mathsf{nk})$ in a
+//! circuit.
+//!
+//! The commitment computed by the [`commit_ivk`] gadget is constrained to equal the one
+//! computed out of circuit with the Orchard `Commit^ivk` Sinsemilla domain.
+
+mod common;
+
+use core::iter;
+
+use common::{constrain_equal, witness, CommonConfig, K};
+use ff::{Field, PrimeFieldBits};
+use halo2_gadgets::ecc::ScalarFixed;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use orchard::{
+    circuit::gadget::{commit_ivk, CommitIvkChip, CommitIvkConfig},
+    primitives::sinsemilla::commit_ivk_domain,
+};
+use pasta_curves::pallas;
+use rand::rngs::OsRng;
+
+#[derive(Default)]
+struct CommitIvkCircuit {
+    ak: Value<pallas::Base>,
+    nk: Value<pallas::Base>,
+    rivk: Value<pallas::Scalar>,
+    ivk: Value<pallas::Base>,
+}
+
+impl Circuit<pallas::Base> for CommitIvkCircuit {
+    type Config = (CommonConfig, CommitIvkConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let common = CommonConfig::configure(meta);
+        let commit_ivk = CommitIvkChip::configure(meta, common.advices);
+        (common, commit_ivk)
+    }
+
+    fn synthesize(
+        &self,
+        (common, commit_ivk_config): Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        common.load(&mut layouter)?;
+        let ecc_chip = common.ecc_chip();
+
+        let ak = witness(layouter.namespace(|| "ak"), common.advices[0], self.ak)?;
+        let nk = witness(layouter.namespace(|| "nk"), common.advices[0], self.nk)?;
+        let rivk = ScalarFixed::new(ecc_chip.clone(), layouter.namespace(|| "rivk"), self.rivk)?;
+
+        let ivk = commit_ivk(
+            common.sinsemilla_chip(0),
+            ecc_chip,
+            CommitIvkChip::construct(commit_ivk_config),
+            layouter.namespace(|| "CommitIvk"),
+            ak,
+            nk,
+            rivk,
+        )?;
+
+        let expected = witness(layouter.namespace(|| "ivk"), common.advices[0], self.ivk)?;
+        constrain_equal(
+            layouter.namespace(|| "ivk equality"),
+            ivk.inner(),
+            &expected,
+        )
+    }
+}
+
+fn main() {
+    let mut rng = OsRng;
+    let ak = pallas::Base::random(&mut rng);
+    let nk = pallas::Base::random(&mut rng);
+    let rivk = pallas::Scalar::random(&mut rng);
+
+    let ivk = commit_ivk_domain()
+        .short_commit(
+            iter::empty()
+                .chain(ak.to_le_bits().iter().by_vals().take(255))
+                .chain(nk.to_le_bits().iter().by_vals().take(255)),
+            &rivk,
+        )
+        .unwrap();
+
+    let circuit = CommitIvkCircuit {
+        ak: Value::known(ak),
+        nk: Value::known(nk),
+        rivk: Value::known(rivk),
+        ivk: Value::known(ivk),
+    };
+    let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // A different commitment trapdoor gives a different commitment.
+    let circuit = CommitIvkCircuit {
+        rivk: Value::known(rivk + pallas::Scalar::one()),
+        ..circuit
+    };
+    let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+
+    println!("Commit^ivk verified");
+}
@@ -0,0 +1,127 @@
+//! Column layout shared by the gadget examples.
+//!
+//! This mirrors the layout of the Orchard Action circuit: ten advice columns with
+//! equality enabled, eight fixed columns for the ECC chip's Lagrange coefficients (the
+//! first of which also holds constants), and two Sinsemilla chips over disjoint advice
+//! columns that share a single generator table.
+
+#![allow(dead_code)]
+
+use halo2_gadgets::{
+    ecc::chip::{EccChip, EccConfig},
+    sinsemilla::chip::{SinsemillaChip, SinsemillaConfig},
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Assigned, Column, ConstraintSystem, Error},
+};
+use orchard::circuit::gadget::{OrchardCommitDomains, OrchardFixedBases, OrchardHashDomains};
+use pasta_curves::pallas;
+
+/// The number of rows used by the examples, as a power of two.
+pub const K: u32 = 11;
+
+/// The Sinsemilla configuration used by the Orchard circuit.
+pub type OrchardSinsemillaConfig =
+    SinsemillaConfig<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>;
+
+/// The Sinsemilla chip used by the Orchard circuit.
+pub type OrchardSinsemillaChip =
+    SinsemillaChip<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>;
+
+#[derive(Clone, Debug)]
+pub struct CommonConfig {
+    pub advices: [Column<Advice>; 10],
+    pub sinsemilla: [OrchardSinsemillaConfig; 2],
+    pub ecc: EccConfig<OrchardFixedBases>,
+}
+
+impl CommonConfig {
+    pub fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self {
+        let advices = [(); 10].map(|_| meta.advice_column());
+        for advice in advices.iter() {
+            meta.enable_equality(*advice);
+        }
+
+        let lagrange_coeffs = [(); 8].map(|_| meta.fixed_column());
+        meta.enable_constant(lagrange_coeffs[0]);
+
+        let table_idx = meta.lookup_table_column();
+        let lookup = (
+            table_idx,
+            meta.lookup_table_column(),
+            meta.lookup_table_column(),
+        );
+        let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+
+        let ecc = EccChip::configure(meta, advices, lagrange_coeffs, range_check);
+        let sinsemilla = [
+            SinsemillaChip::configure(
+                meta,
+                advices[..5].try_into().unwrap(),
+                advices[6],
+                lagrange_coeffs[0],
+                lookup,
+                range_check,
+                false,
+            ),
+            SinsemillaChip::configure(
+                meta,
+                advices[5..].try_into().unwrap(),
+                advices[7],
+                lagrange_coeffs[1],
+                lookup,
+                range_check,
+                false,
+            ),
+        ];
+
+        CommonConfig {
+            advices,
+            sinsemilla,
+            ecc,
+        }
+    }
+
+    /// Loads the Sinsemilla generator table, which both Sinsemilla chips share.
+    pub fn load(&self, layouter: &mut impl Layouter<pallas::Base>) -> Result<(), Error> {
+        OrchardSinsemillaChip::load(self.sinsemilla[0].clone(), layouter)
+    }
+
+    pub fn ecc_chip(&self) -> EccChip<OrchardFixedBases> {
+        EccChip::construct(self.ecc.clone())
+    }
+
+    pub fn sinsemilla_chip(&self, i: usize) -> OrchardSinsemillaChip {
+        SinsemillaChip::construct(self.sinsemilla[i].clone())
+    }
+}
+
+/// Witnesses `value` in a new region.
+pub fn witness<V>(
+    mut layouter: impl Layouter<pallas::Base>,
+    column: Column<Advice>,
+    value: Value<V>,
+) -> Result<AssignedCell<V, pallas::Base>, Error>
+where
+    V: Clone,
+    for<'v> Assigned<pallas::Base>: From<&'v V>,
+{
+    layouter.assign_region(
+        || "witness",
+        |mut region| region.assign_advice(|| "witness", column, 0, || value.clone()),
+    )
+}
+
+/// Constrains two cells to be equal.
+pub fn constrain_equal<V>(
+    mut layouter: impl Layouter<pallas::Base>,
+    a: &AssignedCell<V, pallas::Base>,
+    b: &AssignedCell<V, pallas::Base>,
+) -> Result<(), Error> {
+    layouter.assign_region(
+        || "constrain equal",
+        |mut region| region.constrain_equal(a.cell(), b.cell()),
+    )
+}
@@ -0,0 +1,26 @@
+//! Verifies a Merkle path in a shallow tree with the Orchard Merkle hash.
+//!
+//! The root computed by [`OrchardMerklePath`] in the circuit is constrained to equal the
+//! root computed out of circuit by [`root_of_depth`].
+//!
+//! [`OrchardMerklePath`]: orchard::circuit::gadget::merkle::OrchardMerklePath
+//! [`root_of_depth`]: orchard::tree::root_of_depth
+
+use orchard::circuit::gadget::merkle::testing::MerklePathCircuit;
+use rand::{rngs::OsRng, RngCore};
+
+const DEPTH: usize = 4;
+
+fn main() {
+    let position = OsRng.next_u32() % (1 << DEPTH);
+    let circuit = MerklePathCircuit::<DEPTH>::random(OsRng, position);
+    assert_eq!(circuit.verify(), Ok(()));
+
+    // The same path does not authenticate the leaf at a different position.
+    assert!(circuit.with_position(position ^ 1).verify().is_err());
+
+    println!(
+        "Merkle path of depth {} verified at position {}",
+        DEPTH, position
+    );
+}
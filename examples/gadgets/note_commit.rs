@@ -0,0 +1,137 @@
+//! Computes $\mathsf{NoteCommit}^\mathsf{Orchard}$ in a circuit.
+//!
+//! The commitment computed by the [`note_commit`] gadget is constrained to equal the one
+//! computed out of circuit with the Orchard `NoteCommit` Sinsemilla domain.
+
+mod common;
+
+use core::iter;
+
+use common::{witness, CommonConfig, K};
+use ff::{Field, PrimeFieldBits};
+use group::{Curve, Group, GroupEncoding};
+use halo2_gadgets::ecc::{NonIdentityPoint, Point, ScalarFixed};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use orchard::{
+    circuit::gadget::{note_commit, NoteCommitChip, NoteCommitConfig},
+    primitives::sinsemilla::note_commit_domain,
+    value::NoteValue,
+};
+use pasta_curves::pallas;
+use rand::{rngs::OsRng, RngCore};
+
+#[derive(Default)]
+struct NoteCommitCircuit {
+    g_d: Value<pallas::Affine>,
+    pk_d: Value<pallas::Affine>,
+    value: Value<NoteValue>,
+    rho: Value<pallas::Base>,
+    psi: Value<pallas::Base>,
+    rcm: Value<pallas::Scalar>,
+    cm: Value<pallas::Affine>,
+}
+
+impl Circuit<pallas::Base> for NoteCommitCircuit {
+    type Config = (CommonConfig, NoteCommitConfig);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+        let common = CommonConfig::configure(meta);
+        let note_commit =
+            NoteCommitChip::configure(meta, common.advices, common.sinsemilla[0].clone());
+        (common, note_commit)
+    }
+
+    fn synthesize(
+        &self,
+        (common, note_commit_config): Self::Config,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<(), Error> {
+        common.load(&mut layouter)?;
+        let ecc_chip = common.ecc_chip();
+
+        let g_d = NonIdentityPoint::new(ecc_chip.clone(), layouter.namespace(|| "g_d"), self.g_d)?;
+        let pk_d =
+            NonIdentityPoint::new(ecc_chip.clone(), layouter.namespace(|| "pk_d"), self.pk_d)?;
+        let value = witness(layouter.namespace(|| "v"), common.advices[0], self.value)?;
+        let rho = witness(layouter.namespace(|| "rho"), common.advices[0], self.rho)?;
+        let psi = witness(layouter.namespace(|| "psi"), common.advices[0], self.psi)?;
+        let rcm = ScalarFixed::new(ecc_chip.clone(), layouter.namespace(|| "rcm"), self.rcm)?;
+
+        let cm = note_commit(
+            layouter.namespace(|| "NoteCommit"),
+            common.sinsemilla_chip(0),
+            ecc_chip.clone(),
+            NoteCommitChip::construct(note_commit_config),
+            g_d.inner(),
+            pk_d.inner(),
+            value,
+            rho,
+            psi,
+            rcm,
+        )?;
+
+        let expected = Point::new(ecc_chip, layouter.namespace(|| "cm"), self.cm)?;
+        cm.constrain_equal(layouter.namespace(|| "cm equality"), &expected)
+    }
+}
+
+/// The bits of `bytes`, least significant bit of the first byte first.
+fn le_bits(bytes: [u8; 32]) -> impl Iterator<Item = bool> {
+    bytes
+        .into_iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+}
+
+fn main() {
+    let mut rng = OsRng;
+    let g_d = pallas::Point::random(&mut rng);
+    let pk_d = pallas::Point::random(&mut rng);
+    let value = rng.next_u64() % 1_000_000;
+    let rho = pallas::Base::random(&mut rng);
+    let psi = pallas::Base::random(&mut rng);
+    let rcm = pallas::Scalar::random(&mut rng);
+
+    // repr_P(g_d) || repr_P(pk_d) || I2LEBSP_64(v) || I2LEBSP_255(rho) || I2LEBSP_255(psi)
+    let cm = note_commit_domain()
+        .commit(
+            iter::empty()
+                .chain(le_bits(g_d.to_bytes()))
+                .chain(le_bits(pk_d.to_bytes()))
+                .chain((0..64).map(|i| (value >> i) & 1 == 1))
+                .chain(rho.to_le_bits().iter().by_vals().take(255))
+                .chain(psi.to_le_bits().iter().by_vals().take(255)),
+            &rcm,
+        )
+        .unwrap();
+
+    let circuit = NoteCommitCircuit {
+        g_d: Value::known(g_d.to_affine()),
+        pk_d: Value::known(pk_d.to_affine()),
+        value: Value::known(NoteValue::from_raw(value)),
+        rho: Value::known(rho),
+        psi: Value::known(psi),
+        rcm: Value::known(rcm),
+        cm: Value::known(cm.to_affine()),
+    };
+    let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // Committing to a different value gives a different commitment.
+    let circuit = NoteCommitCircuit {
+        value: Value::known(NoteValue::from_raw(value + 1)),
+        ..circuit
+    };
+    let prover = MockProver::run(K, &circuit, vec![]).unwrap();
+    assert!(prover.verify().is_err());
+
+    println!("NoteCommit^Orchard verified for a note of value {}", value);
+}
@@ -122,25 +122,87 @@ impl DynamicUsage for Action<redpallas::Signature<SpendAuth>> {
 #[cfg(any(test, feature = "test-dependencies"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "test-dependencies")))]
 pub(crate) mod testing {
-    use rand::{rngs::StdRng, SeedableRng};
+    use ff::Field;
+    use pasta_curves::pallas;
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
     use reddsa::orchard::SpendAuth;
 
     use proptest::prelude::*;
 
     use crate::{
+        keys::{FullViewingKey, SpendValidatingKey},
         note::{
             commitment::ExtractedNoteCommitment, nullifier::testing::arb_nullifier,
-            testing::arb_note, TransmittedNoteCiphertext,
+            testing::arb_note, Rho, TransmittedNoteCiphertext,
         },
+        note_encryption::OrchardNoteEncryption,
         primitives::redpallas::{
             self,
             testing::{arb_spendauth_signing_key, arb_spendauth_verification_key},
         },
         value::{NoteValue, ValueCommitTrapdoor, ValueCommitment},
+        Note,
     };
 
     use super::Action;
 
+    /// Generates a dummy action, which spends a dummy note and creates a dummy output.
+    ///
+    /// Unlike the actions generated by [`arb_unauthorized_action`], this is structurally
+    /// identical to the dummy actions that [`Builder`](crate::builder::Builder) pads
+    /// bundles with: the output note is encrypted, its $\rho$ is the spent note's
+    /// nullifier, and `rk` is a randomization of the spent note's spend validating key.
+    pub fn dummy_action(rng: impl RngCore) -> Action<()> {
+        dummy_action_with_parts(rng).0
+    }
+
+    /// The secrets from which [`dummy_action`] builds an action.
+    pub(super) struct DummyActionParts {
+        pub(super) fvk: FullViewingKey,
+        pub(super) spent: Note,
+        pub(super) alpha: pallas::Scalar,
+        pub(super) output_fvk: FullViewingKey,
+        pub(super) output: Note,
+        pub(super) rcv: ValueCommitTrapdoor,
+    }
+
+    pub(super) fn dummy_action_with_parts(mut rng: impl RngCore) -> (Action<()>, DummyActionParts) {
+        let (_, fvk, spent) = Note::dummy(&mut rng, None);
+        let nf = spent.nullifier(&fvk);
+        let alpha = pallas::Scalar::random(&mut rng);
+        let rk = SpendValidatingKey::from(fvk.clone()).randomize(&alpha);
+
+        let (_, output_fvk, output) = Note::dummy(&mut rng, Some(Rho::from_nf_old(nf)));
+        let cmx = ExtractedNoteCommitment::from(output.commitment());
+        let rcv = ValueCommitTrapdoor::random(&mut rng);
+        let cv_net = ValueCommitment::derive(spent.value() - output.value(), rcv.clone());
+
+        let encryptor = OrchardNoteEncryption::new(None, output, [0; 512]);
+        let encrypted_note = TransmittedNoteCiphertext {
+            epk_bytes: encryptor.epk().to_bytes().0,
+            enc_ciphertext: encryptor.encrypt_note_plaintext(),
+            out_ciphertext: encryptor.encrypt_outgoing_plaintext(&cv_net, &cmx, &mut rng),
+        };
+
+        let action = Action {
+            nf,
+            rk,
+            cmx,
+            encrypted_note,
+            cv_net,
+            authorization: (),
+        };
+        let parts = DummyActionParts {
+            fvk,
+            spent,
+            alpha,
+            output_fvk,
+            output,
+            rcv,
+        };
+        (action, parts)
+    }
+
     prop_compose! {
         /// Generate an action without authorization data.
         pub fn arb_unauthorized_action(spend_value: NoteValue, output_value: NoteValue)(
@@ -205,3 +267,46 @@ pub(crate) mod testing {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use zcash_note_encryption::try_note_decryption;
+
+    use super::testing::dummy_action_with_parts;
+    use crate::{
+        keys::{PreparedIncomingViewingKey, Scope, SpendValidatingKey},
+        note::{ExtractedNoteCommitment, Rho},
+        note_encryption::OrchardDomain,
+        value::ValueCommitment,
+    };
+
+    #[test]
+    fn dummy_action_is_consistent() {
+        let (action, parts) = dummy_action_with_parts(OsRng);
+
+        assert_eq!(action.nullifier(), &parts.spent.nullifier(&parts.fvk));
+        assert_eq!(
+            action.rk(),
+            &SpendValidatingKey::from(parts.fvk.clone()).randomize(&parts.alpha)
+        );
+        assert_eq!(parts.output.rho(), Rho::from_nf_old(*action.nullifier()));
+        assert_eq!(
+            action.cmx(),
+            &ExtractedNoteCommitment::from(parts.output.commitment())
+        );
+        assert_eq!(
+            action.cv_net().to_bytes(),
+            ValueCommitment::derive(parts.spent.value() - parts.output.value(), parts.rcv)
+                .to_bytes()
+        );
+
+        // The output decrypts to the dummy output note, as builder padding does.
+        let ivk = PreparedIncomingViewingKey::new(&parts.output_fvk.to_ivk(Scope::External));
+        let (note, recipient, memo) =
+            try_note_decryption(&OrchardDomain::for_action(&action), &ivk, &action).unwrap();
+        assert_eq!(note, parts.output);
+        assert_eq!(recipient, parts.output.recipient());
+        assert_eq!(memo, [0; 512]);
+    }
+}
@@ -0,0 +1,306 @@
+//! Structure-aware [`Arbitrary`] implementations, for fuzzing.
+//!
+//! Most byte strings do not encode valid Orchard values (field elements must be
+//! canonical, points must be on the curve, notes must have a commitment, and so on), so
+//! the implementations here do not parse their input. Instead, they consume it as a source
+//! of entropy for constructing values that are valid by construction. Generation is
+//! deterministic in the input, so fuzzer-minimized inputs reproduce exactly.
+
+use alloc::vec::Vec;
+
+use arbitrary::{Arbitrary, Error, Result, Unstructured};
+use ff::FromUniformBytes;
+use pasta_curves::pallas;
+
+use crate::{
+    bundle::Flags,
+    keys::{Diversifier, FullViewingKey, Scope, SpendingKey},
+    note::{Nullifier, RandomSeed, Rho},
+    value::NoteValue,
+    Address, Note,
+};
+
+#[cfg(feature = "circuit")]
+use {
+    crate::{
+        builder::{Builder, BundleType, InProgress, Unauthorized, Unproven},
+        bundle::Bundle,
+        circuit::Instance,
+        constants::MERKLE_DEPTH_ORCHARD,
+        keys::SpendValidatingKey,
+        note::ExtractedNoteCommitment,
+        tree::{MerkleHashOrchard, MerklePath},
+        value::{ValueCommitTrapdoor, ValueCommitment},
+    },
+    ff::PrimeField,
+    rand::{rngs::StdRng, SeedableRng},
+};
+
+/// Consumes 32 bytes and reduces them into a base field element.
+fn base(u: &mut Unstructured<'_>) -> Result<pallas::Base> {
+    let mut buf = [0; 64];
+    buf[..32].copy_from_slice(&u.arbitrary::<[u8; 32]>()?);
+    Ok(pallas::Base::from_uniform_bytes(&buf))
+}
+
+/// Consumes 32 bytes and reduces them into a scalar field element.
+#[cfg(feature = "circuit")]
+fn scalar(u: &mut Unstructured<'_>) -> Result<pallas::Scalar> {
+    let mut buf = [0; 64];
+    buf[..32].copy_from_slice(&u.arbitrary::<[u8; 32]>()?);
+    Ok(pallas::Scalar::from_uniform_bytes(&buf))
+}
+
+/// Increments `bytes` as a little-endian integer, wrapping on overflow.
+///
+/// Used to deterministically search for a valid encoding near the given bytes.
+fn increment(bytes: &mut [u8; 32]) {
+    for b in bytes.iter_mut() {
+        *b = b.wrapping_add(1);
+        if *b != 0 {
+            break;
+        }
+    }
+}
+
+/// Consumes 32 bytes and reduces them into a Merkle tree node.
+#[cfg(feature = "circuit")]
+fn merkle_hash(u: &mut Unstructured<'_>) -> Result<MerkleHashOrchard> {
+    Ok(MerkleHashOrchard::from_bytes(&base(u)?.to_repr()).unwrap())
+}
+
+/// Consumes 32 bytes and derives a full viewing key from them.
+fn full_viewing_key(u: &mut Unstructured<'_>) -> Result<FullViewingKey> {
+    let mut bytes = u.arbitrary::<[u8; 32]>()?;
+    // Almost all byte strings are valid spending keys.
+    loop {
+        if let Some(sk) = Option::<SpendingKey>::from(SpendingKey::from_bytes(bytes)) {
+            return Ok(FullViewingKey::from(&sk));
+        }
+        increment(&mut bytes);
+    }
+}
+
+/// Generates an address for `fvk`.
+fn address_for(u: &mut Unstructured<'_>, fvk: &FullViewingKey) -> Result<Address> {
+    let scope = if u.arbitrary()? {
+        Scope::Internal
+    } else {
+        Scope::External
+    };
+    Ok(fvk.address(Diversifier::from_bytes(u.arbitrary()?), scope))
+}
+
+/// Generates a note with the given recipient and value.
+fn note_for(u: &mut Unstructured<'_>, recipient: Address, value: NoteValue) -> Result<Note> {
+    let rho = Rho::from_nf_old(Nullifier(base(u)?));
+    let mut rseed = u.arbitrary::<[u8; 32]>()?;
+    // Almost all seeds produce a valid note.
+    loop {
+        let note = Option::<RandomSeed>::from(RandomSeed::from_bytes(rseed, &rho))
+            .and_then(|rseed| Note::from_parts(recipient, value, rho, rseed).ok());
+        if let Some(note) = note {
+            return Ok(note);
+        }
+        increment(&mut rseed);
+    }
+}
+
+impl<'a> Arbitrary<'a> for Flags {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Flags::from_parts(u.arbitrary()?, u.arbitrary()?))
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (2, Some(2))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Address {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let fvk = full_viewing_key(u)?;
+        address_for(u, &fvk)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Note {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let recipient = u.arbitrary()?;
+        let value = NoteValue::from_raw(u.arbitrary()?);
+        note_for(u, recipient, value)
+    }
+}
+
+#[cfg(feature = "circuit")]
+impl<'a> Arbitrary<'a> for Instance {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let anchor = base(u)?.into();
+
+        let value = NoteValue::from_raw(u.arbitrary()?) - NoteValue::from_raw(u.arbitrary()?);
+        let rcv = ValueCommitTrapdoor::from_bytes(scalar(u)?.to_repr()).unwrap();
+        let cv_net = ValueCommitment::derive(value, rcv);
+
+        let nf_old = Nullifier(base(u)?);
+
+        let ak = SpendValidatingKey::from(full_viewing_key(u)?);
+        let rk = ak.randomize(&scalar(u)?);
+
+        let cmx = ExtractedNoteCommitment::from_bytes(&base(u)?.to_repr()).unwrap();
+
+        Ok(Instance::from_parts(
+            anchor,
+            cv_net,
+            nf_old,
+            rk,
+            cmx,
+            u.arbitrary()?,
+            u.arbitrary()?,
+        ))
+    }
+}
+
+/// Generates bundles by running the [`Builder`], so that every generated bundle is
+/// internally consistent: spent notes belong to the spending keys, Merkle paths lead to
+/// the anchor, and the value balance matches the notes.
+///
+/// Each bundle has up to two spends and two outputs. Spent notes are siblings in the
+/// note commitment tree, so that both of their paths share the bundle anchor.
+#[cfg(feature = "circuit")]
+impl<'a, V: TryFrom<i64>> Arbitrary<'a> for Bundle<InProgress<Unproven, Unauthorized>, V> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // Keep every individual value small enough that the value balance fits in an i64.
+        let max_value = (i64::MAX as u64) / 2;
+
+        let spends_enabled = u.arbitrary()?;
+        let outputs_enabled = !spends_enabled || u.arbitrary()?;
+        let num_spends = if spends_enabled {
+            u.int_in_range(0..=2)?
+        } else {
+            0
+        };
+        let num_outputs = if outputs_enabled {
+            u.int_in_range(0..=2)?
+        } else {
+            0
+        };
+
+        let mut spends = Vec::with_capacity(num_spends);
+        for _ in 0..num_spends {
+            let fvk = full_viewing_key(u)?;
+            let recipient = address_for(u, &fvk)?;
+            let value = NoteValue::from_raw(u.int_in_range(0..=max_value)?);
+            let note = note_for(u, recipient, value)?;
+            spends.push((fvk, note));
+        }
+
+        // Place the spent notes at positions 2k and 2k + 1.
+        let position = u.arbitrary::<u32>()? & !1;
+        let leaves: Vec<MerkleHashOrchard> = match &spends[..] {
+            [(_, a), (_, b)] => vec![
+                MerkleHashOrchard::from_cmx(&ExtractedNoteCommitment::from(a.commitment())),
+                MerkleHashOrchard::from_cmx(&ExtractedNoteCommitment::from(b.commitment())),
+            ],
+            [(_, a)] => vec![
+                MerkleHashOrchard::from_cmx(&ExtractedNoteCommitment::from(a.commitment())),
+                merkle_hash(u)?,
+            ],
+            _ => vec![],
+        };
+        let mut auth_path = Vec::with_capacity(MERKLE_DEPTH_ORCHARD);
+        for _ in 0..MERKLE_DEPTH_ORCHARD {
+            auth_path.push(merkle_hash(u)?);
+        }
+        let auth_path: [MerkleHashOrchard; MERKLE_DEPTH_ORCHARD] = auth_path.try_into().unwrap();
+        let path = |i: usize| {
+            let mut auth_path = auth_path;
+            auth_path[0] = leaves[1 - i];
+            MerklePath::from_parts(position + i as u32, auth_path)
+        };
+
+        let anchor = match spends.first() {
+            Some((_, note)) => path(0).root(note.commitment().into()),
+            None => base(u)?.into(),
+        };
+
+        let mut builder = Builder::new(
+            BundleType::Transactional {
+                flags: Flags::from_parts(spends_enabled, outputs_enabled),
+                bundle_required: true,
+            },
+            anchor,
+        );
+        for (i, (fvk, note)) in spends.into_iter().enumerate() {
+            builder
+                .add_spend(fvk, note, path(i))
+                .map_err(|_| Error::IncorrectFormat)?;
+        }
+        for _ in 0..num_outputs {
+            let fvk = full_viewing_key(u)?;
+            let ovk = if u.arbitrary()? {
+                Some(fvk.to_ovk(Scope::External))
+            } else {
+                None
+            };
+            let recipient = u.arbitrary()?;
+            let value = NoteValue::from_raw(u.int_in_range(0..=max_value)?);
+            builder
+                .add_output(ovk, recipient, value, u.arbitrary()?)
+                .map_err(|_| Error::IncorrectFormat)?;
+        }
+
+        let rng = StdRng::from_seed(u.arbitrary()?);
+        match builder.build(rng) {
+            Ok(Some((bundle, _))) => Ok(bundle),
+            _ => Err(Error::IncorrectFormat),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use crate::{builder::UnauthorizedBundle, bundle::Flags, circuit::Instance, Note};
+
+    /// Deterministic pseudorandom input bytes.
+    fn input(len: usize) -> alloc::vec::Vec<u8> {
+        (0..len as u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn deterministic() {
+        let data = input(4096);
+
+        let a = Note::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let b = Note::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(a, b);
+
+        let a = Instance::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let b = Instance::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(a.to_bytes(), b.to_bytes());
+
+        let a = Flags::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let b = Flags::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn unauthorized_bundle() {
+        for offset in 0..4 {
+            let data = input(8192);
+            let bundle: UnauthorizedBundle<i64> =
+                Arbitrary::arbitrary(&mut Unstructured::new(&data[offset..])).unwrap();
+
+            // Padding ensures that transactional bundles have at least two actions.
+            assert!(bundle.actions().len() >= 2);
+        }
+    }
+
+    #[test]
+    fn short_input() {
+        // Exhausted input is read as zeroes, rather than rejected.
+        Note::arbitrary(&mut Unstructured::new(&[])).unwrap();
+    }
+}
@@ -4,6 +4,10 @@ use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use core::fmt;
 use core::iter;
+use core::num::NonZeroU32;
+use core::time::Duration;
+
+use core2::io::{self, Read, Write};
 
 use ff::Field;
 use pasta_curves::pallas;
@@ -11,15 +15,18 @@ use rand::{prelude::SliceRandom, CryptoRng, RngCore};
 
 use crate::{
     address::Address,
-    bundle::{Authorization, Authorized, Bundle, Flags},
+    bundle::{
+        find_duplicate_nullifier, Authorization, Authorized, Bundle, DuplicateNullifier, Flags,
+    },
+    constants::MERKLE_DEPTH_ORCHARD,
     keys::{
         FullViewingKey, OutgoingViewingKey, Scope, SpendAuthorizingKey, SpendValidatingKey,
         SpendingKey,
     },
-    note::{ExtractedNoteCommitment, Note, Nullifier, Rho, TransmittedNoteCiphertext},
+    note::{ExtractedNoteCommitment, Note, Nullifier, RandomSeed, Rho, TransmittedNoteCiphertext},
     note_encryption::OrchardNoteEncryption,
     primitives::redpallas::{self, Binding, SpendAuth},
-    tree::{Anchor, MerklePath},
+    tree::{Anchor, MerkleHashOrchard, MerklePath},
     value::{self, NoteValue, OverflowError, ValueCommitTrapdoor, ValueCommitment, ValueSum},
     Proof,
 };
@@ -35,6 +42,9 @@ use {
 
 const MIN_ACTIONS: usize = 2;
 
+/// The version byte of the encoding written by [`Builder::write`].
+const BUILDER_SNAPSHOT_VERSION: u8 = 1;
+
 /// An enumeration of rules for Orchard bundle construction.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BundleType {
@@ -67,6 +77,26 @@ impl BundleType {
         bundle_required: false,
     };
 
+    /// The OUTPUTS_ONLY bundle type has spends disabled, and does not require a bundle to be
+    /// produced if no outputs have been added to the bundle.
+    ///
+    /// Bundles of this type do not depend on the state of the note commitment tree; see
+    /// [`Builder::outputs_only`].
+    pub const OUTPUTS_ONLY: BundleType = BundleType::Transactional {
+        flags: Flags::SPENDS_DISABLED,
+        bundle_required: false,
+    };
+
+    /// The SPENDS_ONLY bundle type has outputs disabled, and does not require a bundle to be
+    /// produced if no spends have been added to the bundle.
+    ///
+    /// Bundles of this type move value out of the Orchard pool only; see
+    /// [`Builder::spends_only`].
+    pub const SPENDS_ONLY: BundleType = BundleType::Transactional {
+        flags: Flags::OUTPUTS_DISABLED,
+        bundle_required: false,
+    };
+
     /// Returns the number of logical actions that builder will produce in constructing a bundle
     /// of this type, given the specified numbers of spends and outputs.
     ///
@@ -139,6 +169,11 @@ pub enum BuildError {
     DuplicateSignature,
     /// The bundle being constructed violated the construction rules for the requested bundle type.
     BundleTypeNotSatisfiable,
+    /// The spends at the given indices reveal the same nullifier; that is, they spend the
+    /// same note.
+    DuplicateNullifier(usize, usize),
+    /// The bundle would have exceeded one of the [`BuilderLimits`] set on the builder.
+    LimitExceeded(Limit),
 }
 
 impl fmt::Display for BuildError {
@@ -159,6 +194,10 @@ impl fmt::Display for BuildError {
             AnchorMismatch => {
                 f.write_str("All spends must share the anchor requested for the transaction.")
             }
+            DuplicateNullifier(a, b) => {
+                write!(f, "Spends {} and {} reveal the same nullifier.", a, b)
+            }
+            LimitExceeded(limit) => write!(f, "Builder limit exceeded: {}", limit),
         }
     }
 }
@@ -179,6 +218,33 @@ impl From<value::OverflowError> for BuildError {
     }
 }
 
+/// A limit that can be imposed on bundle construction with [`BuilderLimits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// The number of spends added to the builder.
+    Spends,
+    /// The number of outputs added to the builder.
+    Outputs,
+    /// The number of actions in the bundle, after padding.
+    Actions,
+    /// The number of bytes drawn from the random number generator during building.
+    RngBytes,
+    /// The time elapsed between the start of building and the start of proving.
+    ProvingTime,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Spends => f.write_str("too many spends"),
+            Limit::Outputs => f.write_str("too many outputs"),
+            Limit::Actions => f.write_str("too many actions"),
+            Limit::RngBytes => f.write_str("too much randomness drawn"),
+            Limit::ProvingTime => f.write_str("proving deadline passed"),
+        }
+    }
+}
+
 /// An error type for adding a spend to the builder.
 #[derive(Debug, PartialEq, Eq)]
 pub enum SpendError {
@@ -188,16 +254,22 @@ pub enum SpendError {
     AnchorMismatch,
     /// The full viewing key provided didn't match the note provided
     FvkMismatch,
+    /// The note has already been added to this builder. The fields are the indices of the
+    /// existing spend and of the rejected spend.
+    DuplicateNullifier(usize, usize),
 }
 
 impl fmt::Display for SpendError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use SpendError::*;
-        f.write_str(match self {
-            SpendsDisabled => "Spends are not enabled for this builder",
-            AnchorMismatch => "All anchors must be equal.",
-            FvkMismatch => "FullViewingKey does not correspond to the given note",
-        })
+        match self {
+            SpendsDisabled => f.write_str("Spends are not enabled for this builder"),
+            AnchorMismatch => f.write_str("All anchors must be equal."),
+            FvkMismatch => f.write_str("FullViewingKey does not correspond to the given note"),
+            DuplicateNullifier(a, b) => {
+                write!(f, "Spend {} reveals the same nullifier as spend {}", b, a)
+            }
+        }
     }
 }
 
@@ -217,6 +289,87 @@ impl fmt::Display for OutputError {
 #[cfg(feature = "std")]
 impl std::error::Error for OutputError {}
 
+/// An error type for [`Builder::add_output_with_spends`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum SelectionError {
+    /// A selected note could not be added as a spend.
+    Spend(SpendError),
+    /// Outputs aren't enabled for this builder.
+    Output(OutputError),
+    /// The candidate notes do not have enough value to fund the output.
+    InsufficientFunds {
+        /// The total value of the candidate notes.
+        available: u64,
+        /// The value of the requested output.
+        required: u64,
+    },
+}
+
+impl fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionError::Spend(e) => write!(f, "Could not add spend: {}", e),
+            SelectionError::Output(e) => e.fmt(f),
+            SelectionError::InsufficientFunds {
+                available,
+                required,
+            } => write!(
+                f,
+                "Insufficient funds: {} available, {} required",
+                available, required
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SelectionError {}
+
+impl From<SpendError> for SelectionError {
+    fn from(e: SpendError) -> Self {
+        SelectionError::Spend(e)
+    }
+}
+
+impl From<OutputError> for SelectionError {
+    fn from(e: OutputError) -> Self {
+        SelectionError::Output(e)
+    }
+}
+
+/// The spends selected by [`Builder::add_output_with_spends`] to fund an output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpendSelection {
+    selected: Vec<usize>,
+    total: NoteValue,
+    change: NoteValue,
+    num_actions: usize,
+}
+
+impl SpendSelection {
+    /// Returns the indices, into the candidate notes, of the notes that were added as
+    /// spends, in the order they were added.
+    pub fn selected(&self) -> &[usize] {
+        &self.selected
+    }
+
+    /// Returns the total value of the selected notes.
+    pub fn total(&self) -> NoteValue {
+        self.total
+    }
+
+    /// Returns the value of the selected notes in excess of the output.
+    pub fn change(&self) -> NoteValue {
+        self.change
+    }
+
+    /// Returns the number of actions that the bundle will contain if it is built now,
+    /// including any dummy spends or outputs needed to pad it.
+    pub fn num_actions(&self) -> usize {
+        self.num_actions
+    }
+}
+
 /// Information about a specific note to be spent in an [`Action`].
 #[derive(Debug)]
 pub struct SpendInfo {
@@ -251,6 +404,7 @@ impl SpendInfo {
     /// Defined in [Zcash Protocol Spec § 4.8.3: Dummy Notes (Orchard)][orcharddummynotes].
     ///
     /// [orcharddummynotes]: https://zips.z.cash/protocol/nu5.pdf#orcharddummynotes
+    #[cfg_attr(feature = "test-dependencies", visibility::make(pub))]
     fn dummy(rng: &mut impl RngCore) -> Self {
         let (sk, fvk, note) = Note::dummy(rng, None);
         let merkle_path = MerklePath::dummy(rng);
@@ -276,6 +430,11 @@ impl SpendInfo {
         }
     }
 
+    /// Returns the nullifier revealed by spending this note.
+    fn nullifier(&self) -> Nullifier {
+        self.note.nullifier(&self.fvk)
+    }
+
     /// Builds the spend half of an action.
     ///
     /// The returned values are chosen as in [Zcash Protocol Spec § 4.7.3: Sending Notes (Orchard)][orchardsend].
@@ -528,14 +687,138 @@ impl BundleMetadata {
     }
 }
 
+/// Optional limits on the resources that a [`Builder`] may use.
+///
+/// These allow a service that builds bundles on behalf of others to reject requests that
+/// would occupy a worker for an unreasonable amount of time. Each limit that is exceeded
+/// causes building to fail with [`BuildError::LimitExceeded`]. By default, no limits are
+/// imposed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BuilderLimits {
+    /// The maximum number of spends that may be added to the builder.
+    pub max_spends: Option<usize>,
+    /// The maximum number of outputs that may be added to the builder.
+    pub max_outputs: Option<usize>,
+    /// The maximum number of actions in the built bundle, including dummy actions added
+    /// as padding.
+    ///
+    /// The cost of proof creation is linear in the number of actions, so together with
+    /// [`BuilderLimits::max_proving_time`] this bounds the time spent proving.
+    pub max_actions: Option<usize>,
+    /// The maximum number of bytes that building may draw from the random number
+    /// generator.
+    ///
+    /// Building stops before constructing the next action once the budget is exhausted,
+    /// and [`RngCore::try_fill_bytes`] calls that would exceed it fail without drawing
+    /// from the underlying generator.
+    pub max_rng_bytes: Option<u64>,
+    /// The maximum time from the start of [`Builder::build`] until proving starts.
+    ///
+    /// [`Bundle::create_proof`] fails instead of starting to prove once this has elapsed.
+    /// Proof creation cannot be interrupted once it has started, so this does not bound
+    /// the time spent inside it; use [`BuilderLimits::max_actions`] for that. This limit
+    /// does not apply to bundles built with [`Builder::build_for_pczt`].
+    pub max_proving_time: Option<Duration>,
+}
+
+impl BuilderLimits {
+    fn check(&self, limit: Limit, max: Option<usize>, value: usize) -> Result<(), BuildError> {
+        match max {
+            Some(max) if value > max => Err(BuildError::LimitExceeded(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_counts(
+        &self,
+        bundle_type: &BundleType,
+        num_spends: usize,
+        num_outputs: usize,
+    ) -> Result<(), BuildError> {
+        self.check(Limit::Spends, self.max_spends, num_spends)?;
+        self.check(Limit::Outputs, self.max_outputs, num_outputs)?;
+        if let Ok(num_actions) = bundle_type.num_actions(num_spends, num_outputs) {
+            self.check(Limit::Actions, self.max_actions, num_actions)?;
+        }
+        Ok(())
+    }
+}
+
+/// A random number generator that counts the bytes drawn from it against a budget.
+struct BudgetedRng<R> {
+    rng: R,
+    remaining: Option<u64>,
+    exceeded: bool,
+}
+
+impl<R: RngCore> BudgetedRng<R> {
+    fn new(rng: R, budget: Option<u64>) -> Self {
+        BudgetedRng {
+            rng,
+            remaining: budget,
+            exceeded: false,
+        }
+    }
+
+    /// Charges `bytes` against the budget, and returns `false` if that exceeds it.
+    fn charge(&mut self, bytes: usize) -> bool {
+        if let Some(remaining) = self.remaining.as_mut() {
+            match remaining.checked_sub(bytes as u64) {
+                Some(r) => *remaining = r,
+                None => self.exceeded = true,
+            }
+        }
+        !self.exceeded
+    }
+
+    /// Returns an error if the budget has been exceeded, so that building can stop
+    /// before doing any more work.
+    fn check(&self) -> Result<(), BuildError> {
+        if self.exceeded {
+            Err(BuildError::LimitExceeded(Limit::RngBytes))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<R: RngCore> RngCore for BudgetedRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.charge(4);
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.charge(8);
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.charge(dest.len());
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        if self.charge(dest.len()) {
+            self.rng.try_fill_bytes(dest)
+        } else {
+            Err(NonZeroU32::new(rand::Error::CUSTOM_START).unwrap().into())
+        }
+    }
+}
+
 /// A builder that constructs a [`Bundle`] from a set of notes to be spent, and outputs
 /// to receive funds.
+///
+/// `Builder` is `Send + Sync`, as are the bundles it produces at every stage of
+/// authorization, so bundle construction can be moved onto a worker thread.
 #[derive(Debug)]
 pub struct Builder {
     spends: Vec<SpendInfo>,
     outputs: Vec<OutputInfo>,
     bundle_type: BundleType,
     anchor: Anchor,
+    limits: BuilderLimits,
 }
 
 impl Builder {
@@ -546,7 +829,46 @@ impl Builder {
             outputs: vec![],
             bundle_type,
             anchor,
+            limits: BuilderLimits::default(),
+        }
+    }
+
+    /// Sets the resource limits that this builder enforces when building.
+    pub fn with_limits(mut self, limits: BuilderLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Constructs a new empty builder for an Orchard bundle that contains no spends.
+    ///
+    /// Because no notes are spent, the bundle does not need to commit to a particular state
+    /// of the note commitment tree, and the anchor of the empty tree is used. This is
+    /// convenient for shielding coinbase outputs, or for faucets that only create notes.
+    ///
+    /// Returns [`BuildError::BundleTypeNotSatisfiable`] if `bundle_type` permits spends;
+    /// use [`BundleType::OUTPUTS_ONLY`] or [`BundleType::Coinbase`] instead.
+    pub fn outputs_only(bundle_type: BundleType) -> Result<Self, BuildError> {
+        if bundle_type.flags().spends_enabled() {
+            return Err(BuildError::BundleTypeNotSatisfiable);
+        }
+
+        Ok(Builder::new(bundle_type, Anchor::empty_tree()))
+    }
+
+    /// Constructs a new empty builder for an Orchard bundle that contains no outputs, such
+    /// as one that fully unshields funds to the transparent pool.
+    ///
+    /// Any outputs in the resulting bundle are dummies, and [`Builder::add_output`] will
+    /// return an error. The value balance of such a bundle is therefore never negative.
+    ///
+    /// Returns [`BuildError::BundleTypeNotSatisfiable`] if `bundle_type` permits outputs;
+    /// use [`BundleType::SPENDS_ONLY`] instead.
+    pub fn spends_only(bundle_type: BundleType, anchor: Anchor) -> Result<Self, BuildError> {
+        if bundle_type.flags().outputs_enabled() {
+            return Err(BuildError::BundleTypeNotSatisfiable);
         }
+
+        Ok(Builder::new(bundle_type, anchor))
     }
 
     /// Adds a note to be spent in this transaction.
@@ -557,7 +879,7 @@ impl Builder {
     ///   instantiated with [`MerkleHashOrchard`].
     ///
     /// Returns an error if the given Merkle path does not have the required anchor for
-    /// the given note.
+    /// the given note, or if the note has already been added to this builder.
     ///
     /// [`OrchardDomain`]: crate::note_encryption::OrchardDomain
     /// [`MerkleHashOrchard`]: crate::tree::MerkleHashOrchard
@@ -573,13 +895,33 @@ impl Builder {
         }
 
         let spend = SpendInfo::new(fvk, note, merkle_path).ok_or(SpendError::FvkMismatch)?;
+        self.check_spend(&spend, &[])?;
+        self.spends.push(spend);
 
+        Ok(())
+    }
+
+    /// Checks that `spend` can be added to this builder after `pending`.
+    fn check_spend(&self, spend: &SpendInfo, pending: &[&SpendInfo]) -> Result<(), SpendError> {
         // Consistency check: all anchors must be equal.
         if !spend.has_matching_anchor(&self.anchor) {
             return Err(SpendError::AnchorMismatch);
         }
 
-        self.spends.push(spend);
+        // Spending the same note twice would produce a bundle that is invalid by
+        // consensus, which we would otherwise only discover after proving.
+        let nf = spend.nullifier();
+        if let Some(index) = self
+            .spends
+            .iter()
+            .chain(pending.iter().copied())
+            .position(|s| s.nullifier() == nf)
+        {
+            return Err(SpendError::DuplicateNullifier(
+                index,
+                self.spends.len() + pending.len(),
+            ));
+        }
 
         Ok(())
     }
@@ -603,6 +945,92 @@ impl Builder {
         Ok(())
     }
 
+    /// Adds an output of `value` to `recipient`, together with enough of the `candidates`
+    /// to fund it.
+    ///
+    /// Notes are selected from `candidates` in order of decreasing value until their total
+    /// is at least `value`, so an output that exceeds the value of any single note is
+    /// split across as many spends as required. If the selected notes exceed `value` and
+    /// `change` is provided, an output of the excess is added to that address with an
+    /// empty memo; otherwise the excess contributes to the value balance of the bundle.
+    /// Dummy spends or outputs are added when the bundle is built, to pad it to the
+    /// number of actions given in the returned [`SpendSelection`].
+    ///
+    /// Nothing is added to the builder if an error is returned.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_output_with_spends(
+        &mut self,
+        ovk: Option<OutgoingViewingKey>,
+        recipient: Address,
+        value: NoteValue,
+        memo: [u8; 512],
+        candidates: Vec<SpendInfo>,
+        change: Option<Address>,
+    ) -> Result<SpendSelection, SelectionError> {
+        let flags = self.bundle_type.flags();
+        if !flags.outputs_enabled() {
+            return Err(OutputError.into());
+        }
+        if !flags.spends_enabled() {
+            return Err(SpendError::SpendsDisabled.into());
+        }
+
+        let mut order: Vec<usize> = (0..candidates.len()).collect();
+        order.sort_by_key(|&i| core::cmp::Reverse(candidates[i].note.value().inner()));
+
+        let mut selected = vec![];
+        let mut total = 0u64;
+        for i in order {
+            if total >= value.inner() {
+                break;
+            }
+            total = total.saturating_add(candidates[i].note.value().inner());
+            selected.push(i);
+        }
+        if total < value.inner() {
+            return Err(SelectionError::InsufficientFunds {
+                available: total,
+                required: value.inner(),
+            });
+        }
+
+        let mut pending: Vec<&SpendInfo> = vec![];
+        for &i in &selected {
+            self.check_spend(&candidates[i], &pending)?;
+            pending.push(&candidates[i]);
+        }
+
+        let change_value = NoteValue::from_raw(total - value.inner());
+
+        let mut candidates: Vec<Option<SpendInfo>> = candidates.into_iter().map(Some).collect();
+        self.spends.extend(
+            selected
+                .iter()
+                .map(|&i| candidates[i].take().expect("selected indices are distinct")),
+        );
+        self.outputs
+            .push(OutputInfo::new(ovk.clone(), recipient, value, memo));
+        if let Some(change) = change.filter(|_| change_value.inner() > 0) {
+            // ZIP 302: a memo field beginning with 0xF6 followed by zeroes is empty.
+            let mut empty_memo = [0u8; 512];
+            empty_memo[0] = 0xF6;
+            self.outputs
+                .push(OutputInfo::new(ovk, change, change_value, empty_memo));
+        }
+
+        let num_actions = self
+            .bundle_type
+            .num_actions(self.spends.len(), self.outputs.len())
+            .expect("spends and outputs are enabled");
+
+        Ok(SpendSelection {
+            selected,
+            total: NoteValue::from_raw(total),
+            change: change_value,
+            num_actions,
+        })
+    }
+
     /// Returns the action spend components that will be produced by the
     /// transaction being constructed
     pub fn spends(&self) -> &Vec<impl InputView<()>> {
@@ -640,6 +1068,169 @@ impl Builder {
         i64::try_from(value_balance).and_then(|i| V::try_from(i).map_err(|_| value::OverflowError))
     }
 
+    /// Writes a snapshot of the spends and outputs staged in this builder, so that
+    /// construction of the bundle can be resumed later with [`Builder::read`].
+    ///
+    /// The snapshot contains full viewing keys, notes, Merkle paths, and memos, but no
+    /// spending keys. It does not include the builder's [`BuilderLimits`], which should be
+    /// set again by whoever resumes building.
+    ///
+    /// The encoding consists of a version byte, the bundle type, the anchor, the number
+    /// of spends as a little-endian `u32` followed by the spends, and the number of outputs
+    /// as a little-endian `u32` followed by the outputs.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let len = |n: usize| {
+            u32::try_from(n)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Builder too large"))
+        };
+
+        writer.write_all(&[BUILDER_SNAPSHOT_VERSION])?;
+        match self.bundle_type {
+            BundleType::Transactional {
+                flags,
+                bundle_required,
+            } => writer.write_all(&[0, flags.to_byte(), bundle_required.into()])?,
+            BundleType::Coinbase => writer.write_all(&[1])?,
+        }
+        writer.write_all(&self.anchor.to_bytes())?;
+
+        writer.write_all(&len(self.spends.len())?.to_le_bytes())?;
+        for spend in &self.spends {
+            spend.fvk.write(&mut writer)?;
+            let note = &spend.note;
+            writer.write_all(&note.recipient().to_raw_address_bytes())?;
+            writer.write_all(&note.value().to_bytes())?;
+            writer.write_all(&note.rho().to_bytes())?;
+            writer.write_all(note.rseed().as_bytes())?;
+            writer.write_all(&spend.merkle_path.position().to_le_bytes())?;
+            for node in spend.merkle_path.auth_path() {
+                writer.write_all(&node.to_bytes())?;
+            }
+        }
+
+        writer.write_all(&len(self.outputs.len())?.to_le_bytes())?;
+        for output in &self.outputs {
+            match &output.ovk {
+                Some(ovk) => {
+                    writer.write_all(&[1])?;
+                    writer.write_all(ovk.as_ref())?;
+                }
+                None => writer.write_all(&[0])?,
+            }
+            writer.write_all(&output.recipient.to_raw_address_bytes())?;
+            writer.write_all(&output.value.to_bytes())?;
+            writer.write_all(&output.memo)?;
+        }
+        Ok(())
+    }
+
+    /// Restores a builder from a snapshot written by [`Builder::write`].
+    ///
+    /// Each spend and output is added to the restored builder as if by
+    /// [`Builder::add_spend`] and [`Builder::add_output`], so a snapshot that would not
+    /// have been accepted by those methods is rejected.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != BUILDER_SNAPSHOT_VERSION {
+            return Err(invalid("Unsupported builder snapshot version"));
+        }
+
+        let read_byte = |reader: &mut R| -> io::Result<u8> {
+            let mut byte = [0u8; 1];
+            reader.read_exact(&mut byte)?;
+            Ok(byte[0])
+        };
+        let read_u32 = |reader: &mut R| -> io::Result<u32> {
+            let mut bytes = [0u8; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        };
+        let read_32_bytes = |reader: &mut R| -> io::Result<[u8; 32]> {
+            let mut bytes = [0u8; 32];
+            reader.read_exact(&mut bytes)?;
+            Ok(bytes)
+        };
+        let read_address = |reader: &mut R| -> io::Result<Address> {
+            let mut bytes = [0u8; 43];
+            reader.read_exact(&mut bytes)?;
+            Option::from(Address::from_raw_address_bytes(&bytes))
+                .ok_or_else(|| invalid("Invalid address in builder snapshot"))
+        };
+        let read_value = |reader: &mut R| -> io::Result<NoteValue> {
+            let mut bytes = [0u8; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(NoteValue::from_bytes(bytes))
+        };
+
+        let bundle_type = match read_byte(&mut reader)? {
+            0 => {
+                let flags = Flags::from_byte(read_byte(&mut reader)?)
+                    .ok_or_else(|| invalid("Invalid flags in builder snapshot"))?;
+                let bundle_required = match read_byte(&mut reader)? {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(invalid("Invalid bundle type in builder snapshot")),
+                };
+                BundleType::Transactional {
+                    flags,
+                    bundle_required,
+                }
+            }
+            1 => BundleType::Coinbase,
+            _ => return Err(invalid("Invalid bundle type in builder snapshot")),
+        };
+        let anchor = Option::from(Anchor::from_bytes(read_32_bytes(&mut reader)?))
+            .ok_or_else(|| invalid("Invalid anchor in builder snapshot"))?;
+        let mut builder = Builder::new(bundle_type, anchor);
+
+        // The counts are untrusted, so nothing is allocated up front.
+        let num_spends = read_u32(&mut reader)?;
+        for _ in 0..num_spends {
+            let fvk = FullViewingKey::read(&mut reader)?;
+            let recipient = read_address(&mut reader)?;
+            let value = read_value(&mut reader)?;
+            let rho = Option::from(Rho::from_bytes(&read_32_bytes(&mut reader)?))
+                .ok_or_else(|| invalid("Invalid rho in builder snapshot"))?;
+            let rseed = Option::from(RandomSeed::from_bytes(read_32_bytes(&mut reader)?, &rho))
+                .ok_or_else(|| invalid("Invalid rseed in builder snapshot"))?;
+            let note = Note::from_parts(recipient, value, rho, rseed)
+                .map_err(|_| invalid("Invalid note in builder snapshot"))?;
+            let position = read_u32(&mut reader)?;
+            let auth_path = (0..MERKLE_DEPTH_ORCHARD)
+                .map(|_| {
+                    Option::from(MerkleHashOrchard::from_bytes(&read_32_bytes(&mut reader)?))
+                        .ok_or_else(|| invalid("Invalid Merkle path in builder snapshot"))
+                })
+                .collect::<io::Result<Vec<_>>>()?
+                .try_into()
+                .expect("auth path has MERKLE_DEPTH_ORCHARD nodes");
+            builder
+                .add_spend(fvk, note, MerklePath::from_parts(position, auth_path))
+                .map_err(|_| invalid("Invalid spend in builder snapshot"))?;
+        }
+
+        let num_outputs = read_u32(&mut reader)?;
+        for _ in 0..num_outputs {
+            let ovk = match read_byte(&mut reader)? {
+                0 => None,
+                1 => Some(OutgoingViewingKey::from(read_32_bytes(&mut reader)?)),
+                _ => return Err(invalid("Invalid output in builder snapshot")),
+            };
+            let recipient = read_address(&mut reader)?;
+            let value = read_value(&mut reader)?;
+            let mut memo = [0u8; 512];
+            reader.read_exact(&mut memo)?;
+            builder
+                .add_output(ovk, recipient, value, memo)
+                .map_err(|_| invalid("Invalid output in builder snapshot"))?;
+        }
+
+        Ok(builder)
+    }
+
     /// Builds a bundle containing the given spent notes and outputs.
     ///
     /// The returned bundle will have no proof or signatures; these can be applied with
@@ -649,8 +1240,16 @@ impl Builder {
         self,
         rng: impl RngCore,
     ) -> Result<Option<(UnauthorizedBundle<V>, BundleMetadata)>, BuildError> {
-        bundle(
-            rng,
+        self.limits
+            .check_counts(&self.bundle_type, self.spends.len(), self.outputs.len())?;
+        let deadline = self
+            .limits
+            .max_proving_time
+            .map(|t| std::time::Instant::now() + t);
+        let mut rng = BudgetedRng::new(rng, self.limits.max_rng_bytes);
+        bundle_inner(
+            &mut rng,
+            deadline,
             self.anchor,
             self.bundle_type,
             self.spends,
@@ -664,18 +1263,24 @@ impl Builder {
         self,
         rng: impl RngCore,
     ) -> Result<(crate::pczt::Bundle, BundleMetadata), BuildError> {
+        self.limits
+            .check_counts(&self.bundle_type, self.spends.len(), self.outputs.len())?;
+        let mut rng = BudgetedRng::new(rng, self.limits.max_rng_bytes);
         build_bundle(
-            rng,
+            &mut rng,
             self.anchor,
             self.bundle_type,
             self.spends,
             self.outputs,
-            |pre_actions, flags, value_sum, bundle_meta, mut rng| {
+            |pre_actions, flags, value_sum, bundle_meta, rng| {
                 // Create the actions.
                 let actions = pre_actions
                     .into_iter()
-                    .map(|a| a.build_for_pczt(&mut rng))
-                    .collect::<Vec<_>>();
+                    .map(|a| {
+                        let action = a.build_for_pczt(&mut *rng);
+                        rng.check().map(|()| action)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
 
                 Ok((
                     crate::pczt::Bundle {
@@ -704,6 +1309,27 @@ pub fn bundle<V: TryFrom<i64>>(
     bundle_type: BundleType,
     spends: Vec<SpendInfo>,
     outputs: Vec<OutputInfo>,
+) -> Result<Option<(UnauthorizedBundle<V>, BundleMetadata)>, BuildError> {
+    bundle_inner(
+        &mut BudgetedRng::new(rng, None),
+        None,
+        anchor,
+        bundle_type,
+        spends,
+        outputs,
+    )
+}
+
+/// Builds a bundle, stopping early if `rng`'s budget is exceeded, and records the
+/// deadline by which proving must start.
+#[cfg(feature = "circuit")]
+fn bundle_inner<V: TryFrom<i64>, R: RngCore>(
+    rng: &mut BudgetedRng<R>,
+    deadline: Option<std::time::Instant>,
+    anchor: Anchor,
+    bundle_type: BundleType,
+    spends: Vec<SpendInfo>,
+    outputs: Vec<OutputInfo>,
 ) -> Result<Option<(UnauthorizedBundle<V>, BundleMetadata)>, BuildError> {
     build_bundle(
         rng,
@@ -711,7 +1337,7 @@ pub fn bundle<V: TryFrom<i64>>(
         bundle_type,
         spends,
         outputs,
-        |pre_actions, flags, value_balance, bundle_meta, mut rng| {
+        |pre_actions, flags, value_balance, bundle_meta, rng| {
             let result_value_balance: V = i64::try_from(value_balance)
                 .map_err(BuildError::ValueSum)
                 .and_then(|i| {
@@ -719,20 +1345,21 @@ pub fn bundle<V: TryFrom<i64>>(
                 })?;
 
             // Compute the transaction binding signing key.
-            let bsk = pre_actions
-                .iter()
-                .map(|a| &a.rcv)
-                .sum::<ValueCommitTrapdoor>()
-                .into_bsk();
+            let bsk = value::derive_bsk(pre_actions.iter().map(|a| &a.rcv));
 
             // Create the actions.
-            let (actions, circuits): (Vec<_>, Vec<_>) =
-                pre_actions.into_iter().map(|a| a.build(&mut rng)).unzip();
+            let (actions, circuits): (Vec<_>, Vec<_>) = pre_actions
+                .into_iter()
+                .map(|a| {
+                    let built = a.build(&mut *rng);
+                    rng.check().map(|()| built)
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .unzip();
 
             // Verify that bsk and bvk are consistent.
-            let bvk = (actions.iter().map(|a| a.cv_net()).sum::<ValueCommitment>()
-                - ValueCommitment::derive(value_balance, ValueCommitTrapdoor::zero()))
-            .into_bvk();
+            let bvk = value::derive_bvk_from_sum(actions.iter().map(|a| a.cv_net()), value_balance);
             assert_eq!(redpallas::VerificationKey::from(&bsk), bvk);
 
             Ok(NonEmpty::from_vec(actions).map(|actions| {
@@ -743,7 +1370,7 @@ pub fn bundle<V: TryFrom<i64>>(
                         result_value_balance,
                         anchor,
                         InProgress {
-                            proof: Unproven { circuits },
+                            proof: Unproven { circuits, deadline },
                             sigs: Unauthorized { bsk },
                         },
                     ),
@@ -755,12 +1382,18 @@ pub fn bundle<V: TryFrom<i64>>(
 }
 
 fn build_bundle<B, R: RngCore>(
-    mut rng: R,
+    rng: &mut BudgetedRng<R>,
     anchor: Anchor,
     bundle_type: BundleType,
     spends: Vec<SpendInfo>,
     outputs: Vec<OutputInfo>,
-    finisher: impl FnOnce(Vec<ActionInfo>, Flags, ValueSum, BundleMetadata, R) -> Result<B, BuildError>,
+    finisher: impl FnOnce(
+        Vec<ActionInfo>,
+        Flags,
+        ValueSum,
+        BundleMetadata,
+        &mut BudgetedRng<R>,
+    ) -> Result<B, BuildError>,
 ) -> Result<B, BuildError> {
     let flags = bundle_type.flags();
 
@@ -775,6 +1408,12 @@ fn build_bundle<B, R: RngCore>(
         }
     }
 
+    if let Some(DuplicateNullifier(a, b)) =
+        find_duplicate_nullifier(spends.iter().map(SpendInfo::nullifier))
+    {
+        return Err(BuildError::DuplicateNullifier(a, b));
+    }
+
     let num_requested_outputs = outputs.len();
     if !flags.outputs_enabled() && num_requested_outputs > 0 {
         return Err(BuildError::OutputsDisabled);
@@ -788,23 +1427,25 @@ fn build_bundle<B, R: RngCore>(
     let (pre_actions, bundle_meta) = {
         let mut indexed_spends = spends
             .into_iter()
-            .chain(iter::repeat_with(|| SpendInfo::dummy(&mut rng)))
+            .chain(iter::repeat_with(|| SpendInfo::dummy(&mut *rng)))
             .enumerate()
             .take(num_actions)
             .collect::<Vec<_>>();
+        rng.check()?;
 
         let mut indexed_outputs = outputs
             .into_iter()
-            .chain(iter::repeat_with(|| OutputInfo::dummy(&mut rng)))
+            .chain(iter::repeat_with(|| OutputInfo::dummy(&mut *rng)))
             .enumerate()
             .take(num_actions)
             .collect::<Vec<_>>();
+        rng.check()?;
 
         // Shuffle the spends and outputs, so that learning the position of a
         // specific spent note or output note doesn't reveal anything on its own about
         // the meaning of that note in the transaction context.
-        indexed_spends.shuffle(&mut rng);
-        indexed_outputs.shuffle(&mut rng);
+        indexed_spends.shuffle(&mut *rng);
+        indexed_outputs.shuffle(&mut *rng);
 
         let mut bundle_meta = BundleMetadata::new(num_requested_spends, num_requested_outputs);
         let pre_actions = indexed_spends
@@ -822,9 +1463,10 @@ fn build_bundle<B, R: RngCore>(
                     bundle_meta.output_indices[out_idx] = action_idx;
                 }
 
-                ActionInfo::new(spend, output, &mut rng)
+                let action = ActionInfo::new(spend, output, &mut *rng);
+                rng.check().map(|()| action)
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>, _>>()?;
 
         (pre_actions, bundle_meta)
     };
@@ -862,10 +1504,28 @@ impl<P: fmt::Debug, S: InProgressSignatures> Authorization for InProgress<P, S>
 #[derive(Clone, Debug)]
 pub struct Unproven {
     circuits: Vec<Circuit>,
+    /// The time after which proving may no longer start, if limited.
+    deadline: Option<std::time::Instant>,
+}
+
+#[cfg(feature = "circuit")]
+impl Unproven {
+    fn deadline_passed(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| std::time::Instant::now() >= deadline)
+    }
 }
 
 #[cfg(feature = "circuit")]
 impl<S: InProgressSignatures> InProgress<Unproven, S> {
+    /// Returns the Action circuits for this bundle, in action order.
+    ///
+    /// This allows the proof to be created outside of the builder, for example by a
+    /// dedicated proving service.
+    pub fn circuits(&self) -> &[Circuit] {
+        &self.proof.circuits
+    }
+
     /// Creates the proof for this bundle.
     pub fn create_proof(
         &self,
@@ -894,6 +1554,9 @@ impl<S: InProgressSignatures, V> Bundle<InProgress<Unproven, S>, V> {
             &mut (),
             |_, _, a| Ok(a),
             |_, auth| {
+                if auth.proof.deadline_passed() {
+                    return Err(BuildError::LimitExceeded(Limit::ProvingTime));
+                }
                 let proof = auth.create_proof(pk, &instances, &mut rng)?;
                 Ok(InProgress {
                     proof,
@@ -914,6 +1577,28 @@ pub struct SigningParts {
     alpha: pallas::Scalar,
 }
 
+/// Overwrites $\alpha$ with zero.
+///
+/// The dummy spending keys held by [`SpendInfo`] and the spend authorizing keys held by
+/// [`SigningMetadata`] are zeroized when they are dropped. $\alpha$ is not a key, but
+/// links the action's $\mathsf{rk}$ to $\mathsf{ak}$, so it is scrubbed as well once the
+/// action has been signed.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for SigningParts {
+    fn zeroize(&mut self) {
+        self.alpha = pallas::Scalar::zero();
+        // Prevent the write from being elided as a dead store.
+        core::hint::black_box(&self.alpha);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SigningParts {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
 /// Marker for an unauthorized bundle with no signatures.
 #[derive(Clone, Debug)]
 pub struct Unauthorized {
@@ -967,6 +1652,70 @@ impl MaybeSigned {
     }
 }
 
+/// A signer that can create spend authorization signatures for a particular
+/// [`SpendValidatingKey`], without exposing the corresponding [`SpendAuthorizingKey`].
+///
+/// This allows a bundle to be authorized by a hardware wallet or remote signing service.
+/// The host only needs to know the signer's `ak`; for each spend of a note controlled by
+/// it, the signer is given the spend's randomizer $\alpha$ and the sighash, and must
+/// return a signature under the randomized key $\mathsf{ask} + \alpha$.
+pub trait SpendAuthSigner {
+    /// The error returned when the signer fails to produce a signature.
+    type Error;
+
+    /// Returns the spend validating key of the notes that this signer can authorize.
+    fn ak(&self) -> SpendValidatingKey;
+
+    /// Signs `sighash` with the spend authorizing key randomized by `alpha`.
+    fn sign<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        alpha: &pallas::Scalar,
+        sighash: &[u8; 32],
+    ) -> Result<redpallas::Signature<SpendAuth>, Self::Error>;
+}
+
+impl SpendAuthSigner for SpendAuthorizingKey {
+    type Error = core::convert::Infallible;
+
+    fn ak(&self) -> SpendValidatingKey {
+        self.into()
+    }
+
+    fn sign<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        alpha: &pallas::Scalar,
+        sighash: &[u8; 32],
+    ) -> Result<redpallas::Signature<SpendAuth>, Self::Error> {
+        Ok(self.randomize(alpha).sign(rng, sighash))
+    }
+}
+
+/// An error returned by [`Bundle::sign_with`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignerError<E> {
+    /// The signer failed to produce a signature.
+    Signer(E),
+    /// The signer produced a signature that is not valid for the spend it was asked to
+    /// authorize.
+    InvalidSignature,
+}
+
+impl<E: fmt::Display> fmt::Display for SignerError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerError::Signer(e) => write!(f, "Signer failed: {}", e),
+            SignerError::InvalidSignature => {
+                f.write_str("Signer produced an invalid spend authorization signature")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug + fmt::Display> std::error::Error for SignerError<E> {}
+
 impl<P: fmt::Debug, V> Bundle<InProgress<P, Unauthorized>, V> {
     /// Loads the sighash into this bundle, preparing it for signing.
     ///
@@ -1035,10 +1784,42 @@ impl<P: fmt::Debug, V> Bundle<InProgress<P, PartiallyAuthorized>, V> {
             |_, partial| partial,
         )
     }
-    /// Appends externally computed [`Signature`]s.
+
+    /// Signs this bundle with the given [`SpendAuthSigner`].
     ///
-    /// Each signature will be applied to the one input for which it is valid. An error
-    /// will be returned if the signature is not valid for any inputs, or if it is valid
+    /// This will request signatures for all notes controlled by the signer's `ak`, and
+    /// check each signature before applying it.
+    pub fn sign_with<R: RngCore + CryptoRng, S: SpendAuthSigner>(
+        self,
+        mut rng: R,
+        signer: &mut S,
+    ) -> Result<Self, SignerError<S::Error>> {
+        let expected_ak = signer.ak();
+        self.try_map_authorization(
+            &mut (&mut rng, signer),
+            |(rng, signer), partial, maybe| match maybe {
+                MaybeSigned::SigningMetadata(parts) if parts.ak == expected_ak => {
+                    let sighash = &partial.sigs.sighash;
+                    let signature = signer
+                        .sign(rng, &parts.alpha, sighash)
+                        .map_err(SignerError::Signer)?;
+                    parts
+                        .ak
+                        .randomize(&parts.alpha)
+                        .verify(&sighash[..], &signature)
+                        .map_err(|_| SignerError::InvalidSignature)?;
+                    Ok(MaybeSigned::Signature(signature))
+                }
+                s => Ok(s),
+            },
+            |_, partial| Ok(partial),
+        )
+    }
+
+    /// Appends externally computed [`Signature`]s.
+    ///
+    /// Each signature will be applied to the one input for which it is valid. An error
+    /// will be returned if the signature is not valid for any inputs, or if it is valid
     /// for more than one input.
     ///
     /// [`Signature`]: redpallas::Signature
@@ -1077,6 +1858,94 @@ impl<P: fmt::Debug, V> Bundle<InProgress<P, PartiallyAuthorized>, V> {
             _ => Err(BuildError::DuplicateSignature),
         }
     }
+
+    /// Returns the data needed to create each spend authorization signature that this
+    /// bundle is still missing.
+    ///
+    /// Each signature must be a RedPallas signature over the request's `sighash`, under
+    /// `rk`. An external signer such as a hardware wallet, holding the spend authorizing
+    /// key, signs with `ask.randomize(alpha)`, and should display or otherwise check the
+    /// transaction that `sighash` commits to. With the `unstable-frost` feature flag, the
+    /// request also carries `ak`: re-randomized FROST produces such a signature when the
+    /// group key is `ak` and `alpha` is used as the session's randomizer, and a spend
+    /// authority shared between two devices can use [`redpallas::cosign`].
+    ///
+    /// The signature can then be applied with [`Bundle::apply_spend_auth_signature`].
+    pub fn spend_auth_requests(&self) -> Vec<SpendAuthRequest> {
+        let sighash = self.authorization().sigs.sighash;
+        self.actions()
+            .iter()
+            .enumerate()
+            .filter_map(|(action_index, action)| match action.authorization() {
+                MaybeSigned::SigningMetadata(parts) => Some(SpendAuthRequest {
+                    action_index,
+                    alpha: parts.alpha,
+                    rk: action.rk().clone(),
+                    #[cfg(feature = "unstable-frost")]
+                    ak: parts.ak.clone(),
+                    sighash,
+                }),
+                MaybeSigned::Signature(_) => None,
+            })
+            .collect()
+    }
+
+    /// Applies an externally produced spend authorization signature to the action at
+    /// `action_index`.
+    ///
+    /// Returns [`BuildError::InvalidExternalSignature`] if that action does not need a
+    /// signature, or if `signature` is not valid for it.
+    pub fn apply_spend_auth_signature(
+        self,
+        action_index: usize,
+        signature: redpallas::Signature<SpendAuth>,
+    ) -> Result<Self, BuildError> {
+        let mut applied = false;
+        let bundle = self.map_authorization(
+            &mut (0usize, &mut applied),
+            |(index, applied), partial, maybe| {
+                let this_index = *index;
+                *index += 1;
+                match maybe {
+                    MaybeSigned::SigningMetadata(parts) if this_index == action_index => {
+                        let rk = parts.ak.randomize(&parts.alpha);
+                        if rk.verify(&partial.sigs.sighash[..], &signature).is_ok() {
+                            **applied = true;
+                            MaybeSigned::Signature(signature.clone())
+                        } else {
+                            MaybeSigned::SigningMetadata(parts)
+                        }
+                    }
+                    s => s,
+                }
+            },
+            |_, partial| partial,
+        );
+        if applied {
+            Ok(bundle)
+        } else {
+            Err(BuildError::InvalidExternalSignature)
+        }
+    }
+}
+
+/// The data needed to create the spend authorization signature of one action, as
+/// returned by [`Bundle::spend_auth_requests`].
+#[derive(Clone, Debug)]
+pub struct SpendAuthRequest {
+    /// The index of the action in the bundle.
+    pub action_index: usize,
+    /// The randomizer $\alpha$ of the action's spend authorization key.
+    pub alpha: pallas::Scalar,
+    /// The randomized key $\mathsf{rk}$ under which the signature will be verified.
+    pub rk: redpallas::VerificationKey<SpendAuth>,
+    /// The spend validating key of the note being spent, which re-randomized by `alpha`
+    /// gives `rk`.
+    #[cfg(feature = "unstable-frost")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unstable-frost")))]
+    pub ak: SpendValidatingKey,
+    /// The message to be signed.
+    pub sighash: [u8; 32],
 }
 
 impl<V> Bundle<InProgress<Proof, PartiallyAuthorized>, V> {
@@ -1248,7 +2117,7 @@ pub mod testing {
 
             ArbitraryBundleInputs {
                 rng: StdRng::from_seed(rng_seed),
-                sk,
+                sk: sk.clone(),
                 anchor: frontier.root().into(),
                 notes: notes_and_auth_paths,
                 output_amounts
@@ -1273,16 +2142,33 @@ pub mod testing {
 
 #[cfg(test)]
 mod tests {
-    use rand::rngs::OsRng;
+    use core::time::Duration;
+
+    use pasta_curves::pallas;
+    use rand::{rngs::OsRng, CryptoRng, RngCore};
+    use zcash_note_encryption::try_output_recovery_with_pkd_esk;
 
-    use super::Builder;
+    use alloc::vec::Vec;
+
+    use incrementalmerkletree::Hashable;
+
+    use super::{
+        BuildError, Builder, BuilderLimits, Limit, MaybeSigned, SelectionError, SignerError,
+        SpendAuthSigner, SpendError, SpendInfo,
+    };
     use crate::{
         builder::BundleType,
-        bundle::{Authorized, Bundle},
+        bundle::{check_unique_nullifiers, Authorized, Bundle, DuplicateNullifier},
         circuit::ProvingKey,
         constants::MERKLE_DEPTH_ORCHARD,
-        keys::{FullViewingKey, Scope, SpendingKey},
-        tree::EMPTY_ROOTS,
+        keys::{
+            EphemeralSecretKey, FullViewingKey, Scope, SpendAuthorizingKey, SpendValidatingKey,
+            SpendingKey,
+        },
+        note::{Note, Nullifier, Rho},
+        note_encryption::OrchardDomain,
+        primitives::redpallas::{self, SpendAuth},
+        tree::{Anchor, MerkleHashOrchard, MerklePath, EMPTY_ROOTS},
         value::NoteValue,
     };
 
@@ -1316,6 +2202,667 @@ mod tests {
             .prepare(rng, [0; 32])
             .finalize()
             .unwrap();
+        assert_eq!(bundle.value_balance(), &(-5000));
+
+        // The authorizing data can be read back in its encoded form.
+        let auth = bundle.authorization();
+        assert_eq!(auth.proof_bytes(), auth.proof().as_ref());
+        assert_eq!(
+            auth.binding_signature_bytes(),
+            <[u8; 64]>::from(auth.binding_signature())
+        );
+        let sigs: Vec<_> = bundle.spend_auth_signature_bytes().collect();
+        assert_eq!(sigs.len(), bundle.actions().len());
+        assert_eq!(
+            sigs[0],
+            <[u8; 64]>::from(bundle.actions().first().authorization())
+        );
+    }
+
+    #[test]
+    fn scan_with_fvk() {
+        let mut rng = OsRng;
+
+        let fvk = FullViewingKey::from(&SpendingKey::random(&mut rng));
+        let external = fvk.address_at(3u32, Scope::External);
+        let change = fvk.change_address();
+        assert_eq!(fvk.scope_for_address(&change), Some(Scope::Internal));
+
+        let mut builder = Builder::new(
+            BundleType::DEFAULT,
+            EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into(),
+        );
+        builder
+            .add_output(None, external, NoteValue::from_raw(3000), [0; 512])
+            .unwrap();
+        builder
+            .add_output(None, change, NoteValue::from_raw(2000), [0; 512])
+            .unwrap();
+        let (bundle, meta) = builder.build::<i64>(&mut rng).unwrap().unwrap();
+
+        let mut received = bundle.decrypt_outputs_with_fvk(&fvk);
+        received.sort_by_key(|(idx, _, _, _, _)| *idx);
+        let mut expected = vec![
+            (
+                meta.output_action_index(0).unwrap(),
+                Scope::External,
+                external,
+            ),
+            (
+                meta.output_action_index(1).unwrap(),
+                Scope::Internal,
+                change,
+            ),
+        ];
+        expected.sort_by_key(|(idx, _, _)| *idx);
+        assert_eq!(
+            received
+                .into_iter()
+                .map(|(idx, scope, _, addr, _)| (idx, scope, addr))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn recover_sent_outputs() {
+        let mut rng = OsRng;
+
+        let sk = SpendingKey::random(&mut rng);
+        let fvk = FullViewingKey::from(&sk);
+        let recipient =
+            FullViewingKey::from(&SpendingKey::random(&mut rng)).address_at(0u32, Scope::External);
+        let change = fvk.address_at(0u32, Scope::Internal);
+
+        let mut builder = Builder::new(
+            BundleType::DEFAULT,
+            EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into(),
+        );
+        let ovk = Some(fvk.to_ovk(Scope::External));
+        let change_ovk = Some(fvk.to_ovk(Scope::Internal));
+        builder
+            .add_output(ovk, recipient, NoteValue::from_raw(3000), [1; 512])
+            .unwrap();
+        builder
+            .add_output(change_ovk, change, NoteValue::from_raw(2000), [2; 512])
+            .unwrap();
+        builder
+            .add_output(None, recipient, NoteValue::from_raw(1000), [3; 512])
+            .unwrap();
+        let (bundle, meta) = builder.build::<i64>(&mut rng).unwrap().unwrap();
+
+        let mut recovered = bundle.recover_outputs_with_fvk(&fvk);
+        recovered.sort_by_key(|(_, _, note, _, _)| note.value().inner());
+        assert_eq!(recovered.len(), 2);
+
+        let (idx, scope, note, addr, memo) = &recovered[0];
+        assert_eq!(Some(*idx), meta.output_action_index(1));
+        assert_eq!(*scope, Scope::Internal);
+        assert_eq!(note.value().inner(), 2000);
+        assert_eq!(*addr, change);
+        assert_eq!(memo, &[2; 512]);
+
+        let (idx, scope, _, addr, memo) = &recovered[1];
+        assert_eq!(Some(*idx), meta.output_action_index(0));
+        assert_eq!(*scope, Scope::External);
+        assert_eq!(*addr, recipient);
+        assert_eq!(memo, &[1; 512]);
+    }
+
+    #[test]
+    fn payment_disclosure() {
+        let mut rng = OsRng;
+
+        let fvk = FullViewingKey::from(&SpendingKey::random(&mut rng));
+        let recipient =
+            FullViewingKey::from(&SpendingKey::random(&mut rng)).address_at(0u32, Scope::External);
+
+        let mut builder = Builder::new(
+            BundleType::DEFAULT,
+            EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into(),
+        );
+        builder
+            .add_output(
+                Some(fvk.to_ovk(Scope::External)),
+                recipient,
+                NoteValue::from_raw(3000),
+                [1; 512],
+            )
+            .unwrap();
+        let (bundle, meta) = builder.build::<i64>(&mut rng).unwrap().unwrap();
+
+        // The sender recovers the note it sent, and finds its action by the ephemeral key.
+        let (idx, _, note, _, _) = bundle.recover_outputs_with_fvk(&fvk).pop().unwrap();
+        assert_eq!(Some(idx), meta.output_action_index(0));
+        let action = bundle.actions().get(idx).unwrap();
+        assert_eq!(note.ephemeral_key().0, action.encrypted_note().epk_bytes);
+        let disclosed = note.esk().to_bytes();
+
+        // An auditor given esk and the recipient's address can decrypt the output, without
+        // any viewing key.
+        let esk = EphemeralSecretKey::from_bytes(&disclosed).unwrap();
+        let (decrypted, addr, memo) = try_output_recovery_with_pkd_esk(
+            &OrchardDomain::for_action(action),
+            *recipient.pk_d(),
+            esk,
+            action,
+        )
+        .unwrap();
+        assert_eq!(decrypted, note);
+        assert_eq!(addr, recipient);
+        assert_eq!(memo, [1; 512]);
+    }
+
+    #[test]
+    fn outputs_only_bundle() {
+        let pk = ProvingKey::build();
+        let mut rng = OsRng;
+
+        let sk = SpendingKey::random(&mut rng);
+        let fvk = FullViewingKey::from(&sk);
+        let recipient = fvk.address_at(0u32, Scope::External);
+
+        assert!(matches!(
+            Builder::outputs_only(BundleType::DEFAULT),
+            Err(super::BuildError::BundleTypeNotSatisfiable)
+        ));
+
+        let mut builder = Builder::outputs_only(BundleType::OUTPUTS_ONLY).unwrap();
+        builder
+            .add_output(None, recipient, NoteValue::from_raw(5000), [0u8; 512])
+            .unwrap();
+
+        let bundle: Bundle<Authorized, i64> = builder
+            .build(&mut rng)
+            .unwrap()
+            .unwrap()
+            .0
+            .create_proof(&pk, &mut rng)
+            .unwrap()
+            .prepare(rng, [0; 32])
+            .finalize()
+            .unwrap();
+        assert_eq!(bundle.anchor(), &Anchor::empty_tree());
+        assert!(!bundle.flags().spends_enabled());
         assert_eq!(bundle.value_balance(), &(-5000))
     }
+
+    #[test]
+    fn builder_limits() {
+        let mut rng = OsRng;
+
+        let sk = SpendingKey::random(&mut rng);
+        let fvk = FullViewingKey::from(&sk);
+        let recipient = fvk.address_at(0u32, Scope::External);
+
+        let build = |limits: BuilderLimits, num_outputs: usize| {
+            let mut builder = Builder::outputs_only(BundleType::OUTPUTS_ONLY)
+                .unwrap()
+                .with_limits(limits);
+            for _ in 0..num_outputs {
+                builder
+                    .add_output(None, recipient, NoteValue::from_raw(5000), [0u8; 512])
+                    .unwrap();
+            }
+            builder.build_for_pczt(OsRng).map(|_| ())
+        };
+
+        assert!(build(BuilderLimits::default(), 3).is_ok());
+        assert!(matches!(
+            build(
+                BuilderLimits {
+                    max_outputs: Some(2),
+                    ..Default::default()
+                },
+                3
+            ),
+            Err(BuildError::LimitExceeded(Limit::Outputs))
+        ));
+        // A single output is padded to two actions.
+        assert!(matches!(
+            build(
+                BuilderLimits {
+                    max_actions: Some(1),
+                    ..Default::default()
+                },
+                1
+            ),
+            Err(BuildError::LimitExceeded(Limit::Actions))
+        ));
+        assert!(matches!(
+            build(
+                BuilderLimits {
+                    max_rng_bytes: Some(64),
+                    ..Default::default()
+                },
+                1
+            ),
+            Err(BuildError::LimitExceeded(Limit::RngBytes))
+        ));
+    }
+
+    #[test]
+    fn rng_budget_stops_building_early() {
+        /// Counts the bytes drawn from `OsRng`.
+        struct CountingRng(usize);
+
+        impl RngCore for CountingRng {
+            fn next_u32(&mut self) -> u32 {
+                self.0 += 4;
+                OsRng.next_u32()
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                self.0 += 8;
+                OsRng.next_u64()
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                self.0 += dest.len();
+                OsRng.fill_bytes(dest)
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+                self.0 += dest.len();
+                OsRng.try_fill_bytes(dest)
+            }
+        }
+
+        let recipient = FullViewingKey::from(&SpendingKey::random(&mut OsRng))
+            .address_at(0u32, Scope::External);
+        let build = |limits: BuilderLimits, rng: &mut CountingRng| {
+            let mut builder = Builder::new(
+                BundleType::DEFAULT,
+                EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into(),
+            )
+            .with_limits(limits);
+            for _ in 0..8 {
+                builder
+                    .add_output(None, recipient, NoteValue::from_raw(5000), [0u8; 512])
+                    .unwrap();
+            }
+            builder.build::<i64>(rng).map(|_| ())
+        };
+
+        let mut unlimited = CountingRng(0);
+        assert!(build(BuilderLimits::default(), &mut unlimited).is_ok());
+
+        // Building stops once the budget is exhausted, well before all of the actions
+        // have been constructed.
+        let mut limited = CountingRng(0);
+        assert!(matches!(
+            build(
+                BuilderLimits {
+                    max_rng_bytes: Some(64),
+                    ..Default::default()
+                },
+                &mut limited
+            ),
+            Err(BuildError::LimitExceeded(Limit::RngBytes))
+        ));
+        assert!(limited.0 * 2 < unlimited.0);
+    }
+
+    #[test]
+    fn proving_deadline() {
+        let pk = ProvingKey::build();
+        let mut rng = OsRng;
+        let recipient =
+            FullViewingKey::from(&SpendingKey::random(&mut rng)).address_at(0u32, Scope::External);
+
+        let build = |limits: BuilderLimits| {
+            let mut builder = Builder::new(
+                BundleType::DEFAULT,
+                EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into(),
+            )
+            .with_limits(limits);
+            builder
+                .add_output(None, recipient, NoteValue::from_raw(5000), [0u8; 512])
+                .unwrap();
+            builder.build::<i64>(OsRng).unwrap().unwrap().0
+        };
+
+        let expired = build(BuilderLimits {
+            max_proving_time: Some(Duration::ZERO),
+            ..Default::default()
+        });
+        assert!(matches!(
+            expired.create_proof(&pk, &mut rng),
+            Err(BuildError::LimitExceeded(Limit::ProvingTime))
+        ));
+
+        let generous = build(BuilderLimits {
+            max_proving_time: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        });
+        assert!(generous.create_proof(&pk, &mut rng).is_ok());
+    }
+
+    #[test]
+    fn spends_only_builder() {
+        let mut rng = OsRng;
+
+        let sk = SpendingKey::random(&mut rng);
+        let fvk = FullViewingKey::from(&sk);
+        let recipient = fvk.address_at(0u32, Scope::External);
+        let anchor = EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into();
+
+        assert!(matches!(
+            Builder::spends_only(BundleType::DEFAULT, anchor),
+            Err(super::BuildError::BundleTypeNotSatisfiable)
+        ));
+
+        let mut builder = Builder::spends_only(BundleType::SPENDS_ONLY, anchor).unwrap();
+        assert!(builder
+            .add_output(None, recipient, NoteValue::from_raw(5000), [0u8; 512])
+            .is_err());
+        assert_eq!(builder.value_balance::<i64>().unwrap(), 0);
+    }
+
+    #[test]
+    fn duplicate_nullifiers() {
+        let mut rng = OsRng;
+        let anchor: Anchor = EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into();
+
+        // Zero-valued notes are not checked against the anchor.
+        let (_, fvk, note) = Note::dummy(&mut rng, None);
+        let (_, other_fvk, other_note) = Note::dummy(&mut rng, None);
+
+        let mut builder = Builder::new(BundleType::DEFAULT, anchor);
+        builder
+            .add_spend(fvk.clone(), note, MerklePath::dummy(&mut rng))
+            .unwrap();
+        builder
+            .add_spend(other_fvk, other_note, MerklePath::dummy(&mut rng))
+            .unwrap();
+        assert_eq!(
+            builder.add_spend(fvk.clone(), note, MerklePath::dummy(&mut rng)),
+            Err(SpendError::DuplicateNullifier(0, 2))
+        );
+        assert_eq!(builder.spends().len(), 2);
+
+        // Spends passed directly to `bundle` are also checked.
+        let spends = (0..2)
+            .map(|_| SpendInfo::new(fvk.clone(), note, MerklePath::dummy(&mut rng)).unwrap())
+            .collect();
+        assert!(matches!(
+            super::bundle::<i64>(&mut rng, anchor, BundleType::DEFAULT, spends, vec![]),
+            Err(BuildError::DuplicateNullifier(0, 1))
+        ));
+
+        // Bundles that would be combined are checked against each other.
+        let (bundle, _) = builder.build::<i64>(&mut rng).unwrap().unwrap();
+        let num_actions = bundle.actions().len();
+        assert_eq!(bundle.nullifiers().count(), num_actions);
+        assert_eq!(bundle.nullifier_set().len(), num_actions);
+        assert!(bundle.nullifier_set().contains(&note.nullifier(&fvk)));
+        assert_eq!(bundle.commitment_set().len(), num_actions);
+        assert_eq!(check_unique_nullifiers([&bundle]), Ok(()));
+        assert_eq!(
+            check_unique_nullifiers([&bundle, &bundle]),
+            Err(DuplicateNullifier(0, num_actions))
+        );
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut rng = OsRng;
+        let anchor: Anchor = EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into();
+
+        let (_, fvk, note) = Note::dummy(&mut rng, None);
+        let recipient = fvk.address_at(0u32, Scope::Internal);
+
+        let mut builder = Builder::new(BundleType::DEFAULT, anchor);
+        builder
+            .add_spend(fvk.clone(), note, MerklePath::dummy(&mut rng))
+            .unwrap();
+        builder
+            .add_output(
+                Some(fvk.to_ovk(Scope::External)),
+                recipient,
+                NoteValue::from_raw(5000),
+                [1; 512],
+            )
+            .unwrap();
+        builder
+            .add_output(None, recipient, NoteValue::from_raw(7), [0; 512])
+            .unwrap();
+
+        let mut snapshot = vec![];
+        builder.write(&mut snapshot).unwrap();
+        let restored = Builder::read(&snapshot[..]).unwrap();
+        assert_eq!(restored.spends().len(), 1);
+        assert_eq!(restored.outputs().len(), 2);
+        assert_eq!(restored.value_balance::<i64>().unwrap(), -5007);
+
+        let mut rewritten = vec![];
+        restored.write(&mut rewritten).unwrap();
+        assert_eq!(rewritten, snapshot);
+
+        // The restored builder can still build the bundle.
+        assert!(restored.build::<i64>(&mut rng).unwrap().is_some());
+
+        // Truncated and unknown snapshots are rejected.
+        assert!(Builder::read(&snapshot[..snapshot.len() - 1]).is_err());
+        snapshot[0] = 0;
+        assert!(Builder::read(&snapshot[..]).is_err());
+    }
+
+    #[test]
+    fn external_signer() {
+        struct Device {
+            ak: SpendValidatingKey,
+            ask: SpendAuthorizingKey,
+            available: bool,
+        }
+
+        impl SpendAuthSigner for Device {
+            type Error = &'static str;
+
+            fn ak(&self) -> SpendValidatingKey {
+                self.ak.clone()
+            }
+
+            fn sign<R: RngCore + CryptoRng>(
+                &mut self,
+                rng: &mut R,
+                alpha: &pallas::Scalar,
+                sighash: &[u8; 32],
+            ) -> Result<redpallas::Signature<SpendAuth>, Self::Error> {
+                if !self.available {
+                    return Err("device unavailable");
+                }
+                SpendAuthSigner::sign(&mut self.ask, rng, alpha, sighash).map_err(|e| match e {})
+            }
+        }
+
+        let mut rng = OsRng;
+        let (sk, fvk, note) = Note::dummy(&mut rng, None);
+        let (other_sk, _, _) = Note::dummy(&mut rng, None);
+        let ask = SpendAuthorizingKey::from(&sk);
+
+        let prepare = || {
+            let mut builder = Builder::new(
+                BundleType::DEFAULT,
+                EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into(),
+            );
+            builder
+                .add_spend(fvk.clone(), note, MerklePath::dummy(&mut OsRng))
+                .unwrap();
+            let (bundle, _) = builder.build::<i64>(OsRng).unwrap().unwrap();
+            bundle.prepare(OsRng, [7; 32])
+        };
+
+        let mut device = Device {
+            ak: (&ask).into(),
+            ask: SpendAuthorizingKey::from(&other_sk),
+            available: false,
+        };
+        assert_eq!(
+            prepare().sign_with(&mut rng, &mut device).unwrap_err(),
+            SignerError::Signer("device unavailable")
+        );
+
+        device.available = true;
+        assert_eq!(
+            prepare().sign_with(&mut rng, &mut device).unwrap_err(),
+            SignerError::InvalidSignature
+        );
+
+        device.ask = ask;
+        let signed = prepare().sign_with(&mut rng, &mut device).unwrap();
+        assert!(signed
+            .actions()
+            .iter()
+            .all(|a| matches!(a.authorization(), MaybeSigned::Signature(_))));
+    }
+
+    #[test]
+    fn spend_auth_requests() {
+        let mut rng = OsRng;
+        let (sk, fvk, note) = Note::dummy(&mut rng, None);
+        let ask = SpendAuthorizingKey::from(&sk);
+
+        let prepare = || {
+            let mut builder = Builder::new(
+                BundleType::DEFAULT,
+                EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into(),
+            );
+            builder
+                .add_spend(fvk.clone(), note, MerklePath::dummy(&mut OsRng))
+                .unwrap();
+            let (bundle, meta) = builder.build::<i64>(OsRng).unwrap().unwrap();
+            (bundle.prepare(OsRng, [7; 32]), meta)
+        };
+
+        for wrong_index in [false, true] {
+            let (bundle, meta) = prepare();
+
+            // Dummy spends are signed by the builder, so only the real spend is requested.
+            let requests = bundle.spend_auth_requests();
+            assert_eq!(requests.len(), 1);
+            let request = &requests[0];
+            assert_eq!(Some(request.action_index), meta.spend_action_index(0));
+            assert_eq!(request.sighash, [7; 32]);
+            #[cfg(feature = "unstable-frost")]
+            assert_eq!(request.ak.randomize(&request.alpha), request.rk);
+
+            // The signer only needs alpha to derive the key that verifies under rk.
+            let rsk = ask.randomize(&request.alpha);
+            assert_eq!(redpallas::VerificationKey::from(&rsk), request.rk);
+            let signature = rsk.sign(&mut rng, &request.sighash);
+
+            if wrong_index {
+                let other_index = (request.action_index + 1) % bundle.actions().len();
+                assert!(matches!(
+                    bundle.apply_spend_auth_signature(other_index, signature),
+                    Err(BuildError::InvalidExternalSignature)
+                ));
+            } else {
+                let bundle = bundle
+                    .apply_spend_auth_signature(request.action_index, signature)
+                    .unwrap();
+                assert!(bundle.spend_auth_requests().is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn add_output_with_spends() {
+        let mut rng = OsRng;
+
+        let sk = SpendingKey::random(&mut rng);
+        let fvk = FullViewingKey::from(&sk);
+        let recipient = fvk.address_at(0u32, Scope::External);
+        let change = fvk.address_at(0u32, Scope::Internal);
+
+        // Three notes at positions 0, 1 and 2 of an otherwise empty tree.
+        let notes = [3000, 5000, 4000].map(|value| {
+            let rho = Rho::from_nf_old(Nullifier::dummy(&mut rng));
+            Note::new(recipient, NoteValue::from_raw(value), rho, &mut rng)
+        });
+        let leaves = notes.map(|note| MerkleHashOrchard::from_cmx(&note.commitment().into()));
+        let nodes = [
+            MerkleHashOrchard::combine(0.into(), &leaves[0], &leaves[1]),
+            MerkleHashOrchard::combine(0.into(), &leaves[2], &EMPTY_ROOTS[0]),
+        ];
+        let auth_path = |position: usize| {
+            let mut path: [MerkleHashOrchard; MERKLE_DEPTH_ORCHARD] =
+                EMPTY_ROOTS[..MERKLE_DEPTH_ORCHARD].try_into().unwrap();
+            path[0] = [leaves[1], leaves[0], EMPTY_ROOTS[0]][position];
+            path[1] = nodes[1 - position / 2];
+            MerklePath::from_parts(position as u32, path)
+        };
+        let candidates = || {
+            (0..3)
+                .map(|i| SpendInfo::new(fvk.clone(), notes[i], auth_path(i)).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let anchor = auth_path(0).root(notes[0].commitment().into());
+
+        // An output larger than any single note is funded by the two largest notes.
+        let mut builder = Builder::new(BundleType::DEFAULT, anchor);
+        let selection = builder
+            .add_output_with_spends(
+                None,
+                recipient,
+                NoteValue::from_raw(8000),
+                [0u8; 512],
+                candidates(),
+                Some(change),
+            )
+            .unwrap();
+        assert_eq!(selection.selected(), &[1, 2]);
+        assert_eq!(selection.total(), NoteValue::from_raw(9000));
+        assert_eq!(selection.change(), NoteValue::from_raw(1000));
+        assert_eq!(selection.num_actions(), 2);
+        assert_eq!(builder.spends().len(), 2);
+        assert_eq!(builder.outputs().len(), 2);
+        assert_eq!(builder.value_balance::<i64>().unwrap(), 0);
+
+        // The same notes cannot be selected again.
+        assert_eq!(
+            builder.add_output_with_spends(
+                None,
+                recipient,
+                NoteValue::from_raw(8000),
+                [0u8; 512],
+                candidates(),
+                None,
+            ),
+            Err(SelectionError::Spend(SpendError::DuplicateNullifier(0, 2)))
+        );
+
+        // An output larger than all of the notes is rejected.
+        let mut builder = Builder::new(BundleType::DEFAULT, anchor);
+        assert_eq!(
+            builder.add_output_with_spends(
+                None,
+                recipient,
+                NoteValue::from_raw(12001),
+                [0u8; 512],
+                candidates(),
+                None,
+            ),
+            Err(SelectionError::InsufficientFunds {
+                available: 12000,
+                required: 12001,
+            })
+        );
+        assert!(builder.spends().is_empty());
+        assert!(builder.outputs().is_empty());
+    }
+
+    #[test]
+    fn auto_traits() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Builder>();
+        assert_send_sync::<super::UnauthorizedBundle<i64>>();
+        assert_send_sync::<Bundle<super::InProgress<crate::Proof, super::Unauthorized>, i64>>();
+        assert_send_sync::<Bundle<Authorized, i64>>();
+        assert_send_sync::<ProvingKey>();
+        assert_send_sync::<crate::circuit::VerifyingKey>();
+        assert_send_sync::<crate::pczt::Bundle>();
+    }
 }
@@ -1,5 +1,6 @@
 //! Structs related to bundles of Orchard actions.
 
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::vec::Vec;
 
 pub mod commitments;
@@ -13,6 +14,7 @@ use core::fmt;
 
 use blake2b_simd::Hash as Blake2bHash;
 use nonempty::NonEmpty;
+use subtle::ConstantTimeEq;
 use zcash_note_encryption::{try_note_decryption, try_output_recovery_with_ovk};
 
 #[cfg(feature = "std")]
@@ -22,12 +24,14 @@ use crate::{
     action::Action,
     address::Address,
     bundle::commitments::{hash_bundle_auth_data, hash_bundle_txid_data},
-    keys::{IncomingViewingKey, OutgoingViewingKey, PreparedIncomingViewingKey},
-    note::Note,
+    keys::{
+        FullViewingKey, IncomingViewingKey, OutgoingViewingKey, PreparedIncomingViewingKey, Scope,
+    },
+    note::{ExtractedNoteCommitment, Note, Nullifier},
     note_encryption::OrchardDomain,
     primitives::redpallas::{self, Binding, SpendAuth},
     tree::Anchor,
-    value::{ValueCommitTrapdoor, ValueCommitment, ValueSum},
+    value::derive_bvk,
     Proof,
 };
 
@@ -217,6 +221,31 @@ impl<T: Authorization, V> Bundle<T, V> {
         &self.actions
     }
 
+    /// Returns the nullifiers revealed by the actions in this bundle, in action order.
+    pub fn nullifiers(&self) -> impl Iterator<Item = &Nullifier> {
+        self.actions.iter().map(|action| action.nullifier())
+    }
+
+    /// Returns the commitments to the notes created by the actions in this bundle, in
+    /// action order.
+    pub fn commitments(&self) -> impl Iterator<Item = &ExtractedNoteCommitment> {
+        self.actions.iter().map(|action| action.cmx())
+    }
+
+    /// Returns the set of nullifiers revealed by this bundle.
+    ///
+    /// Nullifiers revealed more than once (which make the bundle invalid) appear only
+    /// once in the set; compare its length to the number of actions, or use
+    /// [`check_unique_nullifiers`], to detect this.
+    pub fn nullifier_set(&self) -> BTreeSet<Nullifier> {
+        self.nullifiers().copied().collect()
+    }
+
+    /// Returns the set of note commitments created by this bundle.
+    pub fn commitment_set(&self) -> BTreeSet<ExtractedNoteCommitment> {
+        self.commitments().copied().collect()
+    }
+
     /// Returns the Orchard-specific transaction-level flags for this bundle.
     pub fn flags(&self) -> &Flags {
         &self.flags
@@ -331,6 +360,34 @@ impl<T: Authorization, V> Bundle<T, V> {
             .collect()
     }
 
+    /// Performs trial decryption of each action in the bundle with the incoming viewing
+    /// keys of both scopes of `fvk`, and returns a vector of each decrypted note
+    /// plaintext along with the index of its action and the scope that it was sent to.
+    ///
+    /// Notes received at [`FullViewingKey::change_address`], or at any other internal
+    /// address, are reported with [`Scope::Internal`].
+    pub fn decrypt_outputs_with_fvk(
+        &self,
+        fvk: &FullViewingKey,
+    ) -> Vec<(usize, Scope, Note, Address, [u8; 512])> {
+        let ivks = [
+            (Scope::External, fvk.to_ivk(Scope::External)),
+            (Scope::Internal, fvk.to_ivk(Scope::Internal)),
+        ]
+        .map(|(scope, ivk)| (scope, PreparedIncomingViewingKey::new(&ivk)));
+        self.actions
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, action)| {
+                let domain = OrchardDomain::for_action(action);
+                ivks.iter().find_map(|(scope, ivk)| {
+                    try_note_decryption(&domain, ivk, action)
+                        .map(|(n, a, m)| (idx, *scope, n, a, m))
+                })
+            })
+            .collect()
+    }
+
     /// Performs trial decryption of the action at `action_idx` in the bundle with the
     /// specified incoming viewing key, and returns the decrypted note plaintext
     /// contents if successful.
@@ -373,6 +430,32 @@ impl<T: Authorization, V> Bundle<T, V> {
             .collect()
     }
 
+    /// Recovers the outputs of this bundle that were sent by the holder of `fvk`, such as
+    /// when rebuilding a wallet's transaction history after restoring it from a seed.
+    ///
+    /// Each action is trial-decrypted with the outgoing viewing keys of both scopes, so
+    /// this finds both payments (created with the external `ovk`) and change outputs
+    /// (created with the internal `ovk`). Returns a vector of each decrypted note
+    /// plaintext along with the index of its action, and the scope of the `ovk` that
+    /// recovered it. Outputs that were created without an `ovk` cannot be recovered.
+    pub fn recover_outputs_with_fvk(
+        &self,
+        fvk: &FullViewingKey,
+    ) -> Vec<(usize, Scope, Note, Address, [u8; 512])> {
+        let ovks = [fvk.to_ovk(Scope::External), fvk.to_ovk(Scope::Internal)];
+        self.recover_outputs_with_ovks(&ovks)
+            .into_iter()
+            .map(|(idx, ovk, note, addr, memo)| {
+                let scope = if ovk.ct_eq(&ovks[0]).into() {
+                    Scope::External
+                } else {
+                    Scope::Internal
+                };
+                (idx, scope, note, addr, memo)
+            })
+            .collect()
+    }
+
     /// Attempts to decrypt the action at the specified index with the specified
     /// outgoing viewing key, and returns the decrypted note plaintext contents
     /// if successful.
@@ -407,16 +490,10 @@ impl<T: Authorization, V: Copy + Into<i64>> Bundle<T, V> {
     /// [`Bundle::authorization`].
     pub fn binding_validating_key(&self) -> redpallas::VerificationKey<Binding> {
         // https://p.z.cash/TCR:bad-txns-orchard-binding-signature-invalid?partial
-        (self
-            .actions
-            .iter()
-            .map(|a| a.cv_net())
-            .sum::<ValueCommitment>()
-            - ValueCommitment::derive(
-                ValueSum::from_raw(self.value_balance.into()),
-                ValueCommitTrapdoor::zero(),
-            ))
-        .into_bvk()
+        derive_bvk(
+            self.actions.iter().map(|a| a.cv_net()),
+            self.value_balance.into(),
+        )
     }
 }
 
@@ -457,9 +534,27 @@ impl Authorized {
     pub fn binding_signature(&self) -> &redpallas::Signature<Binding> {
         &self.binding_signature
     }
+
+    /// Returns the encoding of the proof, as it appears in a transaction.
+    pub fn proof_bytes(&self) -> &[u8] {
+        self.proof.as_ref()
+    }
+
+    /// Returns the 64-byte encoding of the binding signature.
+    pub fn binding_signature_bytes(&self) -> [u8; 64] {
+        (&self.binding_signature).into()
+    }
 }
 
 impl<V> Bundle<Authorized, V> {
+    /// Returns the 64-byte encodings of the spend authorization signatures of this
+    /// bundle's actions, in action order.
+    pub fn spend_auth_signature_bytes(&self) -> impl Iterator<Item = [u8; 64]> + '_ {
+        self.actions
+            .iter()
+            .map(|action| action.authorization().into())
+    }
+
     /// Computes a commitment to the authorizing data within for this bundle.
     ///
     /// This together with `Bundle::commitment` bind the entire bundle.
@@ -474,6 +569,33 @@ impl<V> Bundle<Authorized, V> {
             .proof()
             .verify(vk, &self.to_instances())
     }
+
+    /// Verifies the proof for this bundle, and returns the public inputs of the action at
+    /// `index` that it attests to.
+    ///
+    /// An Orchard bundle has a single proof covering all of its actions, so the validity
+    /// of one action cannot be established independently of the others: this verifies the
+    /// whole proof, with the same cost as [`Bundle::verify_proof`]. On success, the
+    /// returned [`Instance`] is the statement proven for that action, for example to
+    /// display per-action validity in a block explorer. The spend authorization signature
+    /// of the action is not checked.
+    ///
+    /// Returns `None` if there is no action at `index`, without verifying the proof.
+    #[cfg(feature = "circuit")]
+    pub fn verify_action_membership(
+        &self,
+        vk: &VerifyingKey,
+        index: usize,
+    ) -> Option<Result<Instance, halo2_proofs::plonk::Error>> {
+        let instances = self.to_instances();
+        let instance = instances.get(index)?.clone();
+        Some(
+            self.authorization()
+                .proof()
+                .verify(vk, &instances)
+                .map(|()| instance),
+        )
+    }
 }
 
 #[cfg(feature = "std")]
@@ -502,6 +624,56 @@ impl<V: DynamicUsage> DynamicUsage for Bundle<Authorized, V> {
     }
 }
 
+/// An error indicating that two actions reveal the same nullifier.
+///
+/// The fields are the indices of the two actions, in increasing order. Such actions
+/// would spend the same note twice, and are rejected by consensus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DuplicateNullifier(pub usize, pub usize);
+
+impl fmt::Display for DuplicateNullifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Actions {} and {} reveal the same nullifier",
+            self.0, self.1
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DuplicateNullifier {}
+
+/// Returns the indices of the first two of `nullifiers` that are equal, if any.
+pub(crate) fn find_duplicate_nullifier(
+    nullifiers: impl IntoIterator<Item = Nullifier>,
+) -> Option<DuplicateNullifier> {
+    let mut seen = BTreeMap::new();
+    nullifiers.into_iter().enumerate().find_map(|(index, nf)| {
+        seen.insert(nf, index)
+            .map(|first| DuplicateNullifier(first, index))
+    })
+}
+
+/// Checks that no nullifier is revealed more than once across the given bundles, for
+/// example before combining them into a single transaction.
+///
+/// Actions are indexed in order across all of the bundles, so an index in the returned
+/// error refers to the position of the action within the concatenation of the bundles'
+/// actions.
+pub fn check_unique_nullifiers<'a, T: Authorization + 'a, V: 'a>(
+    bundles: impl IntoIterator<Item = &'a Bundle<T, V>>,
+) -> Result<(), DuplicateNullifier> {
+    match find_duplicate_nullifier(
+        bundles
+            .into_iter()
+            .flat_map(|bundle| bundle.nullifiers().copied()),
+    ) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
 /// A commitment to a bundle of actions.
 ///
 /// This commitment is non-malleable, in the sense that a bundle's commitment will only
@@ -543,7 +715,7 @@ pub mod testing {
 
     use super::{Action, Authorized, Bundle, Flags};
 
-    pub use crate::action::testing::{arb_action, arb_unauthorized_action};
+    pub use crate::action::testing::{arb_action, arb_unauthorized_action, dummy_action};
 
     /// Marker type for a bundle that contains no authorizing data.
     pub type Unauthorized = super::EffectsOnly;
@@ -1,22 +1,10 @@
-use alloc::vec::Vec;
-
 use halo2_proofs::plonk;
 use pasta_curves::vesta;
 use rand::{CryptoRng, RngCore};
 use tracing::debug;
 
 use super::{Authorized, Bundle};
-use crate::{
-    circuit::VerifyingKey,
-    primitives::redpallas::{self, Binding, SpendAuth},
-};
-
-/// A signature within an authorized Orchard bundle.
-#[derive(Debug)]
-struct BundleSignature {
-    /// The signature item for validation.
-    signature: redpallas::batch::Item<SpendAuth, Binding>,
-}
+use crate::{circuit::VerifyingKey, primitives::redpallas};
 
 /// Batch validation context for Orchard.
 ///
@@ -24,7 +12,7 @@ struct BundleSignature {
 #[derive(Debug, Default)]
 pub struct BatchValidator {
     proofs: plonk::BatchVerifier<vesta::Affine>,
-    signatures: Vec<BundleSignature>,
+    signatures: redpallas::BatchVerifier,
 }
 
 impl BatchValidator {
@@ -32,7 +20,7 @@ impl BatchValidator {
     pub fn new() -> Self {
         BatchValidator {
             proofs: plonk::BatchVerifier::new(),
-            signatures: vec![],
+            signatures: redpallas::BatchVerifier::new(),
         }
     }
 
@@ -43,18 +31,15 @@ impl BatchValidator {
         sighash: [u8; 32],
     ) {
         for action in bundle.actions().iter() {
-            self.signatures.push(BundleSignature {
-                signature: action
-                    .rk()
-                    .create_batch_item(action.authorization().clone(), &sighash),
-            });
+            self.signatures
+                .queue_spend_auth(action.rk(), &sighash, action.authorization());
         }
 
-        self.signatures.push(BundleSignature {
-            signature: bundle
-                .binding_validating_key()
-                .create_batch_item(bundle.authorization().binding_signature().clone(), &sighash),
-        });
+        self.signatures.queue_binding(
+            &bundle.binding_validating_key(),
+            &sighash,
+            bundle.authorization().binding_signature(),
+        );
 
         bundle
             .authorization()
@@ -78,12 +63,7 @@ impl BatchValidator {
             return true;
         }
 
-        let mut validator = redpallas::batch::Verifier::new();
-        for sig in self.signatures.iter() {
-            validator.queue(sig.signature.clone());
-        }
-
-        match validator.verify(rng) {
+        match self.signatures.verify(rng) {
             // If signatures are valid, check the proofs.
             Ok(()) => self.proofs.finalize(&vk.params, &vk.vk),
             Err(e) => {
@@ -1,8 +1,11 @@
 //! The Orchard Action circuit implementation.
 
 use alloc::vec::Vec;
+use core::fmt;
 
-use group::{Curve, GroupEncoding};
+use core2::io::{self, Read, Write};
+
+use group::{Curve, Group, GroupEncoding};
 use halo2_proofs::{
     circuit::{floor_planner, Layouter, Value},
     plonk::{
@@ -25,6 +28,7 @@ use self::{
 };
 use crate::{
     builder::SpendInfo,
+    bundle::Flags,
     constants::{
         OrchardCommitDomains, OrchardFixedBases, OrchardFixedBasesFull, OrchardHashDomains,
         MERKLE_DEPTH_ORCHARD,
@@ -60,23 +64,92 @@ use halo2_gadgets::{
 
 mod commit_ivk;
 pub mod gadget;
+pub mod marshal;
 mod note_commit;
 
 pub use crate::Proof;
 
-/// Size of the Orchard circuit.
-const K: u32 = 11;
+/// Size of the Orchard circuit, as the base-2 logarithm of the number of rows.
+///
+/// This is the smallest size the Action circuit fits in: the 10-bit Sinsemilla lookup
+/// table alone occupies $2^{10}$ rows, independently of the Merkle depth.
+pub const K: u32 = 11;
 
 // Absolute offsets for public inputs.
-const ANCHOR: usize = 0;
-const CV_NET_X: usize = 1;
-const CV_NET_Y: usize = 2;
-const NF_OLD: usize = 3;
-const RK_X: usize = 4;
-const RK_Y: usize = 5;
-const CMX: usize = 6;
-const ENABLE_SPEND: usize = 7;
-const ENABLE_OUTPUT: usize = 8;
+
+/// Offset of the Merkle root of the note commitment tree in the instance column.
+pub const ANCHOR: usize = 0;
+/// Offset of the $x$-coordinate of the net value commitment in the instance column.
+pub const CV_NET_X: usize = 1;
+/// Offset of the $y$-coordinate of the net value commitment in the instance column.
+pub const CV_NET_Y: usize = 2;
+/// Offset of the nullifier of the spent note in the instance column.
+pub const NF_OLD: usize = 3;
+/// Offset of the $x$-coordinate of the randomized validating key in the instance column.
+pub const RK_X: usize = 4;
+/// Offset of the $y$-coordinate of the randomized validating key in the instance column.
+pub const RK_Y: usize = 5;
+/// Offset of the extracted commitment to the output note in the instance column.
+pub const CMX: usize = 6;
+/// Offset of the `enableSpends` flag in the instance column.
+pub const ENABLE_SPEND: usize = 7;
+/// Offset of the `enableOutputs` flag in the instance column.
+pub const ENABLE_OUTPUT: usize = 8;
+
+/// A public input to the Action circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicInput {
+    /// The name of the offset constant for this input, such as `"ANCHOR"`.
+    pub name: &'static str,
+    /// The offset of this input in the instance column.
+    pub index: usize,
+    /// A short description of this input.
+    pub description: &'static str,
+}
+
+/// Returns the layout of the instance column of the Action circuit, ordered by offset.
+///
+/// This is the layout produced by [`Instance`] for the current circuit version, and is
+/// intended for external verifiers, debuggers and formal models, which would otherwise
+/// need to copy the offsets out of this crate.
+pub fn public_input_layout() -> [PublicInput; marshal::NUM_PUBLIC_INPUTS] {
+    let input = |name, index, description| PublicInput {
+        name,
+        index,
+        description,
+    };
+    [
+        input("ANCHOR", ANCHOR, "Root of the note commitment tree"),
+        input(
+            "CV_NET_X",
+            CV_NET_X,
+            "x-coordinate of the net value commitment",
+        ),
+        input(
+            "CV_NET_Y",
+            CV_NET_Y,
+            "y-coordinate of the net value commitment",
+        ),
+        input("NF_OLD", NF_OLD, "Nullifier of the spent note"),
+        input(
+            "RK_X",
+            RK_X,
+            "x-coordinate of the randomized validating key",
+        ),
+        input(
+            "RK_Y",
+            RK_Y,
+            "y-coordinate of the randomized validating key",
+        ),
+        input("CMX", CMX, "Extracted commitment to the output note"),
+        input("ENABLE_SPEND", ENABLE_SPEND, "Whether spends are enabled"),
+        input(
+            "ENABLE_OUTPUT",
+            ENABLE_OUTPUT,
+            "Whether outputs are enabled",
+        ),
+    ]
+}
 
 /// Configuration needed to use the Orchard Action circuit.
 #[derive(Clone, Debug)]
@@ -757,6 +830,9 @@ impl plonk::Circuit<pallas::Base> for Circuit {
 }
 
 /// The verifying key for the Orchard Action circuit.
+///
+/// Building this key is expensive. It is `Send + Sync`, so a single instance can be
+/// shared between verification threads (for example behind an `Arc`).
 #[derive(Debug)]
 pub struct VerifyingKey {
     pub(crate) params: halo2_proofs::poly::commitment::Params<vesta::Affine>,
@@ -773,9 +849,26 @@ impl VerifyingKey {
 
         VerifyingKey { params, vk }
     }
+
+    /// Returns the commitment scheme parameters used with this verifying key.
+    pub fn params(&self) -> &halo2_proofs::poly::commitment::Params<vesta::Affine> {
+        &self.params
+    }
+
+    /// Returns the underlying halo2 verifying key.
+    ///
+    /// This is useful for callers that construct their own verifiers (for example, when
+    /// combining the Orchard circuit with other circuits), or that need to export the
+    /// key for use in another environment.
+    pub fn inner(&self) -> &plonk::VerifyingKey<vesta::Affine> {
+        &self.vk
+    }
 }
 
 /// The proving key for the Orchard Action circuit.
+///
+/// Like [`VerifyingKey`], this is `Send + Sync` and can be shared between threads that
+/// create proofs concurrently.
 #[derive(Debug)]
 pub struct ProvingKey {
     params: halo2_proofs::poly::commitment::Params<vesta::Affine>,
@@ -795,6 +888,12 @@ impl ProvingKey {
     }
 }
 
+/// The version of the [`Instance`] encoding produced by [`Instance::write`].
+pub const INSTANCE_ENCODING_VERSION: u8 = 1;
+
+/// The length in bytes of the [`Instance`] encoding produced by [`Instance::write`].
+pub const INSTANCE_ENCODING_SIZE: usize = 1 + 5 * 32 + 1;
+
 /// Public inputs to the Orchard Action circuit.
 #[derive(Clone, Debug)]
 pub struct Instance {
@@ -835,8 +934,102 @@ impl Instance {
         }
     }
 
-    fn to_halo2_instance(&self) -> [[vesta::Scalar; 9]; 1] {
-        let mut instance = [vesta::Scalar::zero(); 9];
+    /// Returns a copy of this instance with the given flags.
+    ///
+    /// The flags are the last two public inputs to the Action circuit. When
+    /// `enable_spend` is `false`, the circuit requires the spent note to have zero value;
+    /// when `enable_output` is `false`, it requires the output note to have zero value.
+    /// For an action in a bundle they are taken from the bundle's [`Flags`], but a
+    /// verification pipeline can use this method to check whether a proof would also be
+    /// valid under other flag values.
+    ///
+    /// [`Flags`]: crate::bundle::Flags
+    pub fn with_flags(&self, enable_spend: bool, enable_output: bool) -> Self {
+        Instance {
+            enable_spend,
+            enable_output,
+            ..self.clone()
+        }
+    }
+
+    /// Serializes this instance.
+    ///
+    /// The encoding is [`INSTANCE_ENCODING_SIZE`] bytes long, and consists of:
+    /// - the version byte [`INSTANCE_ENCODING_VERSION`];
+    /// - the 32-byte encodings of `anchor`, `cv_net`, `nf_old`, `rk` and `cmx`, in that
+    ///   order, as they appear in an Orchard action description;
+    /// - a flags byte, encoded as in [`Flags::to_byte`].
+    ///
+    /// [`Flags::to_byte`]: crate::bundle::Flags::to_byte
+    pub fn to_bytes(&self) -> [u8; INSTANCE_ENCODING_SIZE] {
+        let mut result = [0u8; INSTANCE_ENCODING_SIZE];
+        result[0] = INSTANCE_ENCODING_VERSION;
+        result[1..33].copy_from_slice(&self.anchor.to_bytes());
+        result[33..65].copy_from_slice(&self.cv_net.to_bytes());
+        result[65..97].copy_from_slice(&self.nf_old.to_bytes());
+        result[97..129].copy_from_slice(&<[u8; 32]>::from(&self.rk));
+        result[129..161].copy_from_slice(&self.cmx.to_bytes());
+        result[161] = Flags::from_parts(self.enable_spend, self.enable_output).to_byte();
+        result
+    }
+
+    /// Writes the encoding of this instance described in [`Instance::to_bytes`].
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    /// Parses an instance from the encoding described in [`Instance::to_bytes`].
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut data = [0u8; INSTANCE_ENCODING_SIZE];
+        reader.read_exact(&mut data)?;
+
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let field =
+            |i: usize| -> [u8; 32] { data[1 + 32 * i..1 + 32 * (i + 1)].try_into().unwrap() };
+
+        if data[0] != INSTANCE_ENCODING_VERSION {
+            return Err(invalid("Unsupported Orchard instance encoding version"));
+        }
+
+        let anchor = Option::from(Anchor::from_bytes(field(0)))
+            .ok_or_else(|| invalid("Invalid anchor in Orchard instance"))?;
+        let cv_net = Option::from(ValueCommitment::from_bytes(&field(1)))
+            .ok_or_else(|| invalid("Invalid cv_net in Orchard instance"))?;
+        let nf_old = Option::from(Nullifier::from_bytes(&field(2)))
+            .ok_or_else(|| invalid("Invalid nullifier in Orchard instance"))?;
+        // `rk` must be a non-identity point for the instance to be usable.
+        let rk = VerificationKey::try_from(field(3))
+            .ok()
+            .filter(|rk| <[u8; 32]>::from(rk) != pallas::Point::identity().to_bytes())
+            .ok_or_else(|| invalid("Invalid rk in Orchard instance"))?;
+        let cmx = Option::from(ExtractedNoteCommitment::from_bytes(&field(4)))
+            .ok_or_else(|| invalid("Invalid cmx in Orchard instance"))?;
+        let flags = Flags::from_byte(data[161])
+            .ok_or_else(|| invalid("Invalid flags in Orchard instance"))?;
+
+        Ok(Instance {
+            anchor,
+            cv_net,
+            nf_old,
+            rk,
+            cmx,
+            enable_spend: flags.spends_enabled(),
+            enable_output: flags.outputs_enabled(),
+        })
+    }
+
+    /// Converts this instance into the field elements that are passed to the verifier.
+    ///
+    /// This decompresses `rk`, which dominates the cost of instance conversion. Callers
+    /// that verify the same proof more than once (for example, when revalidating mempool
+    /// transactions) can prepare their instances once and then use
+    /// [`Proof::verify_prepared`].
+    pub fn prepare(&self) -> PreparedInstance {
+        PreparedInstance(self.to_halo2_instance())
+    }
+
+    fn to_halo2_instance(&self) -> [[vesta::Scalar; marshal::NUM_PUBLIC_INPUTS]; 1] {
+        let mut instance = [vesta::Scalar::zero(); marshal::NUM_PUBLIC_INPUTS];
 
         instance[ANCHOR] = self.anchor.inner();
         instance[CV_NET_X] = self.cv_net.x();
@@ -859,6 +1052,13 @@ impl Instance {
     }
 }
 
+/// Public inputs to the Orchard Action circuit, converted to the form used by the
+/// verifier.
+///
+/// Obtained via [`Instance::prepare`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreparedInstance([[vesta::Scalar; marshal::NUM_PUBLIC_INPUTS]; 1]);
+
 impl Proof {
     /// Creates a proof for the given circuits and instances.
     pub fn create(
@@ -888,10 +1088,19 @@ impl Proof {
 
     /// Verifies this proof with the given instances.
     pub fn verify(&self, vk: &VerifyingKey, instances: &[Instance]) -> Result<(), plonk::Error> {
-        let instances: Vec<_> = instances.iter().map(|i| i.to_halo2_instance()).collect();
+        let instances: Vec<_> = instances.iter().map(|i| i.prepare()).collect();
+        self.verify_prepared(vk, &instances)
+    }
+
+    /// Verifies this proof with the given prepared instances.
+    pub fn verify_prepared(
+        &self,
+        vk: &VerifyingKey,
+        instances: &[PreparedInstance],
+    ) -> Result<(), plonk::Error> {
         let instances: Vec<Vec<_>> = instances
             .iter()
-            .map(|i| i.iter().map(|c| &c[..]).collect())
+            .map(|i| i.0.iter().map(|c| &c[..]).collect())
             .collect();
         let instances: Vec<_> = instances.iter().map(|i| &i[..]).collect();
 
@@ -900,6 +1109,40 @@ impl Proof {
         plonk::verify_proof(&vk.params, &vk.vk, strategy, &instances, &mut transcript)
     }
 
+    /// Verifies this proof with the given instances, first checking that the request
+    /// is within `limits`.
+    ///
+    /// The checks on `limits` and on the proof length are performed before any
+    /// cryptographic work, so that requests that are too large or obviously malformed
+    /// are rejected cheaply, at a cost that does not depend on the size of the request.
+    /// See [`VerificationLimits`] for the cost of the verification itself.
+    pub fn verify_with_limits(
+        &self,
+        vk: &VerifyingKey,
+        instances: &[Instance],
+        limits: &VerificationLimits,
+    ) -> Result<(), VerificationError> {
+        if instances.is_empty() {
+            return Err(VerificationError::NoInstances);
+        }
+        if instances.len() > limits.max_instances {
+            return Err(VerificationError::TooManyInstances {
+                limit: limits.max_instances,
+                actual: instances.len(),
+            });
+        }
+        let expected = proof_size(instances.len());
+        if self.0.len() != expected {
+            return Err(VerificationError::MalformedProof {
+                expected,
+                actual: self.0.len(),
+            });
+        }
+
+        self.verify(vk, instances)
+            .map_err(VerificationError::Invalid)
+    }
+
     /// Adds this proof to the given batch for verification with the given instances.
     ///
     /// Use this API if you want more control over how proof batches are processed. If you
@@ -907,31 +1150,176 @@ impl Proof {
     ///
     /// [`bundle::BatchValidator`]: crate::bundle::BatchValidator
     pub fn add_to_batch(&self, batch: &mut BatchVerifier<vesta::Affine>, instances: Vec<Instance>) {
+        self.add_prepared_to_batch(batch, instances.iter().map(|i| i.prepare()).collect())
+    }
+
+    /// Adds this proof to the given batch for verification with the given prepared
+    /// instances.
+    pub fn add_prepared_to_batch(
+        &self,
+        batch: &mut BatchVerifier<vesta::Affine>,
+        instances: Vec<PreparedInstance>,
+    ) {
         let instances = instances
-            .iter()
-            .map(|i| {
-                i.to_halo2_instance()
-                    .into_iter()
-                    .map(|c| c.into_iter().collect())
-                    .collect()
-            })
+            .into_iter()
+            .map(|i| i.0.into_iter().map(|c| c.into_iter().collect()).collect())
             .collect();
 
         batch.add_proof(instances, self.0.clone());
     }
 }
 
+/// The size in bytes of a proof for zero Actions, excluding the per-Action terms.
+pub const PROOF_SIZE_BASE: usize = 2720;
+
+/// The number of bytes that each Action adds to the size of a proof.
+pub const PROOF_SIZE_PER_ACTION: usize = 2272;
+
+/// Returns the size in bytes of a valid proof for `num_actions` Actions.
+pub fn proof_size(num_actions: usize) -> usize {
+    PROOF_SIZE_BASE + PROOF_SIZE_PER_ACTION * num_actions
+}
+
+/// Bounds on the work that [`Proof::verify_with_limits`] is willing to perform.
+///
+/// Verifying a proof for $n$ Actions costs a fixed multiscalar multiplication of size
+/// $2^K = 2048$ for the polynomial commitment opening, plus work linear in $n$: the
+/// verifier evaluates the circuit's constraints and absorbs the commitments for each
+/// Action's instance, and reads [`PROOF_SIZE_PER_ACTION`] bytes of proof per Action.
+/// Bounding $n$ therefore bounds both the time and the memory needed to verify a
+/// request, and a proof whose length differs from [`proof_size`] can be rejected
+/// without doing any of this work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerificationLimits {
+    max_instances: usize,
+}
+
+impl VerificationLimits {
+    /// Constructs limits that accept proofs for up to `max_instances` Actions.
+    pub const fn new(max_instances: usize) -> Self {
+        VerificationLimits { max_instances }
+    }
+
+    /// Returns the maximum number of Actions accepted in a single proof.
+    pub fn max_instances(&self) -> usize {
+        self.max_instances
+    }
+}
+
+/// Errors that can occur in [`Proof::verify_with_limits`].
+#[derive(Debug)]
+pub enum VerificationError {
+    /// No instances were provided.
+    NoInstances,
+    /// More instances were provided than the limits allow.
+    TooManyInstances {
+        /// The maximum number of instances allowed.
+        limit: usize,
+        /// The number of instances provided.
+        actual: usize,
+    },
+    /// The proof does not have the length of a proof for the provided instances.
+    MalformedProof {
+        /// The expected length of the proof, in bytes.
+        expected: usize,
+        /// The actual length of the proof, in bytes.
+        actual: usize,
+    },
+    /// The proof is not valid for the provided instances.
+    Invalid(plonk::Error),
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::NoInstances => write!(f, "No instances provided"),
+            VerificationError::TooManyInstances { limit, actual } => write!(
+                f,
+                "Too many instances: {} provided, at most {} allowed",
+                actual, limit
+            ),
+            VerificationError::MalformedProof { expected, actual } => write!(
+                f,
+                "Proof has length {} bytes, expected {} bytes",
+                actual, expected
+            ),
+            VerificationError::Invalid(e) => write!(f, "Proof is invalid: {:?}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerificationError {}
+
+/// Test utilities for downstream crates that exercise the Action circuit.
+///
+/// The Action circuit cannot be instantiated with a smaller size or a shallower Merkle
+/// tree for tests. [`K`] is already the smallest size that fits the Sinsemilla lookup
+/// table, and a circuit with a different Merkle depth would check a different statement
+/// than the one that is proven on chain. Instead, the helpers here amortize the cost of
+/// the full circuit: keys are built at most once per process, and [`check_circuit`]
+/// avoids key generation entirely.
+#[cfg(any(test, feature = "test-dependencies"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-dependencies")))]
+pub mod testing {
+    use alloc::vec::Vec;
+
+    use halo2_proofs::dev::{MockProver, VerifyFailure};
+    use lazy_static::lazy_static;
+
+    use super::{Circuit, Instance, ProvingKey, VerifyingKey, K};
+
+    lazy_static! {
+        static ref PROVING_KEY: ProvingKey = ProvingKey::build();
+        static ref VERIFYING_KEY: VerifyingKey = VerifyingKey::build();
+    }
+
+    /// Returns a proving key that is built once and shared by every caller in this
+    /// process, so that test suites only pay the key generation cost once.
+    pub fn proving_key() -> &'static ProvingKey {
+        &PROVING_KEY
+    }
+
+    /// Returns a verifying key that is built once and shared by every caller in this
+    /// process.
+    pub fn verifying_key() -> &'static VerifyingKey {
+        &VERIFYING_KEY
+    }
+
+    /// Checks that `circuit` is satisfied for the public inputs in `instance`, using
+    /// [`MockProver`].
+    ///
+    /// This does not require key generation or proof creation, and is sufficient for
+    /// tests that only need to confirm that a witness is consistent with its instance.
+    pub fn check_circuit(circuit: &Circuit, instance: &Instance) -> Result<(), Vec<VerifyFailure>> {
+        MockProver::run(
+            K,
+            circuit,
+            instance
+                .to_halo2_instance()
+                .iter()
+                .map(|p| p.to_vec())
+                .collect(),
+        )
+        .expect("the Action circuit fits in 2^K rows")
+        .verify()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use alloc::vec::Vec;
     use core::iter;
 
     use ff::Field;
-    use halo2_proofs::{circuit::Value, dev::MockProver};
+    use halo2_proofs::circuit::Value;
     use pasta_curves::pallas;
     use rand::{rngs::OsRng, RngCore};
 
-    use super::{Circuit, Instance, Proof, ProvingKey, VerifyingKey, K};
+    use super::{
+        proof_size, Circuit, Instance, Proof, ProvingKey, VerificationError, VerificationLimits,
+        VerifyingKey, INSTANCE_ENCODING_SIZE, INSTANCE_ENCODING_VERSION, K,
+    };
     use crate::{
         keys::SpendValidatingKey,
         note::{Note, Rho},
@@ -939,7 +1327,7 @@ mod tests {
         value::{ValueCommitTrapdoor, ValueCommitment},
     };
 
-    fn generate_circuit_instance<R: RngCore>(mut rng: R) -> (Circuit, Instance) {
+    pub(super) fn generate_circuit_instance<R: RngCore>(mut rng: R) -> (Circuit, Instance) {
         let (_, fvk, spent_note) = Note::dummy(&mut rng, None);
 
         let sender_address = spent_note.recipient();
@@ -1025,32 +1413,97 @@ mod tests {
                 );
             assert_eq!(usize::from(circuit_cost.proof_size(1)), 4992);
             assert_eq!(usize::from(circuit_cost.proof_size(2)), 7264);
+            assert_eq!(usize::from(circuit_cost.proof_size(1)), proof_size(1));
+            assert_eq!(usize::from(circuit_cost.proof_size(2)), proof_size(2));
             usize::from(circuit_cost.proof_size(instances.len()))
         };
 
         for (circuit, instance) in circuits.iter().zip(instances.iter()) {
-            assert_eq!(
-                MockProver::run(
-                    K,
-                    circuit,
-                    instance
-                        .to_halo2_instance()
-                        .iter()
-                        .map(|p| p.to_vec())
-                        .collect()
-                )
-                .unwrap()
-                .verify(),
-                Ok(())
-            );
+            assert_eq!(super::testing::check_circuit(circuit, instance), Ok(()));
         }
 
         let pk = ProvingKey::build();
         let proof = Proof::create(&pk, &circuits, &instances, &mut rng).unwrap();
         assert!(proof.verify(&vk, &instances).is_ok());
+        let prepared: Vec<_> = instances.iter().map(|i| i.prepare()).collect();
+        assert!(proof.verify_prepared(&vk, &prepared).is_ok());
+
+        // The notes in these actions have zero value, so the proof is also valid with
+        // spends and outputs disabled.
+        let disabled: Vec<_> = instances
+            .iter()
+            .map(|i| i.with_flags(false, false))
+            .collect();
+        assert!(proof.verify(&vk, &disabled).is_ok());
+
+        let limits = VerificationLimits::new(instances.len());
+        assert!(proof.verify_with_limits(&vk, &instances, &limits).is_ok());
+        assert!(matches!(
+            proof.verify_with_limits(&vk, &instances, &VerificationLimits::new(0)),
+            Err(VerificationError::TooManyInstances { .. })
+        ));
+        assert!(matches!(
+            proof.verify_with_limits(&vk, &[], &limits),
+            Err(VerificationError::NoInstances)
+        ));
+        assert!(matches!(
+            Proof::new(proof.0[1..].to_vec()).verify_with_limits(&vk, &instances, &limits),
+            Err(VerificationError::MalformedProof { .. })
+        ));
+
+        let mut inputs = super::marshal::VerificationInputs::new();
+        inputs.push(&proof, &instances);
+        assert!(inputs.verify(&vk).is_ok());
         assert_eq!(proof.0.len(), expected_proof_size);
     }
 
+    #[test]
+    fn instance_encoding() {
+        let mut rng = OsRng;
+        let (_, instance) = generate_circuit_instance(&mut rng);
+        let instance = instance.with_flags(true, false);
+
+        let encoded = instance.to_bytes();
+        let mut written = vec![];
+        instance.write(&mut written).unwrap();
+        assert_eq!(&written[..], &encoded[..]);
+
+        let decoded = Instance::read(&encoded[..]).unwrap();
+        assert_eq!(decoded.prepare(), instance.prepare());
+        assert_eq!(decoded.to_bytes(), encoded);
+
+        // Unknown versions and flag bits are rejected.
+        let mut bad_version = encoded;
+        bad_version[0] = INSTANCE_ENCODING_VERSION + 1;
+        assert!(Instance::read(&bad_version[..]).is_err());
+        let mut bad_flags = encoded;
+        bad_flags[INSTANCE_ENCODING_SIZE - 1] = 0x80;
+        assert!(Instance::read(&bad_flags[..]).is_err());
+
+        // rk may not be the identity.
+        let mut identity_rk = encoded;
+        identity_rk[1 + 32 * 3..1 + 32 * 4].fill(0);
+        assert!(Instance::read(&identity_rk[..]).is_err());
+    }
+
+    #[test]
+    fn public_input_layout() {
+        let layout = super::public_input_layout();
+        for (i, input) in layout.iter().enumerate() {
+            assert_eq!(input.index, i);
+        }
+
+        let mut rng = OsRng;
+        let (_, instance) = generate_circuit_instance(&mut rng);
+        let instance = instance.with_flags(false, true);
+        let column = instance.to_halo2_instance()[0];
+        assert_eq!(column[super::ANCHOR], instance.anchor.inner());
+        assert_eq!(column[super::NF_OLD], instance.nf_old.0);
+        assert_eq!(column[super::CMX], instance.cmx.inner());
+        assert_eq!(column[super::ENABLE_SPEND], pallas::Base::zero());
+        assert_eq!(column[super::ENABLE_OUTPUT], pallas::Base::one());
+    }
+
     #[test]
     fn serialized_proof_test_case() {
         use std::io::{Read, Write};
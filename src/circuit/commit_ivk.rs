@@ -15,19 +15,26 @@ use halo2_gadgets::{
     utilities::{bool_check, RangeConstrained},
 };
 
+/// Configuration for the canonicity gate used by [`gadgets::commit_ivk`].
 #[derive(Clone, Debug)]
 pub struct CommitIvkConfig {
     q_commit_ivk: Selector,
     advices: [Column<Advice>; 10],
 }
 
+/// Chip enforcing the decomposition and canonicity of the message in
+/// [`gadgets::commit_ivk`] and [`gadgets::short_commit_base_pair`].
 #[derive(Clone, Debug)]
 pub struct CommitIvkChip {
     config: CommitIvkConfig,
 }
 
 impl CommitIvkChip {
-    pub(in crate::circuit) fn configure(
+    /// Configures the canonicity gate.
+    ///
+    /// `advices` must be the same columns as those given to the ECC chip, and must have
+    /// equality enabled.
+    pub fn configure(
         meta: &mut ConstraintSystem<pallas::Base>,
         advices: [Column<Advice>; 10],
     ) -> CommitIvkConfig {
@@ -222,7 +229,8 @@ impl CommitIvkChip {
         config
     }
 
-    pub(in crate::circuit) fn construct(config: CommitIvkConfig) -> Self {
+    /// Constructs a chip from its configuration.
+    pub fn construct(config: CommitIvkConfig) -> Self {
         Self { config }
     }
 }
@@ -232,6 +240,10 @@ pub(in crate::circuit) mod gadgets {
         lookup_range_check::{LookupRangeCheck, LookupRangeCheckConfig},
         RangeConstrained,
     };
+    use halo2_gadgets::{
+        ecc::chip::FixedPoints,
+        sinsemilla::{CommitDomains, HashDomains},
+    };
     use halo2_proofs::circuit::Chip;
 
     use super::*;
@@ -239,9 +251,8 @@ pub(in crate::circuit) mod gadgets {
     /// `Commit^ivk` from [Section 5.4.8.4 Sinsemilla commitments].
     ///
     /// [Section 5.4.8.4 Sinsemilla commitments]: https://zips.z.cash/protocol/protocol.pdf#concretesinsemillacommit
-    #[allow(non_snake_case)]
     #[allow(clippy::type_complexity)]
-    pub(in crate::circuit) fn commit_ivk(
+    pub fn commit_ivk(
         sinsemilla_chip: SinsemillaChip<
             OrchardHashDomains,
             OrchardCommitDomains,
@@ -249,11 +260,52 @@ pub(in crate::circuit) mod gadgets {
         >,
         ecc_chip: EccChip<OrchardFixedBases>,
         commit_ivk_chip: CommitIvkChip,
-        mut layouter: impl Layouter<pallas::Base>,
+        layouter: impl Layouter<pallas::Base>,
         ak: AssignedCell<pallas::Base, pallas::Base>,
         nk: AssignedCell<pallas::Base, pallas::Base>,
         rivk: ScalarFixed<pallas::Affine, EccChip<OrchardFixedBases>>,
     ) -> Result<X<pallas::Affine, EccChip<OrchardFixedBases>>, Error> {
+        short_commit_base_pair(
+            sinsemilla_chip,
+            ecc_chip,
+            commit_ivk_chip,
+            layouter,
+            &OrchardCommitDomains::CommitIvk,
+            ak,
+            nk,
+            rivk,
+        )
+    }
+
+    /// Computes $\mathsf{SinsemillaShortCommit}_r(D, \mathsf{I2LEBSP}_{255}(x) \,||\,
+    /// \mathsf{I2LEBSP}_{255}(y))$ for an arbitrary commitment domain $D$.
+    ///
+    /// This has the same message decomposition and canonicity checks as [`commit_ivk`],
+    /// which is this gadget instantiated with the Orchard `CommitIvk` domain. Circuits that
+    /// commit to a different pair of base field elements can reuse it by supplying their
+    /// own `domain`, along with a Sinsemilla chip and ECC chip whose fixed bases include
+    /// that domain's $Q$ and $R$.
+    ///
+    /// The assignment uses the same gate as `commit_ivk`, so `commit_ivk_chip` can be
+    /// shared between commitments in different domains.
+    #[allow(non_snake_case)]
+    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::type_complexity)]
+    pub fn short_commit_base_pair<Hash, Commit, Fixed>(
+        sinsemilla_chip: SinsemillaChip<Hash, Commit, Fixed>,
+        ecc_chip: EccChip<Fixed>,
+        commit_ivk_chip: CommitIvkChip,
+        mut layouter: impl Layouter<pallas::Base>,
+        domain: &Commit,
+        ak: AssignedCell<pallas::Base, pallas::Base>,
+        nk: AssignedCell<pallas::Base, pallas::Base>,
+        rivk: ScalarFixed<pallas::Affine, EccChip<Fixed>>,
+    ) -> Result<X<pallas::Affine, EccChip<Fixed>>, Error>
+    where
+        Hash: HashDomains<pallas::Affine>,
+        Fixed: FixedPoints<pallas::Affine>,
+        Commit: CommitDomains<pallas::Affine, Fixed, Hash>,
+    {
         let lookup_config = sinsemilla_chip.config().lookup_config();
 
         // We need to hash `ak || nk` where each of `ak`, `nk` is a field element (255 bits).
@@ -345,8 +397,7 @@ pub(in crate::circuit) mod gadgets {
                 sinsemilla_chip.clone(),
                 vec![a.clone(), b.clone(), c.clone(), d.clone()],
             );
-            let domain =
-                CommitDomain::new(sinsemilla_chip, ecc_chip, &OrchardCommitDomains::CommitIvk);
+            let domain = CommitDomain::new(sinsemilla_chip, ecc_chip, domain);
             domain.short_commit(layouter.namespace(|| "Hash ak||nk"), message, rivk)?
         };
 
@@ -668,8 +719,8 @@ mod tests {
 
     use super::{gadgets, CommitIvkChip, CommitIvkConfig};
     use crate::constants::{
-        fixed_bases::COMMIT_IVK_PERSONALIZATION, OrchardCommitDomains, OrchardFixedBases,
-        OrchardHashDomains, L_ORCHARD_BASE, T_Q,
+        fixed_bases::{COMMIT_IVK_PERSONALIZATION, NOTE_COMMITMENT_PERSONALIZATION},
+        OrchardCommitDomains, OrchardFixedBases, OrchardHashDomains, L_ORCHARD_BASE, T_Q,
     };
     use group::ff::{Field, PrimeField, PrimeFieldBits};
     use halo2_gadgets::{
@@ -700,6 +751,8 @@ mod tests {
         struct MyCircuit {
             ak: Value<pallas::Base>,
             nk: Value<pallas::Base>,
+            // Commit in the NoteCommit domain instead of the CommitIvk domain.
+            note_commit_domain: bool,
         }
 
         impl UtilitiesInstructions<pallas::Base> for MyCircuit {
@@ -823,22 +876,37 @@ mod tests {
                     Value::known(rivk),
                 )?;
 
-                let ivk = gadgets::commit_ivk(
-                    sinsemilla_chip,
-                    ecc_chip,
-                    commit_ivk_chip,
-                    layouter.namespace(|| "CommitIvk"),
-                    ak,
-                    nk,
-                    rivk_gadget,
-                )?;
+                let (ivk, personalization) = if self.note_commit_domain {
+                    let ivk = gadgets::short_commit_base_pair(
+                        sinsemilla_chip,
+                        ecc_chip,
+                        commit_ivk_chip,
+                        layouter.namespace(|| "NoteCommit(ak || nk)"),
+                        &OrchardCommitDomains::NoteCommit,
+                        ak,
+                        nk,
+                        rivk_gadget,
+                    )?;
+                    (ivk, NOTE_COMMITMENT_PERSONALIZATION)
+                } else {
+                    let ivk = gadgets::commit_ivk(
+                        sinsemilla_chip,
+                        ecc_chip,
+                        commit_ivk_chip,
+                        layouter.namespace(|| "CommitIvk"),
+                        ak,
+                        nk,
+                        rivk_gadget,
+                    )?;
+                    (ivk, COMMIT_IVK_PERSONALIZATION)
+                };
 
                 self.ak
                     .zip(self.nk)
                     .zip(ivk.inner().value())
                     .assert_if_known(|((ak, nk), ivk)| {
                         let expected_ivk = {
-                            let domain = CommitDomain::new(COMMIT_IVK_PERSONALIZATION);
+                            let domain = CommitDomain::new(personalization);
                             // Hash ak || nk
                             domain
                                 .short_commit(
@@ -868,36 +936,43 @@ mod tests {
             MyCircuit {
                 ak: Value::known(pallas::Base::zero()),
                 nk: Value::known(pallas::Base::zero()),
+                note_commit_domain: false,
             },
             // `ak` = T_Q - 1, `nk` = T_Q - 1
             MyCircuit {
                 ak: Value::known(pallas::Base::from_u128(T_Q - 1)),
                 nk: Value::known(pallas::Base::from_u128(T_Q - 1)),
+                note_commit_domain: false,
             },
             // `ak` = T_Q, `nk` = T_Q
             MyCircuit {
                 ak: Value::known(pallas::Base::from_u128(T_Q)),
                 nk: Value::known(pallas::Base::from_u128(T_Q)),
+                note_commit_domain: false,
             },
             // `ak` = 2^127 - 1, `nk` = 2^127 - 1
             MyCircuit {
                 ak: Value::known(pallas::Base::from_u128((1 << 127) - 1)),
                 nk: Value::known(pallas::Base::from_u128((1 << 127) - 1)),
+                note_commit_domain: false,
             },
             // `ak` = 2^127, `nk` = 2^127
             MyCircuit {
                 ak: Value::known(pallas::Base::from_u128(1 << 127)),
                 nk: Value::known(pallas::Base::from_u128(1 << 127)),
+                note_commit_domain: false,
             },
             // `ak` = 2^254 - 1, `nk` = 2^254 - 1
             MyCircuit {
                 ak: Value::known(two_pow_254 - pallas::Base::one()),
                 nk: Value::known(two_pow_254 - pallas::Base::one()),
+                note_commit_domain: false,
             },
             // `ak` = 2^254, `nk` = 2^254
             MyCircuit {
                 ak: Value::known(two_pow_254),
                 nk: Value::known(two_pow_254),
+                note_commit_domain: false,
             },
         ];
 
@@ -905,5 +980,15 @@ mod tests {
             let prover = MockProver::<pallas::Base>::run(11, circuit, vec![]).unwrap();
             assert_eq!(prover.verify(), Ok(()));
         }
+
+        // The same decomposition works in another commitment domain.
+        for circuit in circuits {
+            let circuit = MyCircuit {
+                note_commit_domain: true,
+                ..circuit
+            };
+            let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
     }
 }
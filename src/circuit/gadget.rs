@@ -3,11 +3,7 @@
 use ff::Field;
 use pasta_curves::pallas;
 
-use super::{commit_ivk::CommitIvkChip, note_commit::NoteCommitChip};
-use crate::constants::{
-    NullifierK, OrchardCommitDomains, OrchardFixedBases, OrchardFixedBasesFull, OrchardHashDomains,
-    ValueCommitV,
-};
+use crate::constants::{NullifierK, OrchardFixedBasesFull, ValueCommitV};
 use halo2_gadgets::{
     ecc::{
         chip::EccChip, EccInstructions, FixedPoint, FixedPointBaseField, FixedPointShort, Point,
@@ -24,7 +20,50 @@ use halo2_proofs::{
     plonk::{self, Advice, Assigned, Column},
 };
 
-pub(in crate::circuit) mod add_chip;
+pub mod add_chip;
+
+#[cfg(any(test, feature = "gadgets"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gadgets")))]
+pub mod cond_swap;
+#[cfg(any(test, feature = "gadgets"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gadgets")))]
+pub mod fixed_bases;
+#[cfg(any(test, feature = "gadgets"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gadgets")))]
+pub mod lookup_running_sum;
+#[cfg(any(test, feature = "gadgets"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gadgets")))]
+pub mod merkle;
+#[cfg(any(test, feature = "gadgets"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gadgets")))]
+pub mod message_piece;
+#[cfg(any(test, feature = "gadgets"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gadgets")))]
+pub mod nullifier;
+#[cfg(any(test, feature = "gadgets"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gadgets")))]
+pub mod range_check;
+#[cfg(any(test, feature = "gadgets"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "gadgets")))]
+pub mod u64_add;
+
+/// General-purpose utility gadgets used by the Orchard circuit: running sum
+/// decomposition, lookup range checks, conditional swap, and helpers for boolean and
+/// range constraints.
+///
+/// These are re-exported from [`halo2_gadgets`] so that circuit authors can reuse them
+/// at exactly the version this crate is built against.
+#[cfg(feature = "gadgets")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gadgets")))]
+pub mod utilities {
+    pub use super::lookup_running_sum;
+    pub use halo2_gadgets::utilities::*;
+}
+
+pub use crate::constants::{OrchardCommitDomains, OrchardFixedBases, OrchardHashDomains};
+
+/// The ECC chip used by the Orchard circuit, over the Orchard fixed bases.
+pub type OrchardEccChip = EccChip<OrchardFixedBases>;
 
 impl super::Config {
     pub(super) fn add_chip(&self) -> add_chip::AddChip {
@@ -77,14 +116,30 @@ impl super::Config {
 }
 
 /// An instruction set for adding two circuit words (field elements).
-pub(in crate::circuit) trait AddInstruction<F: Field>: Chip<F> {
-    /// Constraints `a + b` and returns the sum.
+pub trait AddInstruction<F: Field>: Chip<F> {
+    /// Constrains `a + b` and returns the sum.
     fn add(
         &self,
         layouter: impl Layouter<F>,
         a: &AssignedCell<F, F>,
         b: &AssignedCell<F, F>,
     ) -> Result<AssignedCell<F, F>, plonk::Error>;
+
+    /// Constrains `a - b` and returns the difference.
+    fn sub(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, plonk::Error>;
+
+    /// Constrains `a + constant` and returns the sum.
+    fn add_constant(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        constant: F,
+    ) -> Result<AssignedCell<F, F>, plonk::Error>;
 }
 
 /// Witnesses the given value in a standalone region.
@@ -143,9 +198,21 @@ pub(in crate::circuit) fn value_commit_orchard<
 
 /// `DeriveNullifier` from [Section 4.16: Note Commitments and Nullifiers].
 ///
+/// Computes $\mathsf{nf} = \mathsf{Extract}_\mathbb{P}\big([(\mathsf{PoseidonHash}(\mathsf{nk},
+/// \rho) + \psi) \bmod q_\mathbb{P}] \mathcal{K}^\mathsf{Orchard} + \mathsf{cm}\big)$.
+///
+/// This is the gadget used by the Action circuit, and may be used by other circuits that
+/// need to prove knowledge of the nullifier of a note, for example to link a note
+/// commitment to a revealed nullifier. The required chips can be configured with the
+/// same layout as the Action circuit using `nullifier::DeriveNullifierConfig`, which is
+/// available with the `gadgets` feature flag.
+///
+/// The caller is responsible for constraining the inputs: `nk` is not checked to be
+/// derived from a spending key, and `cm` is not checked to be a note commitment.
+///
 /// [Section 4.16: Note Commitments and Nullifiers]: https://zips.z.cash/protocol/protocol.pdf#commitmentsandnullifiers
 #[allow(clippy::too_many_arguments)]
-pub(in crate::circuit) fn derive_nullifier<
+pub fn derive_nullifier<
     PoseidonChip: PoseidonSpongeInstructions<pallas::Base, poseidon::P128Pow5T3, ConstantLength<2>, 3, 2>,
     AddChip: AddInstruction<pallas::Base>,
     EccChip: EccInstructions<
@@ -199,5 +266,12 @@ pub(in crate::circuit) fn derive_nullifier<
         .map(|res| res.extract_p())
 }
 
-pub(in crate::circuit) use crate::circuit::commit_ivk::gadgets::commit_ivk;
-pub(in crate::circuit) use crate::circuit::note_commit::gadgets::note_commit;
+pub use crate::circuit::commit_ivk::{
+    gadgets::{commit_ivk, short_commit_base_pair},
+    CommitIvkChip, CommitIvkConfig,
+};
+#[cfg(feature = "unstable-zsa")]
+pub use crate::circuit::note_commit::zsa::{
+    note_commit_zsa, NoteCommitZsaChip, NoteCommitZsaConfig,
+};
+pub use crate::circuit::note_commit::{gadgets::note_commit, NoteCommitChip, NoteCommitConfig};
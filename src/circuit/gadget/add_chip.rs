@@ -1,3 +1,9 @@
+//! A chip that adds two field elements.
+//!
+//! Subtraction and addition of a constant reuse the single addition gate: `c = a - b` is
+//! laid out as `b + c = a`, and a constant is assigned into the `b` column from the
+//! circuit's constant column.
+
 use halo2_proofs::{
     circuit::{AssignedCell, Chip, Layouter},
     plonk::{self, Advice, Column, ConstraintSystem, Constraints, Selector},
@@ -7,8 +13,9 @@ use pasta_curves::pallas;
 
 use super::AddInstruction;
 
+/// Configuration for an [`AddChip`].
 #[derive(Clone, Debug)]
-pub(in crate::circuit) struct AddConfig {
+pub struct AddConfig {
     a: Column<Advice>,
     b: Column<Advice>,
     c: Column<Advice>,
@@ -16,7 +23,7 @@ pub(in crate::circuit) struct AddConfig {
 }
 
 /// A chip implementing a single addition constraint `c = a + b` on a single row.
-pub(in crate::circuit) struct AddChip {
+pub struct AddChip {
     config: AddConfig,
 }
 
@@ -34,7 +41,12 @@ impl Chip<pallas::Base> for AddChip {
 }
 
 impl AddChip {
-    pub(in crate::circuit) fn configure(
+    /// Configures this chip for use in a circuit.
+    ///
+    /// All of the given advice columns must have equality enabled. To use
+    /// [`AddInstruction::add_constant`], the circuit must also have a constant column
+    /// enabled with [`ConstraintSystem::enable_constant`].
+    pub fn configure(
         meta: &mut ConstraintSystem<pallas::Base>,
         a: Column<Advice>,
         b: Column<Advice>,
@@ -53,7 +65,8 @@ impl AddChip {
         AddConfig { a, b, c, q_add }
     }
 
-    pub(in crate::circuit) fn construct(config: AddConfig) -> Self {
+    /// Constructs a chip from its configuration.
+    pub fn construct(config: AddConfig) -> Self {
         Self { config }
     }
 }
@@ -78,4 +91,154 @@ impl AddInstruction<pallas::Base> for AddChip {
             },
         )
     }
+
+    fn sub(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        a: &AssignedCell<pallas::Base, pallas::Base>,
+        b: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+        layouter.assign_region(
+            || "c = a - b",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+
+                // The difference is constrained by `b + (a - b) = a`.
+                b.copy_advice(|| "copy b", &mut region, self.config.a, 0)?;
+                a.copy_advice(|| "copy a", &mut region, self.config.c, 0)?;
+
+                let scalar_val = a.value().zip(b.value()).map(|(a, b)| a - b);
+                region.assign_advice(|| "c", self.config.b, 0, || scalar_val)
+            },
+        )
+    }
+
+    fn add_constant(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        a: &AssignedCell<pallas::Base, pallas::Base>,
+        constant: pallas::Base,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+        layouter.assign_region(
+            || "c = a + constant",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+
+                a.copy_advice(|| "copy a", &mut region, self.config.a, 0)?;
+                region.assign_advice_from_constant(|| "constant", self.config.b, 0, constant)?;
+
+                let scalar_val = a.value().map(|a| a + constant);
+                region.assign_advice(|| "c", self.config.c, 0, || scalar_val)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    use super::{AddChip, AddConfig};
+    use crate::circuit::gadget::{assign_free_advice, AddInstruction};
+
+    struct MyCircuit {
+        a: Value<pallas::Base>,
+        b: Value<pallas::Base>,
+        constant: pallas::Base,
+        sum: Value<pallas::Base>,
+        difference: Value<pallas::Base>,
+        sum_constant: Value<pallas::Base>,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (AddConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                a: Value::unknown(),
+                b: Value::unknown(),
+                constant: self.constant,
+                sum: Value::unknown(),
+                difference: Value::unknown(),
+                sum_constant: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [(); 3].map(|_| meta.advice_column());
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            (
+                AddChip::configure(meta, advices[0], advices[1], advices[2]),
+                advices[0],
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, advice): Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let chip = AddChip::construct(config);
+
+            let a = assign_free_advice(layouter.namespace(|| "a"), advice, self.a)?;
+            let b = assign_free_advice(layouter.namespace(|| "b"), advice, self.b)?;
+
+            let sum = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+            let difference = chip.sub(layouter.namespace(|| "a - b"), &a, &b)?;
+            let sum_constant =
+                chip.add_constant(layouter.namespace(|| "a + constant"), &a, self.constant)?;
+
+            for (name, actual, expected) in [
+                ("sum", sum, self.sum),
+                ("difference", difference, self.difference),
+                ("sum_constant", sum_constant, self.sum_constant),
+            ] {
+                let expected = assign_free_advice(layouter.namespace(|| name), advice, expected)?;
+                layouter.assign_region(
+                    || name,
+                    |mut region| region.constrain_equal(actual.cell(), expected.cell()),
+                )?;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn add_chip() {
+        let a = pallas::Base::random(OsRng);
+        let b = pallas::Base::random(OsRng);
+        let constant = pallas::Base::random(OsRng);
+
+        let circuit = MyCircuit {
+            a: Value::known(a),
+            b: Value::known(b),
+            constant,
+            sum: Value::known(a + b),
+            difference: Value::known(a - b),
+            sum_constant: Value::known(a + constant),
+        };
+        let prover = MockProver::<pallas::Base>::run(5, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let circuit = MyCircuit {
+            difference: Value::known(b - a),
+            ..circuit
+        };
+        let prover = MockProver::<pallas::Base>::run(5, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
 }
@@ -0,0 +1,395 @@
+//! A gadget that conditionally swaps two sequences of field elements.
+//!
+//! Given a boolean `swap` and two equal-length sequences of limbs `a` and `b`, this
+//! outputs `(b, a)` if `swap = 1` and `(a, b)` otherwise. The same `swap` bit is used for
+//! every limb, so a multi-limb value such as a pair of point coordinates is swapped as a
+//! unit. This is the operation used at each layer of a Merkle path, to order the current
+//! node and its sibling according to the corresponding bit of the leaf position.
+//!
+//! [`halo2_gadgets::utilities::cond_swap`] swaps a single assigned cell with a witnessed
+//! value, and assigns a fresh swap bit for every call. That makes it unsuitable for
+//! multi-limb values: swapping the coordinates of two points with it would leave the two
+//! swap bits unconstrained relative to each other. This chip instead takes both inputs as
+//! assigned cells, and copies one swap bit to every limb.
+
+use alloc::vec::Vec;
+
+use halo2_gadgets::utilities::{bool_check, ternary};
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    plonk::{self, Advice, Column, ConstraintSystem, Constraints, Selector},
+    poly::Rotation,
+};
+use pasta_curves::pallas;
+
+/// Configuration for a [`CondSwapChip`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CondSwapConfig {
+    q_swap: Selector,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    a_swapped: Column<Advice>,
+    b_swapped: Column<Advice>,
+    swap: Column<Advice>,
+}
+
+/// The outputs of [`CondSwapChip::swap_limbs`].
+#[derive(Clone, Debug)]
+pub struct Swapped<const N: usize> {
+    /// The cell containing the swap bit. This is boolean-constrained, and should be
+    /// constrained by the caller to its intended value (for example, a bit of a Merkle
+    /// leaf position).
+    pub swap: AssignedCell<pallas::Base, pallas::Base>,
+    /// The limbs of `a` if `swap = 0`, or of `b` if `swap = 1`.
+    pub left: [AssignedCell<pallas::Base, pallas::Base>; N],
+    /// The limbs of `b` if `swap = 0`, or of `a` if `swap = 1`.
+    pub right: [AssignedCell<pallas::Base, pallas::Base>; N],
+}
+
+/// A chip implementing a conditional swap, one limb per row.
+#[derive(Clone, Debug)]
+pub struct CondSwapChip {
+    config: CondSwapConfig,
+}
+
+impl Chip<pallas::Base> for CondSwapChip {
+    type Config = CondSwapConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl CondSwapChip {
+    /// Configures this chip for use in a circuit.
+    ///
+    /// All of the given advice columns must have equality enabled.
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advices: [Column<Advice>; 5],
+    ) -> CondSwapConfig {
+        let q_swap = meta.selector();
+
+        let config = CondSwapConfig {
+            q_swap,
+            a: advices[0],
+            b: advices[1],
+            a_swapped: advices[2],
+            b_swapped: advices[3],
+            swap: advices[4],
+        };
+
+        meta.create_gate("Conditional swap", |meta| {
+            let q_swap = meta.query_selector(q_swap);
+            let a = meta.query_advice(config.a, Rotation::cur());
+            let b = meta.query_advice(config.b, Rotation::cur());
+            let a_swapped = meta.query_advice(config.a_swapped, Rotation::cur());
+            let b_swapped = meta.query_advice(config.b_swapped, Rotation::cur());
+            let swap = meta.query_advice(config.swap, Rotation::cur());
+
+            // This checks that `a_swapped` is equal to `b` when `swap` is set,
+            // but remains as `a` when `swap` is not set.
+            let a_check = a_swapped - ternary(swap.clone(), b.clone(), a.clone());
+
+            // This checks that `b_swapped` is equal to `a` when `swap` is set,
+            // but remains as `b` when `swap` is not set.
+            let b_check = b_swapped - ternary(swap.clone(), a, b);
+
+            // Check `swap` is boolean.
+            let bool_check = bool_check(swap);
+
+            Constraints::with_selector(
+                q_swap,
+                [
+                    ("a check", a_check),
+                    ("b check", b_check),
+                    ("swap is bool", bool_check),
+                ],
+            )
+        });
+
+        config
+    }
+
+    /// Constructs a chip from its configuration.
+    pub fn construct(config: CondSwapConfig) -> Self {
+        Self { config }
+    }
+
+    /// Swaps `a` and `b` if `swap` is set.
+    ///
+    /// Returns `(b, a)` if `swap = 1`, and `(a, b)` otherwise.
+    pub fn swap(
+        &self,
+        layouter: impl Layouter<pallas::Base>,
+        a: &AssignedCell<pallas::Base, pallas::Base>,
+        b: &AssignedCell<pallas::Base, pallas::Base>,
+        swap: Value<bool>,
+    ) -> Result<Swapped<1>, plonk::Error> {
+        self.swap_limbs(layouter, [a.clone()], [b.clone()], swap)
+    }
+
+    /// Swaps the limbs of `a` and `b` if `swap` is set, using a single swap bit for
+    /// all limbs.
+    ///
+    /// For example, two curve points can be swapped by passing their `(x, y)`
+    /// coordinates as two-limb values.
+    pub fn swap_limbs<const N: usize>(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        a: [AssignedCell<pallas::Base, pallas::Base>; N],
+        b: [AssignedCell<pallas::Base, pallas::Base>; N],
+        swap: Value<bool>,
+    ) -> Result<Swapped<N>, plonk::Error> {
+        assert!(N > 0);
+
+        layouter.assign_region(
+            || "conditional swap",
+            |mut region| {
+                let swap_val = swap.map(|swap| pallas::Base::from(u64::from(swap)));
+                let swap_cell =
+                    region.assign_advice(|| "swap", self.config.swap, 0, || swap_val)?;
+
+                let mut left = Vec::with_capacity(N);
+                let mut right = Vec::with_capacity(N);
+                for (row, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                    self.config.q_swap.enable(&mut region, row)?;
+
+                    // Every row uses the same swap bit.
+                    if row > 0 {
+                        swap_cell.copy_advice(
+                            || "copy swap",
+                            &mut region,
+                            self.config.swap,
+                            row,
+                        )?;
+                    }
+
+                    let a = a.copy_advice(|| "copy a", &mut region, self.config.a, row)?;
+                    let b = b.copy_advice(|| "copy b", &mut region, self.config.b, row)?;
+
+                    let a_swapped = {
+                        let a_swapped = a
+                            .value()
+                            .zip(b.value())
+                            .zip(swap)
+                            .map(|((a, b), swap)| if swap { b } else { a })
+                            .copied();
+                        region.assign_advice(
+                            || "a_swapped",
+                            self.config.a_swapped,
+                            row,
+                            || a_swapped,
+                        )?
+                    };
+                    let b_swapped = {
+                        let b_swapped = a
+                            .value()
+                            .zip(b.value())
+                            .zip(swap)
+                            .map(|((a, b), swap)| if swap { a } else { b })
+                            .copied();
+                        region.assign_advice(
+                            || "b_swapped",
+                            self.config.b_swapped,
+                            row,
+                            || b_swapped,
+                        )?
+                    };
+
+                    left.push(a_swapped);
+                    right.push(b_swapped);
+                }
+
+                Ok(Swapped {
+                    swap: swap_cell,
+                    left: left.try_into().unwrap(),
+                    right: right.try_into().unwrap(),
+                })
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CondSwapChip, CondSwapConfig};
+    use crate::circuit::gadget::assign_free_advice;
+
+    use ff::Field;
+    use group::{Curve, Group};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::{MockProver, VerifyFailure},
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+    use pasta_curves::{arithmetic::CurveAffine, pallas};
+    use rand::rngs::OsRng;
+
+    #[derive(Default)]
+    struct MyCircuit {
+        a: [Value<pallas::Base>; 2],
+        b: [Value<pallas::Base>; 2],
+        swap: Value<bool>,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (CondSwapConfig, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            (CondSwapChip::configure(meta, advices), advices[0])
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let (config, advice) = config;
+            let chip = CondSwapChip::construct(config);
+
+            let a = [
+                assign_free_advice(layouter.namespace(|| "a_x"), advice, self.a[0])?,
+                assign_free_advice(layouter.namespace(|| "a_y"), advice, self.a[1])?,
+            ];
+            let b = [
+                assign_free_advice(layouter.namespace(|| "b_x"), advice, self.b[0])?,
+                assign_free_advice(layouter.namespace(|| "b_y"), advice, self.b[1])?,
+            ];
+
+            let swapped = chip.swap_limbs(
+                layouter.namespace(|| "swap points"),
+                a.clone(),
+                b.clone(),
+                self.swap,
+            )?;
+
+            for i in 0..2 {
+                self.swap
+                    .zip(a[i].value().zip(b[i].value()))
+                    .zip(swapped.left[i].value().zip(swapped.right[i].value()))
+                    .assert_if_known(|((swap, (a, b)), (left, right))| {
+                        if *swap {
+                            left == b && right == a
+                        } else {
+                            left == a && right == b
+                        }
+                    });
+            }
+
+            // A single-limb swap.
+            chip.swap(layouter.namespace(|| "swap x"), &a[0], &b[0], self.swap)?;
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn cond_swap() {
+        let point = |p: pallas::Point| {
+            let p = p.to_affine().coordinates().unwrap();
+            [Value::known(*p.x()), Value::known(*p.y())]
+        };
+
+        for swap in [false, true] {
+            let circuit = MyCircuit {
+                a: point(pallas::Point::random(OsRng)),
+                b: point(pallas::Point::random(OsRng)),
+                swap: Value::known(swap),
+            };
+            let prover = MockProver::<pallas::Base>::run(5, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+
+        // Limbs need not be point coordinates.
+        let circuit = MyCircuit {
+            a: [Value::known(pallas::Base::random(OsRng)); 2],
+            b: [Value::known(pallas::Base::ONE); 2],
+            swap: Value::known(true),
+        };
+        let prover = MockProver::<pallas::Base>::run(5, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// Assigns a swap row directly, with a swap value of 2. The outputs are set to
+    /// `a + swap * (b - a)` and `b + swap * (a - b)`, which satisfy the swap constraints,
+    /// so that only the boolean constraint on `swap` fails.
+    #[derive(Default)]
+    struct NonBooleanSwap;
+
+    impl Circuit<pallas::Base> for NonBooleanSwap {
+        type Config = CondSwapConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [(); 5].map(|_| meta.advice_column());
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+            CondSwapChip::configure(meta, advices)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "non-boolean swap",
+                |mut region| {
+                    let a = pallas::Base::from(5);
+                    let b = pallas::Base::from(7);
+                    let swap = pallas::Base::from(2);
+                    config.q_swap.enable(&mut region, 0)?;
+                    for (column, value) in [
+                        (config.a, a),
+                        (config.b, b),
+                        (config.a_swapped, a + swap * (b - a)),
+                        (config.b_swapped, b + swap * (a - b)),
+                        (config.swap, swap),
+                    ] {
+                        region.assign_advice(|| "", column, 0, || Value::known(value))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn cond_swap_rejects_non_boolean_swap() {
+        let prover = MockProver::<pallas::Base>::run(5, &NonBooleanSwap, vec![]).unwrap();
+        let failures = prover.verify().unwrap_err();
+        assert_eq!(failures.len(), 1);
+        assert!(matches!(
+            failures[0],
+            VerifyFailure::ConstraintNotSatisfied { .. }
+        ));
+        assert!(format!("{:?}", failures[0]).contains("swap is bool"));
+    }
+}
@@ -0,0 +1,282 @@
+//! Fixed bases for the ECC chip, extended with caller-supplied generators.
+//!
+//! The ECC chip is generic over its set of fixed bases, but the set used by the Orchard
+//! circuit is closed. [`ExtendedFixedBases`] can be used in its place to configure an
+//! [`EccChip`] that multiplies by the Orchard fixed bases and by any number of other
+//! generators, each described by a [`FixedBaseTable`].
+//!
+//! Only full-width scalar multiplication is supported for caller-supplied generators.
+//! Multiplication by a base field element and by a short signed scalar remain restricted
+//! to $\mathcal{K}^\mathsf{Orchard}$ and $\mathcal{V}^\mathsf{Orchard}$ respectively.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use ff::PrimeField;
+use halo2_gadgets::ecc::{
+    chip::{constants::find_zs_and_us, EccChip, FixedPoint, FullScalar},
+    FixedPoints,
+};
+use pasta_curves::pallas;
+
+pub use crate::constants::{
+    fixed_bases::{H, NUM_WINDOWS},
+    NullifierK, OrchardFixedBasesFull, ValueCommitV,
+};
+
+/// The precomputed windowed tables for a full-width fixed-base scalar multiplication.
+///
+/// For each of the [`NUM_WINDOWS`] 3-bit windows, $z$ is the smallest non-negative integer
+/// such that $z + y_i$ is a square for each of the [`H`] multiples $(x_i, y_i)$ of the
+/// generator in that window, and $u_i = \sqrt{z + y_i}$.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixedBaseTable {
+    generator: pallas::Affine,
+    u: Vec<[[u8; 32]; H]>,
+    z: Vec<u64>,
+}
+
+impl FixedBaseTable {
+    /// Computes the tables for `generator`.
+    ///
+    /// This performs a search for each window, and takes on the order of a second. To
+    /// avoid repeating it, the result can be stored with [`Self::u`] and [`Self::z`] and
+    /// later restored with [`Self::from_parts`].
+    ///
+    /// Returns `None` if no valid $z$ was found for some window, which does not occur
+    /// for generators in practice.
+    pub fn compute(generator: pallas::Affine) -> Option<Self> {
+        let (z, u) = find_zs_and_us(generator, NUM_WINDOWS)?
+            .into_iter()
+            .map(|(z, us)| (z, us.map(|u| u.to_repr())))
+            .unzip();
+        Some(FixedBaseTable { generator, u, z })
+    }
+
+    /// Constructs a table from precomputed values.
+    ///
+    /// Returns `None` if `u` or `z` does not have exactly [`NUM_WINDOWS`] entries. The
+    /// values themselves are not checked here; an incorrect table causes the fixed-base
+    /// multiplication constraints to be unsatisfiable.
+    pub fn from_parts(
+        generator: pallas::Affine,
+        u: Vec<[[u8; 32]; H]>,
+        z: Vec<u64>,
+    ) -> Option<Self> {
+        (u.len() == NUM_WINDOWS && z.len() == NUM_WINDOWS).then_some(FixedBaseTable {
+            generator,
+            u,
+            z,
+        })
+    }
+
+    /// Returns the generator.
+    pub fn generator(&self) -> pallas::Affine {
+        self.generator
+    }
+
+    /// Returns the $u$ values for each window, as encoded field elements.
+    pub fn u(&self) -> &[[[u8; 32]; H]] {
+        &self.u
+    }
+
+    /// Returns the $z$ value for each window.
+    pub fn z(&self) -> &[u64] {
+        &self.z
+    }
+}
+
+/// A base for full-width fixed-base scalar multiplication with [`ExtendedFixedBases`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FullWidthBase {
+    /// One of the Orchard fixed bases.
+    Orchard(OrchardFixedBasesFull),
+    /// A caller-supplied generator.
+    Custom(Arc<FixedBaseTable>),
+}
+
+impl From<OrchardFixedBasesFull> for FullWidthBase {
+    fn from(base: OrchardFixedBasesFull) -> Self {
+        FullWidthBase::Orchard(base)
+    }
+}
+
+impl From<Arc<FixedBaseTable>> for FullWidthBase {
+    fn from(table: Arc<FixedBaseTable>) -> Self {
+        FullWidthBase::Custom(table)
+    }
+}
+
+impl FixedPoint<pallas::Affine> for FullWidthBase {
+    type FixedScalarKind = FullScalar;
+
+    fn generator(&self) -> pallas::Affine {
+        match self {
+            FullWidthBase::Orchard(base) => base.generator(),
+            FullWidthBase::Custom(table) => table.generator,
+        }
+    }
+
+    fn u(&self) -> Vec<[[u8; 32]; H]> {
+        match self {
+            FullWidthBase::Orchard(base) => base.u(),
+            FullWidthBase::Custom(table) => table.u.clone(),
+        }
+    }
+
+    fn z(&self) -> Vec<u64> {
+        match self {
+            FullWidthBase::Orchard(base) => base.z(),
+            FullWidthBase::Custom(table) => table.z.clone(),
+        }
+    }
+}
+
+/// The Orchard fixed bases, extended with caller-supplied full-width bases.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtendedFixedBases;
+
+impl FixedPoints<pallas::Affine> for ExtendedFixedBases {
+    type FullScalar = FullWidthBase;
+    type Base = NullifierK;
+    type ShortScalar = ValueCommitV;
+}
+
+/// An ECC chip over [`ExtendedFixedBases`].
+pub type ExtendedEccChip = EccChip<ExtendedFixedBases>;
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use ff::Field;
+    use group::Curve;
+    use halo2_gadgets::{
+        ecc::{
+            chip::{EccChip, EccConfig},
+            FixedPoint, NonIdentityPoint, ScalarFixed,
+        },
+        utilities::lookup_range_check::{LookupRangeCheck, LookupRangeCheckConfig},
+    };
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use pasta_curves::{arithmetic::CurveExt, pallas};
+    use rand::rngs::OsRng;
+
+    use super::{ExtendedFixedBases, FixedBaseTable, FullWidthBase, OrchardFixedBasesFull};
+    use crate::constants::fixed_bases::{H, NUM_WINDOWS};
+
+    struct MyCircuit {
+        base: FullWidthBase,
+        scalar: Value<pallas::Scalar>,
+        product: Value<pallas::Affine>,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (
+            EccConfig<ExtendedFixedBases>,
+            LookupRangeCheckConfig<pallas::Base, 10>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                base: self.base.clone(),
+                scalar: Value::unknown(),
+                product: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [(); 10].map(|_| meta.advice_column());
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            let lagrange_coeffs = [(); 8].map(|_| meta.fixed_column());
+            meta.enable_constant(lagrange_coeffs[0]);
+
+            let table_idx = meta.lookup_table_column();
+            let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+
+            (
+                EccChip::<ExtendedFixedBases>::configure(
+                    meta,
+                    advices,
+                    lagrange_coeffs,
+                    range_check,
+                ),
+                range_check,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (ecc_config, range_check): Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            range_check.load(&mut layouter)?;
+            let ecc_chip = EccChip::construct(ecc_config);
+
+            let scalar = ScalarFixed::new(
+                ecc_chip.clone(),
+                layouter.namespace(|| "scalar"),
+                self.scalar,
+            )?;
+            let base = FixedPoint::from_inner(ecc_chip.clone(), self.base.clone());
+            let (product, _) = base.mul(layouter.namespace(|| "[scalar] base"), scalar)?;
+
+            let expected =
+                NonIdentityPoint::new(ecc_chip, layouter.namespace(|| "expected"), self.product)?;
+            product.constrain_equal(layouter.namespace(|| "product"), &expected)
+        }
+    }
+
+    fn check(base: FullWidthBase, generator: pallas::Point) {
+        let scalar = pallas::Scalar::random(OsRng);
+        let circuit = MyCircuit {
+            base,
+            scalar: Value::known(scalar),
+            product: Value::known((generator * scalar).to_affine()),
+        };
+        let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // A different product is rejected.
+        let circuit = MyCircuit {
+            product: Value::known((generator * (scalar + pallas::Scalar::ONE)).to_affine()),
+            ..circuit
+        };
+        let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn custom_fixed_base() {
+        let generator = pallas::Point::hash_to_curve("orchard-test")(b"G");
+        let table = FixedBaseTable::compute(generator.to_affine()).unwrap();
+        assert_eq!(table.z().len(), NUM_WINDOWS);
+
+        // Tables round-trip through their parts.
+        assert_eq!(
+            FixedBaseTable::from_parts(table.generator(), table.u().to_vec(), table.z().to_vec()),
+            Some(table.clone()),
+        );
+        assert_eq!(
+            FixedBaseTable::from_parts(table.generator(), vec![[[0; 32]; H]], vec![0]),
+            None,
+        );
+
+        check(Arc::new(table).into(), generator);
+    }
+
+    #[test]
+    fn orchard_fixed_base() {
+        let base = OrchardFixedBasesFull::SpendAuthG;
+        let generator = halo2_gadgets::ecc::chip::FixedPoint::generator(&base);
+        check(base.into(), generator.into());
+    }
+}
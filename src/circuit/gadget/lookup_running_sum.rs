@@ -0,0 +1,492 @@
+//! Running sum decomposition with lookup-constrained windows.
+//!
+//! Given a field element $\alpha$, this decomposes it into $W$-bit windows $k_i$ using
+//! the running sum
+//!
+//! $$
+//! \begin{aligned}
+//! z_0 &= \alpha, \\
+//! z_{i + 1} &= (z_i - k_i) / 2^W.
+//! \end{aligned}
+//! $$
+//!
+//! Each window $k_i = z_i - 2^W \cdot z_{i + 1}$ is range-constrained to $[0, 2^W)$ by a
+//! lookup into a table of $W$-bit values. Unlike the polynomial range check used by
+//! [`halo2_gadgets::utilities::decompose_running_sum`], which is limited to windows of at
+//! most 3 bits, the cost of each window here is a single lookup regardless of $W$. This
+//! makes 8-bit or 10-bit windows practical, and reduces the number of rows required for
+//! 64-bit and 255-bit decompositions accordingly.
+//!
+//! The windows are not assigned by default, since the lookup only needs the running sum.
+//! A config created with [`LookupRunningSumConfig::configure_with_windows`] additionally
+//! assigns each $k_i$ to its own column, so that callers can equality-constrain the
+//! windows (for example, into a fixed-base scalar multiplication) without recomputing
+//! them.
+
+use alloc::vec::Vec;
+use core::{marker::PhantomData, ops::Deref};
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector, TableColumn},
+    poly::Rotation,
+};
+
+use crate::primitives::bits::decompose_word;
+
+/// The running sum $[z_0, ..., z_W]$. If created in strict mode, $z_W = 0$.
+#[derive(Clone, Debug)]
+pub struct RunningSum<F: PrimeFieldBits> {
+    zs: Vec<AssignedCell<F, F>>,
+    windows: Option<Vec<AssignedCell<F, F>>>,
+}
+
+impl<F: PrimeFieldBits> RunningSum<F> {
+    /// Returns the assigned windows $[k_0, ..., k_{W - 1}]$, if the decomposition was
+    /// created by a config with a window column.
+    ///
+    /// Each window is constrained to equal $z_i - 2^W \cdot z_{i + 1}$, and is therefore
+    /// range-constrained by the same lookup as the running sum.
+    pub fn windows(&self) -> Option<&[AssignedCell<F, F>]> {
+        self.windows.as_deref()
+    }
+}
+
+impl<F: PrimeFieldBits> Deref for RunningSum<F> {
+    type Target = Vec<AssignedCell<F, F>>;
+
+    fn deref(&self) -> &Vec<AssignedCell<F, F>> {
+        &self.zs
+    }
+}
+
+/// Configuration for a running sum decomposition into `WINDOW_NUM_BITS`-bit windows,
+/// each of which is range-constrained by a lookup.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LookupRunningSumConfig<F: PrimeFieldBits, const WINDOW_NUM_BITS: usize> {
+    q_lookup: Selector,
+    z: Column<Advice>,
+    table: TableColumn,
+    window: Option<(Selector, Column<Advice>)>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeFieldBits, const WINDOW_NUM_BITS: usize> LookupRunningSumConfig<F, WINDOW_NUM_BITS> {
+    /// Returns the selector that enables the lookup on each window.
+    pub fn q_lookup(&self) -> Selector {
+        self.q_lookup
+    }
+
+    /// Returns the advice column in which the running sum is assigned.
+    pub fn z(&self) -> Column<Advice> {
+        self.z
+    }
+
+    /// Returns the table column containing the values $[0, 2^W)$.
+    pub fn table(&self) -> TableColumn {
+        self.table
+    }
+
+    /// Configures a lookup-constrained running sum decomposition.
+    ///
+    /// `table` must contain exactly the values $[0, 2^W)$. It may be shared with other
+    /// gadgets that use a table of the same width (for example, the 10-bit table used by
+    /// Sinsemilla); otherwise, call [`Self::load`] to populate it.
+    ///
+    /// `z` must have equality enabled. Strict decompositions additionally require a
+    /// fixed column to have been enabled for constants.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WINDOW_NUM_BITS` is zero or greater than 16.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        z: Column<Advice>,
+        table: TableColumn,
+    ) -> Self {
+        assert!((1..=16).contains(&WINDOW_NUM_BITS));
+
+        let config = Self {
+            q_lookup: meta.complex_selector(),
+            z,
+            table,
+            window: None,
+            _marker: PhantomData,
+        };
+
+        meta.lookup(|meta| {
+            let q_lookup = meta.query_selector(config.q_lookup);
+            let z_cur = meta.query_advice(config.z, Rotation::cur());
+            let z_next = meta.query_advice(config.z, Rotation::next());
+
+            //    z_i = 2^W ⋅ z_{i + 1} + k_i
+            // => k_i = z_i - 2^W ⋅ z_{i + 1}
+            let word = z_cur - z_next * F::from(1 << WINDOW_NUM_BITS);
+
+            // When q_lookup is disabled the looked-up value is 0, which is in the table.
+            vec![(q_lookup * word, config.table)]
+        });
+
+        config
+    }
+
+    /// Configures a lookup-constrained running sum decomposition that also assigns each
+    /// window to the column `k`, where it can be retrieved with [`RunningSum::windows`].
+    ///
+    /// `k` must have equality enabled, and must not be `z`. The requirements of
+    /// [`Self::configure`] also apply.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WINDOW_NUM_BITS` is zero or greater than 16, or if `k` is `z`.
+    pub fn configure_with_windows(
+        meta: &mut ConstraintSystem<F>,
+        z: Column<Advice>,
+        k: Column<Advice>,
+        table: TableColumn,
+    ) -> Self {
+        assert_ne!(z, k);
+
+        let mut config = Self::configure(meta, z, table);
+        let q_window = meta.selector();
+
+        meta.create_gate("running sum window", |meta| {
+            let q_window = meta.query_selector(q_window);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let k = meta.query_advice(k, Rotation::cur());
+
+            // k_i = z_i - 2^W ⋅ z_{i + 1}
+            vec![q_window * (k - (z_cur - z_next * F::from(1 << WINDOW_NUM_BITS)))]
+        });
+
+        config.window = Some((q_window, k));
+        config
+    }
+
+    /// Loads the values $[0, 2^W)$ into the lookup table.
+    ///
+    /// This must not be called if the table column is populated by another gadget.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || format!("{}-bit table", WINDOW_NUM_BITS),
+            |mut table| {
+                for index in 0..(1 << WINDOW_NUM_BITS) {
+                    table.assign_cell(
+                        || "table",
+                        self.table,
+                        index,
+                        || Value::known(F::from(index as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Decomposes a field element $\alpha$ that is witnessed in this helper.
+    ///
+    /// `strict = true` constrains the final running sum output to be zero, i.e.
+    /// constrains $\alpha$ to be within `WINDOW_NUM_BITS * num_windows` bits.
+    pub fn witness_decompose(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        alpha: Value<F>,
+        strict: bool,
+        word_num_bits: usize,
+        num_windows: usize,
+    ) -> Result<RunningSum<F>, Error> {
+        let z_0 = region.assign_advice(|| "z_0 = alpha", self.z, offset, || alpha)?;
+        self.decompose(region, offset, z_0, strict, word_num_bits, num_windows)
+    }
+
+    /// Decomposes an existing cell $\alpha$ that is copied into this helper.
+    ///
+    /// `strict = true` constrains the final running sum output to be zero, i.e.
+    /// constrains $\alpha$ to be within `WINDOW_NUM_BITS * num_windows` bits.
+    pub fn copy_decompose(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        alpha: AssignedCell<F, F>,
+        strict: bool,
+        word_num_bits: usize,
+        num_windows: usize,
+    ) -> Result<RunningSum<F>, Error> {
+        let z_0 = alpha.copy_advice(|| "copy z_0 = alpha", region, self.z, offset)?;
+        self.decompose(region, offset, z_0, strict, word_num_bits, num_windows)
+    }
+
+    /// `z_0` must be the cell at `(self.z, offset)` in `region`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are too many windows for the given word size.
+    fn decompose(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        z_0: AssignedCell<F, F>,
+        strict: bool,
+        word_num_bits: usize,
+        num_windows: usize,
+    ) -> Result<RunningSum<F>, Error> {
+        // Make sure that we do not have more windows than required for the number
+        // of bits in the word. In other words, every window must contain at least
+        // one bit of the word (no empty windows).
+        //
+        // For example, let:
+        //      - word_num_bits = 64
+        //      - WINDOW_NUM_BITS = 10
+        // In this case, the maximum allowed num_windows is 7:
+        //      - 64 = 6 * 10 + 4: the last window contains 4 bits of the word.
+        assert!(WINDOW_NUM_BITS * num_windows < word_num_bits + WINDOW_NUM_BITS);
+        assert!(word_num_bits <= F::NUM_BITS as usize);
+
+        // Decompose the field element into `WINDOW_NUM_BITS`-bit words.
+        let words = z_0
+            .value()
+            .map(|alpha| decompose_word::<F, WINDOW_NUM_BITS>(alpha, word_num_bits))
+            .transpose_vec(num_windows);
+
+        // Initialize empty vector to store running sum values [z_0, ..., z_W].
+        let mut zs: Vec<AssignedCell<F, F>> = vec![z_0.clone()];
+        let mut z = z_0;
+
+        // If configured, assign each window k_i alongside z_i.
+        let mut windows = self.window.map(|_| Vec::with_capacity(num_windows));
+
+        // Assign running sum `z_{i+1}` = (z_i - k_i) / (2^W) for i = 0..=n-1.
+        // Outside of this helper, z_0 = alpha must have already been loaded into the
+        // `z` column at `offset`.
+        let two_pow_w_inv = Value::known(F::from(1 << WINDOW_NUM_BITS).invert().unwrap());
+        for (i, word) in words.iter().enumerate() {
+            // Enable lookup on row `offset + i`, which constrains k_i to be in the table.
+            self.q_lookup.enable(region, offset + i)?;
+
+            if let (Some((q_window, k)), Some(windows)) = (self.window, windows.as_mut()) {
+                q_window.enable(region, offset + i)?;
+                windows.push(region.assign_advice(
+                    || format!("k_{}", i),
+                    k,
+                    offset + i,
+                    || word.map(|word| F::from(word)),
+                )?);
+            }
+
+            // z_next = (z_cur - word) / (2^W)
+            let z_next = {
+                let z_cur_val = z.value().copied();
+                let word = word.map(|word| F::from(word));
+                let z_next_val = (z_cur_val - word) * two_pow_w_inv;
+                region.assign_advice(
+                    || format!("z_{}", i + 1),
+                    self.z,
+                    offset + i + 1,
+                    || z_next_val,
+                )?
+            };
+
+            // Update `z`.
+            z = z_next;
+            zs.push(z.clone());
+        }
+        assert_eq!(zs.len(), num_windows + 1);
+
+        if strict {
+            // Constrain the final running sum output to be zero.
+            region.constrain_constant(zs.last().unwrap().cell(), F::ZERO)?;
+        }
+
+        Ok(RunningSum { zs, windows })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LookupRunningSumConfig;
+
+    use ff::PrimeField;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+    };
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    const WINDOW_NUM_BITS: usize = 8;
+
+    #[derive(Default)]
+    struct MyCircuit {
+        alpha: Value<pallas::Base>,
+        word_num_bits: usize,
+        num_windows: usize,
+        strict: bool,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = LookupRunningSumConfig<pallas::Base, WINDOW_NUM_BITS>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                alpha: Value::unknown(),
+                ..*self
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let z = meta.advice_column();
+            meta.enable_equality(z);
+
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let table = meta.lookup_table_column();
+
+            LookupRunningSumConfig::configure(meta, z, table)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            config.load(&mut layouter)?;
+
+            layouter.assign_region(
+                || "decompose",
+                |mut region| {
+                    let offset = 0;
+                    let zs = config.witness_decompose(
+                        &mut region,
+                        offset,
+                        self.alpha,
+                        self.strict,
+                        self.word_num_bits,
+                        self.num_windows,
+                    )?;
+                    assert_eq!(zs.len(), self.num_windows + 1);
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[derive(Default)]
+    struct WindowCircuit {
+        alpha: Value<pallas::Base>,
+    }
+
+    impl Circuit<pallas::Base> for WindowCircuit {
+        type Config = (
+            LookupRunningSumConfig<pallas::Base, WINDOW_NUM_BITS>,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let z = meta.advice_column();
+            let k = meta.advice_column();
+            meta.enable_equality(z);
+            meta.enable_equality(k);
+
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let primary = meta.instance_column();
+            meta.enable_equality(primary);
+
+            let table = meta.lookup_table_column();
+
+            (
+                LookupRunningSumConfig::configure_with_windows(meta, z, k, table),
+                primary,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, primary): Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            config.load(&mut layouter)?;
+
+            let zs = layouter.assign_region(
+                || "decompose",
+                |mut region| config.witness_decompose(&mut region, 0, self.alpha, true, 32, 4),
+            )?;
+
+            for (i, k) in zs.windows().unwrap().iter().enumerate() {
+                layouter.constrain_instance(k.cell(), primary, i)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn running_sum_windows() {
+        let circuit = WindowCircuit {
+            alpha: Value::known(pallas::Base::from(0x0102_03ff)),
+        };
+        let windows = [0xff, 0x03, 0x02, 0x01].map(pallas::Base::from).to_vec();
+
+        let prover = MockProver::<pallas::Base>::run(9, &circuit, vec![windows]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // The windows are bound to the running sum.
+        let wrong = [0xfe, 0x03, 0x02, 0x01].map(pallas::Base::from).to_vec();
+        let prover = MockProver::<pallas::Base>::run(9, &circuit, vec![wrong]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn lookup_running_sum() {
+        use ff::Field;
+
+        // A 64-bit value decomposes into eight 8-bit windows.
+        {
+            let circuit = MyCircuit {
+                alpha: Value::known(pallas::Base::from(u64::MAX)),
+                word_num_bits: 64,
+                num_windows: 8,
+                strict: true,
+            };
+            let prover = MockProver::<pallas::Base>::run(9, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+
+        // A full-width field element decomposes into 32 windows, the last of which
+        // holds the top 7 bits.
+        {
+            let circuit = MyCircuit {
+                alpha: Value::known(pallas::Base::random(OsRng)),
+                word_num_bits: pallas::Base::NUM_BITS as usize,
+                num_windows: 32,
+                strict: true,
+            };
+            let prover = MockProver::<pallas::Base>::run(9, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+
+        // Strict decomposition of a 16-bit word fails for a 17-bit value, because the
+        // final running sum is not zero.
+        {
+            let circuit = MyCircuit {
+                alpha: Value::known(pallas::Base::from(1 << 16)),
+                word_num_bits: 16,
+                num_windows: 2,
+                strict: true,
+            };
+            let prover = MockProver::<pallas::Base>::run(9, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
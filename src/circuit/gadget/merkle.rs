@@ -0,0 +1,267 @@
+//! Merkle path verification over the Orchard Merkle hash, for trees of any depth.
+//!
+//! The Action circuit verifies paths in the note commitment tree, which has depth
+//! [`MERKLE_DEPTH_ORCHARD`]. [`OrchardMerklePath`] performs the same check for a tree of
+//! depth `DEPTH`, with the matching out-of-circuit computation given by
+//! [`tree::root_of_depth`]. Each layer costs a Sinsemilla hash of 520 bits, so circuits
+//! that use a shallow tree are much cheaper to synthesize and to check with a
+//! `MockProver`.
+//!
+//! [`MERKLE_DEPTH_ORCHARD`]: crate::constants::MERKLE_DEPTH_ORCHARD
+//! [`tree::root_of_depth`]: crate::tree::root_of_depth
+
+use halo2_gadgets::sinsemilla::{
+    merkle::{chip::MerkleChip, MerklePath},
+    primitives as sinsemilla,
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk,
+};
+use pasta_curves::pallas;
+
+use crate::{
+    constants::{OrchardCommitDomains, OrchardFixedBases, OrchardHashDomains},
+    tree::MerkleHashOrchard,
+};
+
+/// The Merkle chip used by the Orchard circuit.
+pub type OrchardMerkleChip =
+    MerkleChip<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>;
+
+/// A witnessed Merkle path of depth `DEPTH` in a tree built with the Orchard Merkle hash.
+///
+/// As in the Action circuit, the layers of the path are split between two Merkle chips,
+/// which should be configured over disjoint columns so that their regions can be laid
+/// out side by side.
+#[derive(Clone, Debug)]
+pub struct OrchardMerklePath<const DEPTH: usize> {
+    inner: MerklePath<
+        pallas::Affine,
+        OrchardMerkleChip,
+        DEPTH,
+        { sinsemilla::K },
+        { sinsemilla::C },
+        2,
+    >,
+}
+
+impl<const DEPTH: usize> OrchardMerklePath<DEPTH> {
+    /// Constructs a Merkle path for the leaf at `position`, with the given authentication
+    /// path.
+    ///
+    /// Only the low `DEPTH` bits of `position` are used.
+    pub fn construct(
+        chips: [OrchardMerkleChip; 2],
+        position: Value<u32>,
+        auth_path: Value<[MerkleHashOrchard; DEPTH]>,
+    ) -> Self {
+        OrchardMerklePath {
+            inner: MerklePath::construct(
+                chips,
+                OrchardHashDomains::MerkleCrh,
+                position,
+                auth_path.map(|path| path.map(|node| node.inner())),
+            ),
+        }
+    }
+
+    /// Calculates the root of the tree containing `leaf`, constraining it to be
+    /// consistent with this path.
+    pub fn calculate_root(
+        &self,
+        layouter: impl Layouter<pallas::Base>,
+        leaf: AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+        self.inner.calculate_root(layouter, leaf)
+    }
+}
+
+/// A test circuit for [`OrchardMerklePath`], shared by this crate's tests and the
+/// `gadget-merkle` example.
+#[cfg(any(test, feature = "test-dependencies"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-dependencies")))]
+pub mod testing {
+    use alloc::vec::Vec;
+
+    use ff::{Field, PrimeField};
+    use halo2_gadgets::{
+        sinsemilla::{
+            chip::{SinsemillaChip, SinsemillaConfig},
+            merkle::chip::{MerkleChip, MerkleConfig},
+        },
+        utilities::lookup_range_check::LookupRangeCheckConfig,
+    };
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::{MockProver, VerifyFailure},
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+    use pasta_curves::pallas;
+    use rand::RngCore;
+
+    use super::OrchardMerklePath;
+    use crate::{
+        circuit::gadget::assign_free_advice,
+        constants::{OrchardCommitDomains, OrchardFixedBases, OrchardHashDomains},
+        tree::{self, MerkleHashOrchard},
+    };
+
+    /// A circuit that computes the root of a Merkle path of depth `DEPTH` with
+    /// [`OrchardMerklePath`], and constrains it to equal a witnessed root.
+    #[derive(Clone, Debug)]
+    pub struct MerklePathCircuit<const DEPTH: usize> {
+        leaf: Value<MerkleHashOrchard>,
+        position: Value<u32>,
+        auth_path: Value<[MerkleHashOrchard; DEPTH]>,
+        root: Value<MerkleHashOrchard>,
+    }
+
+    impl<const DEPTH: usize> MerklePathCircuit<DEPTH> {
+        /// Constructs a circuit for a random leaf and authentication path at
+        /// `position`, with the root computed out of circuit by
+        /// [`tree::root_of_depth`].
+        pub fn random(mut rng: impl RngCore, position: u32) -> Self {
+            let mut random_node = || {
+                MerkleHashOrchard::from_bytes(&pallas::Base::random(&mut rng).to_repr()).unwrap()
+            };
+            let leaf = random_node();
+            let auth_path = [(); DEPTH].map(|_| random_node());
+            let root = tree::root_of_depth(position, &auth_path, leaf);
+
+            MerklePathCircuit {
+                leaf: Value::known(leaf),
+                position: Value::known(position),
+                auth_path: Value::known(auth_path),
+                root: Value::known(root),
+            }
+        }
+
+        /// Returns the same circuit with the path placed at `position`, which does not
+        /// change the witnessed root.
+        pub fn with_position(self, position: u32) -> Self {
+            MerklePathCircuit {
+                position: Value::known(position),
+                ..self
+            }
+        }
+
+        /// Checks whether the circuit is satisfied, using [`MockProver`].
+        pub fn verify(&self) -> Result<(), Vec<VerifyFailure>> {
+            MockProver::<pallas::Base>::run(11, self, alloc::vec![])
+                .unwrap()
+                .verify()
+        }
+    }
+
+    impl<const DEPTH: usize> Circuit<pallas::Base> for MerklePathCircuit<DEPTH> {
+        type Config = (
+            [SinsemillaConfig<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>; 2],
+            [MerkleConfig<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>; 2],
+            Column<Advice>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MerklePathCircuit {
+                leaf: Value::unknown(),
+                position: Value::unknown(),
+                auth_path: Value::unknown(),
+                root: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [(); 10].map(|_| meta.advice_column());
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            let lagrange_coeffs = [(); 2].map(|_| meta.fixed_column());
+            meta.enable_constant(lagrange_coeffs[0]);
+
+            let table_idx = meta.lookup_table_column();
+            let lookup = (
+                table_idx,
+                meta.lookup_table_column(),
+                meta.lookup_table_column(),
+            );
+            let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+
+            let sinsemilla_config_1 = SinsemillaChip::configure(
+                meta,
+                advices[..5].try_into().unwrap(),
+                advices[6],
+                lagrange_coeffs[0],
+                lookup,
+                range_check,
+                false,
+            );
+            let sinsemilla_config_2 = SinsemillaChip::configure(
+                meta,
+                advices[5..].try_into().unwrap(),
+                advices[7],
+                lagrange_coeffs[1],
+                lookup,
+                range_check,
+                false,
+            );
+            let merkle_config_1 = MerkleChip::configure(meta, sinsemilla_config_1.clone());
+            let merkle_config_2 = MerkleChip::configure(meta, sinsemilla_config_2.clone());
+
+            (
+                [sinsemilla_config_1, sinsemilla_config_2],
+                [merkle_config_1, merkle_config_2],
+                advices[0],
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (sinsemilla_configs, merkle_configs, advice): Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            SinsemillaChip::load(sinsemilla_configs[0].clone(), &mut layouter)?;
+
+            let leaf = assign_free_advice(
+                layouter.namespace(|| "witness leaf"),
+                advice,
+                self.leaf.map(|leaf| leaf.inner()),
+            )?;
+
+            let path = OrchardMerklePath::construct(
+                merkle_configs.map(MerkleChip::construct),
+                self.position,
+                self.auth_path,
+            );
+            let root = path.calculate_root(layouter.namespace(|| "Merkle path"), leaf)?;
+
+            let expected = assign_free_advice(
+                layouter.namespace(|| "witness root"),
+                advice,
+                self.root.map(|root| root.inner()),
+            )?;
+            layouter.assign_region(
+                || "constrain root",
+                |mut region| region.constrain_equal(root.cell(), expected.cell()),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::OsRng, RngCore};
+
+    use super::testing::MerklePathCircuit;
+
+    #[test]
+    fn shallow_merkle_path() {
+        let position = OsRng.next_u32() % (1 << 4);
+        let circuit = MerklePathCircuit::<4>::random(OsRng, position);
+        assert_eq!(circuit.verify(), Ok(()));
+
+        // A path for a different position does not produce the same root.
+        assert!(circuit.with_position(position ^ 1).verify().is_err());
+    }
+}
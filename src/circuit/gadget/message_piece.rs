@@ -0,0 +1,533 @@
+//! Construction of Sinsemilla message pieces from arbitrary-width subpieces.
+//!
+//! A Sinsemilla message is hashed in pieces whose lengths are multiples of 10 bits, but
+//! the fields being committed to rarely line up with those boundaries. A piece is
+//! therefore assembled from subpieces, each of which is a bit range of some field
+//! element. [`MessagePieceBuilder`] takes a list of such subpieces, and:
+//! - witnesses the resulting [`MessagePiece`];
+//! - witnesses each subpiece and range-constrains it to its width;
+//! - constrains the piece to equal the concatenation of its subpieces.
+//!
+//! The subpiece cells are returned, so that the caller can tie them back to the field
+//! elements they were taken from with [`MessagePieceChip::constrain_sum`], and add any
+//! canonicity checks that the encoding of those field elements requires.
+//!
+//! The NoteCommit and CommitIvk gadgets predate this API and decompose their message
+//! pieces with bespoke gates; their layout is fixed by the Action circuit's verifying
+//! key, so they are not built on this gadget.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use ff::{Field, PrimeField};
+use halo2_gadgets::{
+    sinsemilla::{chip::SinsemillaChip, MessagePiece},
+    utilities::{lookup_range_check::LookupRangeCheckConfig, RangeConstrained},
+};
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter, Value},
+    plonk::{self, Advice, Column, ConstraintSystem, Constraints, Fixed, Selector},
+    poly::Rotation,
+};
+use pasta_curves::pallas;
+
+use super::range_check::range_check;
+use crate::constants::{
+    sinsemilla::K, OrchardCommitDomains, OrchardFixedBases, OrchardHashDomains,
+};
+
+/// A Sinsemilla message piece for the Orchard Sinsemilla chip.
+pub type OrchardMessagePiece = MessagePiece<
+    pallas::Affine,
+    SinsemillaChip<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>,
+    10,
+    253,
+>;
+
+/// Configuration for a [`MessagePieceChip`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessagePieceConfig {
+    q_first: Selector,
+    q_next: Selector,
+    acc: Column<Advice>,
+    part: Column<Advice>,
+    shift: Column<Fixed>,
+    lookup: LookupRangeCheckConfig<pallas::Base, K>,
+}
+
+/// A chip that constrains a field element to be the concatenation of bit strings.
+///
+/// Given parts $p_0, \ldots, p_{n-1}$ at bit offsets $o_0, \ldots, o_{n-1}$, the chip
+/// computes $\sum_i 2^{o_i} \cdot p_i$ with a running sum, one part per row:
+///
+/// | acc                         | part      | shift         | q_first | q_next |
+/// |-----------------------------|-----------|---------------|---------|--------|
+/// | $2^{o_0} p_0$               | $p_0$     | $2^{o_0}$     | 1       | 0      |
+/// | $acc_0 + 2^{o_1} p_1$       | $p_1$     | $2^{o_1}$     | 0       | 1      |
+/// | ...                         | ...       | ...           | 0       | 1      |
+#[derive(Clone, Debug)]
+pub struct MessagePieceChip {
+    config: MessagePieceConfig,
+}
+
+impl Chip<pallas::Base> for MessagePieceChip {
+    type Config = MessagePieceConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl MessagePieceChip {
+    /// Configures this chip for use in a circuit.
+    ///
+    /// `acc` and `part` must have equality enabled. `lookup` is used to range-constrain
+    /// subpieces, and its table must be loaded separately.
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        acc: Column<Advice>,
+        part: Column<Advice>,
+        shift: Column<Fixed>,
+        lookup: LookupRangeCheckConfig<pallas::Base, K>,
+    ) -> MessagePieceConfig {
+        let q_first = meta.selector();
+        let q_next = meta.selector();
+
+        meta.create_gate("Message piece decomposition", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let q_next = meta.query_selector(q_next);
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let part = meta.query_advice(part, Rotation::cur());
+            let shift = meta.query_fixed(shift);
+
+            let term = part * shift;
+
+            Constraints::without_selector([
+                (
+                    "acc_0 = 2^o_0 * p_0",
+                    q_first * (acc.clone() - term.clone()),
+                ),
+                (
+                    "acc_i = acc_{i-1} + 2^o_i * p_i",
+                    q_next * (acc - acc_prev - term),
+                ),
+            ])
+        });
+
+        MessagePieceConfig {
+            q_first,
+            q_next,
+            acc,
+            part,
+            shift,
+            lookup,
+        }
+    }
+
+    /// Constructs a chip from its configuration.
+    pub fn construct(config: MessagePieceConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns $\sum_i 2^{o_i} \cdot p_i$, for `parts` given as pairs $(p_i, o_i)$.
+    ///
+    /// The parts are not range-constrained by this method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parts` is empty.
+    pub fn linear_combination(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        parts: &[(AssignedCell<pallas::Base, pallas::Base>, usize)],
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+        assert!(!parts.is_empty());
+
+        layouter.assign_region(
+            || "linear combination",
+            |mut region| {
+                let mut acc: Option<AssignedCell<pallas::Base, pallas::Base>> = None;
+                for (row, (part, offset)) in parts.iter().enumerate() {
+                    if row == 0 {
+                        self.config.q_first.enable(&mut region, row)?;
+                    } else {
+                        self.config.q_next.enable(&mut region, row)?;
+                    }
+
+                    let shift = pallas::Base::from(2).pow([*offset as u64]);
+                    region.assign_fixed(
+                        || "shift",
+                        self.config.shift,
+                        row,
+                        || Value::known(shift),
+                    )?;
+                    let part = part.copy_advice(|| "part", &mut region, self.config.part, row)?;
+
+                    let term = part.value().map(|part| *part * shift);
+                    let value = match &acc {
+                        Some(acc) => acc.value().copied() + term,
+                        None => term,
+                    };
+                    acc = Some(region.assign_advice(|| "acc", self.config.acc, row, || value)?);
+                }
+
+                Ok(acc.unwrap())
+            },
+        )
+    }
+
+    /// Constrains `whole` to equal $\sum_i 2^{o_i} \cdot p_i$, for `parts` given as pairs
+    /// $(p_i, o_i)$.
+    ///
+    /// This can be used to check that the subpieces of a message piece are taken from a
+    /// given field element. It does not check that the decomposition is canonical: if
+    /// the parts can together encode an integer as large as the field modulus $p$, the
+    /// caller must additionally constrain them to encode an integer less than $p$.
+    pub fn constrain_sum(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        whole: &AssignedCell<pallas::Base, pallas::Base>,
+        parts: &[(AssignedCell<pallas::Base, pallas::Base>, usize)],
+    ) -> Result<(), plonk::Error> {
+        let sum = self.linear_combination(layouter.namespace(|| "sum of parts"), parts)?;
+        layouter.assign_region(
+            || "whole = sum of parts",
+            |mut region| region.constrain_equal(whole.cell(), sum.cell()),
+        )
+    }
+}
+
+/// A subpiece of a message piece.
+#[derive(Clone, Debug)]
+enum Subpiece {
+    /// A bit range of a field element, which has not yet been witnessed.
+    Bits(RangeConstrained<pallas::Base, Value<pallas::Base>>),
+    /// A cell that has already been witnessed, along with its width in bits.
+    Cell(AssignedCell<pallas::Base, pallas::Base>, usize),
+}
+
+impl Subpiece {
+    fn num_bits(&self) -> usize {
+        match self {
+            Subpiece::Bits(bits) => bits.num_bits(),
+            Subpiece::Cell(_, num_bits) => *num_bits,
+        }
+    }
+
+    fn value(&self) -> RangeConstrained<pallas::Base, Value<pallas::Base>> {
+        match self {
+            Subpiece::Bits(bits) => bits.clone(),
+            Subpiece::Cell(cell, num_bits) => {
+                RangeConstrained::bitrange_of(cell.value(), 0..*num_bits)
+            }
+        }
+    }
+}
+
+/// The outputs of [`MessagePieceBuilder::build`].
+#[derive(Clone, Debug)]
+pub struct DecomposedPiece {
+    /// The message piece, for use in a Sinsemilla hash or commitment.
+    pub piece: OrchardMessagePiece,
+    /// The subpieces, in the order they were added to the builder. Each is
+    /// range-constrained to its width.
+    pub subpieces: Vec<AssignedCell<pallas::Base, pallas::Base>>,
+}
+
+/// A builder for a Sinsemilla message piece made up of arbitrary-width subpieces.
+///
+/// Subpieces are concatenated in the order they are added, starting from the least
+/// significant bit of the piece.
+#[derive(Clone, Debug, Default)]
+pub struct MessagePieceBuilder {
+    subpieces: Vec<Subpiece>,
+}
+
+impl MessagePieceBuilder {
+    /// Constructs a builder for an empty piece.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends bits `range` of `value` to the piece.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty, or extends beyond the bit length of the field.
+    pub fn bitrange_of(mut self, value: Value<&pallas::Base>, range: Range<usize>) -> Self {
+        assert!(!range.is_empty() && range.end <= pallas::Base::NUM_BITS as usize);
+        self.subpieces
+            .push(Subpiece::Bits(RangeConstrained::bitrange_of(value, range)));
+        self
+    }
+
+    /// Appends `cell` to the piece, as a subpiece of `num_bits` bits.
+    ///
+    /// The cell will be range-constrained to `num_bits` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` is zero, or is not less than the bit length of the field.
+    pub fn cell(mut self, cell: AssignedCell<pallas::Base, pallas::Base>, num_bits: usize) -> Self {
+        assert!(num_bits > 0 && num_bits < pallas::Base::NUM_BITS as usize);
+        self.subpieces.push(Subpiece::Cell(cell, num_bits));
+        self
+    }
+
+    /// Returns the total width in bits of the subpieces added so far.
+    pub fn num_bits(&self) -> usize {
+        self.subpieces.iter().map(Subpiece::num_bits).sum()
+    }
+
+    /// Witnesses the message piece and its subpieces, and constrains them to be
+    /// consistent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total width of the subpieces is not a positive multiple of 10
+    /// bits, or is wider than the field.
+    pub fn build(
+        self,
+        chip: &MessagePieceChip,
+        sinsemilla_chip: SinsemillaChip<
+            OrchardHashDomains,
+            OrchardCommitDomains,
+            OrchardFixedBases,
+        >,
+        mut layouter: impl Layouter<pallas::Base>,
+    ) -> Result<DecomposedPiece, plonk::Error> {
+        let num_bits = self.num_bits();
+        assert!(num_bits > 0 && num_bits % K == 0);
+        assert!(num_bits < pallas::Base::NUM_BITS as usize);
+
+        let piece = MessagePiece::from_subpieces(
+            sinsemilla_chip,
+            layouter.namespace(|| "piece"),
+            self.subpieces.iter().map(Subpiece::value),
+        )?;
+
+        let mut subpieces = Vec::with_capacity(self.subpieces.len());
+        let mut parts = Vec::with_capacity(self.subpieces.len());
+        let mut offset = 0;
+        for (i, subpiece) in self.subpieces.into_iter().enumerate() {
+            let num_bits = subpiece.num_bits();
+            let cell = match subpiece {
+                Subpiece::Bits(bits) => layouter.assign_region(
+                    || format!("subpiece {}", i),
+                    |mut region| {
+                        region.assign_advice(|| "subpiece", chip.config.part, 0, || *bits.inner())
+                    },
+                )?,
+                Subpiece::Cell(cell, _) => cell,
+            };
+
+            range_check(
+                &chip.config.lookup,
+                layouter.namespace(|| format!("subpiece {} range check", i)),
+                cell.clone(),
+                num_bits,
+            )?;
+
+            parts.push((cell.clone(), offset));
+            subpieces.push(cell);
+            offset += num_bits;
+        }
+
+        chip.constrain_sum(
+            layouter.namespace(|| "piece = concatenation of subpieces"),
+            &piece.inner().cell_value(),
+            &parts,
+        )?;
+
+        Ok(DecomposedPiece { piece, subpieces })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::{Field, PrimeFieldBits};
+    use halo2_gadgets::{
+        sinsemilla::chip::{SinsemillaChip, SinsemillaConfig},
+        utilities::lookup_range_check::{LookupRangeCheck, LookupRangeCheckConfig},
+    };
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    use super::{MessagePieceBuilder, MessagePieceChip, MessagePieceConfig};
+    use crate::{
+        circuit::gadget::assign_free_advice,
+        constants::{OrchardCommitDomains, OrchardFixedBases, OrchardHashDomains},
+    };
+
+    #[derive(Default)]
+    struct MyCircuit {
+        a: Value<pallas::Base>,
+        b: Value<pallas::Base>,
+        // Whether to claim an incorrect decomposition of `b`.
+        tamper: bool,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (
+            SinsemillaConfig<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>,
+            MessagePieceConfig,
+            Column<Advice>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                tamper: self.tamper,
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [(); 10].map(|_| meta.advice_column());
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let table_idx = meta.lookup_table_column();
+            let lookup = (
+                table_idx,
+                meta.lookup_table_column(),
+                meta.lookup_table_column(),
+            );
+            let fixed_y_q = meta.fixed_column();
+
+            let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+            let sinsemilla_config = SinsemillaChip::configure(
+                meta,
+                advices[..5].try_into().unwrap(),
+                advices[2],
+                fixed_y_q,
+                lookup,
+                range_check,
+                false,
+            );
+
+            let shift = meta.fixed_column();
+            let message_piece_config =
+                MessagePieceChip::configure(meta, advices[6], advices[7], shift, range_check);
+
+            (sinsemilla_config, message_piece_config, advices[0])
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let (sinsemilla_config, config, advice) = config;
+
+            SinsemillaChip::load(sinsemilla_config.clone(), &mut layouter)?;
+            let sinsemilla_chip = SinsemillaChip::construct(sinsemilla_config);
+            let chip = MessagePieceChip::construct(config);
+
+            let a = assign_free_advice(layouter.namespace(|| "a"), advice, self.a)?;
+            let b = assign_free_advice(layouter.namespace(|| "b"), advice, self.b)?;
+
+            // A 20-bit piece: bits 250..255 of a, then b (a 3-bit value), then
+            // bits 0..12 of a.
+            let piece = MessagePieceBuilder::new()
+                .bitrange_of(a.value(), 250..255)
+                .cell(b.clone(), 3)
+                .bitrange_of(a.value(), 0..12);
+            assert_eq!(piece.num_bits(), 20);
+            let piece = piece.build(&chip, sinsemilla_chip, layouter.namespace(|| "piece"))?;
+            assert_eq!(piece.subpieces.len(), 3);
+            assert_eq!(piece.piece.num_words(), 2);
+
+            piece
+                .piece
+                .inner()
+                .cell_value()
+                .value()
+                .zip(self.a.zip(self.b))
+                .assert_if_known(|(piece, (a, b))| {
+                    let a = a.to_le_bits();
+                    let expected = a[250..255]
+                        .iter()
+                        .by_vals()
+                        .chain(b.to_le_bits().iter().by_vals().take(3))
+                        .chain(a[0..12].iter().by_vals())
+                        .rev()
+                        .fold(pallas::Base::ZERO, |acc, bit| {
+                            acc.double() + pallas::Base::from(u64::from(bit))
+                        });
+                    **piece == expected
+                });
+
+            // Tie the first subpiece back to `a`: a = low + 2^250 * high, where `low`
+            // is the low 250 bits of `a`.
+            let low = assign_free_advice(
+                layouter.namespace(|| "low"),
+                advice,
+                self.a.map(|a| {
+                    let high = pallas::Base::from_u128(1 << 125).square()
+                        * a.to_le_bits()[250..255]
+                            .iter()
+                            .by_vals()
+                            .rev()
+                            .fold(pallas::Base::ZERO, |acc, bit| {
+                                acc.double() + pallas::Base::from(u64::from(bit))
+                            });
+                    let low = a - high;
+                    if self.tamper {
+                        low + pallas::Base::ONE
+                    } else {
+                        low
+                    }
+                }),
+            )?;
+            chip.constrain_sum(
+                layouter.namespace(|| "a = low || high"),
+                &a,
+                &[(low, 0), (piece.subpieces[0].clone(), 250)],
+            )
+        }
+    }
+
+    #[test]
+    fn message_piece_builder() {
+        let circuit = MyCircuit {
+            a: Value::known(pallas::Base::random(OsRng)),
+            b: Value::known(pallas::Base::from(5)),
+            tamper: false,
+        };
+        let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // An incorrect decomposition of `a` is rejected.
+        let circuit = MyCircuit {
+            tamper: true,
+            ..circuit
+        };
+        let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // A subpiece cell that is wider than claimed is rejected.
+        let circuit = MyCircuit {
+            a: Value::known(pallas::Base::random(OsRng)),
+            b: Value::known(pallas::Base::from(8)),
+            tamper: false,
+        };
+        let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Configuration for the chips used by [`derive_nullifier`].
+//!
+//! [`derive_nullifier`]: super::derive_nullifier
+
+use halo2_gadgets::{
+    ecc::chip::{EccChip, EccConfig},
+    poseidon::{primitives as poseidon, Pow5Chip as PoseidonChip, Pow5Config as PoseidonConfig},
+    utilities::lookup_range_check::LookupRangeCheckConfig,
+};
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Fixed};
+use pasta_curves::pallas;
+
+use super::{
+    add_chip::{AddChip, AddConfig},
+    OrchardEccChip,
+};
+use crate::constants::{sinsemilla::K, OrchardFixedBases};
+
+/// Configuration for the Poseidon, addition, and ECC chips required by
+/// [`derive_nullifier`](super::derive_nullifier), using the same column layout as the
+/// Action circuit.
+#[derive(Clone, Debug)]
+pub struct DeriveNullifierConfig {
+    poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    add_config: AddConfig,
+    ecc_config: EccConfig<OrchardFixedBases>,
+}
+
+impl DeriveNullifierConfig {
+    /// Configures the chips required by [`derive_nullifier`](super::derive_nullifier).
+    ///
+    /// The caller must:
+    /// - enable equality on all of `advices`;
+    /// - enable `lagrange_coeffs[0]` as a constant column;
+    /// - load the lookup table of `range_check` during synthesis.
+    ///
+    /// As in the Action circuit, the Poseidon chip shares its fixed columns with the ECC
+    /// chip, so `lagrange_coeffs` are also used for the Poseidon round constants.
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advices: [Column<Advice>; 10],
+        lagrange_coeffs: [Column<Fixed>; 8],
+        range_check: LookupRangeCheckConfig<pallas::Base, K>,
+    ) -> Self {
+        let add_config = AddChip::configure(meta, advices[7], advices[8], advices[6]);
+
+        let ecc_config =
+            EccChip::<OrchardFixedBases>::configure(meta, advices, lagrange_coeffs, range_check);
+
+        let poseidon_config = PoseidonChip::configure::<poseidon::P128Pow5T3>(
+            meta,
+            advices[6..9].try_into().unwrap(),
+            advices[5],
+            lagrange_coeffs[2..5].try_into().unwrap(),
+            lagrange_coeffs[5..8].try_into().unwrap(),
+        );
+
+        DeriveNullifierConfig {
+            poseidon_config,
+            add_config,
+            ecc_config,
+        }
+    }
+
+    /// Constructs the Poseidon chip.
+    pub fn poseidon_chip(&self) -> PoseidonChip<pallas::Base, 3, 2> {
+        PoseidonChip::construct(self.poseidon_config.clone())
+    }
+
+    /// Constructs the addition chip.
+    pub fn add_chip(&self) -> AddChip {
+        AddChip::construct(self.add_config.clone())
+    }
+
+    /// Constructs the ECC chip.
+    pub fn ecc_chip(&self) -> OrchardEccChip {
+        EccChip::construct(self.ecc_config.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use group::Curve;
+    use halo2_gadgets::{
+        ecc::Point,
+        utilities::lookup_range_check::{LookupRangeCheck, LookupRangeCheckConfig},
+    };
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{self, Advice, Circuit, Column, ConstraintSystem, Instance},
+    };
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    use super::DeriveNullifierConfig;
+    use crate::{
+        circuit::gadget::{assign_free_advice, derive_nullifier},
+        constants::sinsemilla::K,
+        note::Note,
+    };
+
+    #[derive(Default)]
+    struct MyCircuit {
+        nk: Value<pallas::Base>,
+        rho: Value<pallas::Base>,
+        psi: Value<pallas::Base>,
+        cm: Value<pallas::Affine>,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (
+            DeriveNullifierConfig,
+            LookupRangeCheckConfig<pallas::Base, K>,
+            Column<Advice>,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [(); 10].map(|_| meta.advice_column());
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            let lagrange_coeffs = [(); 8].map(|_| meta.fixed_column());
+            meta.enable_constant(lagrange_coeffs[0]);
+
+            let primary = meta.instance_column();
+            meta.enable_equality(primary);
+
+            let table_idx = meta.lookup_table_column();
+            let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+
+            (
+                DeriveNullifierConfig::configure(meta, advices, lagrange_coeffs, range_check),
+                range_check,
+                advices[0],
+                primary,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), plonk::Error> {
+            let (config, range_check, advice, primary) = config;
+            range_check.load(&mut layouter)?;
+
+            let ecc_chip = config.ecc_chip();
+
+            let nk = assign_free_advice(layouter.namespace(|| "nk"), advice, self.nk)?;
+            let rho = assign_free_advice(layouter.namespace(|| "rho"), advice, self.rho)?;
+            let psi = assign_free_advice(layouter.namespace(|| "psi"), advice, self.psi)?;
+            let cm = Point::new(ecc_chip.clone(), layouter.namespace(|| "cm"), self.cm)?;
+
+            let nf = derive_nullifier(
+                layouter.namespace(|| "nf = DeriveNullifier_nk(rho, psi, cm)"),
+                config.poseidon_chip(),
+                config.add_chip(),
+                ecc_chip,
+                rho,
+                &psi,
+                &cm,
+                nk,
+            )?;
+
+            layouter.constrain_instance(nf.inner().cell(), primary, 0)
+        }
+    }
+
+    #[test]
+    fn derive_nullifier_matches_primitive() {
+        let mut rng = OsRng;
+
+        for _ in 0..2 {
+            let (_, fvk, note) = Note::dummy(&mut rng, None);
+            let nf = note.nullifier(&fvk);
+
+            let circuit = MyCircuit {
+                nk: Value::known(fvk.nk().inner()),
+                rho: Value::known(note.rho().into_inner()),
+                psi: Value::known(note.rseed().psi(&note.rho())),
+                cm: Value::known(note.commitment().inner().to_affine()),
+            };
+
+            let prover = MockProver::run(11, &circuit, vec![vec![nf.0]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            // A different nullifier is rejected.
+            let (_, other_fvk, _) = Note::dummy(&mut rng, None);
+            let other_nf = note.nullifier(&other_fvk);
+            let prover = MockProver::run(11, &circuit, vec![vec![other_nf.0]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
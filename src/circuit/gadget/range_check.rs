@@ -0,0 +1,244 @@
+//! Range checks for arbitrary bit lengths.
+//!
+//! The lookup range check used by the Orchard circuit constrains values in $K$-bit
+//! words, where $K$ is the width of the Sinsemilla lookup table. This gadget combines
+//! that running sum with a final short range check, so that a value can be constrained to
+//! any number of bits $n$, not just to multiples of $K$.
+
+use ff::PrimeField;
+use halo2_gadgets::utilities::lookup_range_check::{LookupRangeCheck, LookupRangeCheckConfig};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{self, Advice, Column},
+};
+use pasta_curves::pallas;
+
+use super::assign_free_advice;
+use crate::constants::sinsemilla::K;
+
+/// Constrains `element` to be less than $2^n$, where $n$ is `num_bits`.
+///
+/// This decomposes `element` into $\lfloor n / K \rfloor$ words using `lookup`, and
+/// then constrains the remaining high bits with a short range check if $n$ is not a
+/// multiple of $K$. The cost is one lookup per $K$-bit word, plus two lookups for the
+/// short range check.
+///
+/// # Panics
+///
+/// Panics if `num_bits` is zero, or is not less than the bit length of the field.
+pub fn range_check(
+    lookup: &LookupRangeCheckConfig<pallas::Base, K>,
+    mut layouter: impl Layouter<pallas::Base>,
+    element: AssignedCell<pallas::Base, pallas::Base>,
+    num_bits: usize,
+) -> Result<(), plonk::Error> {
+    assert!(num_bits > 0 && num_bits < pallas::Base::NUM_BITS as usize);
+
+    let num_words = num_bits / K;
+    let num_short_bits = num_bits % K;
+
+    if num_words == 0 {
+        return lookup.copy_short_check(
+            layouter.namespace(|| format!("{}-bit short range check", num_short_bits)),
+            element,
+            num_short_bits,
+        );
+    }
+
+    // If `num_bits` is a multiple of `K`, the running sum must be strict. Otherwise, the
+    // final output of the running sum holds the top `num_short_bits` bits of `element`.
+    let zs = lookup.copy_check(
+        layouter.namespace(|| format!("{}-word running sum", num_words)),
+        element,
+        num_words,
+        num_short_bits == 0,
+    )?;
+
+    if num_short_bits > 0 {
+        lookup.copy_short_check(
+            layouter.namespace(|| format!("{}-bit final word", num_short_bits)),
+            zs[num_words].clone(),
+            num_short_bits,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Witnesses `value` in `column`, and constrains it to be less than $2^n$, where $n$ is
+/// `num_bits`.
+///
+/// This is [`range_check`] applied to a freshly witnessed cell, for values that must be
+/// small but are not otherwise constrained by the gadget that loads them. `column` must
+/// have equality enabled.
+///
+/// # Panics
+///
+/// Panics if `num_bits` is zero, or is not less than the bit length of the field.
+pub fn load_private_range(
+    lookup: &LookupRangeCheckConfig<pallas::Base, K>,
+    mut layouter: impl Layouter<pallas::Base>,
+    column: Column<Advice>,
+    value: Value<pallas::Base>,
+    num_bits: usize,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+    let cell = assign_free_advice(layouter.namespace(|| "load private"), column, value)?;
+    range_check(
+        lookup,
+        layouter.namespace(|| "range check"),
+        cell.clone(),
+        num_bits,
+    )?;
+    Ok(cell)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{circuit::gadget::assign_free_advice, constants::sinsemilla::K};
+
+    use ff::Field;
+    use halo2_gadgets::utilities::lookup_range_check::{LookupRangeCheck, LookupRangeCheckConfig};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+    use pasta_curves::pallas;
+
+    struct MyCircuit {
+        element: Value<pallas::Base>,
+        num_bits: usize,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (LookupRangeCheckConfig<pallas::Base, K>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            MyCircuit {
+                element: Value::unknown(),
+                num_bits: self.num_bits,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let running_sum = meta.advice_column();
+            let advice = meta.advice_column();
+            meta.enable_equality(running_sum);
+            meta.enable_equality(advice);
+
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let table_idx = meta.lookup_table_column();
+
+            (
+                LookupRangeCheckConfig::configure(meta, running_sum, table_idx),
+                advice,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let (range_check, advice) = config;
+
+            // Load the 10-bit lookup table.
+            range_check.load(&mut layouter)?;
+
+            let element =
+                assign_free_advice(layouter.namespace(|| "element"), advice, self.element)?;
+
+            super::range_check(
+                &range_check,
+                layouter.namespace(|| "range check"),
+                element,
+                self.num_bits,
+            )
+        }
+    }
+
+    /// Loads the element with [`super::load_private_range`] instead of range-checking an
+    /// already-assigned cell.
+    struct LoadCircuit(MyCircuit);
+
+    impl Circuit<pallas::Base> for LoadCircuit {
+        type Config = <MyCircuit as Circuit<pallas::Base>>::Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            LoadCircuit(self.0.without_witnesses())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            MyCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            (range_check, advice): Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            range_check.load(&mut layouter)?;
+
+            let element = super::load_private_range(
+                &range_check,
+                layouter.namespace(|| "load element"),
+                advice,
+                self.0.element,
+                self.0.num_bits,
+            )?;
+            element
+                .value()
+                .zip(self.0.element)
+                .assert_if_known(|(loaded, element)| *loaded == element);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn range_check() {
+        let two = pallas::Base::from(2);
+
+        for num_bits in [3, 20, 130, 253] {
+            let bound = two.pow([num_bits as u64, 0, 0, 0]);
+
+            // The largest value in range passes.
+            let circuit = MyCircuit {
+                element: Value::known(bound - pallas::Base::ONE),
+                num_bits,
+            };
+            let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "num_bits = {}", num_bits);
+
+            // The smallest value out of range fails.
+            let circuit = MyCircuit {
+                element: Value::known(bound),
+                num_bits,
+            };
+            let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err(), "num_bits = {}", num_bits);
+        }
+    }
+
+    #[test]
+    fn load_private_range() {
+        let bound = pallas::Base::from(1 << 20);
+
+        let circuit = LoadCircuit(MyCircuit {
+            element: Value::known(bound - pallas::Base::ONE),
+            num_bits: 20,
+        });
+        let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let circuit = LoadCircuit(MyCircuit {
+            element: Value::known(bound),
+            num_bits: 20,
+        });
+        let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! Addition of 64-bit values with an overflow flag.
+
+use ff::PrimeField;
+use halo2_gadgets::utilities::{bool_check, lookup_range_check::LookupRangeCheckConfig};
+use halo2_proofs::{
+    circuit::{AssignedCell, Chip, Layouter},
+    plonk::{self, Advice, Column, ConstraintSystem, Constraints, Selector},
+    poly::Rotation,
+};
+use pasta_curves::pallas;
+
+use super::range_check::range_check;
+use crate::constants::{sinsemilla::K, L_VALUE};
+
+/// Configuration for a [`U64AddChip`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct U64AddConfig {
+    q_add: Selector,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    sum: Column<Advice>,
+    overflow: Column<Advice>,
+    lookup: LookupRangeCheckConfig<pallas::Base, K>,
+}
+
+/// The outputs of [`U64AddChip::add`].
+#[derive(Clone, Debug)]
+pub struct U64Sum {
+    /// $(a + b) \bmod 2^{64}$, constrained to 64 bits.
+    pub sum: AssignedCell<pallas::Base, pallas::Base>,
+    /// 1 if $a + b \geq 2^{64}$, and 0 otherwise.
+    pub overflow: AssignedCell<pallas::Base, pallas::Base>,
+}
+
+/// A chip that adds two 64-bit values, such as note values, and exposes whether the
+/// addition overflowed.
+///
+/// The chip constrains
+///
+/// $$a + b = \mathsf{sum} + 2^{64} \cdot \mathsf{overflow}$$
+///
+/// where $\mathsf{overflow}$ is boolean and $\mathsf{sum}$ is range-constrained to
+/// 64 bits with the lookup range check. Given $a, b < 2^{64}$ this uniquely determines
+/// both outputs; callers are responsible for range-constraining the inputs.
+#[derive(Clone, Debug)]
+pub struct U64AddChip {
+    config: U64AddConfig,
+}
+
+impl Chip<pallas::Base> for U64AddChip {
+    type Config = U64AddConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl U64AddChip {
+    /// Configures this chip for use in a circuit.
+    ///
+    /// All of the given advice columns must have equality enabled. `lookup` may be the
+    /// lookup range check configuration shared with the rest of the circuit; its table
+    /// must be loaded separately.
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        advices: [Column<Advice>; 4],
+        lookup: LookupRangeCheckConfig<pallas::Base, K>,
+    ) -> U64AddConfig {
+        let q_add = meta.selector();
+
+        let config = U64AddConfig {
+            q_add,
+            a: advices[0],
+            b: advices[1],
+            sum: advices[2],
+            overflow: advices[3],
+            lookup,
+        };
+
+        meta.create_gate("64-bit addition with overflow", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let a = meta.query_advice(config.a, Rotation::cur());
+            let b = meta.query_advice(config.b, Rotation::cur());
+            let sum = meta.query_advice(config.sum, Rotation::cur());
+            let overflow = meta.query_advice(config.overflow, Rotation::cur());
+
+            let two_pow_64 = pallas::Base::from_u128(1 << 64);
+
+            Constraints::with_selector(
+                q_add,
+                [
+                    (
+                        "a + b = sum + 2^64 * overflow",
+                        a + b - sum - overflow.clone() * two_pow_64,
+                    ),
+                    ("overflow is bool", bool_check(overflow)),
+                ],
+            )
+        });
+
+        config
+    }
+
+    /// Constructs a chip from its configuration.
+    pub fn construct(config: U64AddConfig) -> Self {
+        Self { config }
+    }
+
+    /// Adds `a` and `b`, which must already be constrained to 64 bits.
+    pub fn add(
+        &self,
+        mut layouter: impl Layouter<pallas::Base>,
+        a: &AssignedCell<pallas::Base, pallas::Base>,
+        b: &AssignedCell<pallas::Base, pallas::Base>,
+    ) -> Result<U64Sum, plonk::Error> {
+        let result = layouter.assign_region(
+            || "a + b = sum + 2^64 * overflow",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+
+                let a = a.copy_advice(|| "copy a", &mut region, self.config.a, 0)?;
+                let b = b.copy_advice(|| "copy b", &mut region, self.config.b, 0)?;
+
+                // The inputs are at most 64 bits, so their sum fits in a u128.
+                let total = a.value().zip(b.value()).map(|(a, b)| {
+                    u128::from_le_bytes(a.to_repr()[..16].try_into().unwrap())
+                        + u128::from_le_bytes(b.to_repr()[..16].try_into().unwrap())
+                });
+
+                let sum = region.assign_advice(
+                    || "sum",
+                    self.config.sum,
+                    0,
+                    || total.map(|total| pallas::Base::from(total as u64)),
+                )?;
+                let overflow = region.assign_advice(
+                    || "overflow",
+                    self.config.overflow,
+                    0,
+                    || total.map(|total| pallas::Base::from((total >> 64) as u64)),
+                )?;
+
+                Ok(U64Sum { sum, overflow })
+            },
+        )?;
+
+        range_check(
+            &self.config.lookup,
+            layouter.namespace(|| "sum < 2^64"),
+            result.sum.clone(),
+            L_VALUE,
+        )?;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{U64AddChip, U64AddConfig};
+    use crate::{
+        circuit::gadget::{assign_free_advice, range_check::range_check},
+        constants::{sinsemilla::K, L_VALUE},
+    };
+
+    use ff::PrimeField;
+    use halo2_gadgets::utilities::lookup_range_check::{LookupRangeCheck, LookupRangeCheckConfig};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use pasta_curves::pallas;
+
+    #[derive(Default)]
+    struct MyCircuit {
+        a: Value<u64>,
+        b: Value<u64>,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = U64AddConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+                meta.advice_column(),
+            ];
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let table_idx = meta.lookup_table_column();
+            let lookup = LookupRangeCheckConfig::configure(meta, advices[0], table_idx);
+
+            U64AddChip::configure(meta, advices, lookup)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            config.lookup.load(&mut layouter)?;
+            let chip = U64AddChip::construct(config.clone());
+
+            let a = assign_free_advice(
+                layouter.namespace(|| "a"),
+                config.a,
+                self.a.map(pallas::Base::from),
+            )?;
+            let b = assign_free_advice(
+                layouter.namespace(|| "b"),
+                config.b,
+                self.b.map(pallas::Base::from),
+            )?;
+
+            let result = chip.add(layouter.namespace(|| "a + b"), &a, &b)?;
+
+            self.a
+                .zip(self.b)
+                .zip(result.sum.value().zip(result.overflow.value()))
+                .assert_if_known(|((a, b), (sum, overflow))| {
+                    let (expected_sum, expected_overflow) = a.overflowing_add(*b);
+                    **sum == pallas::Base::from(expected_sum)
+                        && **overflow == pallas::Base::from(u64::from(expected_overflow))
+                });
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn u64_add() {
+        for (a, b) in [
+            (0, 0),
+            (1, 2),
+            (u64::MAX, 0),
+            (u64::MAX, 1),
+            (u64::MAX, u64::MAX),
+            (1 << 63, 1 << 63),
+        ] {
+            let circuit = MyCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+            };
+            let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()), "a = {}, b = {}", a, b);
+        }
+    }
+
+    /// Assigns an addition row directly with the claimed `sum` and `overflow`, and
+    /// range-constrains `sum` as [`U64AddChip::add`] does.
+    struct WrongSum {
+        a: u64,
+        b: u64,
+        sum: pallas::Base,
+        overflow: pallas::Base,
+    }
+
+    impl Circuit<pallas::Base> for WrongSum {
+        type Config = U64AddConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            unimplemented!()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            MyCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            config.lookup.load(&mut layouter)?;
+            let sum = layouter.assign_region(
+                || "wrong sum",
+                |mut region| {
+                    config.q_add.enable(&mut region, 0)?;
+                    for (column, value) in [
+                        (config.a, pallas::Base::from(self.a)),
+                        (config.b, pallas::Base::from(self.b)),
+                        (config.overflow, self.overflow),
+                    ] {
+                        region.assign_advice(|| "", column, 0, || Value::known(value))?;
+                    }
+                    region.assign_advice(|| "sum", config.sum, 0, || Value::known(self.sum))
+                },
+            )?;
+            range_check(
+                &config.lookup,
+                layouter.namespace(|| "sum < 2^64"),
+                sum,
+                L_VALUE,
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn u64_add_rejects_wrong_sum() {
+        let two_pow_64 = pallas::Base::from_u128(1 << 64);
+        for (a, b, sum, overflow) in [
+            // The wrapped sum, without the overflow flag.
+            (u64::MAX, 1, pallas::Base::zero(), pallas::Base::zero()),
+            // The correct sum, with an overflow flag that was not needed.
+            (1, 2, pallas::Base::from(3), pallas::Base::one()),
+            // A non-boolean overflow flag.
+            (1, 2, pallas::Base::from(3), pallas::Base::from(2)),
+            // A sum that satisfies the gate, but is not 64 bits.
+            (
+                1,
+                2,
+                pallas::Base::from(3) - two_pow_64,
+                pallas::Base::one(),
+            ),
+        ] {
+            let circuit = WrongSum {
+                a,
+                b,
+                sum,
+                overflow,
+            };
+            let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err(), "a = {}, b = {}", a, b);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Structure-of-arrays layout of verification inputs.
+//!
+//! Accelerated verifiers (for example, GPU kernels) generally want the public inputs of
+//! many proofs as a handful of flat, fixed-stride buffers rather than as a list of
+//! per-proof objects. [`VerificationInputs`] collects proofs and their instances into
+//! such a layout, and [`VerificationInputs::verify`] is a reference consumer of that
+//! layout that checks every proof on the CPU.
+
+use alloc::vec::Vec;
+
+use ff::PrimeField;
+use halo2_proofs::plonk;
+use pasta_curves::vesta;
+
+use super::{Instance, PreparedInstance, Proof, VerifyingKey};
+
+/// The number of public inputs to the Action circuit.
+pub const NUM_PUBLIC_INPUTS: usize = 9;
+
+/// The size in bytes of each encoded public input.
+pub const PUBLIC_INPUT_SIZE: usize = 32;
+
+/// The alignment in bytes of each proof within [`VerificationInputs::proofs`].
+pub const PROOF_ALIGNMENT: usize = 64;
+
+/// The public inputs and proofs for a batch of Action proofs, in structure-of-arrays
+/// form.
+///
+/// - Public inputs are stored column-wise: for each of the [`NUM_PUBLIC_INPUTS`] public
+///   inputs there is one buffer, holding that input for every instance in the batch as a
+///   sequence of [`PUBLIC_INPUT_SIZE`]-byte little-endian field element encodings. The
+///   columns are ordered as the instance column of the circuit, so column `j` of
+///   instance `i` is at byte offset `i * PUBLIC_INPUT_SIZE` of `public_inputs(j)`.
+/// - Proof transcripts are concatenated into a single buffer, with each proof starting
+///   at a multiple of [`PROOF_ALIGNMENT`] bytes and padded with zeroes.
+/// - Instances belonging to proof `p` are those in
+///   `instance_offsets()[p]..instance_offsets()[p + 1]`.
+#[derive(Clone, Debug)]
+pub struct VerificationInputs {
+    public_inputs: [Vec<u8>; NUM_PUBLIC_INPUTS],
+    instance_offsets: Vec<u32>,
+    proofs: Vec<u8>,
+    proof_offsets: Vec<u64>,
+    proof_lengths: Vec<u32>,
+}
+
+impl Default for VerificationInputs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VerificationInputs {
+    /// Constructs an empty batch.
+    pub fn new() -> Self {
+        VerificationInputs {
+            public_inputs: Default::default(),
+            instance_offsets: vec![0],
+            proofs: vec![],
+            proof_offsets: vec![],
+            proof_lengths: vec![],
+        }
+    }
+
+    /// Adds a proof and the instances it was created for to the batch.
+    pub fn push(&mut self, proof: &Proof, instances: &[Instance]) {
+        for instance in instances {
+            let [prepared] = instance.prepare().0;
+            for (column, input) in self.public_inputs.iter_mut().zip(prepared.iter()) {
+                column.extend_from_slice(&input.to_repr());
+            }
+        }
+        self.instance_offsets.push(
+            self.num_instances()
+                .try_into()
+                .expect("fewer than 2^32 instances"),
+        );
+
+        self.proof_offsets.push(self.proofs.len() as u64);
+        self.proof_lengths.push(
+            proof
+                .as_ref()
+                .len()
+                .try_into()
+                .expect("proofs are shorter than 2^32 bytes"),
+        );
+        self.proofs.extend_from_slice(proof.as_ref());
+        let padding = (PROOF_ALIGNMENT - self.proofs.len() % PROOF_ALIGNMENT) % PROOF_ALIGNMENT;
+        self.proofs.resize(self.proofs.len() + padding, 0);
+    }
+
+    /// Returns the number of proofs in the batch.
+    pub fn num_proofs(&self) -> usize {
+        self.proof_lengths.len()
+    }
+
+    /// Returns the total number of instances in the batch.
+    pub fn num_instances(&self) -> usize {
+        self.public_inputs[0].len() / PUBLIC_INPUT_SIZE
+    }
+
+    /// Returns the buffer containing public input `column` for every instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `column >= NUM_PUBLIC_INPUTS`.
+    pub fn public_inputs(&self, column: usize) -> &[u8] {
+        &self.public_inputs[column]
+    }
+
+    /// Returns the offsets into the instance columns at which each proof's instances
+    /// begin, followed by the total number of instances.
+    pub fn instance_offsets(&self) -> &[u32] {
+        &self.instance_offsets
+    }
+
+    /// Returns the buffer containing all proof transcripts.
+    pub fn proofs(&self) -> &[u8] {
+        &self.proofs
+    }
+
+    /// Returns the byte offset of each proof within [`Self::proofs`].
+    pub fn proof_offsets(&self) -> &[u64] {
+        &self.proof_offsets
+    }
+
+    /// Returns the unpadded length in bytes of each proof.
+    pub fn proof_lengths(&self) -> &[u32] {
+        &self.proof_lengths
+    }
+
+    /// Reads instance `index` back out of the column buffers.
+    fn prepared_instance(&self, index: usize) -> PreparedInstance {
+        let mut instance = [vesta::Scalar::zero(); NUM_PUBLIC_INPUTS];
+        for (input, column) in instance.iter_mut().zip(self.public_inputs.iter()) {
+            let start = index * PUBLIC_INPUT_SIZE;
+            let repr = column[start..start + PUBLIC_INPUT_SIZE].try_into().unwrap();
+            *input = vesta::Scalar::from_repr(repr).unwrap();
+        }
+        PreparedInstance([instance])
+    }
+
+    /// Verifies every proof in the batch, reading its inputs from the structure-of-arrays
+    /// buffers.
+    ///
+    /// This is a reference consumer of the layout, intended for testing alternative
+    /// verifier implementations against.
+    pub fn verify(&self, vk: &VerifyingKey) -> Result<(), plonk::Error> {
+        for p in 0..self.num_proofs() {
+            let instances: Vec<_> = (self.instance_offsets[p] as usize
+                ..self.instance_offsets[p + 1] as usize)
+                .map(|i| self.prepared_instance(i))
+                .collect();
+
+            let start = self.proof_offsets[p] as usize;
+            let proof =
+                Proof::new(self.proofs[start..start + self.proof_lengths[p] as usize].to_vec());
+
+            proof.verify_prepared(vk, &instances)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::rngs::OsRng;
+
+    use super::{VerificationInputs, PROOF_ALIGNMENT, PUBLIC_INPUT_SIZE};
+    use crate::{circuit::tests::generate_circuit_instance, Proof};
+
+    #[test]
+    fn layout() {
+        let mut rng = OsRng;
+        let instances: Vec<_> = (0..3)
+            .map(|_| generate_circuit_instance(&mut rng).1)
+            .collect();
+
+        let mut inputs = VerificationInputs::new();
+        inputs.push(&Proof::new(vec![1; 100]), &instances[..1]);
+        inputs.push(&Proof::new(vec![2; 64]), &instances[1..]);
+
+        assert_eq!(inputs.num_proofs(), 2);
+        assert_eq!(inputs.num_instances(), 3);
+        assert_eq!(inputs.instance_offsets(), &[0, 1, 3]);
+        assert_eq!(inputs.proof_offsets(), &[0, 2 * PROOF_ALIGNMENT as u64]);
+        assert_eq!(inputs.proof_lengths(), &[100, 64]);
+        assert_eq!(inputs.proofs().len(), 3 * PROOF_ALIGNMENT);
+        assert_eq!(inputs.public_inputs(0).len(), 3 * PUBLIC_INPUT_SIZE);
+
+        for (i, instance) in instances.iter().enumerate() {
+            assert_eq!(inputs.prepared_instance(i), instance.prepare());
+        }
+    }
+}
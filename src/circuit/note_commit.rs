@@ -28,6 +28,9 @@ use halo2_gadgets::{
     },
 };
 
+#[cfg(feature = "unstable-zsa")]
+pub(in crate::circuit) mod zsa;
+
 type NoteCommitPiece = MessagePiece<
     pallas::Affine,
     SinsemillaChip<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>,
@@ -1412,6 +1415,8 @@ impl YCanonicity {
     }
 }
 
+/// Configuration for the decomposition and canonicity gates used by
+/// [`gadgets::note_commit`].
 #[allow(non_snake_case)]
 #[derive(Clone, Debug)]
 pub struct NoteCommitConfig {
@@ -1431,15 +1436,21 @@ pub struct NoteCommitConfig {
         SinsemillaConfig<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>,
 }
 
+/// Chip enforcing the decomposition and canonicity of the message in
+/// [`gadgets::note_commit`].
 #[derive(Clone, Debug)]
 pub struct NoteCommitChip {
     config: NoteCommitConfig,
 }
 
 impl NoteCommitChip {
+    /// Configures the NoteCommit gates.
+    ///
+    /// `advices` must be the same columns as those given to the ECC chip, and must have
+    /// equality enabled.
     #[allow(non_snake_case)]
     #[allow(clippy::many_single_char_names)]
-    pub(in crate::circuit) fn configure(
+    pub fn configure(
         meta: &mut ConstraintSystem<pallas::Base>,
         advices: [Column<Advice>; 10],
         sinsemilla_config: SinsemillaConfig<
@@ -1558,7 +1569,8 @@ impl NoteCommitChip {
         }
     }
 
-    pub(in crate::circuit) fn construct(config: NoteCommitConfig) -> Self {
+    /// Constructs a chip from its configuration.
+    pub fn construct(config: NoteCommitConfig) -> Self {
         Self { config }
     }
 }
@@ -1568,10 +1580,13 @@ pub(in crate::circuit) mod gadgets {
 
     use super::*;
 
+    /// $\mathsf{NoteCommit}^\mathsf{Orchard}$ from [Section 5.4.8.4 Sinsemilla commitments].
+    ///
+    /// [Section 5.4.8.4 Sinsemilla commitments]: https://zips.z.cash/protocol/protocol.pdf#concretesinsemillacommit
     #[allow(clippy::many_single_char_names)]
     #[allow(clippy::type_complexity)]
     #[allow(clippy::too_many_arguments)]
-    pub(in crate::circuit) fn note_commit(
+    pub fn note_commit(
         mut layouter: impl Layouter<pallas::Base>,
         chip: SinsemillaChip<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>,
         ecc_chip: EccChip<OrchardFixedBases>,
@@ -1781,7 +1796,7 @@ pub(in crate::circuit) mod gadgets {
     /// Specifications:
     /// - [`g_d` canonicity](https://p.z.cash/orchard-0.1:note-commit-canonicity-g_d?partial)
     /// - [`y` canonicity](https://p.z.cash/orchard-0.1:note-commit-canonicity-y?partial)
-    fn canon_bitshift_130(
+    pub(super) fn canon_bitshift_130(
         lookup_config: &LookupRangeCheckConfig<pallas::Base, 10>,
         mut layouter: impl Layouter<pallas::Base>,
         a: AssignedCell<pallas::Base, pallas::Base>,
@@ -1815,7 +1830,7 @@ pub(in crate::circuit) mod gadgets {
     /// Check canonicity of `x(pk_d)` encoding.
     ///
     /// [Specification](https://p.z.cash/orchard-0.1:note-commit-canonicity-pk_d?partial).
-    fn pkd_x_canonicity(
+    pub(super) fn pkd_x_canonicity(
         lookup_config: &LookupRangeCheckConfig<pallas::Base, 10>,
         mut layouter: impl Layouter<pallas::Base>,
         b_3: RangeConstrained<pallas::Base, AssignedCell<pallas::Base, pallas::Base>>,
@@ -1856,7 +1871,7 @@ pub(in crate::circuit) mod gadgets {
     /// Check canonicity of `rho` encoding.
     ///
     /// [Specification](https://p.z.cash/orchard-0.1:note-commit-canonicity-rho?partial).
-    fn rho_canonicity(
+    pub(super) fn rho_canonicity(
         lookup_config: &LookupRangeCheckConfig<pallas::Base, 10>,
         mut layouter: impl Layouter<pallas::Base>,
         e_1: RangeConstrained<pallas::Base, AssignedCell<pallas::Base, pallas::Base>>,
@@ -1897,7 +1912,7 @@ pub(in crate::circuit) mod gadgets {
     /// Check canonicity of `psi` encoding.
     ///
     /// [Specification](https://p.z.cash/orchard-0.1:note-commit-canonicity-psi?partial).
-    fn psi_canonicity(
+    pub(super) fn psi_canonicity(
         lookup_config: &LookupRangeCheckConfig<pallas::Base, 10>,
         mut layouter: impl Layouter<pallas::Base>,
         g_1: RangeConstrained<pallas::Base, AssignedCell<pallas::Base, pallas::Base>>,
@@ -1939,7 +1954,7 @@ pub(in crate::circuit) mod gadgets {
     /// Specifications:
     /// - [`y` decomposition](https://p.z.cash/orchard-0.1:note-commit-decomposition-y?partial)
     /// - [`y` canonicity](https://p.z.cash/orchard-0.1:note-commit-canonicity-y?partial)
-    fn y_canonicity(
+    pub(super) fn y_canonicity(
         lookup_config: &LookupRangeCheckConfig<pallas::Base, 10>,
         y_canon: &YCanonicity,
         mut layouter: impl Layouter<pallas::Base>,
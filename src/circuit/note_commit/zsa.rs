@@ -0,0 +1,806 @@
+//! The NoteCommit gadget extended with an asset base, for ZSA note commitments.
+//!
+//! [ZIP 226] defines $\mathsf{NoteCommit}^\mathsf{OrchardZSA}$ to coincide with
+//! $\mathsf{NoteCommit}^\mathsf{Orchard}$ for notes of the native asset. For any other
+//! asset, the commitment is
+//!
+//! $$\mathsf{SinsemillaCommit}_\mathsf{rcm}(\texttt{"z.cash:ZSA-NoteCommit"},
+//!   g★_d \,||\, \mathsf{pk}★_d \,||\, \mathsf{I2LEBSP}_{64}(v) \,||\, \rho \,||\, \psi
+//!   \,||\, \mathsf{AssetBase}★)$$
+//!
+//! where the blinding term uses the Orchard NoteCommit generator $R$.
+//!
+//! The message shares its first 1086 bits with the Orchard note commitment, so the
+//! pieces `a` through `g` are decomposed and checked by the same gates as in
+//! [`note_commit`](super::gadgets::note_commit). The final pieces become:
+//!
+//! - `h = h_0 || h_1 || h_2`
+//!   `= (bits 249..=253 of psi) || (bit 254 of psi) || (bits 0..=3 of x(asset))`
+//! - `i = bits 4..=253 of x(asset)`
+//! - `j = j_0 || j_1 || j_2 = (bit 254 of x(asset)) || (ỹ bit of asset) || 8 zero bits`
+//!
+//! The canonicity of `x(asset)` is checked with the `pk_d` gate, which constrains the
+//! same `4 || 250 || 1`-bit decomposition, and `y(asset)` with the shared `y` gate.
+//!
+//! [ZIP 226]: https://zips.z.cash/zip-0226
+
+use halo2_proofs::circuit::Chip;
+
+use super::*;
+
+/// h = h_0 || h_1 || h_2
+///   = (bits 249..=253 of psi) || (bit 254 of psi) || (bits 0..=3 of x(asset))
+///
+/// | A_6 | A_7 | A_8 | q_notecommit_h_zsa |
+/// ----------------------------------------
+/// |  h  | h_0 | h_1 |          1         |
+/// |     | h_2 |     |          0         |
+#[derive(Clone, Debug)]
+struct DecomposeHZsa {
+    q_notecommit_h_zsa: Selector,
+    col_l: Column<Advice>,
+    col_m: Column<Advice>,
+    col_r: Column<Advice>,
+}
+
+impl DecomposeHZsa {
+    fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        col_l: Column<Advice>,
+        col_m: Column<Advice>,
+        col_r: Column<Advice>,
+        two_pow_5: pallas::Base,
+        two_pow_6: pallas::Base,
+    ) -> Self {
+        let q_notecommit_h_zsa = meta.selector();
+
+        meta.create_gate("ZSA NoteCommit MessagePiece h", |meta| {
+            let q_notecommit_h_zsa = meta.query_selector(q_notecommit_h_zsa);
+
+            // h has been constrained to 10 bits by the Sinsemilla hash.
+            let h = meta.query_advice(col_l, Rotation::cur());
+            // h_0 has been constrained to be 5 bits outside this gate.
+            let h_0 = meta.query_advice(col_m, Rotation::cur());
+            // This gate constrains h_1 to be boolean.
+            let h_1 = meta.query_advice(col_r, Rotation::cur());
+            // h_2 has been constrained to be 4 bits outside this gate.
+            let h_2 = meta.query_advice(col_m, Rotation::next());
+
+            // h = h_0 + (2^5) h_1 + (2^6) h_2
+            let decomposition_check = h - (h_0 + h_1.clone() * two_pow_5 + h_2 * two_pow_6);
+
+            Constraints::with_selector(
+                q_notecommit_h_zsa,
+                [
+                    ("bool_check h_1", bool_check(h_1)),
+                    ("decomposition", decomposition_check),
+                ],
+            )
+        });
+
+        Self {
+            q_notecommit_h_zsa,
+            col_l,
+            col_m,
+            col_r,
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn decompose(
+        lookup_config: &LookupRangeCheckConfig<pallas::Base, 10>,
+        chip: SinsemillaChip<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>,
+        layouter: &mut impl Layouter<pallas::Base>,
+        psi: &AssignedCell<pallas::Base, pallas::Base>,
+        asset: &NonIdentityEccPoint,
+    ) -> Result<
+        (
+            NoteCommitPiece,
+            RangeConstrained<pallas::Base, AssignedCell<pallas::Base, pallas::Base>>,
+            RangeConstrained<pallas::Base, Value<pallas::Base>>,
+            RangeConstrained<pallas::Base, AssignedCell<pallas::Base, pallas::Base>>,
+        ),
+        Error,
+    > {
+        // Constrain h_0 to be 5 bits.
+        let h_0 = RangeConstrained::witness_short(
+            lookup_config,
+            layouter.namespace(|| "h_0"),
+            psi.value(),
+            249..254,
+        )?;
+
+        // h_1 will be boolean-constrained in the gate.
+        let h_1 = RangeConstrained::bitrange_of(psi.value(), 254..255);
+
+        // Constrain h_2 to be 4 bits.
+        let h_2 = RangeConstrained::witness_short(
+            lookup_config,
+            layouter.namespace(|| "h_2"),
+            asset.x().value(),
+            0..4,
+        )?;
+
+        let h = MessagePiece::from_subpieces(
+            chip,
+            layouter.namespace(|| "h"),
+            [h_0.value(), h_1, h_2.value()],
+        )?;
+
+        Ok((h, h_0, h_1, h_2))
+    }
+
+    fn assign(
+        &self,
+        layouter: &mut impl Layouter<pallas::Base>,
+        h: NoteCommitPiece,
+        h_0: RangeConstrained<pallas::Base, AssignedCell<pallas::Base, pallas::Base>>,
+        h_1: RangeConstrained<pallas::Base, Value<pallas::Base>>,
+        h_2: RangeConstrained<pallas::Base, AssignedCell<pallas::Base, pallas::Base>>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+        layouter.assign_region(
+            || "ZSA NoteCommit MessagePiece h",
+            |mut region| {
+                self.q_notecommit_h_zsa.enable(&mut region, 0)?;
+
+                h.inner()
+                    .cell_value()
+                    .copy_advice(|| "h", &mut region, self.col_l, 0)?;
+                h_0.inner()
+                    .copy_advice(|| "h_0", &mut region, self.col_m, 0)?;
+                let h_1 = region.assign_advice(|| "h_1", self.col_r, 0, || *h_1.inner())?;
+
+                h_2.inner()
+                    .copy_advice(|| "h_2", &mut region, self.col_m, 1)?;
+
+                Ok(h_1)
+            },
+        )
+    }
+}
+
+/// j = j_0 || j_1 || j_2
+///   = (bit 254 of x(asset)) || (ỹ bit of asset) || 8 zero bits
+///
+/// | A_6 | A_7 | A_8 | q_notecommit_j |
+/// ------------------------------------
+/// |  j  | j_0 | j_1 |       1        |
+#[derive(Clone, Debug)]
+struct DecomposeJ {
+    q_notecommit_j: Selector,
+    col_l: Column<Advice>,
+    col_m: Column<Advice>,
+    col_r: Column<Advice>,
+}
+
+impl DecomposeJ {
+    fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        col_l: Column<Advice>,
+        col_m: Column<Advice>,
+        col_r: Column<Advice>,
+        two: pallas::Base,
+    ) -> Self {
+        let q_notecommit_j = meta.selector();
+
+        meta.create_gate("ZSA NoteCommit MessagePiece j", |meta| {
+            let q_notecommit_j = meta.query_selector(q_notecommit_j);
+
+            // j has been constrained to 10 bits by the Sinsemilla hash.
+            let j = meta.query_advice(col_l, Rotation::cur());
+            // This gate constrains j_0 to be boolean.
+            let j_0 = meta.query_advice(col_m, Rotation::cur());
+            // This gate constrains j_1 to be boolean.
+            let j_1 = meta.query_advice(col_r, Rotation::cur());
+
+            // j = j_0 + (2) j_1
+            let decomposition_check = j - (j_0.clone() + j_1.clone() * two);
+
+            Constraints::with_selector(
+                q_notecommit_j,
+                [
+                    ("bool_check j_0", bool_check(j_0)),
+                    ("bool_check j_1", bool_check(j_1)),
+                    ("decomposition", decomposition_check),
+                ],
+            )
+        });
+
+        Self {
+            q_notecommit_j,
+            col_l,
+            col_m,
+            col_r,
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn decompose(
+        chip: SinsemillaChip<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>,
+        layouter: &mut impl Layouter<pallas::Base>,
+        asset: &NonIdentityEccPoint,
+    ) -> Result<
+        (
+            NoteCommitPiece,
+            RangeConstrained<pallas::Base, Value<pallas::Base>>,
+            RangeConstrained<pallas::Base, Value<pallas::Base>>,
+        ),
+        Error,
+    > {
+        // j_0, j_1 will be boolean-constrained in the gate.
+        let j_0 = RangeConstrained::bitrange_of(asset.x().value(), 254..255);
+        let j_1 = RangeConstrained::bitrange_of(asset.y().value(), 0..1);
+
+        let j = MessagePiece::from_subpieces(
+            chip,
+            layouter.namespace(|| "j"),
+            [
+                j_0,
+                j_1,
+                RangeConstrained::bitrange_of(Value::known(&pallas::Base::zero()), 0..8),
+            ],
+        )?;
+
+        Ok((j, j_0, j_1))
+    }
+
+    fn assign(
+        &self,
+        layouter: &mut impl Layouter<pallas::Base>,
+        j: NoteCommitPiece,
+        j_0: RangeConstrained<pallas::Base, Value<pallas::Base>>,
+        j_1: RangeConstrained<pallas::Base, AssignedCell<pallas::Base, pallas::Base>>,
+    ) -> Result<AssignedCell<pallas::Base, pallas::Base>, Error> {
+        layouter.assign_region(
+            || "ZSA NoteCommit MessagePiece j",
+            |mut region| {
+                self.q_notecommit_j.enable(&mut region, 0)?;
+
+                j.inner()
+                    .cell_value()
+                    .copy_advice(|| "j", &mut region, self.col_l, 0)?;
+                let j_0 = region.assign_advice(|| "j_0", self.col_m, 0, || *j_0.inner())?;
+                j_1.inner()
+                    .copy_advice(|| "j_1", &mut region, self.col_r, 0)?;
+
+                Ok(j_0)
+            },
+        )
+    }
+}
+
+/// Configuration for [`note_commit_zsa`].
+#[derive(Clone, Debug)]
+pub struct NoteCommitZsaConfig {
+    orchard: NoteCommitConfig,
+    h_zsa: DecomposeHZsa,
+    j: DecomposeJ,
+}
+
+/// Chip enforcing the decomposition and canonicity of the message in
+/// [`note_commit_zsa`].
+#[derive(Clone, Debug)]
+pub struct NoteCommitZsaChip {
+    config: NoteCommitZsaConfig,
+}
+
+impl NoteCommitZsaChip {
+    /// Configures the ZSA NoteCommit gates on top of an existing NoteCommit
+    /// configuration.
+    ///
+    /// The gates of `note_commit_config` are shared, so a circuit that computes both
+    /// Orchard and ZSA note commitments only adds the two gates for the asset pieces.
+    pub fn configure(
+        meta: &mut ConstraintSystem<pallas::Base>,
+        note_commit_config: NoteCommitConfig,
+    ) -> NoteCommitZsaConfig {
+        let two = pallas::Base::from(2);
+        let two_pow_5 = pallas::Base::from(1 << 5);
+        let two_pow_6 = pallas::Base::from(1 << 6);
+
+        let col_l = note_commit_config.advices[6];
+        let col_m = note_commit_config.advices[7];
+        let col_r = note_commit_config.advices[8];
+
+        let h_zsa = DecomposeHZsa::configure(meta, col_l, col_m, col_r, two_pow_5, two_pow_6);
+        let j = DecomposeJ::configure(meta, col_l, col_m, col_r, two);
+
+        NoteCommitZsaConfig {
+            orchard: note_commit_config,
+            h_zsa,
+            j,
+        }
+    }
+
+    /// Constructs a chip from its configuration.
+    pub fn construct(config: NoteCommitZsaConfig) -> Self {
+        Self { config }
+    }
+}
+
+/// $\mathsf{NoteCommit}^\mathsf{OrchardZSA}$ for a note of a non-native asset.
+///
+/// Notes of the native asset are committed to with
+/// [`note_commit`](super::gadgets::note_commit); circuits that accept both kinds of note
+/// must select between the two commitments themselves.
+#[allow(clippy::many_single_char_names)]
+#[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
+pub fn note_commit_zsa(
+    mut layouter: impl Layouter<pallas::Base>,
+    chip: SinsemillaChip<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>,
+    ecc_chip: EccChip<OrchardFixedBases>,
+    note_commit_chip: NoteCommitZsaChip,
+    g_d: &NonIdentityEccPoint,
+    pk_d: &NonIdentityEccPoint,
+    value: AssignedCell<NoteValue, pallas::Base>,
+    rho: AssignedCell<pallas::Base, pallas::Base>,
+    psi: AssignedCell<pallas::Base, pallas::Base>,
+    asset: &NonIdentityEccPoint,
+    rcm: ScalarFixed<pallas::Affine, EccChip<OrchardFixedBases>>,
+) -> Result<Point<pallas::Affine, EccChip<OrchardFixedBases>>, Error> {
+    let lookup_config = chip.config().lookup_config();
+
+    // `a` = bits 0..=249 of `x(g_d)`
+    let a = MessagePiece::from_subpieces(
+        chip.clone(),
+        layouter.namespace(|| "a"),
+        [RangeConstrained::bitrange_of(g_d.x().value(), 0..250)],
+    )?;
+
+    // b = b_0 || b_1 || b_2 || b_3
+    //   = (bits 250..=253 of x(g_d)) || (bit 254 of x(g_d)) || (ỹ bit of g_d) || (bits 0..=3 of pk★_d)
+    let (b, b_0, b_1, b_2, b_3) =
+        DecomposeB::decompose(&lookup_config, chip.clone(), &mut layouter, g_d, pk_d)?;
+
+    // c = bits 4..=253 of pk★_d
+    let c = MessagePiece::from_subpieces(
+        chip.clone(),
+        layouter.namespace(|| "c"),
+        [RangeConstrained::bitrange_of(pk_d.x().value(), 4..254)],
+    )?;
+
+    // d = d_0 || d_1 || d_2 || d_3
+    //   = (bit 254 of x(pk_d)) || (ỹ bit of pk_d) || (bits 0..=7 of v) || (bits 8..=57 of v)
+    let (d, d_0, d_1, d_2) =
+        DecomposeD::decompose(&lookup_config, chip.clone(), &mut layouter, pk_d, &value)?;
+
+    // e = e_0 || e_1 = (bits 58..=63 of v) || (bits 0..=3 of rho)
+    let (e, e_0, e_1) =
+        DecomposeE::decompose(&lookup_config, chip.clone(), &mut layouter, &value, &rho)?;
+
+    // f = bits 4..=253 inclusive of rho
+    let f = MessagePiece::from_subpieces(
+        chip.clone(),
+        layouter.namespace(|| "f"),
+        [RangeConstrained::bitrange_of(rho.value(), 4..254)],
+    )?;
+
+    // g = g_0 || g_1 || g_2
+    //   = (bit 254 of rho) || (bits 0..=8 of psi) || (bits 9..=248 of psi)
+    let (g, g_0, g_1) =
+        DecomposeG::decompose(&lookup_config, chip.clone(), &mut layouter, &rho, &psi)?;
+
+    // h = h_0 || h_1 || h_2
+    //   = (bits 249..=253 of psi) || (bit 254 of psi) || (bits 0..=3 of x(asset))
+    let (h, h_0, h_1, h_2) =
+        DecomposeHZsa::decompose(&lookup_config, chip.clone(), &mut layouter, &psi, asset)?;
+
+    // i = bits 4..=253 of x(asset)
+    let i = MessagePiece::from_subpieces(
+        chip.clone(),
+        layouter.namespace(|| "i"),
+        [RangeConstrained::bitrange_of(asset.x().value(), 4..254)],
+    )?;
+
+    // j = j_0 || j_1 || j_2
+    //   = (bit 254 of x(asset)) || (ỹ bit of asset) || 8 zero bits
+    let (j, j_0, j_1) = DecomposeJ::decompose(chip.clone(), &mut layouter, asset)?;
+
+    let cfg = note_commit_chip.config;
+
+    // Check decomposition of `y(g_d)`.
+    let b_2 = gadgets::y_canonicity(
+        &lookup_config,
+        &cfg.orchard.y_canon,
+        layouter.namespace(|| "y(g_d) decomposition"),
+        g_d.y(),
+        b_2,
+    )?;
+    // Check decomposition of `y(pk_d)`.
+    let d_1 = gadgets::y_canonicity(
+        &lookup_config,
+        &cfg.orchard.y_canon,
+        layouter.namespace(|| "y(pk_d) decomposition"),
+        pk_d.y(),
+        d_1,
+    )?;
+    // Check decomposition of `y(asset)`.
+    let j_1 = gadgets::y_canonicity(
+        &lookup_config,
+        &cfg.orchard.y_canon,
+        layouter.namespace(|| "y(asset) decomposition"),
+        asset.y(),
+        j_1,
+    )?;
+
+    // cm = NoteCommit^OrchardZSA_rcm(g★_d || pk★_d || i2lebsp_{64}(v) || rho || psi || asset★)
+    //
+    // As for the Orchard note commitment, `cm = ⊥` is detected during synthesis by
+    // `CommitDomain::commit`.
+    let (cm, zs) = {
+        let message = Message::from_pieces(
+            chip.clone(),
+            vec![
+                a.clone(),
+                b.clone(),
+                c.clone(),
+                d.clone(),
+                e.clone(),
+                f.clone(),
+                g.clone(),
+                h.clone(),
+                i.clone(),
+                j.clone(),
+            ],
+        );
+        let domain = CommitDomain::new(chip, ecc_chip, &OrchardCommitDomains::ZsaNoteCommit);
+        domain.commit(
+            layouter.namespace(|| "Process ZSA NoteCommit inputs"),
+            message,
+            rcm,
+        )?
+    };
+
+    // Grab the running sum outputs that we need for canonicity checks.
+    let z13_a = zs[0][13].clone();
+    let z13_c = zs[2][13].clone();
+    let z1_d = zs[3][1].clone();
+    let z13_f = zs[5][13].clone();
+    let z1_g = zs[6][1].clone();
+    let g_2 = z1_g.clone();
+    let z13_g = zs[6][13].clone();
+    let z13_i = zs[8][13].clone();
+
+    // Witness and constrain the bounds we need to ensure canonicity.
+    let (a_prime, z13_a_prime) = gadgets::canon_bitshift_130(
+        &lookup_config,
+        layouter.namespace(|| "x(g_d) canonicity"),
+        a.inner().cell_value(),
+    )?;
+
+    let (b3_c_prime, z14_b3_c_prime) = gadgets::pkd_x_canonicity(
+        &lookup_config,
+        layouter.namespace(|| "x(pk_d) canonicity"),
+        b_3.clone(),
+        c.inner().cell_value(),
+    )?;
+
+    let (e1_f_prime, z14_e1_f_prime) = gadgets::rho_canonicity(
+        &lookup_config,
+        layouter.namespace(|| "rho canonicity"),
+        e_1.clone(),
+        f.inner().cell_value(),
+    )?;
+
+    let (g1_g2_prime, z13_g1_g2_prime) = gadgets::psi_canonicity(
+        &lookup_config,
+        layouter.namespace(|| "psi canonicity"),
+        g_1.clone(),
+        g_2,
+    )?;
+
+    // `x(asset)` = `h_2 (4 bits) || i (250 bits) || j_0 (1 bit)` has the same shape as
+    // `x(pk_d)`.
+    let (h2_i_prime, z14_h2_i_prime) = gadgets::pkd_x_canonicity(
+        &lookup_config,
+        layouter.namespace(|| "x(asset) canonicity"),
+        h_2.clone(),
+        i.inner().cell_value(),
+    )?;
+
+    // Finally, assign values to all of the NoteCommit regions.
+    let b_1 = cfg
+        .orchard
+        .b
+        .assign(&mut layouter, b, b_0.clone(), b_1, b_2, b_3.clone())?;
+
+    let d_0 = cfg
+        .orchard
+        .d
+        .assign(&mut layouter, d, d_0, d_1, d_2.clone(), z1_d.clone())?;
+
+    cfg.orchard
+        .e
+        .assign(&mut layouter, e, e_0.clone(), e_1.clone())?;
+
+    let g_0 = cfg
+        .orchard
+        .g
+        .assign(&mut layouter, g, g_0, g_1.clone(), z1_g.clone())?;
+
+    let h_1 = cfg
+        .h_zsa
+        .assign(&mut layouter, h, h_0.clone(), h_1, h_2.clone())?;
+
+    let j_0 = cfg.j.assign(&mut layouter, j, j_0, j_1)?;
+
+    cfg.orchard
+        .g_d
+        .assign(&mut layouter, g_d, a, b_0, b_1, a_prime, z13_a, z13_a_prime)?;
+
+    cfg.orchard.pk_d.assign(
+        &mut layouter,
+        pk_d,
+        b_3,
+        c,
+        d_0,
+        b3_c_prime,
+        z13_c,
+        z14_b3_c_prime,
+    )?;
+
+    cfg.orchard
+        .value
+        .assign(&mut layouter, value, d_2, z1_d, e_0)?;
+
+    cfg.orchard.rho.assign(
+        &mut layouter,
+        rho,
+        e_1,
+        f,
+        g_0,
+        e1_f_prime,
+        z13_f,
+        z14_e1_f_prime,
+    )?;
+
+    cfg.orchard.psi.assign(
+        &mut layouter,
+        psi,
+        g_1,
+        z1_g,
+        h_0,
+        h_1,
+        g1_g2_prime,
+        z13_g,
+        z13_g1_g2_prime,
+    )?;
+
+    cfg.orchard.pk_d.assign(
+        &mut layouter,
+        asset,
+        h_2,
+        i,
+        j_0,
+        h2_i_prime,
+        z13_i,
+        z14_h2_i_prime,
+    )?;
+
+    Ok(cm)
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use group::{Curve, Group, GroupEncoding};
+    use halo2_gadgets::{
+        ecc::{
+            chip::{EccChip, EccConfig},
+            NonIdentityPoint, ScalarFixed,
+        },
+        sinsemilla::{chip::SinsemillaChip, primitives::HashDomain},
+        utilities::lookup_range_check::{LookupRangeCheck, LookupRangeCheckConfig},
+    };
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use pasta_curves::{arithmetic::CurveExt, pallas};
+    use rand::{rngs::OsRng, RngCore};
+
+    use super::{note_commit_zsa, NoteCommitZsaChip, NoteCommitZsaConfig};
+    use crate::{
+        circuit::{gadget::assign_free_advice, note_commit::NoteCommitChip},
+        constants::{
+            fixed_bases::NOTE_COMMITMENT_PERSONALIZATION,
+            sinsemilla::ZSA_NOTE_COMMITMENT_PERSONALIZATION, OrchardCommitDomains,
+            OrchardFixedBases, OrchardHashDomains, L_ORCHARD_BASE,
+        },
+        value::NoteValue,
+    };
+
+    #[derive(Default)]
+    struct MyCircuit {
+        g_d: Value<pallas::Affine>,
+        pk_d: Value<pallas::Affine>,
+        value: Value<NoteValue>,
+        rho: Value<pallas::Base>,
+        psi: Value<pallas::Base>,
+        asset: Value<pallas::Affine>,
+        rcm: Value<pallas::Scalar>,
+        cm: Value<pallas::Affine>,
+    }
+
+    impl Circuit<pallas::Base> for MyCircuit {
+        type Config = (NoteCommitZsaConfig, EccConfig<OrchardFixedBases>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            let advices = [(); 10].map(|_| meta.advice_column());
+            for advice in advices.iter() {
+                meta.enable_equality(*advice);
+            }
+
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let table_idx = meta.lookup_table_column();
+            let lookup = (
+                table_idx,
+                meta.lookup_table_column(),
+                meta.lookup_table_column(),
+            );
+            let lagrange_coeffs = [(); 8].map(|_| meta.fixed_column());
+
+            let range_check = LookupRangeCheckConfig::configure(meta, advices[9], table_idx);
+            let sinsemilla_config = SinsemillaChip::<
+                OrchardHashDomains,
+                OrchardCommitDomains,
+                OrchardFixedBases,
+            >::configure(
+                meta,
+                advices[..5].try_into().unwrap(),
+                advices[2],
+                lagrange_coeffs[0],
+                lookup,
+                range_check,
+                false,
+            );
+            let note_commit_config = NoteCommitChip::configure(meta, advices, sinsemilla_config);
+            let note_commit_zsa_config = NoteCommitZsaChip::configure(meta, note_commit_config);
+
+            let ecc_config = EccChip::<OrchardFixedBases>::configure(
+                meta,
+                advices,
+                lagrange_coeffs,
+                range_check,
+            );
+
+            (note_commit_zsa_config, ecc_config)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), Error> {
+            let (config, ecc_config) = config;
+            let sinsemilla_config = config.orchard.sinsemilla_config.clone();
+            let advice = config.orchard.advices[0];
+
+            SinsemillaChip::<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>::load(
+                sinsemilla_config.clone(),
+                &mut layouter,
+            )?;
+            let sinsemilla_chip = SinsemillaChip::construct(sinsemilla_config);
+            let ecc_chip = EccChip::construct(ecc_config);
+
+            let g_d =
+                NonIdentityPoint::new(ecc_chip.clone(), layouter.namespace(|| "g_d"), self.g_d)?;
+            let pk_d =
+                NonIdentityPoint::new(ecc_chip.clone(), layouter.namespace(|| "pk_d"), self.pk_d)?;
+            let asset = NonIdentityPoint::new(
+                ecc_chip.clone(),
+                layouter.namespace(|| "asset"),
+                self.asset,
+            )?;
+            let value = assign_free_advice(layouter.namespace(|| "value"), advice, self.value)?;
+            let rho = assign_free_advice(layouter.namespace(|| "rho"), advice, self.rho)?;
+            let psi = assign_free_advice(layouter.namespace(|| "psi"), advice, self.psi)?;
+            let rcm = ScalarFixed::new(ecc_chip.clone(), layouter.namespace(|| "rcm"), self.rcm)?;
+
+            let cm = note_commit_zsa(
+                layouter.namespace(|| "ZSA NoteCommit"),
+                sinsemilla_chip,
+                ecc_chip.clone(),
+                NoteCommitZsaChip::construct(config),
+                g_d.inner(),
+                pk_d.inner(),
+                value,
+                rho,
+                psi,
+                asset.inner(),
+                rcm,
+            )?;
+
+            let expected_cm =
+                NonIdentityPoint::new(ecc_chip, layouter.namespace(|| "expected cm"), self.cm)?;
+            cm.constrain_equal(layouter.namespace(|| "cm == expected cm"), &expected_cm)
+        }
+    }
+
+    /// The ZSA note commitment, computed from its definition.
+    fn expected_cm(
+        g_d: pallas::Affine,
+        pk_d: pallas::Affine,
+        value: NoteValue,
+        rho: pallas::Base,
+        psi: pallas::Base,
+        asset: pallas::Affine,
+        rcm: pallas::Scalar,
+    ) -> pallas::Affine {
+        use ff::PrimeFieldBits;
+
+        // repr_P(P) encodes x(P) in the low 255 bits, and ỹ in bit 255.
+        let point_bits = |p: pallas::Affine| {
+            let bytes = p.to_bytes();
+            (0..256).map(move |i| bytes[i / 8] & (1 << (i % 8)) != 0)
+        };
+        let base_bits = |b: pallas::Base| {
+            b.to_le_bits()
+                .iter()
+                .by_vals()
+                .take(L_ORCHARD_BASE)
+                .collect::<alloc::vec::Vec<_>>()
+        };
+
+        let msg = point_bits(g_d)
+            .chain(point_bits(pk_d))
+            .chain((0..64).map(|i| (value.inner() >> i) & 1 == 1))
+            .chain(base_bits(rho))
+            .chain(base_bits(psi))
+            .chain(point_bits(asset));
+
+        let hash = HashDomain::new(&format!("{}-M", ZSA_NOTE_COMMITMENT_PERSONALIZATION))
+            .hash_to_point(msg)
+            .unwrap();
+        let r =
+            pallas::Point::hash_to_curve(&format!("{}-r", NOTE_COMMITMENT_PERSONALIZATION))(&[]);
+
+        (hash + r * rcm).to_affine()
+    }
+
+    #[test]
+    fn note_commit_zsa_matches_definition() {
+        let mut rng = OsRng;
+
+        for _ in 0..2 {
+            let g_d = pallas::Point::random(&mut rng).to_affine();
+            let pk_d = pallas::Point::random(&mut rng).to_affine();
+            let value = NoteValue::from_raw(rng.next_u64());
+            let rho = pallas::Base::random(&mut rng);
+            let psi = pallas::Base::random(&mut rng);
+            let asset = pallas::Point::random(&mut rng).to_affine();
+            let rcm = pallas::Scalar::random(&mut rng);
+            let cm = expected_cm(g_d, pk_d, value, rho, psi, asset, rcm);
+
+            let circuit = MyCircuit {
+                g_d: Value::known(g_d),
+                pk_d: Value::known(pk_d),
+                value: Value::known(value),
+                rho: Value::known(rho),
+                psi: Value::known(psi),
+                asset: Value::known(asset),
+                rcm: Value::known(rcm),
+                cm: Value::known(cm),
+            };
+            let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            // The commitment binds the asset.
+            let circuit = MyCircuit {
+                asset: Value::known(-asset),
+                ..circuit
+            };
+            let prover = MockProver::<pallas::Base>::run(11, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+}
@@ -54,12 +54,16 @@ pub const NUM_WINDOWS: usize =
 pub const NUM_WINDOWS_SHORT: usize =
     (L_VALUE + FIXED_BASE_WINDOW_SIZE - 1) / FIXED_BASE_WINDOW_SIZE;
 
+/// The fixed bases used by the Orchard circuit.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 // A sum type for both full-width and short bases. This enables us to use the
 // shared functionality of full-width and short fixed-base scalar multiplication.
 pub enum OrchardFixedBases {
+    /// A base used with full-width scalars.
     Full(OrchardFixedBasesFull),
+    /// The nullifier base $\mathcal{K}^\mathsf{Orchard}$, used with a base field element.
     NullifierK,
+    /// The value base $\mathcal{V}^\mathsf{Orchard}$, used with a short signed scalar.
     ValueCommitV,
 }
 
@@ -84,9 +88,13 @@ impl From<NullifierK> for OrchardFixedBases {
 /// The Orchard fixed bases used in scalar mul with full-width scalars.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum OrchardFixedBasesFull {
+    /// The randomness base of $\mathsf{Commit}^\mathsf{ivk}$.
     CommitIvkR,
+    /// The randomness base of $\mathsf{NoteCommit}^\mathsf{Orchard}$.
     NoteCommitR,
+    /// The randomness base of $\mathsf{ValueCommit}^\mathsf{Orchard}$.
     ValueCommitR,
+    /// The spend authorization base $\mathcal{G}^\mathsf{Orchard}$.
     SpendAuthG,
 }
 
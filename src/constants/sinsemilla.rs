@@ -1,5 +1,5 @@
 //! Sinsemilla generators
-use crate::spec::i2lebsp;
+use crate::primitives::bits::i2lebsp;
 
 #[cfg(feature = "circuit")]
 use {
@@ -64,6 +64,15 @@ pub const Q_MERKLE_CRH: ([u8; 32], [u8; 32]) = (
     ],
 );
 
+/// Personalization for the ZSA note commitment, as defined in [ZIP 226].
+///
+/// The Sinsemilla hash uses the generator $Q$ derived from this personalization, while
+/// the blinding term reuses $R$ from the Orchard note commitment.
+///
+/// [ZIP 226]: https://zips.z.cash/zip-0226
+#[cfg(feature = "unstable-zsa")]
+pub const ZSA_NOTE_COMMITMENT_PERSONALIZATION: &str = "z.cash:ZSA-NoteCommit";
+
 pub(crate) fn lebs2ip_k(bits: &[bool]) -> u32 {
     assert!(bits.len() == K);
     bits.iter()
@@ -78,11 +87,18 @@ pub(crate) fn i2lebsp_k(int: usize) -> [bool; K] {
     i2lebsp(int as u64)
 }
 
+/// The Sinsemilla hash domains used by the Orchard circuit.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum OrchardHashDomains {
+    /// The hash domain of $\mathsf{NoteCommit}^\mathsf{Orchard}$.
     NoteCommit,
+    /// The hash domain of $\mathsf{Commit}^\mathsf{ivk}$.
     CommitIvk,
+    /// The hash domain of $\mathsf{MerkleCRH}^\mathsf{Orchard}$.
     MerkleCrh,
+    /// The hash domain of the ZSA note commitment.
+    #[cfg(feature = "unstable-zsa")]
+    ZsaNoteCommit,
 }
 
 #[cfg(feature = "circuit")]
@@ -105,14 +121,30 @@ impl HashDomains<pallas::Affine> for OrchardHashDomains {
                 pallas::Base::from_repr(Q_MERKLE_CRH.1).unwrap(),
             )
             .unwrap(),
+            #[cfg(feature = "unstable-zsa")]
+            OrchardHashDomains::ZsaNoteCommit => {
+                use group::Curve;
+                use pasta_curves::arithmetic::CurveExt;
+
+                pallas::Point::hash_to_curve("z.cash:SinsemillaQ")(
+                    format!("{}-M", ZSA_NOTE_COMMITMENT_PERSONALIZATION).as_bytes(),
+                )
+                .to_affine()
+            }
         }
     }
 }
 
+/// The Sinsemilla commitment domains used by the Orchard circuit.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum OrchardCommitDomains {
+    /// $\mathsf{NoteCommit}^\mathsf{Orchard}$.
     NoteCommit,
+    /// $\mathsf{Commit}^\mathsf{ivk}$.
     CommitIvk,
+    /// The ZSA note commitment.
+    #[cfg(feature = "unstable-zsa")]
+    ZsaNoteCommit,
 }
 
 #[cfg(feature = "circuit")]
@@ -121,6 +153,8 @@ impl CommitDomains<pallas::Affine, OrchardFixedBases, OrchardHashDomains> for Or
         match self {
             Self::NoteCommit => OrchardFixedBasesFull::NoteCommitR,
             Self::CommitIvk => OrchardFixedBasesFull::CommitIvkR,
+            #[cfg(feature = "unstable-zsa")]
+            Self::ZsaNoteCommit => OrchardFixedBasesFull::NoteCommitR,
         }
     }
 
@@ -128,6 +162,8 @@ impl CommitDomains<pallas::Affine, OrchardFixedBases, OrchardHashDomains> for Or
         match self {
             Self::NoteCommit => OrchardHashDomains::NoteCommit,
             Self::CommitIvk => OrchardHashDomains::CommitIvk,
+            #[cfg(feature = "unstable-zsa")]
+            Self::ZsaNoteCommit => OrchardHashDomains::ZsaNoteCommit,
         }
     }
 }
@@ -197,6 +233,20 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "unstable-zsa")]
+    #[test]
+    fn q_zsa_note_commitment_m() {
+        let domain = CommitDomain::new(ZSA_NOTE_COMMITMENT_PERSONALIZATION);
+        assert_eq!(
+            domain.Q().to_affine(),
+            OrchardHashDomains::ZsaNoteCommit.Q()
+        );
+        assert_eq!(
+            OrchardCommitDomains::ZsaNoteCommit.r(),
+            OrchardCommitDomains::NoteCommit.r()
+        );
+    }
+
     #[test]
     fn q_commit_ivk_m() {
         let domain = CommitDomain::new(COMMIT_IVK_PERSONALIZATION);
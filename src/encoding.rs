@@ -0,0 +1,179 @@
+//! Bech32m string encodings of Orchard addresses and viewing keys.
+//!
+//! With this module's feature flag enabled, [`Address`], [`FullViewingKey`] and
+//! [`IncomingViewingKey`] implement [`Display`](core::fmt::Display) and
+//! [`FromStr`], which encode their raw byte encodings (the same encodings that are
+//! placed in the Orchard items of unified addresses and viewing keys) as Bech32m
+//! strings with the human-readable parts defined here.
+//!
+//! These encodings are specific to this crate, and are intended for command-line tools,
+//! logs and tests. They are not recognized by wallets, which exchange Orchard addresses
+//! and keys inside [ZIP 316] unified encodings.
+//!
+//! [ZIP 316]: https://zips.z.cash/zip-0316
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use bech32::{FromBase32, ToBase32, Variant};
+
+use crate::{
+    keys::{FullViewingKey, IncomingViewingKey},
+    Address,
+};
+
+/// The human-readable part of the string encoding of an [`Address`].
+pub const ADDRESS_HRP: &str = "orchardaddr";
+
+/// The human-readable part of the string encoding of a [`FullViewingKey`].
+pub const FULL_VIEWING_KEY_HRP: &str = "orchardfvk";
+
+/// The human-readable part of the string encoding of an [`IncomingViewingKey`].
+pub const INCOMING_VIEWING_KEY_HRP: &str = "orchardivk";
+
+/// Errors that can occur when parsing a string encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The string is not valid Bech32 or Bech32m.
+    InvalidBech32,
+    /// The string is Bech32 rather than Bech32m.
+    NotBech32m,
+    /// The string has the human-readable part of a different type.
+    WrongHrp,
+    /// The encoded data has the wrong length for this type.
+    InvalidLength,
+    /// The encoded data is not a valid encoding of this type.
+    InvalidEncoding,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidBech32 => write!(f, "Invalid Bech32m string"),
+            ParseError::NotBech32m => write!(f, "String uses Bech32 instead of Bech32m"),
+            ParseError::WrongHrp => write!(f, "Unexpected human-readable part"),
+            ParseError::InvalidLength => write!(f, "Encoded data has the wrong length"),
+            ParseError::InvalidEncoding => write!(f, "Encoded data is invalid"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+fn write_bech32m(f: &mut fmt::Formatter<'_>, hrp: &str, bytes: &[u8]) -> fmt::Result {
+    let encoded =
+        bech32::encode(hrp, bytes.to_base32(), Variant::Bech32m).map_err(|_| fmt::Error)?;
+    f.write_str(&encoded)
+}
+
+fn read_bech32m<const N: usize>(s: &str, hrp: &str) -> Result<[u8; N], ParseError> {
+    let (parsed_hrp, data, variant) = bech32::decode(s).map_err(|_| ParseError::InvalidBech32)?;
+    if variant != Variant::Bech32m {
+        return Err(ParseError::NotBech32m);
+    }
+    if parsed_hrp != hrp {
+        return Err(ParseError::WrongHrp);
+    }
+    Vec::<u8>::from_base32(&data)
+        .map_err(|_| ParseError::InvalidBech32)?
+        .try_into()
+        .map_err(|_| ParseError::InvalidLength)
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_bech32m(f, ADDRESS_HRP, &self.to_raw_address_bytes())
+    }
+}
+
+impl FromStr for Address {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = read_bech32m(s, ADDRESS_HRP)?;
+        Option::from(Address::from_raw_address_bytes(&bytes)).ok_or(ParseError::InvalidEncoding)
+    }
+}
+
+impl fmt::Display for FullViewingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_bech32m(f, FULL_VIEWING_KEY_HRP, &self.to_bytes())
+    }
+}
+
+impl FromStr for FullViewingKey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = read_bech32m(s, FULL_VIEWING_KEY_HRP)?;
+        FullViewingKey::from_bytes(&bytes).ok_or(ParseError::InvalidEncoding)
+    }
+}
+
+impl fmt::Display for IncomingViewingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_bech32m(f, INCOMING_VIEWING_KEY_HRP, &self.to_bytes())
+    }
+}
+
+impl FromStr for IncomingViewingKey {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = read_bech32m(s, INCOMING_VIEWING_KEY_HRP)?;
+        Option::from(IncomingViewingKey::from_bytes(&bytes)).ok_or(ParseError::InvalidEncoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use bech32::{ToBase32, Variant};
+
+    use super::{ParseError, ADDRESS_HRP};
+    use crate::{
+        keys::{FullViewingKey, IncomingViewingKey, Scope, SpendingKey},
+        Address,
+    };
+
+    #[test]
+    fn round_trips() {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([5; 32]).unwrap());
+        let ivk = fvk.to_ivk(Scope::External);
+        let addr = fvk.address_at(0u32, Scope::External);
+
+        let encoded = fvk.to_string();
+        assert!(encoded.starts_with("orchardfvk1"));
+        assert_eq!(encoded.parse::<FullViewingKey>(), Ok(fvk));
+        assert_eq!(ivk.to_string().parse::<IncomingViewingKey>(), Ok(ivk));
+        assert_eq!(addr.to_string().parse::<Address>(), Ok(addr));
+
+        // Strings of another type are rejected.
+        assert_eq!(
+            addr.to_string().parse::<IncomingViewingKey>(),
+            Err(ParseError::WrongHrp)
+        );
+
+        let short = bech32::encode(ADDRESS_HRP, [0u8; 42].to_base32(), Variant::Bech32m);
+        assert_eq!(
+            short.unwrap().parse::<Address>(),
+            Err(ParseError::InvalidLength)
+        );
+        let bech32 = bech32::encode(
+            ADDRESS_HRP,
+            addr.to_raw_address_bytes().to_base32(),
+            Variant::Bech32,
+        );
+        assert_eq!(
+            bech32.unwrap().parse::<Address>(),
+            Err(ParseError::NotBech32m)
+        );
+        assert_eq!(
+            "orchardaddr1".parse::<Address>(),
+            Err(ParseError::InvalidBech32)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Key structures for Orchard.
 
 use alloc::vec::Vec;
+use core::fmt;
 use core2::io::{self, Read, Write};
 
 use ::zip32::{AccountId, ChildIndex};
@@ -14,12 +15,15 @@ use group::{
 };
 use pasta_curves::pallas;
 use rand::RngCore;
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, CtOption};
 use zcash_note_encryption::EphemeralKeyBytes;
 
 use crate::{
     address::Address,
     primitives::redpallas::{self, SpendAuth},
+    redacted::Redacted,
     spec::{
         commit_ivk, diversify_hash, extract_p, ka_orchard, ka_orchard_prepared, prf_nf, to_base,
         to_scalar, NonIdentityPallasPoint, NonZeroPallasBase, NonZeroPallasScalar,
@@ -28,19 +32,110 @@ use crate::{
     zip32::{self, ExtendedSpendingKey},
 };
 
+#[cfg(feature = "serde")]
+use crate::serialization;
+
+pub mod prf_expand;
+
 pub use ::zip32::{DiversifierIndex, Scope};
 
 const KDF_ORCHARD_PERSONALIZATION: &[u8; 16] = b"Zcash_OrchardKDF";
 const ZIP32_PURPOSE: u32 = 32;
 
+/// Errors that can occur when parsing the encoding of a key component.
+///
+/// The Pallas curve has prime order, so every point on it is in the prime-order group and
+/// no separate subgroup check is required.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyComponentError {
+    /// The encoding is not the canonical encoding of a field element.
+    NonCanonical,
+    /// The encoding is not the encoding of a point on the Pallas curve.
+    NotOnCurve,
+    /// The encoded point is the identity.
+    Identity,
+    /// The encoded point has $\tilde{y} = 1$, whereas $\mathsf{ak}$ must have
+    /// $\tilde{y} = 0$.
+    InvalidSign,
+    /// The encoded value is zero, which is not a valid incoming viewing key.
+    Zero,
+}
+
+impl fmt::Display for KeyComponentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyComponentError::NonCanonical => write!(f, "Non-canonical field element encoding"),
+            KeyComponentError::NotOnCurve => write!(f, "Encoding is not a Pallas point"),
+            KeyComponentError::Identity => write!(f, "Point is the identity"),
+            KeyComponentError::InvalidSign => write!(f, "Point has a negative y-coordinate"),
+            KeyComponentError::Zero => write!(f, "Key is zero"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeyComponentError {}
+
+/// Errors that can occur when parsing the raw encoding of a [`FullViewingKey`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FullViewingKeyError {
+    /// The encoding of the spend validating key $\mathsf{ak}$ is invalid.
+    Ak(KeyComponentError),
+    /// The encoding of the nullifier deriving key $\mathsf{nk}$ is invalid.
+    Nk(KeyComponentError),
+    /// The encoding of the commitment randomness $\mathsf{rivk}$ is invalid.
+    Rivk(KeyComponentError),
+    /// The components are individually valid, but the incoming viewing key of one of
+    /// the scopes would be 0 or $\bot$.
+    InvalidIvk,
+    /// The version byte of a [versioned encoding](FullViewingKey::to_versioned_bytes) is
+    /// not one that this version of the crate can parse.
+    UnknownVersion(u8),
+}
+
+impl fmt::Display for FullViewingKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FullViewingKeyError::Ak(e) => write!(f, "Invalid ak: {}", e),
+            FullViewingKeyError::Nk(e) => write!(f, "Invalid nk: {}", e),
+            FullViewingKeyError::Rivk(e) => write!(f, "Invalid rivk: {}", e),
+            FullViewingKeyError::InvalidIvk => {
+                write!(f, "Components do not produce a valid incoming viewing key")
+            }
+            FullViewingKeyError::UnknownVersion(v) => {
+                write!(f, "Unknown full viewing key encoding version {}", v)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FullViewingKeyError {}
+
+/// The version byte of the [versioned encoding](FullViewingKey::to_versioned_bytes) of a
+/// full viewing key whose payload is its raw encoding, `ak || nk || rivk`.
+pub const FVK_ENCODING_V1: u8 = 1;
+
+/// The length of the [versioned encoding](FullViewingKey::to_versioned_bytes) of a full
+/// viewing key.
+pub const FVK_VERSIONED_ENCODING_LEN: usize = 1 + 96;
+
 /// A spending key, from which all key material is derived.
 ///
 /// $\mathsf{sk}$ as defined in [Zcash Protocol Spec § 4.2.3: Orchard Key Components][orchardkeycomponents].
 ///
 /// [orchardkeycomponents]: https://zips.z.cash/protocol/nu5.pdf#orchardkeycomponents
-#[derive(Debug, Copy, Clone)]
+#[derive(Clone)]
 pub struct SpendingKey([u8; 32]);
 
+impl fmt::Debug for SpendingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SpendingKey")
+            .field(&Redacted(&self.0))
+            .finish()
+    }
+}
+
 impl ConstantTimeEq for SpendingKey {
     fn ct_eq(&self, other: &Self) -> Choice {
         self.to_bytes().ct_eq(other.to_bytes())
@@ -91,25 +186,55 @@ impl SpendingKey {
     }
 
     /// Derives the Orchard spending key for the given seed, coin type, and account.
+    ///
+    /// Returns [`zip32::Error::InvalidSeedLength`] if the seed is not between 32 and 252
+    /// bytes long.
     pub fn from_zip32_seed(
         seed: &[u8],
         coin_type: u32,
         account: AccountId,
     ) -> Result<Self, zip32::Error> {
-        if coin_type >= (1 << 31) {
-            return Err(zip32::Error::InvalidChildIndex(coin_type));
-        }
-
         // Call zip32 logic
         let path = &[
             ChildIndex::hardened(ZIP32_PURPOSE),
-            ChildIndex::hardened(coin_type),
+            zip32::hardened_child_index(coin_type)?,
             ChildIndex::hardened(account.into()),
         ];
         ExtendedSpendingKey::from_path(seed, path).map(|esk| esk.sk())
     }
+
+    /// Derives the Orchard spending key for the given seed, coin type, and account,
+    /// along with its full viewing key.
+    pub fn from_zip32_seed_path(
+        seed: &[u8],
+        coin_type: u32,
+        account: AccountId,
+    ) -> Result<(Self, FullViewingKey), zip32::Error> {
+        let sk = Self::from_zip32_seed(seed, coin_type, account)?;
+        let fvk = FullViewingKey::from(&sk);
+        Ok((sk, fvk))
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for SpendingKey {
+    fn zeroize(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.0);
+    }
+}
+
+/// `SpendingKey` is not `Copy`, so every instance of the key, including those cloned
+/// while building a bundle, is zeroized when it is dropped.
+#[cfg(feature = "zeroize")]
+impl Drop for SpendingKey {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
 }
 
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for SpendingKey {}
+
 /// A spend authorizing key, used to create spend authorization signatures.
 /// This type enforces that the corresponding public point (ak^ℙ) has ỹ = 0.
 ///
@@ -125,6 +250,12 @@ impl SpendAuthorizingKey {
         to_scalar(PrfExpand::ORCHARD_ASK.with(&sk.0))
     }
 
+    /// Returns the encoding of $\mathsf{ask}$, for test vectors.
+    #[cfg(any(test, feature = "test-vector-gen"))]
+    pub(crate) fn to_bytes(&self) -> [u8; 32] {
+        self.0.clone().into()
+    }
+
     /// Randomizes this spend authorizing key with the given `randomizer`.
     ///
     /// The resulting key can be used to actually sign a spend.
@@ -133,6 +264,17 @@ impl SpendAuthorizingKey {
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for SpendAuthorizingKey {
+    fn zeroize(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.0);
+    }
+}
+
+/// The wrapped signing key is zeroized when it is dropped.
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for SpendAuthorizingKey {}
+
 impl From<&SpendingKey> for SpendAuthorizingKey {
     fn from(sk: &SpendingKey) -> Self {
         let ask = Self::derive_inner(sk);
@@ -185,10 +327,23 @@ impl SpendValidatingKey {
         self.0.randomize(randomizer)
     }
 
+    /// Checks that `rk` is this spend validating key randomized by `alpha`; that is, that
+    /// $\mathsf{rk} = \mathsf{ak} + [\alpha] \mathcal{G}^\mathsf{Orchard}$.
+    ///
+    /// This is the relation that the action circuit enforces, so a coordinator that
+    /// receives `rk` or a spend authorization signature from another party (such as a
+    /// hardware wallet) can use this to check it before creating a proof or broadcasting.
+    pub fn verifies_randomized(
+        &self,
+        alpha: &pallas::Scalar,
+        rk: &redpallas::VerificationKey<SpendAuth>,
+    ) -> bool {
+        <[u8; 32]>::from(&self.randomize(alpha)) == <[u8; 32]>::from(rk)
+    }
+
     /// Converts this spend validating key to its serialized form,
     /// I2LEOSP_256(ak).
-    #[cfg_attr(feature = "unstable-frost", visibility::make(pub))]
-    pub(crate) fn to_bytes(&self) -> [u8; 32] {
+    pub fn to_bytes(&self) -> [u8; 32] {
         // This is correct because the wrapped point must have ỹ = 0, and
         // so the point repr is the same as I2LEOSP of its x-coordinate.
         let b = <[u8; 32]>::from(&self.0);
@@ -199,22 +354,34 @@ impl SpendValidatingKey {
     /// Attempts to parse a byte slice as a spend validating key, `I2LEOSP_256(ak)`.
     ///
     /// Returns `None` if the given slice does not contain a valid spend validating key.
-    #[cfg_attr(feature = "unstable-frost", visibility::make(pub))]
-    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    /// Parse a `[u8; 32]` with [`TryFrom`] instead to find out why an encoding is invalid.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
         <[u8; 32]>::try_from(bytes)
             .ok()
-            .and_then(|b| {
-                // Structural validity checks for ak_P:
-                // - The point must not be the identity
-                //   (which for Pallas is canonically encoded as all-zeroes).
-                // - The sign of the y-coordinate must be positive.
-                if b != [0; 32] && b[31] & 0x80 == 0 {
-                    <redpallas::VerificationKey<SpendAuth>>::try_from(b).ok()
-                } else {
-                    None
-                }
-            })
-            .map(SpendValidatingKey)
+            .and_then(|b| Self::try_from(b).ok())
+    }
+}
+
+impl TryFrom<[u8; 32]> for SpendValidatingKey {
+    type Error = KeyComponentError;
+
+    /// Parses a spend validating key from `I2LEOSP_256(ak)`.
+    fn try_from(b: [u8; 32]) -> Result<Self, Self::Error> {
+        // Structural validity checks for ak_P:
+        // - The point must not be the identity
+        //   (which for Pallas is canonically encoded as all-zeroes).
+        // - The sign of the y-coordinate must be positive.
+        if b == [0; 32] {
+            Err(KeyComponentError::Identity)
+        } else if b[31] & 0x80 != 0 {
+            Err(KeyComponentError::InvalidSign)
+        } else if bool::from(pallas::Base::from_repr(b).is_none()) {
+            Err(KeyComponentError::NonCanonical)
+        } else {
+            <redpallas::VerificationKey<SpendAuth>>::try_from(b)
+                .map(SpendValidatingKey)
+                .map_err(|_| KeyComponentError::NotOnCurve)
+        }
     }
 }
 
@@ -226,7 +393,7 @@ impl SpendValidatingKey {
 /// [`Note`]: crate::note::Note
 /// [orchardkeycomponents]: https://zips.z.cash/protocol/nu5.pdf#orchardkeycomponents
 #[derive(Copy, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) struct NullifierDerivingKey(pallas::Base);
+pub struct NullifierDerivingKey(pallas::Base);
 
 impl NullifierDerivingKey {
     pub(crate) fn inner(&self) -> pallas::Base {
@@ -246,28 +413,37 @@ impl NullifierDerivingKey {
     }
 
     /// Converts this nullifier deriving key to its serialized form.
-    pub(crate) fn to_bytes(self) -> [u8; 32] {
+    pub fn to_bytes(self) -> [u8; 32] {
         <[u8; 32]>::from(self.0)
     }
 
-    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    /// Parses a nullifier deriving key from its serialized form.
+    ///
+    /// Returns `None` if `bytes` is not the canonical encoding of a Pallas base field
+    /// element.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
         let nk_bytes = <[u8; 32]>::try_from(bytes).ok()?;
-        let nk = pallas::Base::from_repr(nk_bytes).map(NullifierDerivingKey);
-        if nk.is_some().into() {
-            Some(nk.unwrap())
-        } else {
-            None
-        }
+        Self::try_from(nk_bytes).ok()
+    }
+}
+
+impl TryFrom<[u8; 32]> for NullifierDerivingKey {
+    type Error = KeyComponentError;
+
+    fn try_from(bytes: [u8; 32]) -> Result<Self, Self::Error> {
+        Option::from(pallas::Base::from_repr(bytes))
+            .map(NullifierDerivingKey)
+            .ok_or(KeyComponentError::NonCanonical)
     }
 }
 
 /// The randomness for $\mathsf{Commit}^\mathsf{ivk}$.
 ///
-/// $\mashsf{rivk}$ as defined in [Zcash Protocol Spec § 4.2.3: Orchard Key Components][orchardkeycomponents].
+/// $\mathsf{rivk}$ as defined in [Zcash Protocol Spec § 4.2.3: Orchard Key Components][orchardkeycomponents].
 ///
 /// [orchardkeycomponents]: https://zips.z.cash/protocol/nu5.pdf#orchardkeycomponents
 #[derive(Copy, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) struct CommitIvkRandomness(pallas::Scalar);
+pub struct CommitIvkRandomness(pallas::Scalar);
 
 impl From<&SpendingKey> for CommitIvkRandomness {
     fn from(sk: &SpendingKey) -> Self {
@@ -280,19 +456,28 @@ impl CommitIvkRandomness {
         self.0
     }
 
-    /// Converts this nullifier deriving key to its serialized form.
-    pub(crate) fn to_bytes(self) -> [u8; 32] {
+    /// Converts this commitment randomness to its serialized form.
+    pub fn to_bytes(self) -> [u8; 32] {
         <[u8; 32]>::from(self.0)
     }
 
-    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+    /// Parses commitment randomness from its serialized form.
+    ///
+    /// Returns `None` if `bytes` is not the canonical encoding of a Pallas scalar field
+    /// element.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
         let rivk_bytes = <[u8; 32]>::try_from(bytes).ok()?;
-        let rivk = pallas::Scalar::from_repr(rivk_bytes).map(CommitIvkRandomness);
-        if rivk.is_some().into() {
-            Some(rivk.unwrap())
-        } else {
-            None
-        }
+        Self::try_from(rivk_bytes).ok()
+    }
+}
+
+impl TryFrom<[u8; 32]> for CommitIvkRandomness {
+    type Error = KeyComponentError;
+
+    fn try_from(bytes: [u8; 32]) -> Result<Self, Self::Error> {
+        Option::from(pallas::Scalar::from_repr(bytes))
+            .map(CommitIvkRandomness)
+            .ok_or(KeyComponentError::NonCanonical)
     }
 }
 
@@ -334,7 +519,12 @@ impl From<FullViewingKey> for SpendValidatingKey {
 }
 
 impl FullViewingKey {
-    pub(crate) fn nk(&self) -> &NullifierDerivingKey {
+    /// Returns the nullifier deriving key of this full viewing key.
+    ///
+    /// See [`Note::nullifier_with_nk`] for how it can be used on its own.
+    ///
+    /// [`Note::nullifier_with_nk`]: crate::Note::nullifier_with_nk
+    pub fn nk(&self) -> &NullifierDerivingKey {
         &self.nk
     }
 
@@ -371,6 +561,52 @@ impl FullViewingKey {
         self.to_ivk(scope).address_at(j)
     }
 
+    /// Returns the diversifier of the payment address for this key at the given index.
+    ///
+    /// This is cheaper than [`FullViewingKey::address_at`], as it does not derive the
+    /// address's transmission key. To derive many diversifiers, use the
+    /// [`DiversifierKey`] returned by [`FullViewingKey::to_dk`] directly.
+    pub fn diversifier_at(&self, j: impl Into<DiversifierIndex>, scope: Scope) -> Diversifier {
+        self.to_dk(scope).get(j)
+    }
+
+    /// Returns an iterator over the payment addresses for this key in the given scope,
+    /// paired with their diversifier indices, starting from index `start`.
+    ///
+    /// Every diversifier index yields a valid Orchard address, so no indices are skipped.
+    /// The iterator ends after the maximum diversifier index, $2^{88} - 1$.
+    pub fn addresses(
+        &self,
+        start: impl Into<DiversifierIndex>,
+        scope: Scope,
+    ) -> impl Iterator<Item = (DiversifierIndex, Address)> {
+        let ivk = self.to_ivk(scope);
+        core::iter::successors(Some(start.into()), |j| {
+            let mut next = *j;
+            next.increment().ok().map(|()| next)
+        })
+        .map(move |j| (j, ivk.address_at(j)))
+    }
+
+    /// Returns the [ZIP 32] fingerprint of this full viewing key.
+    ///
+    /// [ZIP 32]: https://zips.z.cash/zip-0032#orchard-key-identifiers
+    pub fn fingerprint(&self) -> zip32::FvkFingerprint {
+        zip32::FvkFingerprint::from(self)
+    }
+
+    /// Returns the address that wallets should use for change and other transfers
+    /// between their own accounts.
+    ///
+    /// This is the internal-scope address at diversifier index 0, which is the only
+    /// internal address that [ZIP 316] expects wallets to use. Funds sent to it are
+    /// detected with the internal incoming viewing key, `self.to_ivk(Scope::Internal)`.
+    ///
+    /// [ZIP 316]: https://zips.z.cash/zip-0316#deriving-internal-keys
+    pub fn change_address(&self) -> Address {
+        self.address_at(0u32, Scope::Internal)
+    }
+
     /// Returns the payment address for this key corresponding to the given diversifier.
     pub fn address(&self, d: Diversifier, scope: Scope) -> Address {
         // Shortcut: we don't need to derive DiversifierKey.
@@ -425,11 +661,53 @@ impl FullViewingKey {
     /// Parses a full viewing key from its "raw" encoding as specified in [Zcash Protocol Spec § 5.6.4.4: Orchard Raw Full Viewing Keys][orchardrawfullviewingkeys]
     ///
     /// [orchardrawfullviewingkeys]: https://zips.z.cash/protocol/protocol.pdf#orchardfullviewingkeyencoding
+    ///
+    /// Use the [`TryFrom<[u8; 96]>`](TryFrom) implementation to find out why an encoding
+    /// is invalid.
     pub fn from_bytes(bytes: &[u8; 96]) -> Option<Self> {
-        let ak = SpendValidatingKey::from_bytes(&bytes[..32])?;
-        let nk = NullifierDerivingKey::from_bytes(&bytes[32..64])?;
-        let rivk = CommitIvkRandomness::from_bytes(&bytes[64..])?;
+        Self::try_from(*bytes).ok()
+    }
+
+    /// Serializes the full viewing key as a version byte followed by its
+    /// [raw encoding](FullViewingKey::to_bytes).
+    ///
+    /// The version byte is currently always [`FVK_ENCODING_V1`]. This is intended for
+    /// backup formats that embed a full viewing key, so that they can be parsed by later
+    /// versions of this crate should the encoding ever change.
+    pub fn to_versioned_bytes(&self) -> [u8; FVK_VERSIONED_ENCODING_LEN] {
+        let mut result = [0u8; FVK_VERSIONED_ENCODING_LEN];
+        result[0] = FVK_ENCODING_V1;
+        result[1..].copy_from_slice(&self.to_bytes());
+        result
+    }
+
+    /// Parses a full viewing key from the encoding produced by
+    /// [`FullViewingKey::to_versioned_bytes`].
+    ///
+    /// Returns [`FullViewingKeyError::UnknownVersion`] if the version byte is not
+    /// recognised, and otherwise reports which component is invalid in the same way as
+    /// the [`TryFrom<[u8; 96]>`](TryFrom) implementation.
+    pub fn from_versioned_bytes(
+        bytes: &[u8; FVK_VERSIONED_ENCODING_LEN],
+    ) -> Result<Self, FullViewingKeyError> {
+        match bytes[0] {
+            FVK_ENCODING_V1 => Self::try_from(<[u8; 96]>::try_from(&bytes[1..]).unwrap()),
+            v => Err(FullViewingKeyError::UnknownVersion(v)),
+        }
+    }
 
+    /// Constructs a full viewing key from its components.
+    ///
+    /// This allows a host to reconstruct the full viewing key of a spending key held by
+    /// a hardware wallet, from the components that the device exports.
+    ///
+    /// Returns `None` if the components do not form a valid full viewing key; that is,
+    /// if the incoming viewing key of either scope would be 0 or $\bot$.
+    pub fn from_parts(
+        ak: SpendValidatingKey,
+        nk: NullifierDerivingKey,
+        rivk: CommitIvkRandomness,
+    ) -> Option<Self> {
         let fvk = FullViewingKey { ak, nk, rivk };
 
         // If either ivk is 0 or ⊥, this FVK is invalid.
@@ -440,11 +718,16 @@ impl FullViewingKey {
         Some(fvk)
     }
 
-    /// Derives an internal full viewing key from a full viewing key, as specified in
-    /// [ZIP32][orchardinternalfullviewingkey]. Internal use only.
+    /// Derives the internal full viewing key from a full viewing key, as specified in
+    /// [ZIP32][orchardinternalfullviewingkey].
+    ///
+    /// The keys derived from the returned key with [`Scope::External`] are the keys of
+    /// the internal scope of `self`; for example, `fvk.derive_internal().to_ivk(Scope::External)`
+    /// is equal to `fvk.to_ivk(Scope::Internal)`. Deriving keys from the returned key with
+    /// [`Scope::Internal`] does not produce keys that any wallet will recognize.
     ///
     /// [orchardinternalfullviewingkey]: https://zips.z.cash/zip-0032#orchard-internal-key-derivation
-    fn derive_internal(&self) -> Self {
+    pub fn derive_internal(&self) -> Self {
         FullViewingKey {
             ak: self.ak.clone(),
             nk: self.nk,
@@ -460,6 +743,14 @@ impl FullViewingKey {
         }
     }
 
+    /// Derives the [`DiversifierKey`] for this full viewing key.
+    pub fn to_dk(&self, scope: Scope) -> DiversifierKey {
+        match scope {
+            Scope::External => self.derive_dk_ovk().0,
+            Scope::Internal => self.derive_internal().derive_dk_ovk().0,
+        }
+    }
+
     /// Derives an `OutgoingViewingKey` for this full viewing key.
     pub fn to_ovk(&self, scope: Scope) -> OutgoingViewingKey {
         match scope {
@@ -469,13 +760,67 @@ impl FullViewingKey {
     }
 }
 
+/// Serializes the raw encoding of the key, as a hex string in human-readable formats.
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for FullViewingKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialization::serialize_bytes(&self.to_bytes(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for FullViewingKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes: [u8; 96] = serialization::deserialize_bytes(deserializer)?;
+        FullViewingKey::from_bytes(&bytes)
+            .ok_or_else(|| de::Error::custom("Invalid Orchard full viewing key encoding"))
+    }
+}
+
+impl TryFrom<[u8; 96]> for FullViewingKey {
+    type Error = FullViewingKeyError;
+
+    /// Parses a full viewing key from its raw encoding, `ak || nk || rivk`, as specified in
+    /// [Zcash Protocol Spec § 5.6.4.4: Orchard Raw Full Viewing Keys][orchardrawfullviewingkeys].
+    ///
+    /// This encoding has no version field of its own. Formats that need to tell it apart
+    /// from future encodings can use [`FullViewingKey::from_versioned_bytes`] instead.
+    ///
+    /// [orchardrawfullviewingkeys]: https://zips.z.cash/protocol/protocol.pdf#orchardfullviewingkeyencoding
+    fn try_from(bytes: [u8; 96]) -> Result<Self, Self::Error> {
+        let component = |i: usize| -> [u8; 32] { bytes[32 * i..32 * (i + 1)].try_into().unwrap() };
+        let ak = SpendValidatingKey::try_from(component(0)).map_err(FullViewingKeyError::Ak)?;
+        let nk = NullifierDerivingKey::try_from(component(1)).map_err(FullViewingKeyError::Nk)?;
+        let rivk =
+            CommitIvkRandomness::try_from(component(2)).map_err(FullViewingKeyError::Rivk)?;
+
+        Self::from_parts(ak, nk, rivk).ok_or(FullViewingKeyError::InvalidIvk)
+    }
+}
+
 /// A key that provides the capability to derive a sequence of diversifiers.
 ///
 /// $\mathsf{dk}$ as defined in [Zcash Protocol Spec § 4.2.3: Orchard Key Components][orchardkeycomponents].
 ///
+/// Every 32-byte string is a valid diversifier key, so parsing one cannot fail.
+///
 /// [orchardkeycomponents]: https://zips.z.cash/protocol/nu5.pdf#orchardkeycomponents
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) struct DiversifierKey([u8; 32]);
+#[derive(Clone, Debug, Eq, PartialOrd, Ord)]
+pub struct DiversifierKey([u8; 32]);
+
+impl ConstantTimeEq for DiversifierKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for DiversifierKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
 
 impl DiversifierKey {
     /// Returns the diversifier at the given index.
@@ -549,9 +894,17 @@ impl Diversifier {
 /// decryption of notes). When we actually want to serialize ivk, we're guaranteed to get
 /// a valid base field element encoding, because we always construct ivk from an integer
 /// in the correct range.
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
 struct KeyAgreementPrivateKey(NonZeroPallasScalar);
 
+impl fmt::Debug for KeyAgreementPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("KeyAgreementPrivateKey")
+            .field(&Redacted(&self.0))
+            .finish()
+    }
+}
+
 impl KeyAgreementPrivateKey {
     /// Derives `KeyAgreementPrivateKey` from fvk.
     ///
@@ -613,12 +966,24 @@ impl KeyAgreementPrivateKey {
 /// Defined in [Zcash Protocol Spec § 5.6.4.3: Orchard Raw Incoming Viewing Keys][orchardinviewingkeyencoding].
 ///
 /// [orchardinviewingkeyencoding]: https://zips.z.cash/protocol/nu5.pdf#orchardinviewingkeyencoding
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Eq, PartialOrd, Ord)]
 pub struct IncomingViewingKey {
     dk: DiversifierKey,
     ivk: KeyAgreementPrivateKey,
 }
 
+impl ConstantTimeEq for IncomingViewingKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.dk.ct_eq(&other.dk) & (*self.ivk.0).ct_eq(&*other.ivk.0)
+    }
+}
+
+impl PartialEq for IncomingViewingKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
 impl IncomingViewingKey {
     /// Helper method.
     fn from_fvk(fvk: &FullViewingKey) -> Self {
@@ -641,6 +1006,9 @@ impl IncomingViewingKey {
     }
 
     /// Parses an Orchard incoming viewing key from its raw encoding.
+    ///
+    /// See the [`TryFrom`] implementation for a variant of this that reports why an
+    /// encoding is invalid.
     pub fn from_bytes(bytes: &[u8; 64]) -> CtOption<Self> {
         NonZeroPallasBase::from_bytes(bytes[32..].try_into().unwrap()).map(|ivk| {
             IncomingViewingKey {
@@ -676,12 +1044,70 @@ impl IncomingViewingKey {
     pub fn prepare(&self) -> PreparedIncomingViewingKey {
         PreparedIncomingViewingKey::new(self)
     }
+
+    /// Performs $\mathsf{KA}^\mathsf{Orchard}.\mathsf{Agree}$ between this key's
+    /// $\mathsf{ivk}$ and the given ephemeral public key.
+    ///
+    /// Returns `None` if `ephemeral_key` is not a valid encoding of a non-identity point.
+    ///
+    /// This is a low-level operation for tools that need the shared secret of a note
+    /// outside of note decryption, such as payment disclosures. Anyone holding the shared
+    /// secret for a note can decrypt it, so it must be treated with the same care as the
+    /// incoming viewing key. Wallets should use [`crate::note_encryption`] instead.
+    #[cfg(feature = "hazmat-key-agreement")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "hazmat-key-agreement")))]
+    pub fn key_agreement(&self, ephemeral_key: &EphemeralKeyBytes) -> CtOption<SharedSecret> {
+        EphemeralPublicKey::from_bytes(&ephemeral_key.0).map(|epk| epk.agree(self))
+    }
+}
+
+impl TryFrom<[u8; 64]> for IncomingViewingKey {
+    type Error = KeyComponentError;
+
+    /// Parses an Orchard incoming viewing key from its raw encoding, `dk || ivk`.
+    fn try_from(bytes: [u8; 64]) -> Result<Self, Self::Error> {
+        let ivk: pallas::Base =
+            Option::from(pallas::Base::from_repr(bytes[32..].try_into().unwrap()))
+                .ok_or(KeyComponentError::NonCanonical)?;
+        let ivk: NonZeroPallasBase =
+            Option::from(NonZeroPallasBase::from_base(ivk)).ok_or(KeyComponentError::Zero)?;
+        Ok(IncomingViewingKey {
+            dk: DiversifierKey(bytes[..32].try_into().unwrap()),
+            ivk: KeyAgreementPrivateKey(ivk.into()),
+        })
+    }
+}
+
+/// Serializes the raw encoding of the key, as a hex string in human-readable formats.
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for IncomingViewingKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialization::serialize_bytes(&self.to_bytes(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for IncomingViewingKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes: [u8; 64] = serialization::deserialize_bytes(deserializer)?;
+        IncomingViewingKey::try_from(bytes).map_err(de::Error::custom)
+    }
 }
 
 /// An Orchard incoming viewing key that has been precomputed for trial decryption.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct PreparedIncomingViewingKey(PreparedNonZeroScalar);
 
+impl fmt::Debug for PreparedIncomingViewingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PreparedIncomingViewingKey")
+            .field(&Redacted(&self.0))
+            .finish()
+    }
+}
+
 #[cfg(feature = "std")]
 impl memuse::DynamicUsage for PreparedIncomingViewingKey {
     fn dynamic_usage(&self) -> usize {
@@ -717,6 +1143,20 @@ impl PreparedIncomingViewingKey {
 #[derive(Debug, Clone)]
 pub struct OutgoingViewingKey([u8; 32]);
 
+impl ConstantTimeEq for OutgoingViewingKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for OutgoingViewingKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for OutgoingViewingKey {}
+
 impl OutgoingViewingKey {
     /// Helper method.
     fn from_fvk(fvk: &FullViewingKey) -> Self {
@@ -788,9 +1228,16 @@ impl ConditionallySelectable for DiversifiedTransmissionKey {
 /// Defined in [section 5.4.5.5: Orchard Key Agreement][concreteorchardkeyagreement].
 ///
 /// [concreteorchardkeyagreement]: https://zips.z.cash/protocol/nu5.pdf#concreteorchardkeyagreement
-#[derive(Debug)]
 pub struct EphemeralSecretKey(pub(crate) NonZeroPallasScalar);
 
+impl fmt::Debug for EphemeralSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EphemeralSecretKey")
+            .field(&Redacted(&self.0))
+            .finish()
+    }
+}
+
 impl ConstantTimeEq for EphemeralSecretKey {
     fn ct_eq(&self, other: &Self) -> subtle::Choice {
         self.0.ct_eq(&other.0)
@@ -798,10 +1245,26 @@ impl ConstantTimeEq for EphemeralSecretKey {
 }
 
 impl EphemeralSecretKey {
-    pub(crate) fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
+    /// Parses an ephemeral secret key from its canonical encoding.
+    ///
+    /// Returns `None` if `bytes` does not encode a non-zero Pallas scalar.
+    ///
+    /// This allows a third party given a disclosed `esk` (see [`Note::esk`]) to decrypt
+    /// the corresponding output with
+    /// [`zcash_note_encryption::try_output_recovery_with_pkd_esk`].
+    ///
+    /// [`Note::esk`]: crate::Note::esk
+    pub fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
         NonZeroPallasScalar::from_bytes(bytes).map(EphemeralSecretKey)
     }
 
+    /// Serializes this ephemeral secret key to its canonical encoding.
+    ///
+    /// This is the form in which a sender discloses `esk` for a payment.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+
     pub(crate) fn derive_public(&self, g_d: NonIdentityPallasPoint) -> EphemeralPublicKey {
         EphemeralPublicKey(ka_orchard(&self.0, &g_d))
     }
@@ -825,11 +1288,15 @@ impl EphemeralSecretKey {
 pub struct EphemeralPublicKey(NonIdentityPallasPoint);
 
 impl EphemeralPublicKey {
-    pub(crate) fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
+    /// Parses an ephemeral public key from its encoding.
+    ///
+    /// Returns `None` if `bytes` is not a valid encoding of a non-identity Pallas point.
+    pub fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
         NonIdentityPallasPoint::from_bytes(bytes).map(EphemeralPublicKey)
     }
 
-    pub(crate) fn to_bytes(&self) -> EphemeralKeyBytes {
+    /// Returns the encoding of this ephemeral public key.
+    pub fn to_bytes(&self) -> EphemeralKeyBytes {
         EphemeralKeyBytes(self.0.to_bytes())
     }
 
@@ -839,14 +1306,33 @@ impl EphemeralPublicKey {
 }
 
 /// An Orchard ephemeral public key that has been precomputed for trial decryption.
+///
+/// Preparing a key decodes it and precomputes a table of its multiples, after which
+/// key agreement with each incoming viewing key is cheaper than with an unprepared key.
+/// The batch decryption APIs of [`zcash_note_encryption::batch`] (and
+/// [`Keyring`](crate::note_encryption::Keyring), which uses them) already prepare the
+/// ephemeral key of each output once, and reuse it for every candidate key; scanners
+/// that schedule trial decryption themselves can use this type to do the same.
 #[derive(Clone, Debug)]
 pub struct PreparedEphemeralPublicKey(PreparedNonIdentityBase);
 
 impl PreparedEphemeralPublicKey {
-    pub(crate) fn new(epk: EphemeralPublicKey) -> Self {
+    /// Prepares an ephemeral public key for trial decryption.
+    pub fn new(epk: EphemeralPublicKey) -> Self {
         PreparedEphemeralPublicKey(PreparedNonIdentityBase::new(epk.0))
     }
 
+    /// Parses and prepares an ephemeral public key from its encoding.
+    ///
+    /// Returns `None` if `ephemeral_key` is not a valid encoding of a non-identity Pallas
+    /// point.
+    pub fn from_bytes(ephemeral_key: &EphemeralKeyBytes) -> CtOption<Self> {
+        EphemeralPublicKey::from_bytes(&ephemeral_key.0).map(Self::new)
+    }
+
+    /// Performs $\mathsf{KA}^\mathsf{Orchard}.\mathsf{Agree}$ between this key and the
+    /// given incoming viewing key.
+    #[cfg_attr(feature = "hazmat-key-agreement", visibility::make(pub))]
     pub(crate) fn agree(&self, ivk: &PreparedIncomingViewingKey) -> SharedSecret {
         SharedSecret(ka_orchard_prepared(&ivk.0, &self.0))
     }
@@ -861,8 +1347,9 @@ impl PreparedEphemeralPublicKey {
 pub struct SharedSecret(NonIdentityPallasPoint);
 
 impl SharedSecret {
-    /// For checking test vectors only.
-    #[cfg(test)]
+    /// Returns the encoding of the shared secret point.
+    #[cfg(any(test, feature = "hazmat-key-agreement"))]
+    #[cfg_attr(feature = "hazmat-key-agreement", visibility::make(pub))]
     pub(crate) fn to_bytes(&self) -> [u8; 32] {
         self.0.to_bytes()
     }
@@ -891,6 +1378,7 @@ impl SharedSecret {
     /// Defined in [Zcash Protocol Spec § 5.4.5.6: Orchard Key Agreement][concreteorchardkdf].
     ///
     /// [concreteorchardkdf]: https://zips.z.cash/protocol/nu5.pdf#concreteorchardkdf
+    #[cfg_attr(feature = "hazmat-key-agreement", visibility::make(pub))]
     pub(crate) fn kdf_orchard(self, ephemeral_key: &EphemeralKeyBytes) -> Blake2bHash {
         Self::kdf_orchard_inner(self.0.to_affine(), ephemeral_key)
     }
@@ -986,6 +1474,293 @@ mod tests {
         assert!(SpendValidatingKey::from_bytes(&[0; 32]).is_none());
     }
 
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize() {
+        use zeroize::Zeroize;
+
+        let mut sk = SpendingKey::from_bytes([7; 32]).unwrap();
+        sk.zeroize();
+        assert_eq!(sk.to_bytes(), &[0; 32]);
+    }
+
+    #[cfg(not(feature = "expose-secrets-debug"))]
+    #[test]
+    fn redacted_debug() {
+        let sk = SpendingKey::from_bytes([0xab; 32]).unwrap();
+        let fvk = FullViewingKey::from(&sk);
+        let ivk = fvk.to_ivk(Scope::External);
+
+        assert_eq!(format!("{:?}", sk), "SpendingKey([REDACTED])");
+        assert_eq!(
+            format!("{:?}", SpendAuthorizingKey::from(&sk)),
+            "SpendAuthorizingKey(SigningKey([REDACTED]))"
+        );
+        assert!(format!("{:?}", ivk).contains("ivk: KeyAgreementPrivateKey([REDACTED])"));
+        assert_eq!(
+            format!("{:?}", ivk.prepare()),
+            "PreparedIncomingViewingKey([REDACTED])"
+        );
+        let xsk = ExtendedSpendingKey::master(&[1; 32]).unwrap();
+        assert!(format!("{:?}", xsk).ends_with("inner: [REDACTED] }"));
+    }
+
+    #[test]
+    fn prepared_epk() {
+        for tv in crate::test_vectors::note_encryption::test_vectors() {
+            let ivk = IncomingViewingKey::from_bytes(&tv.incoming_viewing_key).unwrap();
+            let epk = EphemeralPublicKey::from_bytes(&tv.ephemeral_key).unwrap();
+            assert_eq!(epk.to_bytes().0, tv.ephemeral_key);
+            assert_eq!(epk.agree(&ivk).to_bytes(), tv.shared_secret);
+
+            // Preparing either the parsed key or its encoding gives the same result as the
+            // unprepared key agreement.
+            let prepared_ivk = ivk.prepare();
+            let from_bytes =
+                PreparedEphemeralPublicKey::from_bytes(&EphemeralKeyBytes(tv.ephemeral_key))
+                    .unwrap();
+            assert_eq!(from_bytes.agree(&prepared_ivk).to_bytes(), tv.shared_secret);
+            let prepared = PreparedEphemeralPublicKey::new(epk);
+            assert_eq!(prepared.agree(&prepared_ivk).to_bytes(), tv.shared_secret);
+        }
+
+        assert!(bool::from(
+            PreparedEphemeralPublicKey::from_bytes(&EphemeralKeyBytes([0; 32])).is_none()
+        ));
+    }
+
+    #[test]
+    fn internal_fvk() {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([8; 32]).unwrap());
+        let internal = fvk.derive_internal();
+        assert_eq!(internal.ak, fvk.ak);
+        assert_eq!(internal.nk, fvk.nk);
+        assert_eq!(
+            internal.to_ivk(Scope::External),
+            fvk.to_ivk(Scope::Internal)
+        );
+        assert_eq!(
+            internal.to_ovk(Scope::External),
+            fvk.to_ovk(Scope::Internal)
+        );
+        assert_eq!(
+            FullViewingKey::from_bytes(&internal.to_bytes()),
+            Some(internal)
+        );
+    }
+
+    #[test]
+    fn diversifiers_at_index() {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([4; 32]).unwrap());
+        for scope in [Scope::External, Scope::Internal] {
+            let dk = fvk.to_dk(scope);
+            assert_eq!(&dk, &fvk.to_ivk(scope).dk);
+            for j in [0u32, 1, 1000] {
+                let d = fvk.diversifier_at(j, scope);
+                assert_eq!(d, fvk.address_at(j, scope).diversifier());
+                assert_eq!(dk.diversifier_index(&d), j.into());
+            }
+        }
+    }
+
+    #[cfg(feature = "hazmat-key-agreement")]
+    #[test]
+    fn ivk_key_agreement() {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([6; 32]).unwrap());
+        let ivk = fvk.to_ivk(Scope::External);
+        let addr = fvk.address_at(5u32, Scope::External);
+        let rho = Rho::from_bytes(&[0; 32]).unwrap();
+        let note = Note::new(addr, NoteValue::from_raw(10), rho, rand::rngs::OsRng);
+
+        let shared_secret = ivk.key_agreement(&note.ephemeral_key()).unwrap();
+        assert_eq!(
+            shared_secret.to_bytes(),
+            note.esk().agree(addr.pk_d()).to_bytes()
+        );
+        assert!(bool::from(
+            ivk.key_agreement(&EphemeralKeyBytes([0; 32])).is_none()
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn viewing_key_serde() {
+        use serde::de::value::{BytesDeserializer, Error, StrDeserializer};
+
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([3; 32]).unwrap());
+        let ivk = fvk.to_ivk(Scope::External);
+
+        let fvk_hex = hex::encode(fvk.to_bytes());
+        let parsed = FullViewingKey::deserialize(StrDeserializer::<Error>::new(&fvk_hex));
+        assert_eq!(parsed.unwrap(), fvk);
+        let ivk_bytes = ivk.to_bytes();
+        let parsed = IncomingViewingKey::deserialize(BytesDeserializer::<Error>::new(&ivk_bytes));
+        assert_eq!(parsed.unwrap(), ivk);
+
+        // Invalid encodings are rejected.
+        let mut bad = fvk.to_bytes();
+        bad[32..64].copy_from_slice(&[0xff; 32]);
+        assert!(FullViewingKey::deserialize(BytesDeserializer::<Error>::new(&bad)).is_err());
+        assert!(
+            IncomingViewingKey::deserialize(BytesDeserializer::<Error>::new(&[0; 64])).is_err()
+        );
+    }
+
+    #[test]
+    fn randomized_key_check() {
+        let ask = SpendAuthorizingKey::from(&SpendingKey::random(&mut rand::rngs::OsRng));
+        let ak = SpendValidatingKey::from(&ask);
+        let alpha = pallas::Scalar::from(7);
+
+        let rk = ak.randomize(&alpha);
+        assert!(ak.verifies_randomized(&alpha, &rk));
+        assert!(!ak.verifies_randomized(&pallas::Scalar::from(8), &rk));
+        assert!(!ak.verifies_randomized(&alpha, &ak.randomize(&pallas::Scalar::from(0))));
+    }
+
+    #[test]
+    fn address_iterator() {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([7; 32]).unwrap());
+
+        let addrs: Vec<_> = fvk.addresses(5u32, Scope::Internal).take(3).collect();
+        for (k, (j, addr)) in addrs.into_iter().enumerate() {
+            assert_eq!(j, DiversifierIndex::from(5 + k as u32));
+            assert_eq!(addr, fvk.address_at(j, Scope::Internal));
+        }
+
+        // The iterator stops at the maximum diversifier index.
+        let max = DiversifierIndex::from([0xff; 11]);
+        assert_eq!(fvk.addresses(max, Scope::External).count(), 1);
+    }
+
+    #[test]
+    fn fvk_from_parts() {
+        let sk = SpendingKey::from_bytes([3; 32]).unwrap();
+        let fvk = FullViewingKey::from(&sk);
+        let bytes = fvk.to_bytes();
+
+        let ak = SpendValidatingKey::from(&SpendAuthorizingKey::from(&sk));
+        let nk = NullifierDerivingKey::from_bytes(&bytes[32..64]).unwrap();
+        let rivk = CommitIvkRandomness::from_bytes(&bytes[64..]).unwrap();
+        assert_eq!(nk.to_bytes()[..], bytes[32..64]);
+        assert_eq!(rivk.to_bytes()[..], bytes[64..]);
+        assert_eq!(FullViewingKey::from_parts(ak, nk, rivk), Some(fvk));
+
+        // Non-canonical encodings are rejected.
+        assert!(NullifierDerivingKey::from_bytes(&[0xff; 32]).is_none());
+        assert!(CommitIvkRandomness::from_bytes(&[0xff; 32]).is_none());
+    }
+
+    #[test]
+    fn component_errors() {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([3; 32]).unwrap());
+        let bytes = fvk.to_bytes();
+        let ak: [u8; 32] = bytes[..32].try_into().unwrap();
+
+        assert!(SpendValidatingKey::try_from(ak).is_ok());
+        assert_eq!(
+            SpendValidatingKey::try_from([0; 32]).unwrap_err(),
+            KeyComponentError::Identity
+        );
+        let mut negated = ak;
+        negated[31] |= 0x80;
+        assert_eq!(
+            SpendValidatingKey::try_from(negated).unwrap_err(),
+            KeyComponentError::InvalidSign
+        );
+        let mut non_canonical = [0xff; 32];
+        non_canonical[31] = 0x7f;
+        assert_eq!(
+            SpendValidatingKey::try_from(non_canonical).unwrap_err(),
+            KeyComponentError::NonCanonical
+        );
+        // 2^3 + 5 is not a square in the base field.
+        assert_eq!(
+            SpendValidatingKey::try_from(pallas::Base::from(2).to_repr()).unwrap_err(),
+            KeyComponentError::NotOnCurve
+        );
+
+        assert_eq!(
+            NullifierDerivingKey::try_from([0xff; 32]).unwrap_err(),
+            KeyComponentError::NonCanonical
+        );
+        assert_eq!(
+            CommitIvkRandomness::try_from([0xff; 32]).unwrap_err(),
+            KeyComponentError::NonCanonical
+        );
+
+        let ivk = fvk.to_ivk(Scope::External);
+        assert_eq!(
+            IncomingViewingKey::try_from(ivk.to_bytes()),
+            Ok(ivk.clone())
+        );
+        let mut zero = ivk.to_bytes();
+        zero[32..].copy_from_slice(&[0; 32]);
+        assert_eq!(
+            IncomingViewingKey::try_from(zero).unwrap_err(),
+            KeyComponentError::Zero
+        );
+        let mut non_canonical = ivk.to_bytes();
+        non_canonical[32..].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            IncomingViewingKey::try_from(non_canonical).unwrap_err(),
+            KeyComponentError::NonCanonical
+        );
+    }
+
+    #[test]
+    fn constant_time_equality() {
+        let sk = SpendingKey::from_bytes([1; 32]).unwrap();
+        let other = SpendingKey::from_bytes([2; 32]).unwrap();
+        assert!(bool::from(sk.ct_eq(&sk)));
+        assert!(!bool::from(sk.ct_eq(&other)));
+
+        let fvk = FullViewingKey::from(&sk);
+        let other = FullViewingKey::from(&other);
+        for scope in [Scope::External, Scope::Internal] {
+            let ivk = fvk.to_ivk(scope);
+            assert!(bool::from(ivk.ct_eq(&ivk.clone())));
+            assert!(!bool::from(ivk.ct_eq(&other.to_ivk(scope))));
+            let ovk = fvk.to_ovk(scope);
+            assert!(bool::from(ovk.ct_eq(&ovk.clone())));
+            assert!(!bool::from(ovk.ct_eq(&other.to_ovk(scope))));
+        }
+        assert!(!bool::from(
+            fvk.to_ivk(Scope::External)
+                .ct_eq(&fvk.to_ivk(Scope::Internal))
+        ));
+        let (dk, _) = fvk.derive_dk_ovk();
+        assert!(bool::from(dk.ct_eq(&dk.clone())));
+        assert!(!bool::from(dk.ct_eq(&other.derive_dk_ovk().0)));
+    }
+
+    #[test]
+    fn seed_to_account() {
+        let seed = [9; 32];
+        let account = AccountId::try_from(2).unwrap();
+        let (sk, fvk) = SpendingKey::from_zip32_seed_path(&seed, 133, account).unwrap();
+        let expected = SpendingKey::from_zip32_seed(&seed, 133, account).unwrap();
+        assert_eq!(sk.to_bytes(), expected.to_bytes());
+        assert_eq!(fvk, FullViewingKey::from(&expected));
+
+        assert_eq!(
+            SpendingKey::from_zip32_seed_path(&[9; 31], 133, account).unwrap_err(),
+            zip32::Error::InvalidSeedLength(31)
+        );
+        assert_eq!(
+            SpendingKey::from_zip32_seed_path(&[9; 253], 133, account).unwrap_err(),
+            zip32::Error::InvalidSeedLength(253)
+        );
+        assert_eq!(
+            SpendingKey::from_zip32_seed(&[9; 31], 133, account).unwrap_err(),
+            zip32::Error::InvalidSeedLength(31)
+        );
+        assert_eq!(
+            SpendingKey::from_zip32_seed_path(&seed, 1 << 31, account).unwrap_err(),
+            zip32::Error::InvalidChildIndex(1 << 31)
+        );
+    }
+
     #[test]
     fn parsers_reject_invalid() {
         assert!(bool::from(
@@ -996,6 +1771,63 @@ mod tests {
         ));
     }
 
+    proptest! {
+        #[test]
+        fn fvk_encoding_round_trip(
+            sk in arb_spending_key(),
+            byte in 0usize..96,
+            mask in 1u8..=255,
+        ) {
+            let fvk = FullViewingKey::from(&sk);
+            let bytes = fvk.to_bytes();
+            prop_assert_eq!(FullViewingKey::try_from(bytes), Ok(fvk.clone()));
+            prop_assert_eq!(
+                FullViewingKey::from_versioned_bytes(&fvk.to_versioned_bytes()),
+                Ok(fvk.clone())
+            );
+
+            // Flipping bits of any byte either yields a different key, or an error that
+            // blames the component containing that byte.
+            let mut corrupted = bytes;
+            corrupted[byte] ^= mask;
+            match FullViewingKey::try_from(corrupted) {
+                Ok(parsed) => prop_assert_ne!(parsed, fvk),
+                Err(FullViewingKeyError::Ak(_)) => prop_assert_eq!(byte / 32, 0),
+                Err(FullViewingKeyError::Nk(_)) => prop_assert_eq!(byte / 32, 1),
+                Err(FullViewingKeyError::Rivk(_)) => prop_assert_eq!(byte / 32, 2),
+                Err(e) => prop_assert_eq!(e, FullViewingKeyError::InvalidIvk),
+            }
+        }
+    }
+
+    #[test]
+    fn fvk_encoding_errors() {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([7; 32]).unwrap());
+
+        // A non-canonical encoding of each component is reported for that component.
+        for (i, expected) in [
+            FullViewingKeyError::Ak(KeyComponentError::NonCanonical),
+            FullViewingKeyError::Nk(KeyComponentError::NonCanonical),
+            FullViewingKeyError::Rivk(KeyComponentError::NonCanonical),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut bytes = fvk.to_bytes();
+            bytes[32 * i..32 * (i + 1)].copy_from_slice(&[0xff; 32]);
+            bytes[32 * i + 31] = 0x7f;
+            assert_eq!(FullViewingKey::try_from(bytes), Err(expected));
+        }
+
+        let mut versioned = fvk.to_versioned_bytes();
+        assert_eq!(versioned[0], FVK_ENCODING_V1);
+        versioned[0] = 2;
+        assert_eq!(
+            FullViewingKey::from_versioned_bytes(&versioned),
+            Err(FullViewingKeyError::UnknownVersion(2))
+        );
+    }
+
     proptest! {
         #[test]
         fn key_agreement(
@@ -1068,6 +1900,7 @@ mod tests {
             assert_eq!(cmx.to_bytes(), tv.note_cmx);
 
             assert_eq!(note.nullifier(&fvk).to_bytes(), tv.note_nf);
+            assert_eq!(note.nullifier_with_nk(fvk.nk()).to_bytes(), tv.note_nf);
 
             let internal_rivk = fvk.rivk(Scope::Internal);
             assert_eq!(internal_rivk.0.to_repr(), tv.internal_rivk);
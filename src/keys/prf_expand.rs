@@ -0,0 +1,130 @@
+//! The $\mathsf{PRF^{expand}}$ pseudo-random function, and the domain separators with
+//! which Orchard uses it.
+//!
+//! $\mathsf{PRF^{expand}}_\mathsf{sk}(t) = \mathsf{BLAKE2b\text{-}512}(\texttt{"Zcash\_ExpandSeed"}, \mathsf{sk} \| t)$,
+//! where the first byte of $t$ is a domain separator that determines which value is being
+//! derived. The constants in this module are the Orchard domain separators; the remainder
+//! of $t$ for each derivation is listed in its documentation.
+//!
+//! Defined in [Zcash Protocol Spec § 5.4.2: Pseudo Random Functions][concreteprfs].
+//!
+//! [concreteprfs]: https://zips.z.cash/protocol/nu5.pdf#concreteprfs
+
+use blake2b_simd::Params;
+
+/// The BLAKE2b-512 personalization used by $\mathsf{PRF^{expand}}$.
+pub const PRF_EXPAND_PERSONALIZATION: &[u8; 16] = b"Zcash_ExpandSeed";
+
+/// Domain separator for $\mathsf{esk}$, derived from $\mathsf{rseed}$.
+///
+/// $t = \mathtt{0x04} \| \underline{\rho}$.
+pub const ORCHARD_ESK: u8 = 0x04;
+
+/// Domain separator for $\mathsf{rcm}$, derived from $\mathsf{rseed}$.
+///
+/// $t = \mathtt{0x05} \| \underline{\rho}$.
+pub const ORCHARD_RCM: u8 = 0x05;
+
+/// Domain separator for $\mathsf{ask}$, derived from $\mathsf{sk}$.
+///
+/// $t = \mathtt{0x06}$.
+pub const ORCHARD_ASK: u8 = 0x06;
+
+/// Domain separator for $\mathsf{nk}$, derived from $\mathsf{sk}$.
+///
+/// $t = \mathtt{0x07}$.
+pub const ORCHARD_NK: u8 = 0x07;
+
+/// Domain separator for $\mathsf{rivk}$, derived from $\mathsf{sk}$.
+///
+/// $t = \mathtt{0x08}$.
+pub const ORCHARD_RIVK: u8 = 0x08;
+
+/// Domain separator for $\psi$, derived from $\mathsf{rseed}$.
+///
+/// $t = \mathtt{0x09} \| \underline{\rho}$.
+pub const ORCHARD_PSI: u8 = 0x09;
+
+/// Domain separator for ZIP 32 child key derivation, keyed by the parent chain code.
+///
+/// $t = \mathtt{0x81} \| \mathsf{sk}_{par} \| \mathsf{I2LEOSP}_{32}(i)$.
+pub const ORCHARD_ZIP32_CHILD: u8 = 0x81;
+
+/// Domain separator for $\mathsf{dk}$ and $\mathsf{ovk}$, keyed by $\mathsf{rivk}$.
+///
+/// $t = \mathtt{0x82} \| \mathsf{I2LEOSP}_{256}(\mathsf{ak}) \| \mathsf{I2LEOSP}_{256}(\mathsf{nk})$.
+pub const ORCHARD_DK_OVK: u8 = 0x82;
+
+/// Domain separator for $\mathsf{rivk_{internal}}$, keyed by $\mathsf{rivk}$.
+///
+/// $t = \mathtt{0x83} \| \mathsf{I2LEOSP}_{256}(\mathsf{ak}) \| \mathsf{I2LEOSP}_{256}(\mathsf{nk})$.
+pub const ORCHARD_RIVK_INTERNAL: u8 = 0x83;
+
+/// Computes $\mathsf{PRF^{expand}}_\mathsf{sk}(\mathsf{domain} \| t_1 \| t_2 \| \ldots)$.
+///
+/// `domain` should be one of the domain separators in this module, and `ts` the remaining
+/// inputs listed in its documentation, in order.
+pub fn prf_expand(sk: &[u8; 32], domain: u8, ts: &[&[u8]]) -> [u8; 64] {
+    let mut h = Params::new()
+        .hash_length(64)
+        .personal(PRF_EXPAND_PERSONALIZATION)
+        .to_state();
+    h.update(sk);
+    h.update(&[domain]);
+    for t in ts {
+        h.update(t);
+    }
+    *h.finalize().as_array()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::OsRng, RngCore};
+
+    use super::*;
+    use crate::spec::PrfExpand;
+
+    #[test]
+    fn matches_zcash_spec() {
+        let mut rng = OsRng;
+        let mut random = || {
+            let mut bytes = [0; 32];
+            rng.fill_bytes(&mut bytes);
+            bytes
+        };
+        let [sk, rho, ak, nk] = [(); 4].map(|_| random());
+
+        assert_eq!(
+            prf_expand(&sk, ORCHARD_ESK, &[&rho]),
+            PrfExpand::ORCHARD_ESK.with(&sk, &rho),
+        );
+        assert_eq!(
+            prf_expand(&sk, ORCHARD_RCM, &[&rho]),
+            PrfExpand::ORCHARD_RCM.with(&sk, &rho),
+        );
+        assert_eq!(
+            prf_expand(&sk, ORCHARD_ASK, &[]),
+            PrfExpand::ORCHARD_ASK.with(&sk),
+        );
+        assert_eq!(
+            prf_expand(&sk, ORCHARD_NK, &[]),
+            PrfExpand::ORCHARD_NK.with(&sk),
+        );
+        assert_eq!(
+            prf_expand(&sk, ORCHARD_RIVK, &[]),
+            PrfExpand::ORCHARD_RIVK.with(&sk),
+        );
+        assert_eq!(
+            prf_expand(&sk, ORCHARD_PSI, &[&rho]),
+            PrfExpand::PSI.with(&sk, &rho),
+        );
+        assert_eq!(
+            prf_expand(&sk, ORCHARD_DK_OVK, &[&ak, &nk]),
+            PrfExpand::ORCHARD_DK_OVK.with(&sk, &ak, &nk),
+        );
+        assert_eq!(
+            prf_expand(&sk, ORCHARD_RIVK_INTERNAL, &[&ak, &nk]),
+            PrfExpand::ORCHARD_RIVK_INTERNAL.with(&sk, &ak, &nk),
+        );
+    }
+}
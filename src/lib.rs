@@ -27,18 +27,36 @@ use alloc::vec::Vec;
 
 mod action;
 mod address;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
 pub mod builder;
 pub mod bundle;
 #[cfg(feature = "circuit")]
 pub mod circuit;
 mod constants;
+#[cfg(feature = "encoding")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+pub mod encoding;
 pub mod keys;
 pub mod note;
 pub mod note_encryption;
 pub mod pczt;
 pub mod primitives;
-mod spec;
+mod redacted;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "service")]
+#[cfg_attr(docsrs, doc(cfg(feature = "service")))]
+pub mod service;
+pub mod spec;
+#[cfg(any(test, feature = "spec-reference"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "spec-reference")))]
+pub mod spec_reference;
+#[cfg(any(test, feature = "test-vector-gen"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-vector-gen")))]
+pub mod test_vector_gen;
 pub mod tree;
+pub mod unified;
 pub mod value;
 pub mod zip32;
 
@@ -6,21 +6,31 @@ use ff::PrimeField;
 use group::GroupEncoding;
 use pasta_curves::pallas;
 use rand::RngCore;
-use subtle::CtOption;
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use subtle::{ConstantTimeEq, CtOption};
+use zcash_note_encryption::EphemeralKeyBytes;
 
 use crate::{
-    keys::{EphemeralSecretKey, FullViewingKey, Scope, SpendingKey},
+    keys::{EphemeralSecretKey, FullViewingKey, NullifierDerivingKey, Scope, SpendingKey},
+    primitives::sinsemilla::ProtocolDomain,
     spec::{to_base, to_scalar, NonZeroPallasScalar, PrfExpand},
     value::NoteValue,
     Address,
 };
 
+#[cfg(feature = "serde")]
+use crate::serialization;
+
 pub(crate) mod commitment;
-pub use self::commitment::{ExtractedNoteCommitment, NoteCommitment};
+pub use self::commitment::{ExtractedNoteCommitment, NoteCommitTrapdoor, NoteCommitment};
 
 pub(crate) mod nullifier;
 pub use self::nullifier::Nullifier;
 
+mod tracked;
+pub use self::tracked::TrackedNote;
+
 /// The randomness used to construct a note.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Rho(pallas::Base);
@@ -77,7 +87,12 @@ impl RandomSeed {
 
     /// Reads a note's random seed from bytes, given the note's rho value.
     ///
-    /// Returns `None` if the rho value is not for the same note as the seed.
+    /// Returns `None` if the seed does not produce a valid $\mathsf{esk}$ for `rho`, in
+    /// which case the rho value cannot be for the same note as the seed.
+    ///
+    /// The quantities derived from the seed (with [`RandomSeed::psi`],
+    /// [`RandomSeed::rcm`] and [`RandomSeed::esk`]) allow a revealed seed to be checked
+    /// against the note commitment and ephemeral key of an action.
     pub fn from_bytes(rseed: [u8; 32], rho: &Rho) -> CtOption<Self> {
         let rseed = RandomSeed(rseed);
         let esk = rseed.esk_inner(rho);
@@ -92,7 +107,7 @@ impl RandomSeed {
     /// Defined in [Zcash Protocol Spec § 4.7.3: Sending Notes (Orchard)][orchardsend].
     ///
     /// [orchardsend]: https://zips.z.cash/protocol/nu5.pdf#orchardsend
-    pub(crate) fn psi(&self, rho: &Rho) -> pallas::Base {
+    pub fn psi(&self, rho: &Rho) -> pallas::Base {
         to_base(PrfExpand::PSI.with(&self.0, &rho.to_bytes()))
     }
 
@@ -108,15 +123,15 @@ impl RandomSeed {
     /// Defined in [Zcash Protocol Spec § 4.7.3: Sending Notes (Orchard)][orchardsend].
     ///
     /// [orchardsend]: https://zips.z.cash/protocol/nu5.pdf#orchardsend
-    fn esk(&self, rho: &Rho) -> NonZeroPallasScalar {
+    pub fn esk(&self, rho: &Rho) -> EphemeralSecretKey {
         // We can't construct a RandomSeed for which this unwrap fails.
-        self.esk_inner(rho).unwrap()
+        EphemeralSecretKey(self.esk_inner(rho).unwrap())
     }
 
     /// Defined in [Zcash Protocol Spec § 4.7.3: Sending Notes (Orchard)][orchardsend].
     ///
     /// [orchardsend]: https://zips.z.cash/protocol/nu5.pdf#orchardsend
-    pub(crate) fn rcm(&self, rho: &Rho) -> commitment::NoteCommitTrapdoor {
+    pub fn rcm(&self, rho: &Rho) -> commitment::NoteCommitTrapdoor {
         commitment::NoteCommitTrapdoor(to_scalar(
             PrfExpand::ORCHARD_RCM.with(&self.0, &rho.to_bytes()),
         ))
@@ -151,10 +166,60 @@ impl PartialEq for Note {
 
 impl Eq for Note {}
 
+/// Errors that can occur when constructing a [`Note`] from its parts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoteError {
+    /// The random seed does not produce a valid ephemeral secret key for the note's
+    /// $\rho$.
+    InvalidRandomSeed,
+    /// A valid [`NoteCommitment`] cannot be derived from the note.
+    InvalidCommitment,
+}
+
+impl fmt::Display for NoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoteError::InvalidRandomSeed => {
+                write!(f, "Random seed does not produce a valid esk for rho")
+            }
+            NoteError::InvalidCommitment => write!(f, "Note commitment is not valid"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NoteError {}
+
 impl Note {
     /// Creates a `Note` from its component parts.
     ///
-    /// Returns `None` if a valid [`NoteCommitment`] cannot be derived from the note.
+    /// Returns [`NoteError::InvalidRandomSeed`] if `rseed` does not produce a valid
+    /// ephemeral secret key for `rho` (which [`RandomSeed::from_bytes`] also checks), and
+    /// [`NoteError::InvalidCommitment`] if a valid [`NoteCommitment`] cannot be derived
+    /// from the note.
+    ///
+    /// This is how a wallet restores a note that it has stored as its components:
+    ///
+    /// ```
+    /// use orchard::{
+    ///     keys::{FullViewingKey, Scope, SpendingKey},
+    ///     note::{RandomSeed, Rho},
+    ///     value::NoteValue,
+    ///     Address, Note,
+    /// };
+    ///
+    /// # let sk = SpendingKey::from_bytes([7; 32]).unwrap();
+    /// # let address = FullViewingKey::from(&sk).address_at(0u32, Scope::External);
+    /// // The components of a received note, as stored in the wallet's database.
+    /// let (address_bytes, value, rho_bytes, rseed_bytes) =
+    ///     (address.to_raw_address_bytes(), 1000, [3; 32], [4; 32]);
+    ///
+    /// let recipient = Address::from_raw_address_bytes(&address_bytes).unwrap();
+    /// let rho = Rho::from_bytes(&rho_bytes).unwrap();
+    /// let rseed = RandomSeed::from_bytes(rseed_bytes, &rho).unwrap();
+    /// let note = Note::from_parts(recipient, NoteValue::from_raw(value), rho, rseed);
+    /// assert!(note.is_ok());
+    /// ```
     ///
     /// # Caveats
     ///
@@ -171,6 +236,20 @@ impl Note {
         value: NoteValue,
         rho: Rho,
         rseed: RandomSeed,
+    ) -> Result<Self, NoteError> {
+        if bool::from(rseed.esk_inner(&rho).is_none()) {
+            return Err(NoteError::InvalidRandomSeed);
+        }
+        Option::from(Self::from_parts_inner(recipient, value, rho, rseed))
+            .ok_or(NoteError::InvalidCommitment)
+    }
+
+    /// Creates a `Note` from its component parts, checking it in constant time.
+    fn from_parts_inner(
+        recipient: Address,
+        value: NoteValue,
+        rho: Rho,
+        rseed: RandomSeed,
     ) -> CtOption<Self> {
         let note = Note {
             recipient,
@@ -178,7 +257,10 @@ impl Note {
             rho,
             rseed,
         };
-        CtOption::new(note, note.commitment_inner().is_some())
+        CtOption::new(
+            note,
+            rseed.esk_inner(&rho).is_some() & note.commitment_inner().is_some(),
+        )
     }
 
     /// Generates a new note.
@@ -186,6 +268,7 @@ impl Note {
     /// Defined in [Zcash Protocol Spec § 4.7.3: Sending Notes (Orchard)][orchardsend].
     ///
     /// [orchardsend]: https://zips.z.cash/protocol/nu5.pdf#orchardsend
+    #[cfg_attr(feature = "test-dependencies", visibility::make(pub))]
     pub(crate) fn new(
         recipient: Address,
         value: NoteValue,
@@ -193,9 +276,10 @@ impl Note {
         mut rng: impl RngCore,
     ) -> Self {
         loop {
-            let note = Note::from_parts(recipient, value, rho, RandomSeed::random(&mut rng, &rho));
-            if note.is_some().into() {
-                break note.unwrap();
+            if let Ok(note) =
+                Note::from_parts(recipient, value, rho, RandomSeed::random(&mut rng, &rho))
+            {
+                break note;
             }
         }
     }
@@ -205,6 +289,7 @@ impl Note {
     /// Defined in [Zcash Protocol Spec § 4.8.3: Dummy Notes (Orchard)][orcharddummynotes].
     ///
     /// [orcharddummynotes]: https://zips.z.cash/protocol/nu5.pdf#orcharddummynotes
+    #[cfg_attr(feature = "test-dependencies", visibility::make(pub))]
     pub(crate) fn dummy(
         rng: &mut impl RngCore,
         rho: Option<Rho>,
@@ -238,9 +323,28 @@ impl Note {
         &self.rseed
     }
 
-    /// Derives the ephemeral secret key for this note.
-    pub(crate) fn esk(&self) -> EphemeralSecretKey {
-        EphemeralSecretKey(self.rseed.esk(&self.rho))
+    /// Derives the ephemeral secret key $\mathsf{esk}$ with which this note is encrypted.
+    ///
+    /// The sender of a note can disclose `esk` to a third party, who can then use it with
+    /// the recipient's address to decrypt the note's ciphertext, and so confirm that the
+    /// note was sent to that address. This reveals nothing about the viewing keys of
+    /// either party.
+    ///
+    /// Defined in [Zcash Protocol Spec § 4.7.3: Sending Notes (Orchard)][orchardsend].
+    ///
+    /// [orchardsend]: https://zips.z.cash/protocol/nu5.pdf#orchardsend
+    pub fn esk(&self) -> EphemeralSecretKey {
+        self.rseed.esk(&self.rho)
+    }
+
+    /// Derives the encoding of the ephemeral public key
+    /// $\mathsf{epk} = [\mathsf{esk}] \mathsf{g_d}$ with which this note is encrypted,
+    /// which appears in the note's action as `ephemeralKey`.
+    ///
+    /// When disclosing [`Note::esk`], this identifies the action whose ciphertext the
+    /// disclosure applies to.
+    pub fn ephemeral_key(&self) -> EphemeralKeyBytes {
+        self.esk().derive_public(self.recipient.g_d()).to_bytes()
     }
 
     /// Returns rho of this note.
@@ -258,6 +362,30 @@ impl Note {
         self.commitment_inner().unwrap()
     }
 
+    /// Returns `true` if `cmx` is the extracted commitment to this note.
+    ///
+    /// A decrypted note should be checked against the commitment in the action it was
+    /// decrypted from before it is trusted. The note decryption APIs already do this, so
+    /// this is needed only for notes obtained by other means, such as from a wallet
+    /// backup or a note sent out of band.
+    pub fn verify_commitment(&self, cmx: &ExtractedNoteCommitment) -> bool {
+        self.checked_commitment(cmx).is_some()
+    }
+
+    /// Derives the commitment to this note, and returns it if `cmx` is its extracted
+    /// commitment.
+    ///
+    /// Returns `None` if the note does not match `cmx`. See
+    /// [`Note::verify_commitment`].
+    pub fn checked_commitment(&self, cmx: &ExtractedNoteCommitment) -> Option<NoteCommitment> {
+        let cm = self.commitment();
+        if bool::from(ExtractedNoteCommitment::from(cm.clone()).ct_eq(cmx)) {
+            Some(cm)
+        } else {
+            None
+        }
+    }
+
     /// Derives the commitment to this note.
     ///
     /// This is the internal fallible API, used to check at construction time that the
@@ -268,9 +396,19 @@ impl Note {
     ///
     /// [notes]: https://zips.z.cash/protocol/nu5.pdf#notes
     fn commitment_inner(&self) -> CtOption<NoteCommitment> {
+        self.commitment_in(&ProtocolDomain::ZCASH)
+    }
+
+    /// Derives the commitment to this note in the $\mathsf{NoteCommit}$ domain of
+    /// `domain`, for deployments of Orchard that do not use the Zcash personalizations.
+    ///
+    /// With [`ProtocolDomain::ZCASH`], this is the same as [`Note::commitment`]. In any
+    /// other domain, the commitment is not guaranteed to exist.
+    pub fn commitment_in(&self, domain: &ProtocolDomain) -> CtOption<NoteCommitment> {
         let g_d = self.recipient.g_d();
 
-        NoteCommitment::derive(
+        NoteCommitment::derive_in(
+            domain,
             g_d.to_bytes(),
             self.recipient.pk_d().to_bytes(),
             self.value,
@@ -282,12 +420,70 @@ impl Note {
 
     /// Derives the nullifier for this note.
     pub fn nullifier(&self, fvk: &FullViewingKey) -> Nullifier {
-        Nullifier::derive(
-            fvk.nk(),
-            self.rho.0,
-            self.rseed.psi(&self.rho),
-            self.commitment(),
-        )
+        self.nullifier_with_nk(fvk.nk())
+    }
+
+    /// Derives the nullifier for this note from the nullifier deriving key of the full
+    /// viewing key that received it.
+    ///
+    /// This allows a watch-only service that holds only $\mathsf{nk}$ (and the notes it
+    /// is watching) to detect when those notes are spent, without being able to detect
+    /// new notes or to link them to addresses.
+    pub fn nullifier_with_nk(&self, nk: &NullifierDerivingKey) -> Nullifier {
+        Nullifier::derive(nk, self.rho.0, self.rseed.psi(&self.rho), self.commitment())
+    }
+
+    /// Serializes this note.
+    ///
+    /// The encoding is the concatenation of the raw encoding of the recipient address
+    /// (43 bytes), the value as a little-endian `u64`, $\rho$, and $\mathsf{rseed}$,
+    /// for a total of [`NOTE_ENCODING_LEN`] bytes. It contains everything needed to spend
+    /// the note given the spending key of its recipient, and will not change in future
+    /// versions of this crate.
+    pub fn to_bytes(&self) -> [u8; NOTE_ENCODING_LEN] {
+        let mut bytes = [0; NOTE_ENCODING_LEN];
+        bytes[..43].copy_from_slice(&self.recipient.to_raw_address_bytes());
+        bytes[43..51].copy_from_slice(&self.value.inner().to_le_bytes());
+        bytes[51..83].copy_from_slice(&self.rho.to_bytes());
+        bytes[83..].copy_from_slice(self.rseed.as_bytes());
+        bytes
+    }
+
+    /// Parses a note from the encoding produced by [`Note::to_bytes`].
+    ///
+    /// Returns `None` if any component is invalid, or if the components do not form a
+    /// valid note (see [`Note::from_parts`]).
+    pub fn from_bytes(bytes: &[u8; NOTE_ENCODING_LEN]) -> CtOption<Self> {
+        let value = NoteValue::from_raw(u64::from_le_bytes(bytes[43..51].try_into().unwrap()));
+        Address::from_raw_address_bytes(bytes[..43].try_into().unwrap()).and_then(|recipient| {
+            Rho::from_bytes(bytes[51..83].try_into().unwrap()).and_then(|rho| {
+                RandomSeed::from_bytes(bytes[83..].try_into().unwrap(), &rho)
+                    .and_then(|rseed| Note::from_parts_inner(recipient, value, rho, rseed))
+            })
+        })
+    }
+}
+
+/// The length of the encoding of a [`Note`].
+pub const NOTE_ENCODING_LEN: usize = 43 + 8 + 32 + 32;
+
+/// Serializes the encoding produced by [`Note::to_bytes`], as a hex string in
+/// human-readable formats.
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for Note {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialization::serialize_bytes(&self.to_bytes(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for Note {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes: [u8; NOTE_ENCODING_LEN] = serialization::deserialize_bytes(deserializer)?;
+        Option::from(Note::from_bytes(&bytes))
+            .ok_or_else(|| de::Error::custom("Invalid Orchard note encoding"))
     }
 }
 
@@ -348,3 +544,86 @@ pub mod testing {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use group::GroupEncoding;
+    use subtle::ConstantTimeEq;
+
+    use super::{
+        testing::arb_note, ExtractedNoteCommitment, Note, NoteCommitment, RandomSeed, Rho,
+        NOTE_ENCODING_LEN,
+    };
+    use crate::value::testing::arb_note_value;
+
+    proptest! {
+        #[test]
+        fn note_encoding_round_trip(note in arb_note_value().prop_flat_map(arb_note)) {
+            let bytes = note.to_bytes();
+            let parsed = Note::from_bytes(&bytes).unwrap();
+            assert_eq!(parsed, note);
+            assert_eq!(parsed.value(), note.value());
+            assert_eq!(parsed.rseed().as_bytes(), note.rseed().as_bytes());
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn rseed_derivations(note in arb_note_value().prop_flat_map(arb_note)) {
+            let rho = note.rho();
+            let rseed = note.rseed();
+            let cm = NoteCommitment::derive(
+                note.recipient().g_d().to_bytes(),
+                note.recipient().pk_d().to_bytes(),
+                note.value(),
+                rho.into_inner(),
+                rseed.psi(&rho),
+                rseed.rcm(&rho),
+            )
+            .unwrap();
+            assert_eq!(
+                ExtractedNoteCommitment::from(cm),
+                ExtractedNoteCommitment::from(note.commitment())
+            );
+            assert!(bool::from(rseed.esk(&rho).ct_eq(&note.esk())));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn commitment_verification(
+            note in arb_note_value().prop_flat_map(arb_note),
+            other in arb_note_value().prop_flat_map(arb_note),
+        ) {
+            let cmx = ExtractedNoteCommitment::from(note.commitment());
+            assert!(note.verify_commitment(&cmx));
+            assert_eq!(
+                note.checked_commitment(&cmx).map(ExtractedNoteCommitment::from),
+                Some(cmx)
+            );
+
+            let other_cmx = ExtractedNoteCommitment::from(other.commitment());
+            assert_eq!(note.verify_commitment(&other_cmx), cmx == other_cmx);
+        }
+    }
+
+    #[cfg(not(feature = "expose-secrets-debug"))]
+    #[test]
+    fn rcm_debug_is_redacted() {
+        let rho = Rho::from_bytes(&[0; 32]).unwrap();
+        let rseed = RandomSeed::random(&mut rand::rngs::OsRng, &rho);
+        assert_eq!(
+            format!("{:?}", rseed.rcm(&rho)),
+            "NoteCommitTrapdoor([REDACTED])"
+        );
+    }
+
+    #[test]
+    fn note_encoding_rejects_invalid_address() {
+        assert!(bool::from(
+            Note::from_bytes(&[0xff; NOTE_ENCODING_LEN]).is_none()
+        ));
+    }
+}
@@ -1,4 +1,9 @@
-use core::iter;
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter,
+};
 
 use bitvec::{array::BitArray, order::Lsb0};
 use group::ff::{PrimeField, PrimeFieldBits};
@@ -6,18 +11,36 @@ use pasta_curves::pallas;
 use subtle::{ConstantTimeEq, CtOption};
 
 use crate::{
-    constants::{fixed_bases::NOTE_COMMITMENT_PERSONALIZATION, L_ORCHARD_BASE},
+    constants::L_ORCHARD_BASE,
+    primitives::sinsemilla::{self, ProtocolDomain},
+    redacted::Redacted,
     spec::extract_p,
     value::NoteValue,
 };
 
-#[derive(Clone, Debug)]
-pub(crate) struct NoteCommitTrapdoor(pub(super) pallas::Scalar);
+/// The randomness $\mathsf{rcm}$ of a [`NoteCommitment`].
+///
+/// This is derived from the note's [`RandomSeed`](crate::note::RandomSeed).
+#[derive(Clone)]
+pub struct NoteCommitTrapdoor(pub(super) pallas::Scalar);
+
+impl fmt::Debug for NoteCommitTrapdoor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NoteCommitTrapdoor")
+            .field(&Redacted(&self.0))
+            .finish()
+    }
+}
 
 impl NoteCommitTrapdoor {
     pub(crate) fn inner(&self) -> pallas::Scalar {
         self.0
     }
+
+    /// Returns the canonical encoding of this trapdoor.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
 }
 
 /// A commitment to a note.
@@ -44,18 +67,39 @@ impl NoteCommitment {
         psi: pallas::Base,
         rcm: NoteCommitTrapdoor,
     ) -> CtOption<Self> {
-        let domain = sinsemilla::CommitDomain::new(NOTE_COMMITMENT_PERSONALIZATION);
-        domain
-            .commit(
-                iter::empty()
-                    .chain(BitArray::<_, Lsb0>::new(g_d).iter().by_vals())
-                    .chain(BitArray::<_, Lsb0>::new(pk_d).iter().by_vals())
-                    .chain(v.to_le_bits().iter().by_vals())
-                    .chain(rho.to_le_bits().iter().by_vals().take(L_ORCHARD_BASE))
-                    .chain(psi.to_le_bits().iter().by_vals().take(L_ORCHARD_BASE)),
-                &rcm.0,
-            )
-            .map(NoteCommitment)
+        Self::derive_in(&ProtocolDomain::ZCASH, g_d, pk_d, v, rho, psi, rcm)
+    }
+
+    /// $NoteCommit^Orchard$, in the $\mathsf{NoteCommit}$ domain of `domain`.
+    ///
+    /// With the `constant-time-commitments` feature flag enabled, this is computed with
+    /// [`commit_constant_time`], so that the note (and in particular `rcm`) does not leak
+    /// through the cache to other processes on the same host.
+    ///
+    /// [`commit_constant_time`]: crate::primitives::sinsemilla::commit_constant_time
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn derive_in(
+        domain: &ProtocolDomain,
+        g_d: [u8; 32],
+        pk_d: [u8; 32],
+        v: NoteValue,
+        rho: pallas::Base,
+        psi: pallas::Base,
+        rcm: NoteCommitTrapdoor,
+    ) -> CtOption<Self> {
+        let msg = iter::empty()
+            .chain(BitArray::<_, Lsb0>::new(g_d).iter().by_vals())
+            .chain(BitArray::<_, Lsb0>::new(pk_d).iter().by_vals())
+            .chain(v.to_le_bits().iter().by_vals())
+            .chain(rho.to_le_bits().iter().by_vals().take(L_ORCHARD_BASE))
+            .chain(psi.to_le_bits().iter().by_vals().take(L_ORCHARD_BASE));
+
+        let cm = if cfg!(feature = "constant-time-commitments") {
+            sinsemilla::commit_constant_time(domain.note_commit, msg, &rcm.0)
+        } else {
+            domain.note_commit_domain().commit(msg, &rcm.0)
+        };
+        cm.map(NoteCommitment)
     }
 }
 
@@ -111,3 +155,21 @@ impl PartialEq for ExtractedNoteCommitment {
 }
 
 impl Eq for ExtractedNoteCommitment {}
+
+impl PartialOrd for ExtractedNoteCommitment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ExtractedNoteCommitment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for ExtractedNoteCommitment {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
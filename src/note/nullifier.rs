@@ -1,3 +1,5 @@
+use core::hash::{Hash, Hasher};
+
 use group::{ff::PrimeField, Group};
 use memuse::DynamicUsage;
 use pasta_curves::{arithmetic::CurveExt, pallas};
@@ -30,6 +32,7 @@ impl Nullifier {
     ///
     /// Instead of explicitly sampling for a unique nullifier, we rely here on the size of
     /// the base field to make the chance of sampling a colliding nullifier negligible.
+    #[cfg_attr(feature = "test-dependencies", visibility::make(pub))]
     pub(crate) fn dummy(rng: &mut impl RngCore) -> Self {
         Nullifier(extract_p(&pallas::Point::random(rng)))
     }
@@ -61,6 +64,12 @@ impl Nullifier {
     }
 }
 
+impl Hash for Nullifier {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
 impl ConstantTimeEq for Nullifier {
     fn ct_eq(&self, other: &Self) -> subtle::Choice {
         self.0.ct_eq(&other.0)
@@ -0,0 +1,125 @@
+//! Tracking of received notes by watch-only wallets.
+
+use incrementalmerkletree::Position;
+
+use super::{ExtractedNoteCommitment, Note, Nullifier};
+use crate::{
+    builder::SpendInfo,
+    keys::{FullViewingKey, Scope},
+    tree::{MerkleHashOrchard, MerklePath},
+};
+
+/// A note received by a full viewing key, along with what a wallet needs to detect when
+/// it is spent, and to spend it.
+///
+/// A wallet constructs this when it decrypts a note, and stores it (for example, with
+/// [`Note::to_bytes`] and the position). The note is spent by the action that reveals
+/// [`TrackedNote::nullifier`]. To spend the note, the wallet witnesses
+/// [`TrackedNote::leaf`] at [`TrackedNote::position`] in its note commitment tree, and
+/// passes the resulting path to [`TrackedNote::spend_info`].
+#[derive(Clone, Debug)]
+pub struct TrackedNote {
+    note: Note,
+    scope: Scope,
+    position: Position,
+    cmx: ExtractedNoteCommitment,
+    nf: Nullifier,
+}
+
+impl TrackedNote {
+    /// Constructs the tracking information for `note`, whose commitment is at `position`
+    /// in the note commitment tree.
+    ///
+    /// Returns `None` if `note` was not sent to an address of `fvk`.
+    pub fn new(fvk: &FullViewingKey, note: Note, position: Position) -> Option<Self> {
+        let scope = fvk.scope_for_address(&note.recipient())?;
+        Some(TrackedNote {
+            note,
+            scope,
+            position,
+            cmx: note.commitment().into(),
+            nf: note.nullifier(fvk),
+        })
+    }
+
+    /// Returns the note.
+    pub fn note(&self) -> &Note {
+        &self.note
+    }
+
+    /// Returns the scope of the address that received the note.
+    pub fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// Returns the position of the note's commitment in the note commitment tree.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Returns the extracted commitment to the note.
+    pub fn cmx(&self) -> ExtractedNoteCommitment {
+        self.cmx
+    }
+
+    /// Returns the leaf of the note commitment tree for the note, which a wallet should
+    /// mark in its tree so that it can witness the note later.
+    pub fn leaf(&self) -> MerkleHashOrchard {
+        MerkleHashOrchard::from_cmx(&self.cmx)
+    }
+
+    /// Returns the nullifier that will be revealed when the note is spent.
+    pub fn nullifier(&self) -> Nullifier {
+        self.nf
+    }
+
+    /// Returns the information needed to spend the note, given a Merkle path to its
+    /// commitment.
+    ///
+    /// Returns `None` if `fvk` is not the key that received the note, or if
+    /// `merkle_path` is not a path for the note's position.
+    pub fn spend_info(&self, fvk: FullViewingKey, merkle_path: MerklePath) -> Option<SpendInfo> {
+        if u64::from(self.position) != u64::from(merkle_path.position())
+            || self.note.nullifier(&fvk) != self.nf
+        {
+            return None;
+        }
+        SpendInfo::new(fvk, self.note, merkle_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use incrementalmerkletree::{Hashable, Level, Position};
+    use rand::rngs::OsRng;
+
+    use super::TrackedNote;
+    use crate::{
+        keys::{FullViewingKey, Scope, SpendingKey},
+        note::{Nullifier, Rho},
+        tree::{MerkleHashOrchard, MerklePath},
+        value::NoteValue,
+        Note,
+    };
+
+    #[test]
+    fn tracks_received_notes() {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([1; 32]).unwrap());
+        let other = FullViewingKey::from(&SpendingKey::from_bytes([2; 32]).unwrap());
+        let rho = Rho::from_nf_old(Nullifier::dummy(&mut OsRng));
+        let note = Note::new(fvk.change_address(), NoteValue::from_raw(7), rho, OsRng);
+
+        assert!(TrackedNote::new(&other, note, Position::from(5)).is_none());
+        let tracked = TrackedNote::new(&fvk, note, Position::from(5)).unwrap();
+        assert_eq!(tracked.scope(), Scope::Internal);
+        assert_eq!(tracked.nullifier(), note.nullifier(&fvk));
+        assert_eq!(tracked.leaf(), MerkleHashOrchard::from_cmx(&tracked.cmx()));
+
+        let auth_path =
+            core::array::from_fn(|l| MerkleHashOrchard::empty_root(Level::from(l as u8)));
+        let path = |position| MerklePath::from_parts(position, auth_path);
+        assert!(tracked.spend_info(fvk.clone(), path(5)).is_some());
+        assert!(tracked.spend_info(fvk.clone(), path(4)).is_none());
+        assert!(tracked.spend_info(other, path(5)).is_none());
+    }
+}
@@ -22,6 +22,17 @@ use crate::{
     Address, Note,
 };
 
+mod keyring;
+pub use self::keyring::Keyring;
+mod policy;
+pub use self::policy::{
+    try_compact_note_decryption_with_policy, try_note_decryption_with_policy, DecryptionError,
+    RseedPolicy,
+};
+
+mod scan;
+pub use self::scan::{BirthdayScanner, ScanError, ScannedNote};
+
 const PRF_OCK_ORCHARD_PERSONALIZATION: &[u8; 16] = b"Zcash_Orchardock";
 
 /// Defined in [Zcash Protocol Spec § 5.4.2: Pseudo Random Functions][concreteprfs].
@@ -75,7 +86,7 @@ where
     let pk_d = get_pk_d(&diversifier);
 
     let recipient = Address::from_parts(diversifier, pk_d);
-    let note = Option::from(Note::from_parts(recipient, value, domain.rho, rseed))?;
+    let note = Note::from_parts(recipient, value, domain.rho, rseed).ok()?;
     Some((note, recipient))
 }
 
@@ -360,6 +371,21 @@ impl CompactAction {
     pub fn rho(&self) -> Rho {
         Rho::from_nf_old(self.nullifier)
     }
+
+    /// Returns `true` if this compact action is consistent with the given full action.
+    ///
+    /// A compact action is a truncation of its full action: the nullifier, note
+    /// commitment and ephemeral key must be equal, and the compact ciphertext must be a
+    /// prefix of the full `enc_ciphertext`. Light clients can use this to check that the
+    /// compact data they were served (and may have trial-decrypted) matches the full
+    /// transaction, once that has been obtained.
+    pub fn is_prefix_of<T>(&self, action: &Action<T>) -> bool {
+        self.nullifier == *action.nullifier()
+            && self.cmx == *action.cmx()
+            && self.ephemeral_key.0 == action.encrypted_note().epk_bytes
+            && self.enc_ciphertext[..]
+                == action.encrypted_note().enc_ciphertext[..COMPACT_NOTE_SIZE]
+    }
 }
 
 /// Utilities for constructing test data.
@@ -515,7 +541,13 @@ mod tests {
                 None => panic!("Note decryption failed"),
             }
 
-            match try_compact_note_decryption(&domain, &ivk, &CompactAction::from(&action)) {
+            let compact_action = CompactAction::from(&action);
+            assert!(compact_action.is_prefix_of(&action));
+            let mut mismatched = compact_action.clone();
+            mismatched.enc_ciphertext[0] ^= 1;
+            assert!(!mismatched.is_prefix_of(&action));
+
+            match try_compact_note_decryption(&domain, &ivk, &compact_action) {
                 Some((decrypted_note, decrypted_to)) => {
                     assert_eq!(decrypted_note, note);
                     assert_eq!(decrypted_to, recipient);
@@ -0,0 +1,280 @@
+//! Trial decryption with the incoming viewing keys of many accounts at once.
+
+use alloc::vec::Vec;
+
+use zcash_note_encryption::batch;
+
+use super::{CompactAction, OrchardDomain};
+use crate::{
+    action::Action,
+    keys::{FullViewingKey, IncomingViewingKey, PreparedIncomingViewingKey, Scope},
+    Address, Note,
+};
+
+/// A set of prepared incoming viewing keys, each labelled with the account and scope it
+/// belongs to.
+///
+/// Outputs are trial-decrypted with every key in the keyring in a single batch, so the
+/// cost of decoding each output's ephemeral key and of batch-normalizing the shared
+/// secrets is paid once per output, rather than once per output and key. The keys are
+/// prepared when they are added to the keyring.
+///
+/// The account labels `A` are chosen by the caller, and are typically a
+/// [`zip32::AccountId`](::zip32::AccountId) or a wallet database key.
+#[derive(Debug)]
+pub struct Keyring<A> {
+    labels: Vec<(A, Scope)>,
+    ivks: Vec<PreparedIncomingViewingKey>,
+}
+
+impl<A> Default for Keyring<A> {
+    fn default() -> Self {
+        Keyring {
+            labels: Vec::new(),
+            ivks: Vec::new(),
+        }
+    }
+}
+
+impl<A> Keyring<A> {
+    /// Constructs an empty keyring.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an incoming viewing key for the given scope of `account`.
+    pub fn add_ivk(&mut self, account: A, scope: Scope, ivk: &IncomingViewingKey) {
+        self.labels.push((account, scope));
+        self.ivks.push(PreparedIncomingViewingKey::new(ivk));
+    }
+
+    /// Adds the external and internal incoming viewing keys of `account`'s full viewing
+    /// key.
+    pub fn add_fvk(&mut self, account: A, fvk: &FullViewingKey)
+    where
+        A: Clone,
+    {
+        self.add_ivk(
+            account.clone(),
+            Scope::External,
+            &fvk.to_ivk(Scope::External),
+        );
+        self.add_ivk(account, Scope::Internal, &fvk.to_ivk(Scope::Internal));
+    }
+
+    /// Returns the number of keys in the keyring.
+    pub fn len(&self) -> usize {
+        self.ivks.len()
+    }
+
+    /// Returns `true` if the keyring contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.ivks.is_empty()
+    }
+
+    /// Returns the prepared keys in the keyring, for use with the batch decryption APIs
+    /// of [`zcash_note_encryption::batch`].
+    ///
+    /// The key indices returned by those APIs can be mapped back to their account and
+    /// scope with [`Keyring::label`].
+    pub fn ivks(&self) -> &[PreparedIncomingViewingKey] {
+        &self.ivks
+    }
+
+    /// Returns the account and scope of the key at `index`.
+    pub fn label(&self, index: usize) -> Option<(&A, Scope)> {
+        self.labels
+            .get(index)
+            .map(|(account, scope)| (account, *scope))
+    }
+
+    /// Trial-decrypts each of the given actions with every key in the keyring.
+    ///
+    /// Returns the index of each action that was decrypted, along with the account and
+    /// scope of the key that decrypted it, and the decrypted note plaintext contents.
+    pub fn decrypt_actions<T: Clone>(
+        &self,
+        actions: &[Action<T>],
+    ) -> Vec<(usize, &A, Scope, Note, Address, [u8; 512])> {
+        let outputs: Vec<_> = actions
+            .iter()
+            .map(|action| (OrchardDomain::for_action(action), action.clone()))
+            .collect();
+        self.decrypt_with(
+            |ivks| batch::try_note_decryption(ivks, &outputs),
+            |idx, account, scope, (note, recipient, memo)| {
+                (idx, account, scope, note, recipient, memo)
+            },
+        )
+    }
+
+    /// Trial-decrypts each of the given compact actions with every key in the keyring.
+    ///
+    /// Returns the index of each action that was decrypted, along with the account and
+    /// scope of the key that decrypted it, and the decrypted note and recipient.
+    pub fn decrypt_compact_actions(
+        &self,
+        actions: &[CompactAction],
+    ) -> Vec<(usize, &A, Scope, Note, Address)> {
+        let outputs: Vec<_> = actions
+            .iter()
+            .map(|action| (OrchardDomain::for_compact_action(action), action.clone()))
+            .collect();
+        self.decrypt_with(
+            |ivks| batch::try_compact_note_decryption(ivks, &outputs),
+            |idx, account, scope, (note, recipient)| (idx, account, scope, note, recipient),
+        )
+    }
+
+    /// Runs `decrypt` on the keys in the keyring, and labels each successful result with
+    /// its action index and the account and scope of the key that decrypted it.
+    fn decrypt_with<'a, D, R>(
+        &'a self,
+        decrypt: impl FnOnce(&[PreparedIncomingViewingKey]) -> Vec<Option<(D, usize)>>,
+        labelled: impl Fn(usize, &'a A, Scope, D) -> R,
+    ) -> Vec<R> {
+        if self.ivks.is_empty() {
+            return Vec::new();
+        }
+        decrypt(&self.ivks)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, result)| {
+                result.map(|(decrypted, ivk_idx)| {
+                    let (account, scope) = &self.labels[ivk_idx];
+                    labelled(idx, account, *scope, decrypted)
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use rand::rngs::OsRng;
+    use zcash_note_encryption::{Domain, COMPACT_NOTE_SIZE};
+
+    use super::Keyring;
+    use crate::{
+        action::Action,
+        keys::{FullViewingKey, Scope, SpendingKey},
+        note::{ExtractedNoteCommitment, Nullifier, Rho, TransmittedNoteCiphertext},
+        note_encryption::{CompactAction, OrchardDomain, OrchardNoteEncryption},
+        primitives::redpallas,
+        value::{NoteValue, ValueCommitTrapdoor, ValueCommitment},
+        Address, Note,
+    };
+
+    #[test]
+    fn matches_account_and_scope() {
+        let mut rng = OsRng;
+        let fvks: Vec<_> = (0..3u8)
+            .map(|i| FullViewingKey::from(&SpendingKey::from_bytes([i + 1; 32]).unwrap()))
+            .collect();
+        let stranger = FullViewingKey::from(&SpendingKey::from_bytes([9; 32]).unwrap());
+
+        let mut keyring = Keyring::new();
+        assert!(keyring.is_empty());
+        for (account, fvk) in fvks.iter().enumerate() {
+            keyring.add_fvk(account, fvk);
+        }
+        assert_eq!(keyring.len(), 6);
+        assert_eq!(keyring.label(3), Some((&1, Scope::Internal)));
+
+        let mut action_to = |recipient: Address| {
+            let nf = Nullifier::dummy(&mut rng);
+            let note = Note::new(
+                recipient,
+                NoteValue::from_raw(10),
+                Rho::from_nf_old(nf),
+                &mut rng,
+            );
+            let ne = OrchardNoteEncryption::new(None, note, [0; 512]);
+            CompactAction::from_parts(
+                nf,
+                ExtractedNoteCommitment::from(note.commitment()),
+                OrchardDomain::epk_bytes(ne.epk()),
+                ne.encrypt_note_plaintext()[..COMPACT_NOTE_SIZE]
+                    .try_into()
+                    .unwrap(),
+            )
+        };
+        let actions = [
+            action_to(fvks[2].address_at(0u32, Scope::External)),
+            action_to(stranger.address_at(0u32, Scope::External)),
+            action_to(fvks[0].change_address()),
+        ];
+
+        let decrypted = keyring.decrypt_compact_actions(&actions);
+        assert_eq!(decrypted.len(), 2);
+        assert_eq!((decrypted[0].0, decrypted[0].1), (0, &2));
+        assert_eq!(decrypted[0].2, Scope::External);
+        assert_eq!((decrypted[1].0, decrypted[1].1), (2, &0));
+        assert_eq!(decrypted[1].2, Scope::Internal);
+        assert_eq!(decrypted[1].4, fvks[0].change_address());
+
+        assert!(Keyring::<u32>::new()
+            .decrypt_compact_actions(&actions)
+            .is_empty());
+    }
+
+    #[test]
+    fn decrypts_full_actions() {
+        let mut rng = OsRng;
+        let fvks: Vec<_> = (0..3u8)
+            .map(|i| FullViewingKey::from(&SpendingKey::from_bytes([i + 1; 32]).unwrap()))
+            .collect();
+        let stranger = FullViewingKey::from(&SpendingKey::from_bytes([9; 32]).unwrap());
+
+        let mut keyring = Keyring::new();
+        for (account, fvk) in fvks.iter().enumerate() {
+            keyring.add_fvk(account, fvk);
+        }
+
+        let mut action_to = |recipient: Address, memo: [u8; 512]| {
+            let nf = Nullifier::dummy(&mut rng);
+            let note = Note::new(
+                recipient,
+                NoteValue::from_raw(10),
+                Rho::from_nf_old(nf),
+                &mut rng,
+            );
+            let cmx = ExtractedNoteCommitment::from(note.commitment());
+            let cv_net = ValueCommitment::derive(
+                NoteValue::zero() - note.value(),
+                ValueCommitTrapdoor::random(&mut rng),
+            );
+            let ne = OrchardNoteEncryption::new(None, note, memo);
+            let encrypted_note = TransmittedNoteCiphertext {
+                epk_bytes: OrchardDomain::epk_bytes(ne.epk()).0,
+                enc_ciphertext: ne.encrypt_note_plaintext(),
+                out_ciphertext: ne.encrypt_outgoing_plaintext(&cv_net, &cmx, &mut rng),
+            };
+            Action::from_parts(
+                nf,
+                redpallas::VerificationKey::dummy(),
+                cmx,
+                encrypted_note,
+                cv_net,
+                (),
+            )
+        };
+        let actions = [
+            action_to(stranger.address_at(0u32, Scope::External), [1; 512]),
+            action_to(fvks[1].address_at(3u32, Scope::External), [2; 512]),
+            action_to(stranger.change_address(), [3; 512]),
+        ];
+
+        let decrypted = keyring.decrypt_actions(&actions);
+        assert_eq!(decrypted.len(), 1);
+        let (idx, account, scope, note, recipient, memo) = &decrypted[0];
+        assert_eq!((*idx, *account, *scope), (1, &1, Scope::External));
+        assert_eq!(note.value(), NoteValue::from_raw(10));
+        assert_eq!(*recipient, fvks[1].address_at(3u32, Scope::External));
+        assert_eq!(memo, &[2; 512]);
+
+        assert!(Keyring::<u32>::new().decrypt_actions(&actions).is_empty());
+    }
+}
@@ -0,0 +1,389 @@
+//! Trial decryption with a configurable check of the ephemeral key against `rseed`.
+//!
+//! [ZIP 212] requires the recipient of a note to check that the ephemeral key of the
+//! output was derived from the note's `rseed`, which `zcash_note_encryption` always
+//! enforces. The functions in this module allow that check to be relaxed, and report
+//! which check a rejected output failed.
+//!
+//! [ZIP 212]: https://zips.z.cash/zip-0212
+
+use core::fmt;
+
+use subtle::ConstantTimeEq;
+use zcash_note_encryption::{
+    try_compact_note_decryption, try_note_decryption, Domain, EphemeralKeyBytes,
+    NotePlaintextBytes, OutPlaintextBytes, OutgoingCipherKey, ShieldedOutput, COMPACT_NOTE_SIZE,
+    ENC_CIPHERTEXT_SIZE,
+};
+
+use super::OrchardDomain;
+use crate::{keys::PreparedIncomingViewingKey, Address, Note};
+
+/// How trial decryption treats an output whose ephemeral key was not derived from the
+/// decrypted note's `rseed`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RseedPolicy {
+    /// Reject the output with [`DecryptionError::EphemeralKeyMismatch`].
+    ///
+    /// This is the behaviour required by [ZIP 212], and the policy that wallets should
+    /// use when detecting received notes.
+    ///
+    /// [ZIP 212]: https://zips.z.cash/zip-0212
+    #[default]
+    Strict,
+    /// Accept the output without checking its ephemeral key.
+    ///
+    /// Outputs that are only accepted under this policy do not conform to ZIP 212, and
+    /// should not be treated as received notes by a wallet. It is intended for archival
+    /// scanning, where every output that decrypts should be surfaced.
+    Lenient,
+}
+
+/// Errors that can occur in [`try_note_decryption_with_policy`] and
+/// [`try_compact_note_decryption_with_policy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecryptionError {
+    /// The output could not be decrypted with the given key, or its plaintext is not a
+    /// valid note with the output's note commitment.
+    NotDecrypted,
+    /// The output decrypted to a valid note, but its ephemeral key was not derived from
+    /// the note's `rseed`.
+    EphemeralKeyMismatch,
+}
+
+impl fmt::Display for DecryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecryptionError::NotDecrypted => write!(f, "Output could not be decrypted"),
+            DecryptionError::EphemeralKeyMismatch => write!(
+                f,
+                "Ephemeral key of the output was not derived from the note's rseed"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecryptionError {}
+
+/// Trial-decrypts `output` with `ivk`, checking its ephemeral key according to `policy`.
+///
+/// With [`RseedPolicy::Strict`], this accepts exactly the outputs accepted by
+/// [`zcash_note_encryption::try_note_decryption`].
+pub fn try_note_decryption_with_policy<
+    Output: ShieldedOutput<OrchardDomain, ENC_CIPHERTEXT_SIZE>,
+>(
+    domain: &OrchardDomain,
+    ivk: &PreparedIncomingViewingKey,
+    output: &Output,
+    policy: RseedPolicy,
+) -> Result<(Note, Address, [u8; 512]), DecryptionError> {
+    let (note, recipient, memo) =
+        try_note_decryption(&LenientDomain(domain), ivk, &LenientOutput(output))
+            .ok_or(DecryptionError::NotDecrypted)?;
+    check_ephemeral_key(&note, &output.ephemeral_key(), policy)?;
+    Ok((note, recipient, memo))
+}
+
+/// Trial-decrypts the compact `output` with `ivk`, checking its ephemeral key according
+/// to `policy`.
+///
+/// With [`RseedPolicy::Strict`], this accepts exactly the outputs accepted by
+/// [`zcash_note_encryption::try_compact_note_decryption`].
+pub fn try_compact_note_decryption_with_policy<
+    Output: ShieldedOutput<OrchardDomain, COMPACT_NOTE_SIZE>,
+>(
+    domain: &OrchardDomain,
+    ivk: &PreparedIncomingViewingKey,
+    output: &Output,
+    policy: RseedPolicy,
+) -> Result<(Note, Address), DecryptionError> {
+    let (note, recipient) =
+        try_compact_note_decryption(&LenientDomain(domain), ivk, &LenientOutput(output))
+            .ok_or(DecryptionError::NotDecrypted)?;
+    check_ephemeral_key(&note, &output.ephemeral_key(), policy)?;
+    Ok((note, recipient))
+}
+
+fn check_ephemeral_key(
+    note: &Note,
+    ephemeral_key: &EphemeralKeyBytes,
+    policy: RseedPolicy,
+) -> Result<(), DecryptionError> {
+    match policy {
+        RseedPolicy::Lenient => Ok(()),
+        RseedPolicy::Strict => {
+            let esk = note.esk();
+            let epk = OrchardDomain::epk_bytes(&OrchardDomain::ka_derive_public(note, &esk));
+            if bool::from(epk.ct_eq(ephemeral_key)) {
+                Ok(())
+            } else {
+                Err(DecryptionError::EphemeralKeyMismatch)
+            }
+        }
+    }
+}
+
+/// An [`OrchardDomain`] that does not derive `esk` from decrypted notes, which causes
+/// `zcash_note_encryption` to skip its ephemeral key check.
+#[derive(Debug)]
+struct LenientDomain<'a>(&'a OrchardDomain);
+
+/// Presents an output of an [`OrchardDomain`] as an output of a [`LenientDomain`].
+struct LenientOutput<'a, Output>(&'a Output);
+
+impl<Output: ShieldedOutput<OrchardDomain, N>, const N: usize> ShieldedOutput<LenientDomain<'_>, N>
+    for LenientOutput<'_, Output>
+{
+    fn ephemeral_key(&self) -> EphemeralKeyBytes {
+        self.0.ephemeral_key()
+    }
+
+    fn cmstar_bytes(&self) -> [u8; 32] {
+        self.0.cmstar_bytes()
+    }
+
+    fn enc_ciphertext(&self) -> &[u8; N] {
+        self.0.enc_ciphertext()
+    }
+}
+
+impl Domain for LenientDomain<'_> {
+    type EphemeralSecretKey = <OrchardDomain as Domain>::EphemeralSecretKey;
+    type EphemeralPublicKey = <OrchardDomain as Domain>::EphemeralPublicKey;
+    type PreparedEphemeralPublicKey = <OrchardDomain as Domain>::PreparedEphemeralPublicKey;
+    type SharedSecret = <OrchardDomain as Domain>::SharedSecret;
+    type SymmetricKey = <OrchardDomain as Domain>::SymmetricKey;
+    type Note = <OrchardDomain as Domain>::Note;
+    type Recipient = <OrchardDomain as Domain>::Recipient;
+    type DiversifiedTransmissionKey = <OrchardDomain as Domain>::DiversifiedTransmissionKey;
+    type IncomingViewingKey = <OrchardDomain as Domain>::IncomingViewingKey;
+    type OutgoingViewingKey = <OrchardDomain as Domain>::OutgoingViewingKey;
+    type ValueCommitment = <OrchardDomain as Domain>::ValueCommitment;
+    type ExtractedCommitment = <OrchardDomain as Domain>::ExtractedCommitment;
+    type ExtractedCommitmentBytes = <OrchardDomain as Domain>::ExtractedCommitmentBytes;
+    type Memo = <OrchardDomain as Domain>::Memo;
+
+    fn derive_esk(_: &Self::Note) -> Option<Self::EphemeralSecretKey> {
+        None
+    }
+
+    fn get_pk_d(note: &Self::Note) -> Self::DiversifiedTransmissionKey {
+        OrchardDomain::get_pk_d(note)
+    }
+
+    fn prepare_epk(epk: Self::EphemeralPublicKey) -> Self::PreparedEphemeralPublicKey {
+        OrchardDomain::prepare_epk(epk)
+    }
+
+    fn ka_derive_public(
+        note: &Self::Note,
+        esk: &Self::EphemeralSecretKey,
+    ) -> Self::EphemeralPublicKey {
+        OrchardDomain::ka_derive_public(note, esk)
+    }
+
+    fn ka_agree_enc(
+        esk: &Self::EphemeralSecretKey,
+        pk_d: &Self::DiversifiedTransmissionKey,
+    ) -> Self::SharedSecret {
+        OrchardDomain::ka_agree_enc(esk, pk_d)
+    }
+
+    fn ka_agree_dec(
+        ivk: &Self::IncomingViewingKey,
+        epk: &Self::PreparedEphemeralPublicKey,
+    ) -> Self::SharedSecret {
+        OrchardDomain::ka_agree_dec(ivk, epk)
+    }
+
+    fn kdf(secret: Self::SharedSecret, ephemeral_key: &EphemeralKeyBytes) -> Self::SymmetricKey {
+        OrchardDomain::kdf(secret, ephemeral_key)
+    }
+
+    fn note_plaintext_bytes(note: &Self::Note, memo: &Self::Memo) -> NotePlaintextBytes {
+        OrchardDomain::note_plaintext_bytes(note, memo)
+    }
+
+    fn derive_ock(
+        ovk: &Self::OutgoingViewingKey,
+        cv: &Self::ValueCommitment,
+        cmstar_bytes: &Self::ExtractedCommitmentBytes,
+        ephemeral_key: &EphemeralKeyBytes,
+    ) -> OutgoingCipherKey {
+        OrchardDomain::derive_ock(ovk, cv, cmstar_bytes, ephemeral_key)
+    }
+
+    fn outgoing_plaintext_bytes(
+        note: &Self::Note,
+        esk: &Self::EphemeralSecretKey,
+    ) -> OutPlaintextBytes {
+        OrchardDomain::outgoing_plaintext_bytes(note, esk)
+    }
+
+    fn epk_bytes(epk: &Self::EphemeralPublicKey) -> EphemeralKeyBytes {
+        OrchardDomain::epk_bytes(epk)
+    }
+
+    fn epk(ephemeral_key: &EphemeralKeyBytes) -> Option<Self::EphemeralPublicKey> {
+        OrchardDomain::epk(ephemeral_key)
+    }
+
+    fn cmstar(note: &Self::Note) -> Self::ExtractedCommitment {
+        OrchardDomain::cmstar(note)
+    }
+
+    fn parse_note_plaintext_without_memo_ivk(
+        &self,
+        ivk: &Self::IncomingViewingKey,
+        plaintext: &[u8],
+    ) -> Option<(Self::Note, Self::Recipient)> {
+        self.0.parse_note_plaintext_without_memo_ivk(ivk, plaintext)
+    }
+
+    fn parse_note_plaintext_without_memo_ovk(
+        &self,
+        pk_d: &Self::DiversifiedTransmissionKey,
+        plaintext: &NotePlaintextBytes,
+    ) -> Option<(Self::Note, Self::Recipient)> {
+        self.0
+            .parse_note_plaintext_without_memo_ovk(pk_d, plaintext)
+    }
+
+    fn extract_memo(&self, plaintext: &NotePlaintextBytes) -> Self::Memo {
+        self.0.extract_memo(plaintext)
+    }
+
+    fn extract_pk_d(out_plaintext: &OutPlaintextBytes) -> Option<Self::DiversifiedTransmissionKey> {
+        OrchardDomain::extract_pk_d(out_plaintext)
+    }
+
+    fn extract_esk(out_plaintext: &OutPlaintextBytes) -> Option<Self::EphemeralSecretKey> {
+        OrchardDomain::extract_esk(out_plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use zcash_note_encryption::{
+        Domain, EphemeralKeyBytes, ShieldedOutput, COMPACT_NOTE_SIZE, ENC_CIPHERTEXT_SIZE,
+    };
+
+    use super::{
+        try_compact_note_decryption_with_policy, try_note_decryption_with_policy, DecryptionError,
+        RseedPolicy,
+    };
+    use crate::{
+        keys::{FullViewingKey, PreparedIncomingViewingKey, Scope, SpendingKey},
+        note::{ExtractedNoteCommitment, Nullifier, Rho},
+        note_encryption::{OrchardDomain, OrchardNoteEncryption},
+        value::NoteValue,
+        Note,
+    };
+
+    struct Output {
+        epk: EphemeralKeyBytes,
+        cmx: [u8; 32],
+        enc_ciphertext: [u8; ENC_CIPHERTEXT_SIZE],
+    }
+
+    impl ShieldedOutput<OrchardDomain, ENC_CIPHERTEXT_SIZE> for Output {
+        fn ephemeral_key(&self) -> EphemeralKeyBytes {
+            self.epk.clone()
+        }
+
+        fn cmstar_bytes(&self) -> [u8; 32] {
+            self.cmx
+        }
+
+        fn enc_ciphertext(&self) -> &[u8; ENC_CIPHERTEXT_SIZE] {
+            &self.enc_ciphertext
+        }
+    }
+
+    struct CompactOutput<'a>(&'a Output);
+
+    impl ShieldedOutput<OrchardDomain, COMPACT_NOTE_SIZE> for CompactOutput<'_> {
+        fn ephemeral_key(&self) -> EphemeralKeyBytes {
+            self.0.epk.clone()
+        }
+
+        fn cmstar_bytes(&self) -> [u8; 32] {
+            self.0.cmx
+        }
+
+        fn enc_ciphertext(&self) -> &[u8; COMPACT_NOTE_SIZE] {
+            self.0.enc_ciphertext[..COMPACT_NOTE_SIZE]
+                .try_into()
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn rseed_policy() {
+        let mut rng = OsRng;
+        let fvk = FullViewingKey::from(&SpendingKey::random(&mut rng));
+        let ivk = PreparedIncomingViewingKey::new(&fvk.to_ivk(Scope::External));
+        let recipient = fvk.address_at(0u32, Scope::External);
+        let rho = Rho::from_nf_old(Nullifier::dummy(&mut rng));
+        let domain = OrchardDomain { rho };
+
+        let note = Note::new(recipient, NoteValue::from_raw(10), rho, &mut rng);
+        let other = Note::new(recipient, NoteValue::from_raw(10), rho, &mut rng);
+
+        let encrypt = |esk| {
+            let ne = OrchardNoteEncryption::new_with_esk(esk, None, note, [0; 512]);
+            Output {
+                epk: OrchardDomain::epk_bytes(ne.epk()),
+                cmx: ExtractedNoteCommitment::from(note.commitment()).to_bytes(),
+                enc_ciphertext: ne.encrypt_note_plaintext(),
+            }
+        };
+        let valid = encrypt(note.esk());
+        let mismatched = encrypt(other.esk());
+
+        for policy in [RseedPolicy::Strict, RseedPolicy::Lenient] {
+            let (decrypted, _, _) =
+                try_note_decryption_with_policy(&domain, &ivk, &valid, policy).unwrap();
+            assert_eq!(decrypted, note);
+            assert!(try_compact_note_decryption_with_policy(
+                &domain,
+                &ivk,
+                &CompactOutput(&valid),
+                policy
+            )
+            .is_ok());
+        }
+
+        assert_eq!(
+            try_note_decryption_with_policy(&domain, &ivk, &mismatched, RseedPolicy::Strict)
+                .map(|_| ()),
+            Err(DecryptionError::EphemeralKeyMismatch),
+        );
+        assert_eq!(
+            try_compact_note_decryption_with_policy(
+                &domain,
+                &ivk,
+                &CompactOutput(&mismatched),
+                RseedPolicy::Strict
+            )
+            .map(|_| ()),
+            Err(DecryptionError::EphemeralKeyMismatch),
+        );
+        let (decrypted, _, _) =
+            try_note_decryption_with_policy(&domain, &ivk, &mismatched, RseedPolicy::Lenient)
+                .unwrap();
+        assert_eq!(decrypted, note);
+
+        // An output for another recipient is not decrypted under either policy.
+        let other_ivk = PreparedIncomingViewingKey::new(
+            &FullViewingKey::from(&SpendingKey::random(&mut rng)).to_ivk(Scope::External),
+        );
+        assert_eq!(
+            try_note_decryption_with_policy(&domain, &other_ivk, &valid, RseedPolicy::Lenient)
+                .map(|_| ()),
+            Err(DecryptionError::NotDecrypted),
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Scanning of compact blocks for a wallet that starts from its birthday.
+//!
+//! A light wallet does not need to trial-decrypt any output created before its birthday
+//! height, but it does need the note commitment tree to be correct from that point on.
+//! [`BirthdayScanner`] starts from a trusted frontier of the tree as of some checkpoint
+//! height at or before the birthday, appends the commitments of every subsequent block,
+//! and only trial-decrypts the outputs of blocks at or after the birthday.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use incrementalmerkletree::Position;
+use zcash_note_encryption::batch;
+
+use super::{CompactAction, OrchardDomain};
+use crate::{keys::PreparedIncomingViewingKey, tree::NoteCommitmentTree, Address, Note};
+
+/// A note received by one of the scanner's incoming viewing keys.
+#[derive(Clone, Debug)]
+pub struct ScannedNote {
+    /// The height of the block containing the note.
+    pub height: u32,
+    /// The index of the note's action within its block.
+    pub action_index: usize,
+    /// The index of the incoming viewing key that decrypted the note.
+    pub ivk_index: usize,
+    /// The position of the note's commitment in the note commitment tree.
+    pub position: Position,
+    /// The decrypted note.
+    pub note: Note,
+    /// The address to which the note was sent.
+    pub recipient: Address,
+}
+
+/// Errors that can occur in [`BirthdayScanner::scan_block`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanError {
+    /// The block was not the next block after the last one scanned.
+    UnexpectedHeight {
+        /// The height of the next block that the scanner expected.
+        expected: u32,
+        /// The height of the block that was provided.
+        actual: u32,
+    },
+    /// The note commitment tree was full before every commitment in the block could be
+    /// appended to it.
+    TreeFull,
+    /// The height of the block after the last one scanned does not fit in a `u32`.
+    HeightOverflow,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::UnexpectedHeight { expected, actual } => write!(
+                f,
+                "Expected to scan block {} but was given block {}",
+                expected, actual
+            ),
+            ScanError::TreeFull => write!(f, "Note commitment tree is full"),
+            ScanError::HeightOverflow => write!(f, "Block height overflowed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ScanError {}
+
+/// Scans consecutive blocks of compact actions, starting from a trusted note commitment
+/// tree frontier, and trial-decrypts the actions of blocks at or after a birthday height.
+#[derive(Debug)]
+pub struct BirthdayScanner {
+    ivks: Vec<PreparedIncomingViewingKey>,
+    birthday: u32,
+    next_height: u32,
+    tree: NoteCommitmentTree,
+}
+
+impl BirthdayScanner {
+    /// Constructs a scanner for the given incoming viewing keys.
+    ///
+    /// `tree` must be the note commitment tree as of the end of the block at
+    /// `checkpoint_height`, which is typically obtained from a trusted checkpoint. The
+    /// first block given to [`BirthdayScanner::scan_block`] must be the block at
+    /// `checkpoint_height + 1`. Blocks below `birthday` are not trial-decrypted.
+    ///
+    /// Returns [`ScanError::HeightOverflow`] if `checkpoint_height` is `u32::MAX`.
+    pub fn new(
+        ivks: Vec<PreparedIncomingViewingKey>,
+        birthday: u32,
+        checkpoint_height: u32,
+        tree: NoteCommitmentTree,
+    ) -> Result<Self, ScanError> {
+        let next_height = checkpoint_height
+            .checked_add(1)
+            .ok_or(ScanError::HeightOverflow)?;
+        Ok(BirthdayScanner {
+            ivks,
+            birthday,
+            next_height,
+            tree,
+        })
+    }
+
+    /// Returns the height of the next block that this scanner expects.
+    pub fn next_height(&self) -> u32 {
+        self.next_height
+    }
+
+    /// Returns the note commitment tree as of the end of the last block scanned.
+    pub fn tree(&self) -> &NoteCommitmentTree {
+        &self.tree
+    }
+
+    /// Scans the actions of the block at `height`, in order.
+    ///
+    /// The commitments of all actions are appended to the tree. If `height` is at or
+    /// after the birthday, the notes received by the scanner's keys are returned. If an
+    /// error is returned, the scanner is left unchanged.
+    pub fn scan_block(
+        &mut self,
+        height: u32,
+        actions: &[CompactAction],
+    ) -> Result<Vec<ScannedNote>, ScanError> {
+        if height != self.next_height {
+            return Err(ScanError::UnexpectedHeight {
+                expected: self.next_height,
+                actual: height,
+            });
+        }
+        let next_height = height.checked_add(1).ok_or(ScanError::HeightOverflow)?;
+
+        let start = self.tree.size();
+        let mut tree = self.tree.clone();
+        for action in actions {
+            if !tree.append(&action.cmx()) {
+                return Err(ScanError::TreeFull);
+            }
+        }
+
+        let received = if height >= self.birthday && !self.ivks.is_empty() {
+            let outputs: Vec<_> = actions
+                .iter()
+                .map(|action| (OrchardDomain::for_compact_action(action), action.clone()))
+                .collect();
+            batch::try_compact_note_decryption(&self.ivks, &outputs)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(action_index, result)| {
+                    result.map(|((note, recipient), ivk_index)| ScannedNote {
+                        height,
+                        action_index,
+                        ivk_index,
+                        position: Position::from(start + action_index as u64),
+                        note,
+                        recipient,
+                    })
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        self.tree = tree;
+        self.next_height = next_height;
+        Ok(received)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use incrementalmerkletree::Position;
+    use rand::rngs::OsRng;
+    use zcash_note_encryption::{Domain, COMPACT_NOTE_SIZE};
+
+    use super::{BirthdayScanner, ScanError};
+    use crate::{
+        keys::{FullViewingKey, PreparedIncomingViewingKey, Scope, SpendingKey},
+        note::{ExtractedNoteCommitment, Nullifier, Rho},
+        note_encryption::{CompactAction, OrchardDomain, OrchardNoteEncryption},
+        tree::NoteCommitmentTree,
+        value::NoteValue,
+        Note,
+    };
+
+    #[test]
+    fn skips_decryption_before_birthday() {
+        let mut rng = OsRng;
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([7; 32]).unwrap());
+        let recipient = fvk.address_at(0u32, Scope::External);
+        let ivk = PreparedIncomingViewingKey::new(&fvk.to_ivk(Scope::External));
+
+        let mut block = || -> Vec<CompactAction> {
+            let nf = Nullifier::dummy(&mut rng);
+            let note = Note::new(
+                recipient,
+                NoteValue::from_raw(10),
+                Rho::from_nf_old(nf),
+                &mut rng,
+            );
+            let ne = OrchardNoteEncryption::new(None, note, [0; 512]);
+            vec![CompactAction::from_parts(
+                nf,
+                ExtractedNoteCommitment::from(note.commitment()),
+                OrchardDomain::epk_bytes(ne.epk()),
+                ne.encrypt_note_plaintext()[..COMPACT_NOTE_SIZE]
+                    .try_into()
+                    .unwrap(),
+            )]
+        };
+        let blocks = [block(), block(), block()];
+
+        let mut scanner =
+            BirthdayScanner::new(vec![ivk], 102, 99, NoteCommitmentTree::empty()).unwrap();
+        assert_eq!(
+            scanner.scan_block(101, &blocks[0]).unwrap_err(),
+            ScanError::UnexpectedHeight {
+                expected: 100,
+                actual: 101,
+            },
+        );
+
+        assert!(scanner.scan_block(100, &blocks[0]).unwrap().is_empty());
+        assert!(scanner.scan_block(101, &blocks[1]).unwrap().is_empty());
+        let received = scanner.scan_block(102, &blocks[2]).unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].position, Position::from(2));
+        assert_eq!(received[0].recipient, recipient);
+
+        let mut tree = NoteCommitmentTree::empty();
+        for action in blocks.iter().flatten() {
+            tree.append(&action.cmx());
+        }
+        assert_eq!(scanner.tree().root(), tree.root());
+    }
+
+    #[test]
+    fn rejects_height_overflow() {
+        assert_eq!(
+            BirthdayScanner::new(vec![], 0, u32::MAX, NoteCommitmentTree::empty()).unwrap_err(),
+            ScanError::HeightOverflow,
+        );
+
+        let mut scanner =
+            BirthdayScanner::new(vec![], 0, u32::MAX - 1, NoteCommitmentTree::empty()).unwrap();
+        assert_eq!(
+            scanner.scan_block(u32::MAX, &[]).unwrap_err(),
+            ScanError::HeightOverflow,
+        );
+        assert_eq!(scanner.next_height(), u32::MAX);
+    }
+}
@@ -5,7 +5,7 @@ use rand::{CryptoRng, RngCore};
 use crate::{
     keys::SpendAuthorizingKey,
     primitives::redpallas,
-    value::{ValueCommitTrapdoor, ValueCommitment},
+    value::{derive_bsk, derive_bvk_from_sum},
 };
 
 use super::SignerError;
@@ -27,16 +27,10 @@ impl super::Bundle {
                     .ok_or(IoFinalizerError::MissingValueCommitTrapdoor)
             })
             .collect::<Result<Vec<_>, _>>()?;
-        let bsk = rcvs.into_iter().sum::<ValueCommitTrapdoor>().into_bsk();
+        let bsk = derive_bsk(rcvs);
 
         // Verify that bsk and bvk are consistent.
-        let bvk = (self
-            .actions
-            .iter()
-            .map(|a| a.cv_net())
-            .sum::<ValueCommitment>()
-            - ValueCommitment::derive(self.value_sum, ValueCommitTrapdoor::zero()))
-        .into_bvk();
+        let bvk = derive_bvk_from_sum(self.actions.iter().map(|a| a.cv_net()), self.value_sum);
         if redpallas::VerificationKey::from(&bsk) != bvk {
             return Err(IoFinalizerError::ValueCommitMismatch);
         }
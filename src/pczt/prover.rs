@@ -43,8 +43,7 @@ impl super::Bundle {
                     action.spend.rho.ok_or(ProverError::MissingRho)?,
                     action.spend.rseed.ok_or(ProverError::MissingRandomSeed)?,
                 )
-                .into_option()
-                .ok_or(ProverError::InvalidSpendNote)?;
+                .map_err(|_| ProverError::InvalidSpendNote)?;
 
                 let merkle_path = action
                     .spend
@@ -64,8 +63,7 @@ impl super::Bundle {
                     Rho::from_nf_old(action.spend.nullifier),
                     action.output.rseed.ok_or(ProverError::MissingRandomSeed)?,
                 )
-                .into_option()
-                .ok_or(ProverError::InvalidOutputNote)?;
+                .map_err(|_| ProverError::InvalidOutputNote)?;
 
                 let alpha = action
                     .spend
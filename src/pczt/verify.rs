@@ -72,8 +72,7 @@ impl super::Spend {
             self.rho.ok_or(VerifyError::MissingRho)?,
             self.rseed.ok_or(VerifyError::MissingRandomSeed)?,
         )
-        .into_option()
-        .ok_or(VerifyError::InvalidSpendNote)?;
+        .map_err(|_| VerifyError::InvalidSpendNote)?;
 
         // We need both the note and the FVK to verify the nullifier; we have everything
         // needed to also verify that the correct FVK was provided (the nullifier check
@@ -130,8 +129,7 @@ impl super::Output {
             Rho::from_nf_old(spend.nullifier),
             self.rseed.ok_or(VerifyError::MissingRandomSeed)?,
         )
-        .into_option()
-        .ok_or(VerifyError::InvalidOutputNote)?;
+        .map_err(|_| VerifyError::InvalidOutputNote)?;
 
         if ExtractedNoteCommitment::from(note.commitment()) == self.cmx {
             Ok(())
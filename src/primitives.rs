@@ -4,4 +4,7 @@
 //     - EphemeralPublicKey
 //     - EphemeralSecretKey
 
+pub mod bits;
+pub mod poseidon;
 pub mod redpallas;
+pub mod sinsemilla;
@@ -0,0 +1,123 @@
+//! Conversions between integers and little-endian bit sequences.
+//!
+//! The Zcash Protocol Specification encodes Sinsemilla messages as sequences of bits, with
+//! integers converted by $\mathsf{I2LEBSP}$ and $\mathsf{LEBS2IP}$ (defined in
+//! [§ 5.1: Integers, Bit Sequences, and Endianness][endian]). All conversions in this
+//! module are little-endian: the first bit of a sequence is the least significant bit.
+//!
+//! [endian]: https://zips.z.cash/protocol/nu5.pdf#endian
+
+use alloc::vec::Vec;
+use core::iter;
+
+use ff::{PrimeField, PrimeFieldBits};
+
+use crate::constants::util::gen_const_array;
+
+/// The field element representation of a u64 integer represented by
+/// an L-bit little-endian bitstring.
+pub fn lebs2ip_field<F: PrimeField, const L: usize>(bits: &[bool; L]) -> F {
+    F::from(lebs2ip::<L>(bits))
+}
+
+/// The u64 integer represented by an L-bit little-endian bitstring.
+///
+/// # Panics
+///
+/// Panics if the bitstring is longer than 64 bits.
+pub fn lebs2ip<const L: usize>(bits: &[bool; L]) -> u64 {
+    assert!(L <= 64);
+    bits.iter()
+        .enumerate()
+        .fold(0u64, |acc, (i, b)| acc + if *b { 1 << i } else { 0 })
+}
+
+/// The sequence of bits representing a u64 in little-endian order.
+///
+/// # Panics
+///
+/// Panics if the expected length of the sequence `NUM_BITS` exceeds
+/// 64.
+pub fn i2lebsp<const NUM_BITS: usize>(int: u64) -> [bool; NUM_BITS] {
+    assert!(NUM_BITS <= 64);
+    gen_const_array(|mask: usize| (int & (1 << mask)) != 0)
+}
+
+/// Decomposes the lowest `word_num_bits` bits of `word` into little-endian windows of
+/// `WINDOW_NUM_BITS` bits each, padding the final window with zeroes.
+///
+/// The first window holds the least significant bits of `word`, and each window is itself
+/// returned as an integer, so that `word` is the sum of `windows[i] << (i * WINDOW_NUM_BITS)`
+/// whenever its value fits in `word_num_bits` bits.
+///
+/// # Panics
+///
+/// Panics if `WINDOW_NUM_BITS` is zero or exceeds 64.
+pub fn decompose_word<F: PrimeFieldBits, const WINDOW_NUM_BITS: usize>(
+    word: &F,
+    word_num_bits: usize,
+) -> Vec<u64> {
+    assert!(WINDOW_NUM_BITS > 0 && WINDOW_NUM_BITS <= 64);
+
+    // Pad bits to multiple of WINDOW_NUM_BITS
+    let padding = (WINDOW_NUM_BITS - (word_num_bits % WINDOW_NUM_BITS)) % WINDOW_NUM_BITS;
+    let bits: Vec<bool> = word
+        .to_le_bits()
+        .iter()
+        .by_vals()
+        .take(word_num_bits)
+        .chain(iter::repeat(false).take(padding))
+        .collect();
+
+    bits.chunks_exact(WINDOW_NUM_BITS)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .rev()
+                .fold(0, |acc, b| (acc << 1) + u64::from(*b))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pasta_curves::pallas;
+    use proptest::prelude::*;
+
+    use super::{decompose_word, i2lebsp, lebs2ip, lebs2ip_field};
+
+    proptest! {
+        #[test]
+        fn i2lebsp_lebs2ip_round_trip(int in any::<u64>()) {
+            prop_assert_eq!(lebs2ip::<64>(&i2lebsp(int)), int);
+
+            // Truncating to fewer bits keeps the low-order bits.
+            let low = lebs2ip::<20>(&i2lebsp(int));
+            prop_assert_eq!(low, int & ((1 << 20) - 1));
+            prop_assert_eq!(
+                lebs2ip_field::<pallas::Base, 20>(&i2lebsp(int)),
+                pallas::Base::from(low)
+            );
+        }
+
+        #[test]
+        fn lebs2ip_is_little_endian(bits in any::<[bool; 16]>()) {
+            prop_assert_eq!(i2lebsp::<16>(lebs2ip(&bits)), bits);
+            prop_assert_eq!(lebs2ip(&bits) & 1 == 1, bits[0]);
+        }
+
+        #[test]
+        fn decompose_word_recomposes(int in any::<u64>(), word_num_bits in 1usize..=64) {
+            let masked = if word_num_bits == 64 { int } else { int & ((1 << word_num_bits) - 1) };
+            let windows = decompose_word::<_, 10>(&pallas::Base::from(int), word_num_bits);
+
+            prop_assert_eq!(windows.len(), (word_num_bits + 9) / 10);
+            prop_assert!(windows.iter().all(|w| *w < (1 << 10)));
+            let recomposed = windows
+                .iter()
+                .rev()
+                .fold(0u128, |acc, w| (acc << 10) + u128::from(*w));
+            prop_assert_eq!(recomposed, u128::from(masked));
+        }
+    }
+}
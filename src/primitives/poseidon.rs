@@ -0,0 +1,38 @@
+//! The Poseidon hash function, as instantiated in Orchard.
+//!
+//! Orchard uses Poseidon over the Pallas base field with a width of 3, a rate of 2, and
+//! the `P128Pow5T3` round constants, hashing messages of exactly two field elements. It is
+//! defined in [Zcash Protocol Spec § 5.4.1.10: Poseidon Hash Function][poseidonhash], and
+//! is used as $\mathsf{PRF^{nfOrchard}}$ in nullifier derivation.
+//!
+//! [poseidonhash]: https://zips.z.cash/protocol/nu5.pdf#poseidonhash
+
+use ::poseidon::{ConstantLength, Hash, P128Pow5T3};
+use pasta_curves::pallas;
+
+/// Computes $\mathsf{PoseidonHash}(x, y)$.
+///
+/// This is the same instantiation that the Action circuit constrains, so for example
+/// $\mathsf{PRF^{nfOrchard}_{nk}}(\rho) = \mathsf{hash}(\mathsf{nk}, \rho)$.
+pub fn hash(x: pallas::Base, y: pallas::Base) -> pallas::Base {
+    Hash::<_, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash([x, y])
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength, P128Pow5T3};
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    use super::hash;
+
+    #[test]
+    fn matches_gadget_primitive() {
+        let [x, y] = [(); 2].map(|_| pallas::Base::random(OsRng));
+        assert_eq!(
+            hash(x, y),
+            poseidon::Hash::<_, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash([x, y]),
+        );
+    }
+}
@@ -1,13 +1,28 @@
 //! A minimal RedPallas implementation for use in Zcash.
 
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
 use core::cmp::{Ord, Ordering, PartialOrd};
+use core::fmt;
+use core::str::FromStr;
 
+use ff::FromUniformBytes;
 use pasta_curves::pallas;
 use rand::{CryptoRng, RngCore};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::redacted::Redacted;
+#[cfg(feature = "serde")]
+use crate::serialization::{deserialize_bytes, serialize_bytes};
 
 #[cfg(feature = "std")]
 pub use reddsa::batch;
 
+#[cfg(feature = "unstable-frost")]
+#[cfg_attr(docsrs, doc(cfg(feature = "unstable-frost")))]
+pub mod cosign;
+
 #[cfg(test)]
 use rand::rngs::OsRng;
 
@@ -22,10 +37,39 @@ impl SigType for SpendAuth {}
 pub type Binding = reddsa::orchard::Binding;
 impl SigType for Binding {}
 
+/// Derives a spend authorization randomizer $\alpha$ from 64 uniformly random bytes.
+///
+/// The bytes are reduced modulo the order of the Pallas scalar field, so the result is
+/// statistically close to uniform. The randomized keys are then given by
+/// [`SigningKey::randomize`] and [`VerificationKey::randomize`], which satisfy
+/// $\mathsf{rk} = \mathsf{ak} + [\alpha] \mathcal{G}$.
+pub fn randomizer_from_uniform_bytes(bytes: &[u8; 64]) -> pallas::Scalar {
+    pallas::Scalar::from_uniform_bytes(bytes)
+}
+
+/// Samples a spend authorization randomizer $\alpha$ from `rng`.
+///
+/// This draws 64 bytes from `rng` and passes them to [`randomizer_from_uniform_bytes`].
+/// $\alpha$ must be secret and unpredictable for the randomized key $\mathsf{rk}$ to be
+/// unlinkable to $\mathsf{ak}$, so `rng` must be a cryptographically secure RNG.
+pub fn random_randomizer<R: RngCore + CryptoRng>(mut rng: R) -> pallas::Scalar {
+    let mut bytes = [0; 64];
+    rng.fill_bytes(&mut bytes);
+    randomizer_from_uniform_bytes(&bytes)
+}
+
 /// A RedPallas signing key.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct SigningKey<T: SigType>(reddsa::SigningKey<T>);
 
+impl<T: SigType> fmt::Debug for SigningKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SigningKey")
+            .field(&Redacted(&self.0))
+            .finish()
+    }
+}
+
 impl<T: SigType> From<SigningKey<T>> for [u8; 32] {
     fn from(sk: SigningKey<T>) -> [u8; 32] {
         sk.0.into()
@@ -46,6 +90,30 @@ impl<T: SigType> TryFrom<[u8; 32]> for SigningKey<T> {
     }
 }
 
+/// Overwrites the key with the zero scalar.
+///
+/// `reddsa::SigningKey` is `Copy` and does not implement `Zeroize` itself, so this cannot
+/// reach copies of the key made inside `reddsa` while signing; it only ensures that the
+/// key held by this wrapper does not outlive it.
+#[cfg(feature = "zeroize")]
+impl<T: SigType> zeroize::Zeroize for SigningKey<T> {
+    fn zeroize(&mut self) {
+        self.0 = reddsa::SigningKey::try_from([0; 32]).expect("zero is a canonical scalar");
+        // Prevent the write from being elided as a dead store.
+        core::hint::black_box(&self.0);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: SigType> Drop for SigningKey<T> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: SigType> zeroize::ZeroizeOnDrop for SigningKey<T> {}
+
 impl SigningKey<SpendAuth> {
     /// Randomizes this signing key with the given `randomizer`.
     ///
@@ -60,8 +128,92 @@ impl<T: SigType> SigningKey<T> {
     pub fn sign<R: RngCore + CryptoRng>(&self, rng: R, msg: &[u8]) -> Signature<T> {
         Signature(self.0.sign(rng, msg))
     }
+
+    /// Creates a signature of type `T` on `msg` using this `SigningKey`, without drawing
+    /// any randomness.
+    ///
+    /// RedDSA derives its nonce by hashing random bytes $T$ together with the
+    /// verification key and message. Here $T$ is instead derived from this signing key
+    /// and `msg`, so signing the same message twice gives the same signature. For spend
+    /// authorization signatures this key is the randomized key $\mathsf{rsk}$, which
+    /// already depends on the randomizer $\alpha$.
+    ///
+    /// This is intended for signers that lack a reliable source of entropy, such as some
+    /// hardware wallets. Prefer [`SigningKey::sign`] otherwise, which also remains secure
+    /// if an attacker can induce faults during signing.
+    pub fn sign_deterministic(&self, msg: &[u8]) -> Signature<T> {
+        self.sign(SyntheticNonceRng::new(&self.into(), msg), msg)
+    }
+}
+
+/// The personalization for deriving $T$ in [`SigningKey::sign_deterministic`].
+const SYNTHETIC_NONCE_PERSONALIZATION: &[u8; 16] = b"Orchard_RedPalT_";
+
+/// The source of the bytes $T$ used by [`SigningKey::sign_deterministic`].
+///
+/// This outputs $\mathsf{BLAKE2b}$-512 of a secret seed and a block counter, where the
+/// seed is $\mathsf{BLAKE2b}$-256 of the signing key and message.
+struct SyntheticNonceRng {
+    seed: [u8; 32],
+    counter: u32,
+    block: [u8; 64],
+    pos: usize,
+}
+
+impl SyntheticNonceRng {
+    fn new(sk: &[u8; 32], msg: &[u8]) -> Self {
+        let seed = blake2b_simd::Params::new()
+            .hash_length(32)
+            .personal(SYNTHETIC_NONCE_PERSONALIZATION)
+            .to_state()
+            .update(sk)
+            .update(msg)
+            .finalize();
+        SyntheticNonceRng {
+            seed: seed.as_bytes().try_into().expect("hash length is 32 bytes"),
+            counter: 0,
+            block: [0; 64],
+            pos: 64,
+        }
+    }
 }
 
+impl RngCore for SyntheticNonceRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest {
+            if self.pos == self.block.len() {
+                let block = blake2b_simd::Params::new()
+                    .hash_length(64)
+                    .personal(SYNTHETIC_NONCE_PERSONALIZATION)
+                    .to_state()
+                    .update(&self.seed)
+                    .update(&self.counter.to_le_bytes())
+                    .finalize();
+                self.block.copy_from_slice(block.as_bytes());
+                self.counter += 1;
+                self.pos = 0;
+            }
+            *byte = self.block[self.pos];
+            self.pos += 1;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for SyntheticNonceRng {}
+
 /// A RedPallas verification key.
 #[derive(Clone, Debug)]
 pub struct VerificationKey<T: SigType>(reddsa::VerificationKey<T>);
@@ -112,6 +264,40 @@ impl<T: SigType> Ord for VerificationKey<T> {
     }
 }
 
+impl<T: SigType> fmt::Display for VerificationKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(f, &<[u8; 32]>::from(self))
+    }
+}
+
+impl<T: SigType> FromStr for VerificationKey<T> {
+    type Err = FromHexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes: [u8; 32] = decode_hex(s)?;
+        bytes.try_into().map_err(|_| FromHexError::InvalidEncoding)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<T: SigType> Serialize for VerificationKey<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(&<[u8; 32]>::from(self), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de, T: SigType> Deserialize<'de> for VerificationKey<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes: [u8; 32] = deserialize_bytes(deserializer)?;
+        bytes
+            .try_into()
+            .map_err(|_| de::Error::custom("Invalid RedPallas verification key encoding"))
+    }
+}
+
 impl VerificationKey<SpendAuth> {
     /// Used in the note encryption tests.
     #[cfg(test)]
@@ -172,6 +358,132 @@ impl<T: SigType> From<&Signature<T>> for [u8; 64] {
     }
 }
 
+impl<T: SigType> fmt::Display for Signature<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(f, &<[u8; 64]>::from(self))
+    }
+}
+
+impl<T: SigType> FromStr for Signature<T> {
+    type Err = FromHexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        decode_hex::<64>(s).map(Signature::from)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<T: SigType> Serialize for Signature<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(&<[u8; 64]>::from(self), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de, T: SigType> Deserialize<'de> for Signature<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_bytes::<_, 64>(deserializer).map(Signature::from)
+    }
+}
+
+/// An error returned when parsing a RedPallas type from a hex string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FromHexError {
+    /// The string was not the hex encoding of the expected number of bytes.
+    InvalidHex,
+    /// The bytes were not a valid encoding of the type.
+    InvalidEncoding,
+}
+
+impl fmt::Display for FromHexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromHexError::InvalidHex => f.write_str("Invalid hex string"),
+            FromHexError::InvalidEncoding => f.write_str("Invalid RedPallas encoding"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromHexError {}
+
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    bytes.iter().try_for_each(|b| write!(f, "{:02x}", b))
+}
+
+fn decode_hex<const N: usize>(s: &str) -> Result<[u8; N], FromHexError> {
+    let mut bytes = [0; N];
+    hex::decode_to_slice(s, &mut bytes).map_err(|_| FromHexError::InvalidHex)?;
+    Ok(bytes)
+}
+
+/// A batch verifier for RedPallas signatures.
+///
+/// Spend authorization and binding signatures can be queued together, and are then
+/// verified with a single multiscalar multiplication. This is substantially faster than
+/// verifying each signature individually, but if the batch fails to verify, it does not
+/// identify which signatures were invalid.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct BatchVerifier {
+    items: Vec<batch::Item<SpendAuth, Binding>>,
+}
+
+#[cfg(feature = "std")]
+impl BatchVerifier {
+    /// Constructs an empty batch verifier.
+    pub fn new() -> Self {
+        BatchVerifier { items: vec![] }
+    }
+
+    /// Queues a spend authorization signature over `msg` for verification.
+    pub fn queue_spend_auth(
+        &mut self,
+        vk: &VerificationKey<SpendAuth>,
+        msg: &[u8],
+        sig: &Signature<SpendAuth>,
+    ) {
+        self.items.push(vk.create_batch_item(sig.clone(), &msg));
+    }
+
+    /// Queues a binding signature over `msg` for verification.
+    pub fn queue_binding(
+        &mut self,
+        vk: &VerificationKey<Binding>,
+        msg: &[u8],
+        sig: &Signature<Binding>,
+    ) {
+        self.items.push(vk.create_batch_item(sig.clone(), &msg));
+    }
+
+    /// Returns the number of signatures queued for verification.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if no signatures have been queued.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Verifies all of the queued signatures.
+    ///
+    /// Returns `Ok(())` if every signature is valid. An empty batch is valid.
+    pub fn verify<R: RngCore + CryptoRng>(self, rng: R) -> Result<(), reddsa::Error> {
+        if self.items.is_empty() {
+            return Ok(());
+        }
+
+        let mut verifier = batch::Verifier::new();
+        for item in self.items {
+            verifier.queue(item);
+        }
+        verifier.verify(rng)
+    }
+}
+
 pub(crate) mod private {
     use super::{Binding, SpendAuth};
 
@@ -226,3 +538,159 @@ pub mod testing {
         }
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use alloc::string::ToString;
+
+    use rand::rngs::OsRng;
+
+    use super::{
+        random_randomizer, randomizer_from_uniform_bytes, BatchVerifier, Binding, FromHexError,
+        Signature, SigningKey, SpendAuth, VerificationKey,
+    };
+
+    #[test]
+    fn hex_encodings() {
+        let mut rng = OsRng;
+        let sk: SigningKey<SpendAuth> = SigningKey(reddsa::SigningKey::new(&mut rng));
+        let vk = VerificationKey::from(&sk);
+        let sig = sk.sign(&mut rng, b"hex");
+
+        let vk_hex = vk.to_string();
+        assert_eq!(vk_hex, hex::encode(<[u8; 32]>::from(&vk)));
+        assert_eq!(vk_hex.parse::<VerificationKey<SpendAuth>>(), Ok(vk.clone()));
+        let sig_hex = sig.to_string();
+        assert_eq!(sig_hex.len(), 128);
+        let parsed: Signature<SpendAuth> = sig_hex.parse().unwrap();
+        assert!(vk.verify(b"hex", &parsed).is_ok());
+
+        assert_eq!(
+            "zz".parse::<VerificationKey<SpendAuth>>(),
+            Err(FromHexError::InvalidHex)
+        );
+        assert_eq!(
+            hex::encode([0xff; 32]).parse::<VerificationKey<SpendAuth>>(),
+            Err(FromHexError::InvalidEncoding)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_encodings() {
+        use serde::{
+            de::value::{BytesDeserializer, Error, StrDeserializer},
+            Deserialize,
+        };
+
+        let mut rng = OsRng;
+        let sk: SigningKey<SpendAuth> = SigningKey(reddsa::SigningKey::new(&mut rng));
+        let vk = VerificationKey::from(&sk);
+        let sig = sk.sign(&mut rng, b"serde");
+
+        // Both hex strings and raw bytes are accepted when deserializing.
+        let vk_hex = vk.to_string();
+        let from_str =
+            VerificationKey::<SpendAuth>::deserialize(StrDeserializer::<Error>::new(&vk_hex));
+        assert_eq!(from_str.unwrap(), vk);
+        let bytes = <[u8; 32]>::from(&vk);
+        let from_bytes =
+            VerificationKey::<SpendAuth>::deserialize(BytesDeserializer::<Error>::new(&bytes));
+        assert_eq!(from_bytes.unwrap(), vk);
+        assert!(
+            VerificationKey::<SpendAuth>::deserialize(BytesDeserializer::<Error>::new(&bytes[1..]))
+                .is_err()
+        );
+
+        let sig_bytes = <[u8; 64]>::from(&sig);
+        let parsed =
+            Signature::<SpendAuth>::deserialize(BytesDeserializer::<Error>::new(&sig_bytes))
+                .unwrap();
+        assert!(vk.verify(b"serde", &parsed).is_ok());
+        let parsed =
+            Signature::<SpendAuth>::deserialize(StrDeserializer::<Error>::new(&sig.to_string()))
+                .unwrap();
+        assert!(vk.verify(b"serde", &parsed).is_ok());
+    }
+
+    #[test]
+    fn batch_verify() {
+        let mut rng = OsRng;
+        let msg = b"Orchard batch verification";
+
+        let spend_auth: SigningKey<SpendAuth> = SigningKey(reddsa::SigningKey::new(&mut rng));
+        let binding: SigningKey<Binding> = SigningKey(reddsa::SigningKey::new(&mut rng));
+        let spend_auth_sig = spend_auth.sign(&mut rng, msg);
+        let binding_sig = binding.sign(&mut rng, msg);
+
+        let mut batch = BatchVerifier::new();
+        assert!(batch.is_empty());
+        batch.queue_spend_auth(&VerificationKey::from(&spend_auth), msg, &spend_auth_sig);
+        batch.queue_binding(&VerificationKey::from(&binding), msg, &binding_sig);
+        assert_eq!(batch.len(), 2);
+        assert!(batch.verify(&mut rng).is_ok());
+
+        // A signature over a different message invalidates the batch.
+        let mut batch = BatchVerifier::new();
+        batch.queue_spend_auth(&VerificationKey::from(&spend_auth), msg, &spend_auth_sig);
+        batch.queue_binding(&VerificationKey::from(&binding), b"other", &binding_sig);
+        assert!(batch.verify(&mut rng).is_err());
+
+        assert!(BatchVerifier::new().verify(&mut rng).is_ok());
+    }
+
+    #[test]
+    fn randomizers() {
+        let mut rng = OsRng;
+        let sk: SigningKey<SpendAuth> = SigningKey(reddsa::SigningKey::new(&mut rng));
+        let vk = VerificationKey::from(&sk);
+
+        let alpha = randomizer_from_uniform_bytes(&[7; 64]);
+        assert_eq!(alpha, randomizer_from_uniform_bytes(&[7; 64]));
+        assert_ne!(alpha, random_randomizer(&mut rng));
+
+        // rk can be derived from either ak or ask.
+        let rk = vk.randomize(&alpha);
+        assert_eq!(rk, VerificationKey::from(&sk.randomize(&alpha)));
+        let sig = sk.randomize(&alpha).sign(&mut rng, b"randomized");
+        assert!(rk.verify(b"randomized", &sig).is_ok());
+        assert!(vk.verify(b"randomized", &sig).is_err());
+    }
+
+    #[test]
+    fn deterministic_signatures() {
+        let sk: SigningKey<SpendAuth> = SigningKey(reddsa::SigningKey::new(OsRng));
+        let rsk = sk.randomize(&random_randomizer(OsRng));
+        let rk = VerificationKey::from(&rsk);
+
+        let sig = rsk.sign_deterministic(b"message");
+        assert!(rk.verify(b"message", &sig).is_ok());
+        assert_eq!(
+            <[u8; 64]>::from(&sig),
+            <[u8; 64]>::from(&rsk.sign_deterministic(b"message"))
+        );
+        assert_ne!(
+            <[u8; 64]>::from(&sig),
+            <[u8; 64]>::from(&rsk.sign_deterministic(b"other message"))
+        );
+        assert_ne!(
+            <[u8; 64]>::from(&sig),
+            <[u8; 64]>::from(&sk.sign_deterministic(b"message"))
+        );
+
+        let bsk: SigningKey<Binding> = SigningKey(reddsa::SigningKey::new(OsRng));
+        let sig = bsk.sign_deterministic(b"message");
+        assert!(VerificationKey::from(&bsk).verify(b"message", &sig).is_ok());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize_signing_key() {
+        use zeroize::Zeroize;
+
+        let mut sk: SigningKey<SpendAuth> = SigningKey(reddsa::SigningKey::new(OsRng));
+        assert_ne!(<[u8; 32]>::from(&sk), [0; 32]);
+        sk.zeroize();
+        assert_eq!(<[u8; 32]>::from(&sk), [0; 32]);
+    }
+}
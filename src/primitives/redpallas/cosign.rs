@@ -0,0 +1,378 @@
+//! Two-party signing of spend authorizations.
+//!
+//! This produces a single, ordinary RedPallas `SpendAuth` signature when the spend
+//! authorizing key $\mathsf{ask}$ is shared between two devices that must both approve
+//! every spend, such as a phone and a hardware co-signer. The key is split additively as
+//! $\mathsf{ask} = s_0 + s_1$, and neither device ever holds $\mathsf{ask}$ itself.
+//!
+//! A session takes two rounds, and follows the two-party case of FROST with additive
+//! shares:
+//!
+//! 1. Each party creates fresh [`SigningNonces`] with [`SigningNonces::new`], keeps them
+//!    secret, and sends the corresponding [`NonceCommitment`] to the other party.
+//! 2. Once both commitments are known, each party signs the request with
+//!    [`KeyShare::sign`], consuming its nonces, and sends the [`SignatureShare`] to
+//!    whichever party assembles the signature with [`aggregate`].
+//!
+//! Each party derives two nonces and binds them to the message and to both commitments,
+//! so that running several sessions concurrently does not allow the other party to forge
+//! signatures, as it would with a single nonce per party.
+//!
+//! The randomizer $\alpha$, the randomized key $\mathsf{rk}$ and the message are those
+//! of a [`SpendAuthRequest`], and the aggregated signature can be applied to the bundle
+//! with [`Bundle::apply_spend_auth_signature`].
+//!
+//! [`SpendAuthRequest`]: crate::builder::SpendAuthRequest
+//! [`Bundle::apply_spend_auth_signature`]: crate::Bundle::apply_spend_auth_signature
+
+use core::fmt;
+
+use ff::{Field, FromUniformBytes, PrimeField};
+use group::{Group, GroupEncoding};
+use pasta_curves::pallas;
+use rand::{CryptoRng, RngCore};
+use subtle::CtOption;
+
+use super::{Signature, SigningKey, SpendAuth, VerificationKey};
+use crate::{constants::fixed_bases::spend_auth_g, redacted::Redacted};
+
+/// The personalization of the RedPallas challenge hash $H^\circledast$.
+const CHALLENGE_PERSONALIZATION: &[u8; 16] = b"Zcash_RedPallasH";
+
+/// The personalization of the hash binding each party's nonces to a session.
+const BINDING_PERSONALIZATION: &[u8; 16] = b"Orchard_CoSign_H";
+
+/// One party's share of a spend authorizing key.
+#[derive(Clone)]
+pub struct KeyShare {
+    index: usize,
+    share: pallas::Scalar,
+}
+
+impl fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("index", &self.index)
+            .field("share", &Redacted(&self.share))
+            .finish()
+    }
+}
+
+impl KeyShare {
+    /// Splits the spend authorizing key `ask` into shares for the two parties.
+    ///
+    /// The shares are uniformly random subject to summing to $\mathsf{ask}$, so either
+    /// share alone reveals nothing about the key. The party splitting the key must erase
+    /// it once the shares have been distributed.
+    pub fn split(ask: &SigningKey<SpendAuth>, mut rng: impl RngCore + CryptoRng) -> [Self; 2] {
+        let ask = pallas::Scalar::from_repr(ask.into()).unwrap();
+        let share = pallas::Scalar::random(&mut rng);
+        [
+            KeyShare { index: 0, share },
+            KeyShare {
+                index: 1,
+                share: ask - share,
+            },
+        ]
+    }
+
+    /// Returns the index of the party holding this share, which is either 0 or 1.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Creates this party's share of the signature for a spend authorization request.
+    ///
+    /// `randomizer` is $\alpha$, `rk` is the key $\mathsf{ak} + [\alpha] \mathcal{G}$
+    /// under which the signature will be verified, and `msg` is the message to sign.
+    /// `commitments` are the nonce commitments of both parties, in order of their index.
+    ///
+    /// Returns an error if this party's nonces do not match its own commitment, which
+    /// indicates that the commitments were exchanged incorrectly.
+    pub fn sign(
+        &self,
+        nonces: SigningNonces,
+        randomizer: &pallas::Scalar,
+        rk: &VerificationKey<SpendAuth>,
+        msg: &[u8],
+        commitments: &[NonceCommitment; 2],
+    ) -> Result<SignatureShare, CosignError> {
+        if commitments[self.index] != nonces.commitment() {
+            return Err(CosignError::CommitmentMismatch);
+        }
+
+        let session = Session::new(rk, msg, commitments);
+        let rho = session.binding_factors[self.index];
+
+        // The randomizer is added by the first party only, so that the shares of the
+        // randomized key sum to rsk = ask + alpha.
+        let share = if self.index == 0 {
+            self.share + randomizer
+        } else {
+            self.share
+        };
+
+        Ok(SignatureShare(
+            nonces.hiding + nonces.binding * rho + session.challenge * share,
+        ))
+    }
+}
+
+/// A party's secret nonces for a single signing session.
+///
+/// These must never be reused, and so are consumed by [`KeyShare::sign`].
+pub struct SigningNonces {
+    hiding: pallas::Scalar,
+    binding: pallas::Scalar,
+}
+
+impl fmt::Debug for SigningNonces {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningNonces")
+            .field("hiding", &Redacted(&self.hiding))
+            .field("binding", &Redacted(&self.binding))
+            .finish()
+    }
+}
+
+impl SigningNonces {
+    /// Samples fresh nonces for a signing session, returning them along with the
+    /// commitment to send to the other party.
+    pub fn new(mut rng: impl RngCore + CryptoRng) -> (Self, NonceCommitment) {
+        let nonces = SigningNonces {
+            hiding: pallas::Scalar::random(&mut rng),
+            binding: pallas::Scalar::random(&mut rng),
+        };
+        let commitment = nonces.commitment();
+        (nonces, commitment)
+    }
+
+    fn commitment(&self) -> NonceCommitment {
+        let g = pallas::Point::from(spend_auth_g::generator());
+        NonceCommitment {
+            hiding: g * self.hiding,
+            binding: g * self.binding,
+        }
+    }
+}
+
+/// A commitment to a party's [`SigningNonces`], sent to the other party in the first
+/// round of a signing session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NonceCommitment {
+    hiding: pallas::Point,
+    binding: pallas::Point,
+}
+
+impl NonceCommitment {
+    /// Parses a nonce commitment from its byte encoding.
+    ///
+    /// Returns `None` if either point is not a valid non-identity encoding.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Option<Self> {
+        let point = |b: &[u8]| -> Option<pallas::Point> {
+            pallas::Point::from_bytes(&b.try_into().unwrap())
+                .and_then(|p| CtOption::new(p, !p.is_identity()))
+                .into()
+        };
+        Some(NonceCommitment {
+            hiding: point(&bytes[..32])?,
+            binding: point(&bytes[32..])?,
+        })
+    }
+
+    /// Returns the byte encoding of this nonce commitment.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(&self.hiding.to_bytes());
+        bytes[32..].copy_from_slice(&self.binding.to_bytes());
+        bytes
+    }
+}
+
+/// A party's share of a signature, sent in the second round of a signing session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignatureShare(pallas::Scalar);
+
+impl SignatureShare {
+    /// Parses a signature share from its byte encoding.
+    ///
+    /// Returns `None` if the encoding is not canonical.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Option<Self> {
+        pallas::Scalar::from_repr(*bytes).map(SignatureShare).into()
+    }
+
+    /// Returns the byte encoding of this signature share.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+}
+
+/// Assembles the spend authorization signature from the shares of both parties.
+///
+/// `rk`, `msg` and `commitments` must be those that were passed to [`KeyShare::sign`],
+/// and `shares` are the resulting signature shares in order of the parties' indices.
+///
+/// Returns an error if the assembled signature is not valid for `rk`, which happens if
+/// either party did not follow the protocol or the shares are not of the same key.
+pub fn aggregate(
+    rk: &VerificationKey<SpendAuth>,
+    msg: &[u8],
+    commitments: &[NonceCommitment; 2],
+    shares: &[SignatureShare; 2],
+) -> Result<Signature<SpendAuth>, CosignError> {
+    let session = Session::new(rk, msg, commitments);
+
+    let mut bytes = [0; 64];
+    bytes[..32].copy_from_slice(&session.nonce.to_bytes());
+    bytes[32..].copy_from_slice(&(shares[0].0 + shares[1].0).to_repr());
+    let signature = Signature::from(bytes);
+
+    rk.verify(msg, &signature)
+        .map(|()| signature)
+        .map_err(|_| CosignError::InvalidSignature)
+}
+
+/// The values derived from the public inputs of a signing session.
+struct Session {
+    binding_factors: [pallas::Scalar; 2],
+    nonce: pallas::Point,
+    challenge: pallas::Scalar,
+}
+
+impl Session {
+    fn new(
+        rk: &VerificationKey<SpendAuth>,
+        msg: &[u8],
+        commitments: &[NonceCommitment; 2],
+    ) -> Self {
+        let rk = <[u8; 32]>::from(rk);
+
+        let binding_factors = [0u8, 1].map(|index| {
+            let mut state = blake2b_simd::Params::new()
+                .hash_length(64)
+                .personal(BINDING_PERSONALIZATION)
+                .to_state();
+            state.update(&[index]).update(&rk).update(msg);
+            for commitment in commitments {
+                state.update(&commitment.to_bytes());
+            }
+            pallas::Scalar::from_uniform_bytes(state.finalize().as_array())
+        });
+
+        let nonce = commitments
+            .iter()
+            .zip(binding_factors.iter())
+            .map(|(commitment, rho)| commitment.hiding + commitment.binding * rho)
+            .fold(pallas::Point::identity(), |acc, r| acc + r);
+
+        let challenge = pallas::Scalar::from_uniform_bytes(
+            blake2b_simd::Params::new()
+                .hash_length(64)
+                .personal(CHALLENGE_PERSONALIZATION)
+                .to_state()
+                .update(&nonce.to_bytes())
+                .update(&rk)
+                .update(msg)
+                .finalize()
+                .as_array(),
+        );
+
+        Session {
+            binding_factors,
+            nonce,
+            challenge,
+        }
+    }
+}
+
+/// Errors that can occur in a two-party signing session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CosignError {
+    /// A party's nonces did not match its commitment in the session.
+    CommitmentMismatch,
+    /// The assembled signature was not valid for the randomized key.
+    InvalidSignature,
+}
+
+impl fmt::Display for CosignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CosignError::CommitmentMismatch => {
+                f.write_str("Signing nonces do not match the session's commitment")
+            }
+            CosignError::InvalidSignature => {
+                f.write_str("Signature shares do not form a valid signature")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CosignError {}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::{aggregate, CosignError, KeyShare, SignatureShare, SigningNonces};
+    use crate::primitives::redpallas::{random_randomizer, SigningKey, VerificationKey};
+
+    #[test]
+    fn two_party_signature_verifies() {
+        let mut rng = OsRng;
+        let ask = SigningKey(reddsa::SigningKey::new(&mut rng));
+        let ak = VerificationKey::from(&ask);
+        let alpha = random_randomizer(&mut rng);
+        let rk = ak.randomize(&alpha);
+        let msg = b"sighash";
+
+        let shares = KeyShare::split(&ask, &mut rng);
+        let (nonces_0, commitment_0) = SigningNonces::new(&mut rng);
+        let (nonces_1, commitment_1) = SigningNonces::new(&mut rng);
+        let commitments = [commitment_0, commitment_1];
+
+        let sig_shares = [
+            shares[0]
+                .sign(nonces_0, &alpha, &rk, msg, &commitments)
+                .unwrap(),
+            shares[1]
+                .sign(nonces_1, &alpha, &rk, msg, &commitments)
+                .unwrap(),
+        ];
+        let sig = aggregate(&rk, msg, &commitments, &sig_shares).unwrap();
+        assert!(rk.verify(msg, &sig).is_ok());
+
+        // A tampered share is detected when aggregating.
+        let bad_shares = [sig_shares[0], SignatureShare::from_bytes(&[1; 32]).unwrap()];
+        assert_eq!(
+            aggregate(&rk, msg, &commitments, &bad_shares).unwrap_err(),
+            CosignError::InvalidSignature,
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_nonces() {
+        let mut rng = OsRng;
+        let ask = SigningKey(reddsa::SigningKey::new(&mut rng));
+        let alpha = random_randomizer(&mut rng);
+        let rk = VerificationKey::from(&ask).randomize(&alpha);
+
+        let shares = KeyShare::split(&ask, &mut rng);
+        let (nonces_0, _) = SigningNonces::new(&mut rng);
+        let (_, commitment_0) = SigningNonces::new(&mut rng);
+        let (_, commitment_1) = SigningNonces::new(&mut rng);
+
+        assert_eq!(
+            shares[0]
+                .sign(
+                    nonces_0,
+                    &alpha,
+                    &rk,
+                    b"sighash",
+                    &[commitment_0, commitment_1]
+                )
+                .unwrap_err(),
+            CosignError::CommitmentMismatch,
+        );
+    }
+}
@@ -0,0 +1,523 @@
+//! The Sinsemilla hash function and commitment scheme, as instantiated in Orchard.
+//!
+//! Defined in [Zcash Protocol Spec § 5.4.1.9: Sinsemilla Hash Function][concretesinsemillahash]
+//! and [§ 5.4.8.4: Sinsemilla commitments][concretesinsemillacommit].
+//!
+//! [`HashDomain`] and [`CommitDomain`] are re-exported from the [`sinsemilla`] crate at the
+//! version this crate uses, so that values computed with them are consistent with Orchard.
+//! The constructors in this module return the domains used by the Orchard protocol.
+//!
+//! [concretesinsemillahash]: https://zips.z.cash/protocol/protocol.pdf#concretesinsemillahash
+//! [concretesinsemillacommit]: https://zips.z.cash/protocol/protocol.pdf#concretesinsemillacommit
+//! [`sinsemilla`]: https://docs.rs/sinsemilla
+
+use alloc::{format, vec::Vec};
+
+use group::{Curve, Group};
+use lazy_static::lazy_static;
+use pasta_curves::{
+    arithmetic::{CurveAffine, CurveExt},
+    pallas,
+};
+#[cfg(feature = "multicore")]
+use rayon::prelude::*;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, CtOption};
+
+pub use ::sinsemilla::{CommitDomain, HashDomain};
+
+pub use crate::constants::{
+    fixed_bases::{COMMIT_IVK_PERSONALIZATION, NOTE_COMMITMENT_PERSONALIZATION},
+    sinsemilla::MERKLE_CRH_PERSONALIZATION,
+};
+use crate::{
+    constants::sinsemilla::{C, K},
+    spec::extract_p_bottom,
+};
+
+/// SWU hash-to-curve personalization for the Sinsemilla $Q$ generators.
+const Q_PERSONALIZATION: &str = "z.cash:SinsemillaQ";
+
+/// SWU hash-to-curve personalization for the Sinsemilla $S$ generators.
+const S_PERSONALIZATION: &str = "z.cash:SinsemillaS";
+
+/// The personalizations of the Sinsemilla domains used by Orchard.
+///
+/// [`ProtocolDomain::ZCASH`] holds the personalizations used by Zcash, which are the only
+/// ones that the rest of this crate uses: the Action circuit's fixed-base tables, note
+/// commitments, and the note commitment tree are all defined over them. Deployments of
+/// Orchard on other chains can use a different `ProtocolDomain` to obtain separate
+/// Sinsemilla domains for their own out-of-circuit computations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtocolDomain {
+    /// The personalization of $\mathsf{MerkleCRH}$.
+    pub merkle_crh: &'static str,
+    /// The personalization of $\mathsf{NoteCommit}$.
+    pub note_commit: &'static str,
+    /// The personalization of $\mathsf{Commit}^\mathsf{ivk}$.
+    pub commit_ivk: &'static str,
+}
+
+impl ProtocolDomain {
+    /// The personalizations used by the Zcash Orchard protocol.
+    pub const ZCASH: Self = ProtocolDomain {
+        merkle_crh: MERKLE_CRH_PERSONALIZATION,
+        note_commit: NOTE_COMMITMENT_PERSONALIZATION,
+        commit_ivk: COMMIT_IVK_PERSONALIZATION,
+    };
+
+    /// Returns the hash domain of $\mathsf{MerkleCRH}$.
+    pub fn merkle_crh_domain(&self) -> HashDomain {
+        HashDomain::new(self.merkle_crh)
+    }
+
+    /// Returns the commitment domain of $\mathsf{NoteCommit}$.
+    pub fn note_commit_domain(&self) -> CommitDomain {
+        CommitDomain::new(self.note_commit)
+    }
+
+    /// Returns the commitment domain of $\mathsf{Commit}^\mathsf{ivk}$.
+    pub fn commit_ivk_domain(&self) -> CommitDomain {
+        CommitDomain::new(self.commit_ivk)
+    }
+}
+
+impl Default for ProtocolDomain {
+    fn default() -> Self {
+        Self::ZCASH
+    }
+}
+
+/// Returns the hash domain of $\mathsf{MerkleCRH}^\mathsf{Orchard}$.
+pub fn merkle_crh_domain() -> HashDomain {
+    ProtocolDomain::ZCASH.merkle_crh_domain()
+}
+
+/// Returns the commitment domain of $\mathsf{NoteCommit}^\mathsf{Orchard}$.
+pub fn note_commit_domain() -> CommitDomain {
+    ProtocolDomain::ZCASH.note_commit_domain()
+}
+
+/// Returns the commitment domain of $\mathsf{Commit}^\mathsf{ivk}$.
+pub fn commit_ivk_domain() -> CommitDomain {
+    ProtocolDomain::ZCASH.commit_ivk_domain()
+}
+
+lazy_static! {
+    /// The generators $S(j)$ for $j \in \{0..2^K - 1\}$, computed on first use.
+    static ref S_TABLE: Vec<pallas::Point> = (0..(1u32 << K))
+        .map(|j| pallas::Point::hash_to_curve(S_PERSONALIZATION)(&j.to_le_bytes()))
+        .collect();
+}
+
+/// Returns the Sinsemilla generator
+/// $S(j) = \mathsf{GroupHash}^\mathbb{P}(\texttt{"z.cash:SinsemillaS"}, \mathsf{I2LEOSP}_{32}(j))$.
+///
+/// The $2^K$ generators are computed together the first time any of them is needed, and
+/// are then looked up rather than rederived.
+///
+/// # Panics
+///
+/// Panics if `j` is not less than $2^K$.
+pub fn s_generator(j: u32) -> pallas::Point {
+    S_TABLE[j as usize]
+}
+
+/// Returns $S(j)$ without indexing the table by `j`.
+///
+/// Every generator is read, and the one for `j` is kept with a constant-time selection,
+/// so the memory access pattern does not depend on `j`. This is roughly $2^K$ times as
+/// expensive as [`s_generator`].
+fn s_generator_ct(j: u32) -> pallas::Point {
+    S_TABLE
+        .iter()
+        .zip(0u32..)
+        .fold(pallas::Point::identity(), |acc, (s, i)| {
+            pallas::Point::conditional_select(&acc, s, i.ct_eq(&j))
+        })
+}
+
+/// Incomplete addition on Pallas, as used by $\mathsf{SinsemillaHashToPoint}$.
+///
+/// The result is $\bot$ if either input is $\bot$ or the identity, or if the inputs have
+/// the same $x$-coordinate.
+fn incomplete_add(
+    a: CtOption<pallas::Point>,
+    b: CtOption<pallas::Point>,
+) -> CtOption<pallas::Point> {
+    a.and_then(|a| {
+        b.and_then(|b| {
+            let a_x = a.to_affine().coordinates().map(|c| *c.x());
+            let b_x = b.to_affine().coordinates().map(|c| *c.x());
+            a_x.and_then(|a_x| b_x.and_then(|b_x| CtOption::new(a + b, !a_x.ct_eq(&b_x))))
+        })
+    })
+}
+
+/// An incremental $\mathsf{SinsemillaHashToPoint}$.
+///
+/// [`HashDomain::hash_to_point`] collects the whole padded message before hashing it.
+/// This hasher instead absorbs the message $K$ bits at a time as they are provided, so
+/// long messages can be hashed without buffering them, and looks up each $S$ generator in
+/// a shared table (see [`s_generator`]) rather than rederiving it. The result is the same
+/// as hashing the concatenation of every [`SinsemillaHasher::update`] input in one go.
+#[derive(Clone, Debug)]
+pub struct SinsemillaHasher {
+    acc: CtOption<pallas::Point>,
+    /// The bits of the current, incomplete chunk, in little-endian order.
+    pending: u32,
+    pending_len: usize,
+    len: usize,
+    constant_time: bool,
+}
+
+impl SinsemillaHasher {
+    /// Starts hashing a message in the domain with the given personalization.
+    pub fn new(domain: &str) -> Self {
+        SinsemillaHasher {
+            acc: CtOption::new(
+                pallas::Point::hash_to_curve(Q_PERSONALIZATION)(domain.as_bytes()),
+                Choice::from(1),
+            ),
+            pending: 0,
+            pending_len: 0,
+            len: 0,
+            constant_time: false,
+        }
+    }
+
+    /// Starts hashing a secret message in the domain with the given personalization.
+    ///
+    /// The hash is computed without branching on, or indexing memory by, the contents of
+    /// the message, at the cost of reading the entire $S$ table for every $K$ bits
+    /// absorbed. Only the length of the message is revealed through timing. The domain's
+    /// personalization is assumed to be public.
+    pub fn new_constant_time(domain: &str) -> Self {
+        SinsemillaHasher {
+            constant_time: true,
+            ..Self::new(domain)
+        }
+    }
+
+    /// Absorbs the next bits of the message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total length of the message exceeds $K \cdot C$ bits.
+    pub fn update(&mut self, bits: impl IntoIterator<Item = bool>) {
+        for bit in bits {
+            assert!(self.len < K * C, "Sinsemilla message is too long");
+            self.pending |= u32::from(bit) << self.pending_len;
+            self.pending_len += 1;
+            self.len += 1;
+            if self.pending_len == K {
+                self.absorb_chunk();
+            }
+        }
+    }
+
+    fn absorb_chunk(&mut self) {
+        let s = if self.constant_time {
+            s_generator_ct(self.pending)
+        } else {
+            s_generator(self.pending)
+        };
+        self.acc = incomplete_add(
+            incomplete_add(self.acc, CtOption::new(s, Choice::from(1))),
+            self.acc,
+        );
+        self.pending = 0;
+        self.pending_len = 0;
+    }
+
+    /// Pads the message with zeroes to a multiple of $K$ bits, and returns
+    /// $\mathsf{SinsemillaHashToPoint}$ of it.
+    pub fn finalize_to_point(mut self) -> CtOption<pallas::Point> {
+        if self.pending_len > 0 {
+            self.absorb_chunk();
+        }
+        self.acc
+    }
+
+    /// Returns $\mathsf{SinsemillaHash}$ of the absorbed message.
+    pub fn finalize(self) -> CtOption<pallas::Base> {
+        extract_p_bottom(self.finalize_to_point())
+    }
+}
+
+/// Computes $\mathsf{SinsemillaHash}$ of each of `messages`, in the domain with the given
+/// personalization.
+///
+/// The domain's $Q$ generator is derived once for the whole batch. With the `multicore`
+/// feature flag enabled, the messages are hashed in parallel.
+///
+/// # Panics
+///
+/// Panics if any message is longer than $K \cdot C$ bits.
+pub fn hash_batch<M: AsRef<[bool]> + Sync>(
+    domain: &str,
+    messages: &[M],
+) -> Vec<CtOption<pallas::Base>> {
+    let hasher = SinsemillaHasher::new(domain);
+    let hash = |msg: &M| {
+        let mut hasher = hasher.clone();
+        hasher.update(msg.as_ref().iter().copied());
+        hasher.finalize()
+    };
+
+    #[cfg(feature = "multicore")]
+    let messages = messages.par_iter();
+    #[cfg(not(feature = "multicore"))]
+    let messages = messages.iter();
+
+    messages.map(hash).collect()
+}
+
+/// Computes $\mathsf{SinsemillaCommit}_r(M)$ for each pair $(M, r)$ of `messages`, in the
+/// domain with the given personalization.
+///
+/// As for [`CommitDomain::commit`], the commitments are returned as points; the domain's
+/// generators are derived once for the whole batch. With the `multicore` feature flag
+/// enabled, the commitments are computed in parallel.
+///
+/// # Panics
+///
+/// Panics if any message is longer than $K \cdot C$ bits.
+pub fn commit_batch<M: AsRef<[bool]> + Sync>(
+    domain: &str,
+    messages: &[(M, pallas::Scalar)],
+) -> Vec<CtOption<pallas::Point>> {
+    let hasher = SinsemillaHasher::new(&format!("{}-M", domain));
+    let r_base = pallas::Point::hash_to_curve(&format!("{}-r", domain))(&[]);
+    let commit = |(msg, r): &(M, pallas::Scalar)| {
+        let mut hasher = hasher.clone();
+        hasher.update(msg.as_ref().iter().copied());
+        hasher.finalize_to_point().map(|p| p + r_base * r)
+    };
+
+    #[cfg(feature = "multicore")]
+    let messages = messages.par_iter();
+    #[cfg(not(feature = "multicore"))]
+    let messages = messages.iter();
+
+    messages.map(commit).collect()
+}
+
+/// Computes $\mathsf{SinsemillaCommit}_r(M)$ in the domain with the given personalization,
+/// as a point, without branching on or indexing memory by $M$ or $r$.
+///
+/// [`CommitDomain::commit`] looks up Sinsemilla generators by message chunk, and so leaks
+/// information about the message through the cache to other processes on the same host.
+/// Commitments to secret values, such as note commitments or $\mathsf{Commit}^\mathsf{ivk}$,
+/// can use this instead when that is a concern. The result is the same, but the hash is
+/// computed as by [`SinsemillaHasher::new_constant_time`], which is considerably slower.
+///
+/// # Panics
+///
+/// Panics if the message is longer than $K \cdot C$ bits.
+pub fn commit_constant_time(
+    domain: &str,
+    msg: impl IntoIterator<Item = bool>,
+    r: &pallas::Scalar,
+) -> CtOption<pallas::Point> {
+    let mut hasher = SinsemillaHasher::new_constant_time(&format!("{}-M", domain));
+    hasher.update(msg);
+    let r_base = pallas::Point::hash_to_curve(&format!("{}-r", domain))(&[]);
+    hasher.finalize_to_point().map(|p| p + r_base * r)
+}
+
+/// Computes $\mathsf{SinsemillaShortCommit}_r(M)$ in the domain with the given
+/// personalization, as [`commit_constant_time`] does for $\mathsf{SinsemillaCommit}$.
+///
+/// # Panics
+///
+/// Panics if the message is longer than $K \cdot C$ bits.
+pub fn short_commit_constant_time(
+    domain: &str,
+    msg: impl IntoIterator<Item = bool>,
+    r: &pallas::Scalar,
+) -> CtOption<pallas::Base> {
+    extract_p_bottom(commit_constant_time(domain, msg, r))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::iter;
+
+    use ff::{Field, PrimeField, PrimeFieldBits};
+    use incrementalmerkletree::Hashable;
+    use pasta_curves::{arithmetic::CurveExt, pallas};
+    use rand::{rngs::OsRng, Rng};
+
+    use super::{
+        commit_batch, commit_constant_time, hash_batch, merkle_crh_domain, s_generator,
+        s_generator_ct, short_commit_constant_time, CommitDomain, HashDomain, ProtocolDomain,
+        SinsemillaHasher, K,
+    };
+    use crate::{
+        constants::sinsemilla::i2lebsp_k,
+        note::{ExtractedNoteCommitment, Note, NoteCommitment},
+        spec::{commit_ivk, commit_ivk_in},
+        tree::MerkleHashOrchard,
+    };
+
+    #[test]
+    fn merkle_crh() {
+        let [left, right] = [(); 2].map(|_| pallas::Base::random(OsRng));
+        let level = 7;
+
+        let hash = merkle_crh_domain()
+            .hash(
+                iter::empty()
+                    .chain(i2lebsp_k(level).iter().copied())
+                    .chain(left.to_le_bits().iter().by_vals().take(255))
+                    .chain(right.to_le_bits().iter().by_vals().take(255)),
+            )
+            .unwrap();
+
+        let node = |x: pallas::Base| MerkleHashOrchard::from_bytes(&x.to_repr()).unwrap();
+        assert_eq!(
+            MerkleHashOrchard::combine((level as u8).into(), &node(left), &node(right)),
+            node(hash),
+        );
+    }
+
+    #[test]
+    fn zcash_protocol_domain() {
+        assert_eq!(ProtocolDomain::default(), ProtocolDomain::ZCASH);
+        assert_eq!(ProtocolDomain::ZCASH.merkle_crh, "z.cash:Orchard-MerkleCRH");
+        assert_eq!(
+            ProtocolDomain::ZCASH.note_commit,
+            "z.cash:Orchard-NoteCommit"
+        );
+        assert_eq!(ProtocolDomain::ZCASH.commit_ivk, "z.cash:Orchard-CommitIvk");
+    }
+
+    #[test]
+    fn custom_protocol_domain() {
+        let mut rng = OsRng;
+        let custom = ProtocolDomain {
+            merkle_crh: "example:Orchard-MerkleCRH",
+            note_commit: "example:Orchard-NoteCommit",
+            commit_ivk: "example:Orchard-CommitIvk",
+        };
+
+        let [left, right] = [(); 2].map(|_| {
+            MerkleHashOrchard::from_bytes(&pallas::Base::random(&mut rng).to_repr()).unwrap()
+        });
+        let level = 3.into();
+        let parent = MerkleHashOrchard::combine(level, &left, &right);
+        assert_eq!(
+            MerkleHashOrchard::combine_in(&ProtocolDomain::ZCASH, level, &left, &right),
+            parent,
+        );
+        assert_ne!(
+            MerkleHashOrchard::combine_in(&custom, level, &left, &right),
+            parent,
+        );
+
+        let (_, _, note) = Note::dummy(&mut rng, None);
+        let cmx = |cm: NoteCommitment| ExtractedNoteCommitment::from(cm).to_bytes();
+        let expected = cmx(note.commitment());
+        assert_eq!(
+            cmx(note.commitment_in(&ProtocolDomain::ZCASH).unwrap()),
+            expected
+        );
+        assert_ne!(cmx(note.commitment_in(&custom).unwrap()), expected);
+
+        let [ak, nk] = [(); 2].map(|_| pallas::Base::random(&mut rng));
+        let rivk = pallas::Scalar::random(&mut rng);
+        let ivk = commit_ivk(&ak, &nk, &rivk).unwrap();
+        assert_eq!(
+            commit_ivk_in(&ProtocolDomain::ZCASH, &ak, &nk, &rivk).unwrap(),
+            ivk
+        );
+        assert_ne!(commit_ivk_in(&custom, &ak, &nk, &rivk).unwrap(), ivk);
+    }
+
+    #[test]
+    fn s_generators() {
+        let mut rng = OsRng;
+        for j in [0, (1 << K) - 1, rng.gen_range(0..(1 << K))] {
+            assert_eq!(
+                s_generator(j),
+                pallas::Point::hash_to_curve("z.cash:SinsemillaS")(&j.to_le_bytes()),
+            );
+            assert_eq!(s_generator_ct(j), s_generator(j));
+        }
+    }
+
+    #[test]
+    fn constant_time_commitments() {
+        let mut rng = OsRng;
+        let domain = "z.cash:test-Sinsemilla";
+        for len in [0, 10, 255, 510] {
+            let msg: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+            let r = pallas::Scalar::random(&mut rng);
+            let expected = CommitDomain::new(domain);
+            assert_eq!(
+                commit_constant_time(domain, msg.iter().copied(), &r).unwrap(),
+                expected.commit(msg.iter().copied(), &r).unwrap(),
+            );
+            assert_eq!(
+                short_commit_constant_time(domain, msg.iter().copied(), &r).unwrap(),
+                expected.short_commit(msg.iter().copied(), &r).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn streaming_matches_hash_to_point() {
+        let mut rng = OsRng;
+        let domain = "z.cash:test-Sinsemilla";
+        for len in [0, 1, 9, 10, 11, 510, 1000] {
+            let msg: Vec<bool> = (0..len).map(|_| rng.gen()).collect();
+            let expected = HashDomain::new(domain)
+                .hash_to_point(msg.iter().copied())
+                .unwrap();
+
+            // Feed the message in pieces that do not line up with the chunk boundaries.
+            let mut hasher = SinsemillaHasher::new(domain);
+            for piece in msg.chunks(7) {
+                hasher.update(piece.iter().copied());
+            }
+            assert_eq!(hasher.clone().finalize_to_point().unwrap(), expected);
+            assert_eq!(
+                hasher.finalize().unwrap(),
+                HashDomain::new(domain).hash(msg.into_iter()).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn batches() {
+        let mut rng = OsRng;
+        let domain = "z.cash:test-Sinsemilla";
+        let messages: Vec<(Vec<bool>, pallas::Scalar)> = [0, 10, 255, 510]
+            .into_iter()
+            .map(|len| {
+                (
+                    (0..len).map(|_| rng.gen()).collect(),
+                    pallas::Scalar::random(&mut rng),
+                )
+            })
+            .collect();
+
+        let msgs: Vec<&[bool]> = messages.iter().map(|(msg, _)| &msg[..]).collect();
+        for (hash, msg) in hash_batch(domain, &msgs).into_iter().zip(&msgs) {
+            assert_eq!(
+                hash.unwrap(),
+                HashDomain::new(domain).hash(msg.iter().copied()).unwrap(),
+            );
+        }
+
+        for (cm, (msg, r)) in commit_batch(domain, &messages).into_iter().zip(&messages) {
+            assert_eq!(
+                cm.unwrap(),
+                CommitDomain::new(domain)
+                    .commit(msg.iter().copied(), r)
+                    .unwrap(),
+            );
+        }
+    }
+}
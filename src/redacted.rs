@@ -0,0 +1,20 @@
+//! Redaction of secret values in [`Debug`](fmt::Debug) output.
+
+use core::fmt;
+
+/// Wraps secret key material so that its [`fmt::Debug`] output is a placeholder, unless
+/// the `expose-secrets-debug` feature flag is enabled. This keeps secrets out of logs
+/// that format structs containing them.
+pub(crate) struct Redacted<'a, T>(pub(crate) &'a T);
+
+impl<T: fmt::Debug> fmt::Debug for Redacted<'_, T> {
+    #[cfg(feature = "expose-secrets-debug")]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+
+    #[cfg(not(feature = "expose-secrets-debug"))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
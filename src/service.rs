@@ -0,0 +1,315 @@
+//! A pool of worker threads for creating Action proofs.
+//!
+//! [`Proof::create`] blocks the calling thread for the duration of proving. Services that
+//! create proofs on behalf of many clients generally want to bound how many proofs are
+//! created concurrently, and how many requests may wait for a prover, independently of
+//! how many clients are connected. [`ProverPool`] provides this: it owns a fixed number
+//! of worker threads sharing one [`ProvingKey`], and a bounded queue of pending requests.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use orchard::{circuit::ProvingKey, service::ProverPool};
+//!
+//! let pool = ProverPool::new(Arc::new(ProvingKey::build()), 4, 16);
+//! # let (circuits, instances) = (vec![], vec![]);
+//! let ticket = pool.submit(circuits, instances).unwrap();
+//! let proof = ticket.wait().unwrap();
+//! ```
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use std::sync::{
+    mpsc::{self, Receiver, RecvError, SyncSender, TryRecvError, TrySendError},
+    Mutex,
+};
+use std::thread::{self, JoinHandle};
+
+use halo2_proofs::plonk;
+use rand::rngs::OsRng;
+
+use crate::{
+    circuit::{Circuit, Instance, ProvingKey},
+    Proof,
+};
+
+/// Errors that can occur when requesting a proof from a [`ProverPool`].
+#[derive(Debug)]
+pub enum ServiceError {
+    /// The request queue is full. Returned by [`ProverPool::try_submit`].
+    QueueFull,
+    /// The pool was shut down before the proof was created.
+    ShutDown,
+    /// The number of circuits and instances in the request differ.
+    MismatchedInputs,
+    /// Proof creation failed.
+    Proving(plonk::Error),
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::QueueFull => write!(f, "Prover request queue is full"),
+            ServiceError::ShutDown => write!(f, "Prover pool has shut down"),
+            ServiceError::MismatchedInputs => {
+                write!(f, "Number of circuits and instances differ")
+            }
+            ServiceError::Proving(e) => write!(f, "Could not create proof: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+impl From<plonk::Error> for ServiceError {
+    fn from(e: plonk::Error) -> Self {
+        ServiceError::Proving(e)
+    }
+}
+
+/// A request for a proof, queued for a worker.
+struct Job {
+    circuits: Vec<Circuit>,
+    instances: Vec<Instance>,
+    response: SyncSender<Result<Proof, plonk::Error>>,
+}
+
+/// A handle to a proof that has been submitted to a [`ProverPool`].
+#[derive(Debug)]
+pub struct ProofTicket {
+    response: Receiver<Result<Proof, plonk::Error>>,
+}
+
+impl ProofTicket {
+    /// Blocks until the proof has been created.
+    pub fn wait(self) -> Result<Proof, ServiceError> {
+        match self.response.recv() {
+            Ok(res) => res.map_err(ServiceError::Proving),
+            Err(RecvError) => Err(ServiceError::ShutDown),
+        }
+    }
+
+    /// Returns the proof if it has been created, or `None` if it is still pending.
+    pub fn try_wait(&self) -> Option<Result<Proof, ServiceError>> {
+        match self.response.try_recv() {
+            Ok(res) => Some(res.map_err(ServiceError::Proving)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(ServiceError::ShutDown)),
+        }
+    }
+}
+
+/// A fixed-size pool of threads that create Action proofs.
+///
+/// Requests are queued in a bounded channel. When the queue is full,
+/// [`ProverPool::submit`] blocks until a worker takes a request, and
+/// [`ProverPool::try_submit`] returns [`ServiceError::QueueFull`], so that callers can
+/// apply backpressure to their own clients.
+///
+/// Dropping the pool stops accepting requests, lets the workers finish every request that
+/// was already queued, and waits for them to exit.
+pub struct ProverPool {
+    requests: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl fmt::Debug for ProverPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProverPool")
+            .field("workers", &self.workers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProverPool {
+    /// Starts a pool of `workers` threads that create proofs with `pk`, accepting up to
+    /// `queue_depth` requests that are waiting for a worker.
+    ///
+    /// A `queue_depth` of zero means that [`ProverPool::submit`] only returns once a
+    /// worker has taken the request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `workers` is zero, or if a worker thread cannot be spawned.
+    pub fn new(pk: Arc<ProvingKey>, workers: usize, queue_depth: usize) -> Self {
+        assert!(workers > 0, "a prover pool needs at least one worker");
+
+        let (requests, jobs) = mpsc::sync_channel::<Job>(queue_depth);
+        let jobs = Arc::new(Mutex::new(jobs));
+
+        let workers = (0..workers)
+            .map(|i| {
+                let pk = pk.clone();
+                let jobs = jobs.clone();
+                thread::Builder::new()
+                    .name(format!("orchard-prover-{}", i))
+                    .spawn(move || worker(&pk, &jobs))
+                    .expect("can spawn prover thread")
+            })
+            .collect();
+
+        ProverPool {
+            requests: Some(requests),
+            workers,
+        }
+    }
+
+    /// Returns the number of worker threads in this pool.
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queues a request to prove `circuits` against `instances`, blocking while the
+    /// queue is full.
+    pub fn submit(
+        &self,
+        circuits: Vec<Circuit>,
+        instances: Vec<Instance>,
+    ) -> Result<ProofTicket, ServiceError> {
+        let (job, ticket) = Self::job(circuits, instances)?;
+        self.sender()?
+            .send(job)
+            .map_err(|_| ServiceError::ShutDown)?;
+        Ok(ticket)
+    }
+
+    /// Queues a request to prove `circuits` against `instances`, returning
+    /// [`ServiceError::QueueFull`] if the queue is full.
+    pub fn try_submit(
+        &self,
+        circuits: Vec<Circuit>,
+        instances: Vec<Instance>,
+    ) -> Result<ProofTicket, ServiceError> {
+        let (job, ticket) = Self::job(circuits, instances)?;
+        match self.sender()?.try_send(job) {
+            Ok(()) => Ok(ticket),
+            Err(TrySendError::Full(_)) => Err(ServiceError::QueueFull),
+            Err(TrySendError::Disconnected(_)) => Err(ServiceError::ShutDown),
+        }
+    }
+
+    /// Stops accepting requests, and waits for every queued request to be completed.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn job(
+        circuits: Vec<Circuit>,
+        instances: Vec<Instance>,
+    ) -> Result<(Job, ProofTicket), ServiceError> {
+        if circuits.len() != instances.len() {
+            return Err(ServiceError::MismatchedInputs);
+        }
+
+        let (response, ticket) = mpsc::sync_channel(1);
+        Ok((
+            Job {
+                circuits,
+                instances,
+                response,
+            },
+            ProofTicket { response: ticket },
+        ))
+    }
+
+    fn sender(&self) -> Result<&SyncSender<Job>, ServiceError> {
+        self.requests.as_ref().ok_or(ServiceError::ShutDown)
+    }
+
+    fn stop(&mut self) {
+        // Closing the request channel causes each worker to exit once the queue drains.
+        self.requests = None;
+        for worker in self.workers.drain(..) {
+            // A worker only panics if proving panicked; the affected ticket will already
+            // have observed `ServiceError::ShutDown`.
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for ProverPool {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn worker(pk: &ProvingKey, jobs: &Mutex<Receiver<Job>>) {
+    loop {
+        // Only hold the lock while waiting for a job, so that other workers can take the
+        // next one while this worker is proving.
+        let job = match jobs.lock() {
+            Ok(jobs) => jobs.recv(),
+            Err(_) => return,
+        };
+        let job = match job {
+            Ok(job) => job,
+            Err(RecvError) => return,
+        };
+
+        let proof = Proof::create(pk, &job.circuits, &job.instances, OsRng);
+        // The caller may have dropped its ticket; that is not an error for the pool.
+        let _ = job.response.send(proof);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+
+    use rand::rngs::OsRng;
+
+    use super::{ProverPool, ServiceError};
+    use crate::{
+        builder::{Builder, BundleType},
+        circuit::{ProvingKey, VerifyingKey},
+        constants::MERKLE_DEPTH_ORCHARD,
+        keys::{FullViewingKey, Scope, SpendingKey},
+        tree::EMPTY_ROOTS,
+        value::NoteValue,
+    };
+
+    #[test]
+    fn prover_pool() {
+        let mut rng = OsRng;
+        let pk = Arc::new(ProvingKey::build());
+        let vk = VerifyingKey::build();
+
+        let fvk = FullViewingKey::from(&SpendingKey::random(&mut rng));
+        let recipient = fvk.address_at(0u32, Scope::External);
+
+        let mut builder = Builder::new(
+            BundleType::DEFAULT,
+            EMPTY_ROOTS[MERKLE_DEPTH_ORCHARD].into(),
+        );
+        builder
+            .add_output(None, recipient, NoteValue::from_raw(5000), [0u8; 512])
+            .unwrap();
+        let (bundle, _) = builder.build::<i64>(&mut rng).unwrap().unwrap();
+        let circuits = bundle.authorization().circuits().to_vec();
+        let instances: Vec<_> = bundle
+            .actions()
+            .iter()
+            .map(|a| a.to_instance(*bundle.flags(), *bundle.anchor()))
+            .collect();
+
+        let pool = ProverPool::new(pk, 2, 1);
+        assert_eq!(pool.workers(), 2);
+
+        let tickets: Vec<_> = (0..2)
+            .map(|_| pool.submit(circuits.clone(), instances.clone()).unwrap())
+            .collect();
+        for ticket in tickets {
+            let proof = ticket.wait().unwrap();
+            assert!(proof.verify(&vk, &instances).is_ok());
+        }
+
+        assert!(matches!(
+            pool.submit(circuits, instances[..1].to_vec()),
+            Err(ServiceError::MismatchedInputs)
+        ));
+
+        pool.shutdown();
+    }
+}
@@ -1,12 +1,16 @@
 //! Helper functions defined in the Zcash Protocol Specification.
+//!
+//! The public items in this module are the exact implementations used by the rest of this
+//! crate for the named functions of the specification, for use by alternative
+//! implementations of Orchard and by test-vector generators. Unlike the transcriptions in
+//! the `spec_reference` module (available with the `spec-reference` feature flag), they
+//! are constant-time where the rest of the crate relies on that.
 
 use core::iter;
 use core::ops::Deref;
 
 use ff::{Field, FromUniformBytes, PrimeField, PrimeFieldBits};
 use group::{Curve, Group, GroupEncoding, WnafBase, WnafScalar};
-#[cfg(feature = "circuit")]
-use halo2_gadgets::{poseidon::primitives as poseidon, sinsemilla::primitives as sinsemilla};
 #[cfg(feature = "std")]
 use memuse::DynamicUsage;
 use pasta_curves::{
@@ -15,16 +19,24 @@ use pasta_curves::{
 };
 use subtle::{ConditionallySelectable, CtOption};
 
-use crate::constants::{
-    fixed_bases::COMMIT_IVK_PERSONALIZATION, util::gen_const_array,
-    KEY_DIVERSIFICATION_PERSONALIZATION, L_ORCHARD_BASE,
+use crate::{
+    constants::L_ORCHARD_BASE,
+    primitives::{
+        poseidon,
+        sinsemilla::{self, ProtocolDomain},
+    },
+};
+
+pub use crate::constants::{
+    fixed_bases::{ORCHARD_PERSONALIZATION, VALUE_COMMITMENT_PERSONALIZATION},
+    KEY_DIVERSIFICATION_PERSONALIZATION,
 };
 
 pub(crate) use zcash_spec::PrfExpand;
 
 /// A Pallas point that is guaranteed to not be the identity.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) struct NonIdentityPallasPoint(pallas::Point);
+pub struct NonIdentityPallasPoint(pallas::Point);
 
 impl Default for NonIdentityPallasPoint {
     fn default() -> Self {
@@ -39,7 +51,8 @@ impl ConditionallySelectable for NonIdentityPallasPoint {
 }
 
 impl NonIdentityPallasPoint {
-    pub(crate) fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
+    /// Parses a Pallas point from its encoding, rejecting the identity.
+    pub fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
         pallas::Point::from_bytes(bytes)
             .and_then(|p| CtOption::new(NonIdentityPallasPoint(p), !p.is_identity()))
     }
@@ -95,7 +108,7 @@ impl NonZeroPallasBase {
 
 /// An integer in [1..r_P].
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) struct NonZeroPallasScalar(pallas::Scalar);
+pub struct NonZeroPallasScalar(pallas::Scalar);
 
 impl Default for NonZeroPallasScalar {
     fn default() -> Self {
@@ -116,11 +129,13 @@ impl ConditionallySelectable for NonZeroPallasScalar {
 }
 
 impl NonZeroPallasScalar {
-    pub(crate) fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
+    /// Parses a canonical encoding of a Pallas scalar, rejecting zero.
+    pub fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
         pallas::Scalar::from_repr(*bytes).and_then(NonZeroPallasScalar::from_scalar)
     }
 
-    pub(crate) fn from_scalar(s: pallas::Scalar) -> CtOption<Self> {
+    /// Wraps a Pallas scalar, rejecting zero.
+    pub fn from_scalar(s: pallas::Scalar) -> CtOption<Self> {
         CtOption::new(NonZeroPallasScalar(s), !s.is_zero())
     }
 
@@ -179,7 +194,7 @@ impl PreparedNonZeroScalar {
 /// Defined in [Zcash Protocol Spec § 4.2.3: Orchard Key Components][orchardkeycomponents].
 ///
 /// [orchardkeycomponents]: https://zips.z.cash/protocol/nu5.pdf#orchardkeycomponents
-pub(crate) fn to_base(x: [u8; 64]) -> pallas::Base {
+pub fn to_base(x: [u8; 64]) -> pallas::Base {
     pallas::Base::from_uniform_bytes(&x)
 }
 
@@ -188,7 +203,7 @@ pub(crate) fn to_base(x: [u8; 64]) -> pallas::Base {
 /// Defined in [Zcash Protocol Spec § 4.2.3: Orchard Key Components][orchardkeycomponents].
 ///
 /// [orchardkeycomponents]: https://zips.z.cash/protocol/nu5.pdf#orchardkeycomponents
-pub(crate) fn to_scalar(x: [u8; 64]) -> pallas::Scalar {
+pub fn to_scalar(x: [u8; 64]) -> pallas::Scalar {
     pallas::Scalar::from_uniform_bytes(&x)
 }
 
@@ -207,27 +222,66 @@ pub(crate) fn commit_ivk(
     ak: &pallas::Base,
     nk: &pallas::Base,
     rivk: &pallas::Scalar,
+) -> CtOption<pallas::Base> {
+    commit_ivk_in(&ProtocolDomain::ZCASH, ak, nk, rivk)
+}
+
+/// $\mathsf{Commit}^\mathsf{ivk}_\mathsf{rivk}(\mathsf{ak}, \mathsf{nk})$, in the
+/// $\mathsf{Commit}^\mathsf{ivk}$ domain of `domain`.
+///
+/// With [`ProtocolDomain::ZCASH`], this derives $\mathsf{ivk}$ as Orchard does. With the
+/// `constant-time-commitments` feature flag enabled, it is computed with
+/// [`short_commit_constant_time`], so that $\mathsf{ak}$, $\mathsf{nk}$ and
+/// $\mathsf{rivk}$ do not leak through the cache to other processes on the same host.
+///
+/// [`short_commit_constant_time`]: crate::primitives::sinsemilla::short_commit_constant_time
+pub fn commit_ivk_in(
+    domain: &ProtocolDomain,
+    ak: &pallas::Base,
+    nk: &pallas::Base,
+    rivk: &pallas::Scalar,
 ) -> CtOption<pallas::Base> {
     // We rely on the API contract that to_le_bits() returns at least PrimeField::NUM_BITS
     // bits, which is equal to L_ORCHARD_BASE.
-    let domain = sinsemilla::CommitDomain::new(COMMIT_IVK_PERSONALIZATION);
-    domain.short_commit(
-        iter::empty()
-            .chain(ak.to_le_bits().iter().by_vals().take(L_ORCHARD_BASE))
-            .chain(nk.to_le_bits().iter().by_vals().take(L_ORCHARD_BASE)),
-        rivk,
-    )
+    let msg = iter::empty()
+        .chain(ak.to_le_bits().iter().by_vals().take(L_ORCHARD_BASE))
+        .chain(nk.to_le_bits().iter().by_vals().take(L_ORCHARD_BASE));
+
+    if cfg!(feature = "constant-time-commitments") {
+        sinsemilla::short_commit_constant_time(domain.commit_ivk, msg, rivk)
+    } else {
+        domain.commit_ivk_domain().short_commit(msg, rivk)
+    }
+}
+
+/// $\mathsf{GroupHash}^\mathbb{P}(D, M)$, which hashes the message `msg` to a Pallas
+/// point in the domain with personalization `domain`.
+///
+/// Orchard's generators are derived with the personalizations re-exported by this module,
+/// such as [`KEY_DIVERSIFICATION_PERSONALIZATION`] for $\mathsf{DiversifyHash}$. Protocol
+/// extensions should use personalizations of their own.
+///
+/// Defined in [Zcash Protocol Spec § 5.4.9.8: Group Hash into Pallas and Vesta][concretegrouphashpallasandvesta].
+///
+/// [concretegrouphashpallasandvesta]: https://zips.z.cash/protocol/nu5.pdf#concretegrouphashpallasandvesta
+pub fn group_hash(domain: &str, msg: &[u8]) -> pallas::Point {
+    pallas::Point::hash_to_curve(domain)(msg)
 }
 
+/// $\mathsf{DiversifyHash}^\mathsf{Orchard}(d)$, which derives the diversified base
+/// $\mathsf{g_d}$ of an address from its diversifier.
+///
 /// Defined in [Zcash Protocol Spec § 5.4.1.6: DiversifyHash^Sapling and DiversifyHash^Orchard Hash Functions][concretediversifyhash].
 ///
 /// [concretediversifyhash]: https://zips.z.cash/protocol/nu5.pdf#concretediversifyhash
-pub(crate) fn diversify_hash(d: &[u8; 11]) -> NonIdentityPallasPoint {
-    let hasher = pallas::Point::hash_to_curve(KEY_DIVERSIFICATION_PERSONALIZATION);
-    let g_d = hasher(d);
+pub fn diversify_hash(d: &[u8; 11]) -> NonIdentityPallasPoint {
+    let g_d = group_hash(KEY_DIVERSIFICATION_PERSONALIZATION, d);
     // If the identity occurs, we replace it with a different fixed point.
     // TODO: Replace the unwrap_or_else with a cached fixed point.
-    NonIdentityPallasPoint(CtOption::new(g_d, !g_d.is_identity()).unwrap_or_else(|| hasher(&[])))
+    NonIdentityPallasPoint(
+        CtOption::new(g_d, !g_d.is_identity())
+            .unwrap_or_else(|| group_hash(KEY_DIVERSIFICATION_PERSONALIZATION, &[])),
+    )
 }
 
 /// $PRF^\mathsf{nfOrchard}(nk, \rho) := Poseidon(nk, \rho)$
@@ -236,17 +290,15 @@ pub(crate) fn diversify_hash(d: &[u8; 11]) -> NonIdentityPallasPoint {
 ///
 /// [concreteprfs]: https://zips.z.cash/protocol/nu5.pdf#concreteprfs
 pub(crate) fn prf_nf(nk: pallas::Base, rho: pallas::Base) -> pallas::Base {
-    poseidon::Hash::<_, poseidon::P128Pow5T3, poseidon::ConstantLength<2>, 3, 2>::init()
-        .hash([nk, rho])
+    poseidon::hash(nk, rho)
 }
 
+/// $\mathsf{KA}^\mathsf{Orchard}.\mathsf{Agree}(\mathsf{sk}, B) := [\mathsf{sk}] B$
+///
 /// Defined in [Zcash Protocol Spec § 5.4.5.5: Orchard Key Agreement][concreteorchardkeyagreement].
 ///
 /// [concreteorchardkeyagreement]: https://zips.z.cash/protocol/nu5.pdf#concreteorchardkeyagreement
-pub(crate) fn ka_orchard(
-    sk: &NonZeroPallasScalar,
-    b: &NonIdentityPallasPoint,
-) -> NonIdentityPallasPoint {
+pub fn ka_orchard(sk: &NonZeroPallasScalar, b: &NonIdentityPallasPoint) -> NonIdentityPallasPoint {
     ka_orchard_prepared(
         &PreparedNonZeroScalar::new(sk),
         &PreparedNonIdentityBase::new(*b),
@@ -268,7 +320,7 @@ pub(crate) fn ka_orchard_prepared(
 /// Defined in [Zcash Protocol Spec § 5.4.9.7: Coordinate Extractor for Pallas][concreteextractorpallas].
 ///
 /// [concreteextractorpallas]: https://zips.z.cash/protocol/nu5.pdf#concreteextractorpallas
-pub(crate) fn extract_p(point: &pallas::Point) -> pallas::Base {
+pub fn extract_p(point: &pallas::Point) -> pallas::Base {
     point
         .to_affine()
         .coordinates()
@@ -281,42 +333,13 @@ pub(crate) fn extract_p(point: &pallas::Point) -> pallas::Base {
 /// Defined in [Zcash Protocol Spec § 5.4.9.7: Coordinate Extractor for Pallas][concreteextractorpallas].
 ///
 /// [concreteextractorpallas]: https://zips.z.cash/protocol/nu5.pdf#concreteextractorpallas
-pub(crate) fn extract_p_bottom(point: CtOption<pallas::Point>) -> CtOption<pallas::Base> {
+pub fn extract_p_bottom(point: CtOption<pallas::Point>) -> CtOption<pallas::Base> {
     point.map(|p| extract_p(&p))
 }
 
-/// The field element representation of a u64 integer represented by
-/// an L-bit little-endian bitstring.
-pub fn lebs2ip_field<F: PrimeField, const L: usize>(bits: &[bool; L]) -> F {
-    F::from(lebs2ip::<L>(bits))
-}
-
-/// The u64 integer represented by an L-bit little-endian bitstring.
-///
-/// # Panics
-///
-/// Panics if the bitstring is longer than 64 bits.
-pub fn lebs2ip<const L: usize>(bits: &[bool; L]) -> u64 {
-    assert!(L <= 64);
-    bits.iter()
-        .enumerate()
-        .fold(0u64, |acc, (i, b)| acc + if *b { 1 << i } else { 0 })
-}
-
-/// The sequence of bits representing a u64 in little-endian order.
-///
-/// # Panics
-///
-/// Panics if the expected length of the sequence `NUM_BITS` exceeds
-/// 64.
-pub fn i2lebsp<const NUM_BITS: usize>(int: u64) -> [bool; NUM_BITS] {
-    assert!(NUM_BITS <= 64);
-    gen_const_array(|mask: usize| (int & (1 << mask)) != 0)
-}
-
 #[cfg(test)]
 mod tests {
-    use super::{i2lebsp, lebs2ip};
+    use crate::primitives::bits::{i2lebsp, lebs2ip};
 
     use group::Group;
     use halo2_proofs::arithmetic::CurveExt;
@@ -330,6 +353,20 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn group_hash_matches_generators() {
+        let mut d = [0; 11];
+        OsRng.fill_bytes(&mut d);
+        assert_eq!(
+            *super::diversify_hash(&d),
+            super::group_hash(super::KEY_DIVERSIFICATION_PERSONALIZATION, &d)
+        );
+        assert_eq!(
+            super::group_hash(super::ORCHARD_PERSONALIZATION, b"K"),
+            pallas::Point::hash_to_curve("z.cash:Orchard")(b"K")
+        );
+    }
+
     #[test]
     fn lebs2ip_round_trip() {
         let mut rng = OsRng;
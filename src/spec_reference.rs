@@ -0,0 +1,240 @@
+//! Unoptimized reference implementations of Orchard primitives.
+//!
+//! The functions in this module follow the [Zcash Protocol Specification] as literally as
+//! is practical, preferring clarity over performance. They exist so that the optimized
+//! implementations used by the rest of this crate can be differentially tested against an
+//! independent transcription of the specification, and are never called by the rest of
+//! this crate.
+//!
+//! These implementations are **not** constant-time, and **must not** be used on secret
+//! data in production, or in place of the primary implementations for consensus.
+//!
+//! Sinsemilla is implemented here from its definition in terms of $\mathsf{GroupHash}$.
+//! The Poseidon permutation, and $\mathsf{GroupHash}$ itself, are taken from the same
+//! dependencies as the primary implementation, since a transcription of their constants
+//! would add little independent assurance.
+//!
+//! [Zcash Protocol Specification]: https://zips.z.cash/protocol/nu5.pdf
+
+use alloc::vec::Vec;
+
+use ff::{Field, PrimeField, PrimeFieldBits};
+use group::{Curve, GroupEncoding};
+use pasta_curves::{
+    arithmetic::{Coordinates, CurveAffine, CurveExt},
+    pallas,
+};
+
+/// The number of message bits consumed by each step of $\mathsf{SinsemillaHashToPoint}$.
+const SINSEMILLA_K: usize = 10;
+
+/// $\mathsf{GroupHash}^\mathbb{P}(D, M)$.
+fn group_hash(domain: &str, msg: &[u8]) -> pallas::Point {
+    pallas::Point::hash_to_curve(domain)(msg)
+}
+
+/// $\mathsf{Extract}_\mathbb{P}(P)$, mapping the identity to zero.
+///
+/// Defined in [Zcash Protocol Spec § 5.4.9.7: Coordinate Extractor for Pallas][concreteextractorpallas].
+///
+/// [concreteextractorpallas]: https://zips.z.cash/protocol/nu5.pdf#concreteextractorpallas
+pub fn extract_p(point: &pallas::Point) -> pallas::Base {
+    let coords: Option<Coordinates<pallas::Affine>> = point.to_affine().coordinates().into();
+    coords.map_or(pallas::Base::ZERO, |c| *c.x())
+}
+
+/// Incomplete addition $P \mathbin{⸭} Q$, which is undefined ($\bot$) if either input is
+/// the identity, or if the inputs have the same $x$-coordinate.
+fn incomplete_add(p: pallas::Point, q: pallas::Point) -> Option<pallas::Point> {
+    let p_coords: Option<Coordinates<pallas::Affine>> = p.to_affine().coordinates().into();
+    let q_coords: Option<Coordinates<pallas::Affine>> = q.to_affine().coordinates().into();
+
+    if p_coords?.x() == q_coords?.x() {
+        None
+    } else {
+        Some(p + q)
+    }
+}
+
+/// Appends the `num_bits` least significant bits of `bytes`, in little-endian order.
+fn append_le_bits(bits: &mut Vec<bool>, bytes: &[u8], num_bits: usize) {
+    bits.extend((0..num_bits).map(|i| bytes[i / 8] & (1 << (i % 8)) != 0));
+}
+
+/// $\mathsf{SinsemillaHashToPoint}(D, M)$, returning `None` for $\bot$.
+///
+/// Defined in [Zcash Protocol Spec § 5.4.1.9: Sinsemilla Hash Function][concretesinsemillahash].
+///
+/// # Panics
+///
+/// Panics if `msg` is longer than $253 \cdot 10$ bits.
+///
+/// [concretesinsemillahash]: https://zips.z.cash/protocol/nu5.pdf#concretesinsemillahash
+pub fn sinsemilla_hash_to_point(domain: &str, msg: &[bool]) -> Option<pallas::Point> {
+    let n = (msg.len() + SINSEMILLA_K - 1) / SINSEMILLA_K;
+    assert!(n <= 253);
+
+    let mut acc = group_hash("z.cash:SinsemillaQ", domain.as_bytes());
+    for i in 0..n {
+        // The final chunk is padded with zero bits.
+        let j = (0..SINSEMILLA_K)
+            .map(|b| msg.get(i * SINSEMILLA_K + b).copied().unwrap_or(false))
+            .enumerate()
+            .fold(0u32, |j, (b, bit)| j | (u32::from(bit) << b));
+        let s = group_hash("z.cash:SinsemillaS", &j.to_le_bytes());
+
+        acc = incomplete_add(incomplete_add(acc, s)?, acc)?;
+    }
+
+    Some(acc)
+}
+
+/// $\mathsf{SinsemillaCommit}_r(D, M)$, returning `None` for $\bot$.
+///
+/// Defined in [Zcash Protocol Spec § 5.4.8.4: Sinsemilla commitments][concretesinsemillacommit].
+///
+/// [concretesinsemillacommit]: https://zips.z.cash/protocol/nu5.pdf#concretesinsemillacommit
+pub fn sinsemilla_commit(domain: &str, msg: &[bool], r: &pallas::Scalar) -> Option<pallas::Point> {
+    let hash = sinsemilla_hash_to_point(&format!("{}-M", domain), msg)?;
+    Some(hash + group_hash(&format!("{}-r", domain), &[]) * r)
+}
+
+/// $\mathsf{NoteCommit}^\mathsf{Orchard}_\mathsf{rcm}(\mathsf{repr}_\mathbb{P}(g_d),
+/// \mathsf{repr}_\mathbb{P}(\mathsf{pk_d}), v, \rho, \psi)$, returning `None` for $\bot$.
+///
+/// Defined in [Zcash Protocol Spec § 5.4.8.4: Sinsemilla commitments][concretesinsemillacommit].
+///
+/// [concretesinsemillacommit]: https://zips.z.cash/protocol/nu5.pdf#concretesinsemillacommit
+pub fn note_commit(
+    g_d: &pallas::Point,
+    pk_d: &pallas::Point,
+    v: u64,
+    rho: &pallas::Base,
+    psi: &pallas::Base,
+    rcm: &pallas::Scalar,
+) -> Option<pallas::Point> {
+    let mut msg = Vec::with_capacity(256 + 256 + 64 + 255 + 255);
+    append_le_bits(&mut msg, &g_d.to_bytes(), 256);
+    append_le_bits(&mut msg, &pk_d.to_bytes(), 256);
+    append_le_bits(&mut msg, &v.to_le_bytes(), 64);
+    append_le_bits(&mut msg, &rho.to_repr(), pallas::Base::NUM_BITS as usize);
+    append_le_bits(&mut msg, &psi.to_repr(), pallas::Base::NUM_BITS as usize);
+
+    sinsemilla_commit("z.cash:Orchard-NoteCommit", &msg, rcm)
+}
+
+/// $\mathsf{DeriveNullifier}_\mathsf{nk}(\rho, \psi, \mathsf{cm})$.
+///
+/// Defined in [Zcash Protocol Spec § 4.16: Note Commitments and Nullifiers][commitmentsandnullifiers].
+///
+/// [commitmentsandnullifiers]: https://zips.z.cash/protocol/nu5.pdf#commitmentsandnullifiers
+pub fn derive_nullifier(
+    nk: &pallas::Base,
+    rho: &pallas::Base,
+    psi: &pallas::Base,
+    cm: &pallas::Point,
+) -> pallas::Base {
+    let k = group_hash("z.cash:Orchard", b"K");
+
+    // PRF^nf_nk(rho) = PoseidonHash(nk, rho)
+    let prf_nf =
+        poseidon::Hash::<_, poseidon::P128Pow5T3, poseidon::ConstantLength<2>, 3, 2>::init()
+            .hash([*nk, *rho]);
+
+    // (PRF^nf_nk(rho) + psi) mod q_P, interpreted as an integer and reduced mod r_P. The
+    // bits of a base field element are taken one at a time, rather than by reinterpreting
+    // the encoding, to avoid relying on q_P < r_P.
+    let scalar = (prf_nf + psi).to_le_bits().iter().by_vals().rev().fold(
+        pallas::Scalar::ZERO,
+        |acc, bit| {
+            acc.double()
+                + if bit {
+                    pallas::Scalar::ONE
+                } else {
+                    pallas::Scalar::ZERO
+                }
+        },
+    );
+
+    extract_p(&(k * scalar + cm))
+}
+
+/// $\mathsf{ValueCommit}^\mathsf{Orchard}_\mathsf{rcv}(v)$, for a signed value $v$ in the
+/// range $-(2^{64} - 1)..2^{64}$.
+///
+/// Defined in [Zcash Protocol Spec § 5.4.8.3: Homomorphic Pedersen commitments (Sapling and Orchard)][concretehomomorphiccommit].
+///
+/// # Panics
+///
+/// Panics if `v` is out of range.
+///
+/// [concretehomomorphiccommit]: https://zips.z.cash/protocol/nu5.pdf#concretehomomorphiccommit
+pub fn value_commit(v: i128, rcv: &pallas::Scalar) -> pallas::Point {
+    let abs = u64::try_from(v.unsigned_abs()).expect("v must be in range");
+    let v = if v < 0 {
+        -pallas::Scalar::from(abs)
+    } else {
+        pallas::Scalar::from(abs)
+    };
+
+    group_hash("z.cash:Orchard-cv", b"v") * v + group_hash("z.cash:Orchard-cv", b"r") * rcv
+}
+
+#[cfg(test)]
+mod tests {
+    use group::GroupEncoding;
+    use proptest::prelude::*;
+
+    use super::{derive_nullifier, note_commit, value_commit};
+    use crate::{
+        keys::{testing::arb_spending_key, FullViewingKey},
+        note::{testing::arb_note, ExtractedNoteCommitment},
+        value::{
+            testing::{arb_note_value, arb_trapdoor},
+            NoteValue, ValueCommitment,
+        },
+    };
+
+    proptest! {
+        #[test]
+        fn note_commitment_and_nullifier(
+            sk in arb_spending_key(),
+            note in arb_note_value().prop_flat_map(arb_note),
+        ) {
+            let fvk = FullViewingKey::from(&sk);
+            let rho = note.rho().into_inner();
+            let psi = note.rseed().psi(&note.rho());
+
+            let cm = note_commit(
+                &note.recipient().g_d(),
+                &note.recipient().pk_d().inner(),
+                note.value().inner(),
+                &rho,
+                &psi,
+                &note.rseed().rcm(&note.rho()).inner(),
+            )
+            .unwrap();
+            prop_assert_eq!(cm, note.commitment().inner());
+            prop_assert_eq!(
+                super::extract_p(&cm),
+                ExtractedNoteCommitment::from(note.commitment()).inner(),
+            );
+
+            let nf = derive_nullifier(&fvk.nk().inner(), &rho, &psi, &cm);
+            prop_assert_eq!(nf, note.nullifier(&fvk).0);
+        }
+
+        #[test]
+        fn value_commitment(
+            a in arb_note_value(),
+            b in arb_note_value(),
+            rcv in arb_trapdoor(),
+        ) {
+            let cv = value_commit(
+                i128::from(a.inner()) - i128::from(b.inner()),
+                &rcv.inner(),
+            );
+            prop_assert_eq!(cv.to_bytes(), ValueCommitment::derive(a - b, rcv).to_bytes());
+        }
+    }
+}
@@ -0,0 +1,438 @@
+//! Generation of test vectors for Orchard key derivation and the Sinsemilla-based
+//! primitives.
+//!
+//! Each vector type can be computed from given inputs, and the functions in this module
+//! also sample random inputs from a caller-provided RNG. The expected outputs are
+//! computed with the same code that the rest of this crate uses, which is checked
+//! against the published Zcash test vectors. With a seeded RNG the output is
+//! reproducible, so implementations of Orchard in other languages can check themselves
+//! against vectors generated from this crate rather than against hand-copied constants.
+//! Every vector type implements [`Serialize`], for export in whichever format is
+//! convenient.
+//!
+//! Points are given in their compressed $\mathsf{repr}_\mathbb{P}$ encoding, and field
+//! elements in their canonical little-endian encoding.
+
+use alloc::{string::String, vec::Vec};
+
+use ff::{Field, PrimeField};
+use group::GroupEncoding;
+use pasta_curves::pallas;
+use rand::RngCore;
+use serde::Serialize;
+
+use crate::{
+    constants::sinsemilla::{C, K},
+    keys::{FullViewingKey, Scope, SpendAuthorizingKey, SpendingKey},
+    note::{ExtractedNoteCommitment, Note, Nullifier, Rho},
+    primitives::sinsemilla::{SinsemillaHasher, MERKLE_CRH_PERSONALIZATION},
+    spec::commit_ivk,
+    value::NoteValue,
+};
+
+/// A test vector for $\mathsf{SinsemillaHashToPoint}$ and $\mathsf{SinsemillaHash}$.
+#[derive(Clone, Debug, Serialize)]
+pub struct SinsemillaTestVector {
+    /// The personalization of the domain.
+    pub domain: String,
+    /// The message, as a sequence of bits.
+    pub msg: Vec<bool>,
+    /// $\mathsf{SinsemillaHashToPoint}(\mathsf{domain}, \mathsf{msg})$.
+    pub point: [u8; 32],
+    /// $\mathsf{SinsemillaHash}(\mathsf{domain}, \mathsf{msg})$.
+    pub hash: [u8; 32],
+}
+
+impl SinsemillaTestVector {
+    /// Computes the test vector for `msg` in the domain with personalization `domain`.
+    ///
+    /// Returns `None` if the hash is $\bot$.
+    pub fn new(domain: &str, msg: Vec<bool>) -> Option<Self> {
+        let mut hasher = SinsemillaHasher::new(domain);
+        hasher.update(msg.iter().copied());
+        let point = Option::<pallas::Point>::from(hasher.clone().finalize_to_point())?;
+        let hash = hasher.finalize().unwrap();
+
+        Some(SinsemillaTestVector {
+            domain: domain.into(),
+            msg,
+            point: point.to_bytes(),
+            hash: hash.to_repr(),
+        })
+    }
+}
+
+/// A test vector for $\mathsf{NoteCommit}^\mathsf{Orchard}$.
+#[derive(Clone, Debug, Serialize)]
+pub struct NoteCommitTestVector {
+    /// The diversified base $\mathsf{g_d}$.
+    pub g_d: [u8; 32],
+    /// The diversified transmission key $\mathsf{pk_d}$.
+    pub pk_d: [u8; 32],
+    /// The note value $\mathsf{v}$.
+    pub v: u64,
+    /// $\rho$.
+    pub rho: [u8; 32],
+    /// $\psi$.
+    pub psi: [u8; 32],
+    /// The commitment trapdoor $\mathsf{rcm}$.
+    pub rcm: [u8; 32],
+    /// The note commitment $\mathsf{cm}$.
+    pub cm: [u8; 32],
+    /// The extracted note commitment $\mathsf{cm}_x$.
+    pub cmx: [u8; 32],
+}
+
+impl NoteCommitTestVector {
+    /// Computes the test vector for the commitment to `note`.
+    pub fn new(note: &Note) -> Self {
+        let recipient = note.recipient();
+        let rho = note.rho();
+        let cm = note.commitment();
+
+        NoteCommitTestVector {
+            g_d: recipient.g_d().to_bytes(),
+            pk_d: recipient.pk_d().to_bytes(),
+            v: note.value().inner(),
+            rho: rho.to_bytes(),
+            psi: note.rseed().psi(&rho).to_repr(),
+            rcm: note.rseed().rcm(&rho).to_bytes(),
+            cm: cm.inner().to_bytes(),
+            cmx: ExtractedNoteCommitment::from(cm).to_bytes(),
+        }
+    }
+}
+
+/// A test vector for $\mathsf{Commit}^\mathsf{ivk}$.
+#[derive(Clone, Debug, Serialize)]
+pub struct CommitIvkTestVector {
+    /// The $x$-coordinate of the spend validating key $\mathsf{ak}$.
+    pub ak: [u8; 32],
+    /// The nullifier deriving key $\mathsf{nk}$.
+    pub nk: [u8; 32],
+    /// The commitment trapdoor $\mathsf{rivk}$.
+    pub rivk: [u8; 32],
+    /// The incoming viewing key $\mathsf{ivk}$.
+    pub ivk: [u8; 32],
+}
+
+impl CommitIvkTestVector {
+    /// Computes the test vector for the given $x$-coordinate of $\mathsf{ak}$,
+    /// $\mathsf{nk}$ and $\mathsf{rivk}$.
+    ///
+    /// Returns `None` if the commitment is $\bot$.
+    pub fn new(ak: pallas::Base, nk: pallas::Base, rivk: pallas::Scalar) -> Option<Self> {
+        let ivk = Option::<pallas::Base>::from(commit_ivk(&ak, &nk, &rivk))?;
+
+        Some(CommitIvkTestVector {
+            ak: ak.to_repr(),
+            nk: nk.to_repr(),
+            rivk: rivk.to_repr(),
+            ivk: ivk.to_repr(),
+        })
+    }
+}
+
+/// A test vector for the derivation of Orchard keys and the default address from a seed.
+///
+/// The spending key is derived from the seed with [ZIP 32] at the path
+/// `m / 32' / coin_type' / account'`.
+///
+/// [ZIP 32]: https://zips.z.cash/zip-0032
+#[derive(Clone, Debug, Serialize)]
+pub struct KeyTestVector {
+    /// The seed.
+    pub seed: [u8; 32],
+    /// The coin type of the derivation path.
+    pub coin_type: u32,
+    /// The account index of the derivation path.
+    pub account: u32,
+    /// The spending key $\mathsf{sk}$.
+    pub sk: [u8; 32],
+    /// The spend authorizing key $\mathsf{ask}$.
+    pub ask: [u8; 32],
+    /// The spend validating key $\mathsf{ak}$.
+    pub ak: [u8; 32],
+    /// The nullifier deriving key $\mathsf{nk}$.
+    pub nk: [u8; 32],
+    /// The commitment randomness $\mathsf{rivk}$.
+    pub rivk: [u8; 32],
+    /// The diversifier key $\mathsf{dk}$.
+    pub dk: [u8; 32],
+    /// The incoming viewing key $\mathsf{ivk}$.
+    pub ivk: [u8; 32],
+    /// The outgoing viewing key $\mathsf{ovk}$.
+    pub ovk: [u8; 32],
+    /// The diversifier of the address at index 0.
+    pub default_d: [u8; 11],
+    /// The diversified transmission key of the address at index 0.
+    pub default_pk_d: [u8; 32],
+    /// The commitment randomness $\mathsf{rivk_{internal}}$ of the internal scope.
+    pub internal_rivk: [u8; 32],
+    /// The diversifier key of the internal scope.
+    pub internal_dk: [u8; 32],
+    /// The incoming viewing key of the internal scope.
+    pub internal_ivk: [u8; 32],
+    /// The outgoing viewing key of the internal scope.
+    pub internal_ovk: [u8; 32],
+}
+
+/// Generates `count` key derivation test vectors, from random seeds, coin types and
+/// accounts.
+pub fn key_vectors(mut rng: impl RngCore, count: usize) -> Vec<KeyTestVector> {
+    (0..count)
+        .map(|_| {
+            let mut seed = [0; 32];
+            rng.fill_bytes(&mut seed);
+            let coin_type = rng.next_u32() >> 1;
+            let account = rng.next_u32() >> 1;
+
+            let sk = SpendingKey::from_zip32_seed(
+                &seed,
+                coin_type,
+                account.try_into().expect("account is below 2^31"),
+            )
+            .expect("derivation from a random seed succeeds with overwhelming probability");
+            KeyTestVector::from_spending_key(seed, coin_type, account, &sk)
+        })
+        .collect()
+}
+
+impl KeyTestVector {
+    /// Derives the key components of `sk`, which was derived from `seed` at the given
+    /// coin type and account.
+    fn from_spending_key(seed: [u8; 32], coin_type: u32, account: u32, sk: &SpendingKey) -> Self {
+        let fvk = FullViewingKey::from(sk);
+        let fvk_bytes = fvk.to_bytes();
+        let internal_fvk_bytes = fvk.derive_internal().to_bytes();
+        let ivk =
+            |scope: Scope| -> [u8; 32] { fvk.to_ivk(scope).to_bytes()[32..].try_into().unwrap() };
+        let default_address = fvk.address_at(0u32, Scope::External);
+
+        KeyTestVector {
+            seed,
+            coin_type,
+            account,
+            sk: *sk.to_bytes(),
+            ask: SpendAuthorizingKey::from(sk).to_bytes(),
+            ak: fvk_bytes[..32].try_into().unwrap(),
+            nk: fvk_bytes[32..64].try_into().unwrap(),
+            rivk: fvk_bytes[64..].try_into().unwrap(),
+            dk: *fvk.to_dk(Scope::External).to_bytes(),
+            ivk: ivk(Scope::External),
+            ovk: *fvk.to_ovk(Scope::External).as_ref(),
+            default_d: *default_address.diversifier().as_array(),
+            default_pk_d: default_address.to_raw_address_bytes()[11..]
+                .try_into()
+                .unwrap(),
+            internal_rivk: internal_fvk_bytes[64..].try_into().unwrap(),
+            internal_dk: *fvk.to_dk(Scope::Internal).to_bytes(),
+            internal_ivk: ivk(Scope::Internal),
+            internal_ovk: *fvk.to_ovk(Scope::Internal).as_ref(),
+        }
+    }
+}
+
+/// Generates `count` Sinsemilla test vectors in the $\mathsf{MerkleCRH}$ domain, with
+/// messages of random lengths up to the maximum of $k \cdot c$ bits.
+pub fn sinsemilla_vectors(mut rng: impl RngCore, count: usize) -> Vec<SinsemillaTestVector> {
+    (0..count)
+        .map(|_| {
+            let len = rng.next_u32() as usize % (K * C + 1);
+            let msg: Vec<bool> = (0..len).map(|_| rng.next_u32() & 1 == 1).collect();
+
+            SinsemillaTestVector::new(MERKLE_CRH_PERSONALIZATION, msg)
+                .expect("hash of a random message is not ⊥ with overwhelming probability")
+        })
+        .collect()
+}
+
+/// Generates `count` $\mathsf{NoteCommit}^\mathsf{Orchard}$ test vectors, for notes of
+/// random values sent to random addresses.
+pub fn note_commit_vectors(mut rng: impl RngCore, count: usize) -> Vec<NoteCommitTestVector> {
+    (0..count)
+        .map(|_| {
+            let fvk = FullViewingKey::from(&SpendingKey::random(&mut rng));
+            let recipient = fvk.address_at(rng.next_u32(), Scope::External);
+            let value = NoteValue::from_raw(rng.next_u64());
+            let rho = Rho::from_nf_old(Nullifier::dummy(&mut rng));
+
+            NoteCommitTestVector::new(&Note::new(recipient, value, rho, &mut rng))
+        })
+        .collect()
+}
+
+/// Generates `count` $\mathsf{Commit}^\mathsf{ivk}$ test vectors.
+pub fn commit_ivk_vectors(mut rng: impl RngCore, count: usize) -> Vec<CommitIvkTestVector> {
+    (0..count)
+        .map(|_| {
+            CommitIvkTestVector::new(
+                pallas::Base::random(&mut rng),
+                pallas::Base::random(&mut rng),
+                pallas::Scalar::random(&mut rng),
+            )
+            .expect("commitment to random inputs is not ⊥ with overwhelming probability")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use ff::{PrimeField, PrimeFieldBits};
+    use group::GroupEncoding;
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    use super::{
+        commit_ivk_vectors, key_vectors, note_commit_vectors, sinsemilla_vectors,
+        CommitIvkTestVector, KeyTestVector, NoteCommitTestVector, SinsemillaTestVector,
+    };
+    use crate::{
+        constants::sinsemilla::{i2lebsp_k, L_ORCHARD_MERKLE},
+        keys::SpendingKey,
+        note::{Note, RandomSeed, Rho},
+        primitives::sinsemilla::MERKLE_CRH_PERSONALIZATION,
+        spec_reference, test_vectors,
+        value::NoteValue,
+        zip32, Address,
+    };
+
+    #[test]
+    fn vectors_match_published_vectors() {
+        // MerkleCRH of each empty root with itself gives the empty root one level up.
+        let empty_roots = test_vectors::commitment_tree::test_vectors().empty_roots;
+        for (level, pair) in empty_roots.windows(2).enumerate() {
+            let node = pallas::Base::from_repr(pair[0]).unwrap();
+            let msg = i2lebsp_k(level)
+                .into_iter()
+                .chain(node.to_le_bits().iter().by_vals().take(L_ORCHARD_MERKLE))
+                .chain(node.to_le_bits().iter().by_vals().take(L_ORCHARD_MERKLE))
+                .collect();
+            let tv = SinsemillaTestVector::new(MERKLE_CRH_PERSONALIZATION, msg).unwrap();
+            assert_eq!(tv.hash, pair[1]);
+        }
+
+        let note_commit = |d: &[u8; 11], pk_d: &[u8; 32], v, rho: &[u8; 32], rseed| {
+            let mut addr = [0; 43];
+            addr[..11].copy_from_slice(d);
+            addr[11..].copy_from_slice(pk_d);
+            let rho = Rho::from_bytes(rho).unwrap();
+            let note = Note::from_parts(
+                Address::from_raw_address_bytes(&addr).unwrap(),
+                NoteValue::from_raw(v),
+                rho,
+                RandomSeed::from_bytes(rseed, &rho).unwrap(),
+            )
+            .unwrap();
+            NoteCommitTestVector::new(&note)
+        };
+
+        for tv in test_vectors::keys::test_vectors() {
+            let ak = pallas::Base::from_repr(tv.ak).unwrap();
+            let nk = pallas::Base::from_repr(tv.nk).unwrap();
+            for (rivk, ivk) in [(tv.rivk, tv.ivk), (tv.internal_rivk, tv.internal_ivk)] {
+                let rivk = pallas::Scalar::from_repr(rivk).unwrap();
+                assert_eq!(CommitIvkTestVector::new(ak, nk, rivk).unwrap().ivk, ivk);
+            }
+
+            let cm = note_commit(
+                &tv.default_d,
+                &tv.default_pk_d,
+                tv.note_v,
+                &tv.note_rho,
+                tv.note_rseed,
+            );
+            assert_eq!(cm.cmx, tv.note_cmx);
+        }
+
+        for tv in test_vectors::note_encryption::test_vectors() {
+            // The note's rho is the nullifier of the note spent in the same action.
+            let cm = note_commit(&tv.default_d, &tv.default_pk_d, tv.v, &tv.nf_old, tv.rseed);
+            assert_eq!(cm.cmx, tv.cmx);
+        }
+    }
+
+    #[test]
+    fn key_vectors_follow_zip32_path() {
+        for tv in key_vectors(OsRng, 5) {
+            let xsk = zip32::ExtendedSpendingKey::from_path(
+                &tv.seed,
+                &[
+                    zip32::ChildIndex::hardened(32),
+                    zip32::ChildIndex::hardened(tv.coin_type),
+                    zip32::ChildIndex::hardened(tv.account),
+                ],
+            )
+            .unwrap();
+            assert_eq!(xsk.sk().to_bytes(), &tv.sk);
+        }
+    }
+
+    #[test]
+    fn key_vectors_match_published_vectors() {
+        for tv in test_vectors::keys::test_vectors() {
+            let sk = SpendingKey::from_bytes(tv.sk).unwrap();
+            let generated = KeyTestVector::from_spending_key([0; 32], 0, 0, &sk);
+            assert_eq!(generated.sk, tv.sk);
+            assert_eq!(generated.ask, tv.ask);
+            assert_eq!(generated.ak, tv.ak);
+            assert_eq!(generated.nk, tv.nk);
+            assert_eq!(generated.rivk, tv.rivk);
+            assert_eq!(generated.dk, tv.dk);
+            assert_eq!(generated.ivk, tv.ivk);
+            assert_eq!(generated.ovk, tv.ovk);
+            assert_eq!(generated.default_d, tv.default_d);
+            assert_eq!(generated.default_pk_d, tv.default_pk_d);
+            assert_eq!(generated.internal_rivk, tv.internal_rivk);
+            assert_eq!(generated.internal_dk, tv.internal_dk);
+            assert_eq!(generated.internal_ivk, tv.internal_ivk);
+            assert_eq!(generated.internal_ovk, tv.internal_ovk);
+        }
+    }
+
+    #[test]
+    fn generated_vectors_match_reference() {
+        for tv in sinsemilla_vectors(OsRng, 10) {
+            let point = spec_reference::sinsemilla_hash_to_point(&tv.domain, &tv.msg).unwrap();
+            assert_eq!(point.to_bytes(), tv.point);
+            assert_eq!(spec_reference::extract_p(&point).to_repr(), tv.hash);
+        }
+
+        for tv in note_commit_vectors(OsRng, 10) {
+            let cm = spec_reference::note_commit(
+                &pallas::Point::from_bytes(&tv.g_d).unwrap(),
+                &pallas::Point::from_bytes(&tv.pk_d).unwrap(),
+                tv.v,
+                &pallas::Base::from_repr(tv.rho).unwrap(),
+                &pallas::Base::from_repr(tv.psi).unwrap(),
+                &pallas::Scalar::from_repr(tv.rcm).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(cm.to_bytes(), tv.cm);
+            assert_eq!(spec_reference::extract_p(&cm).to_repr(), tv.cmx);
+        }
+
+        for tv in commit_ivk_vectors(OsRng, 10) {
+            let msg: Vec<bool> = [tv.ak, tv.nk]
+                .iter()
+                .flat_map(|repr| {
+                    let x = pallas::Base::from_repr(*repr).unwrap();
+                    x.to_le_bits()
+                        .iter()
+                        .by_vals()
+                        .take(pallas::Base::NUM_BITS as usize)
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            let ivk = spec_reference::sinsemilla_commit(
+                "z.cash:Orchard-CommitIvk",
+                &msg,
+                &pallas::Scalar::from_repr(tv.rivk).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(spec_reference::extract_p(&ivk).to_repr(), tv.ivk);
+        }
+    }
+}
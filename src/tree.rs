@@ -1,19 +1,25 @@
 //! Types related to Orchard note commitment trees and anchors.
 
 use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
 use core::iter;
+use core::ops::Range;
 
 use crate::{
     constants::{
-        sinsemilla::{i2lebsp_k, L_ORCHARD_MERKLE, MERKLE_CRH_PERSONALIZATION},
+        sinsemilla::{i2lebsp_k, L_ORCHARD_MERKLE},
         MERKLE_DEPTH_ORCHARD,
     },
     note::commitment::ExtractedNoteCommitment,
+    primitives::sinsemilla::ProtocolDomain,
 };
 
-use incrementalmerkletree::{Hashable, Level};
+#[cfg(feature = "sinsemilla-table")]
+use crate::primitives::sinsemilla::SinsemillaHasher;
+use incrementalmerkletree::{frontier::Frontier, Hashable, Level, Position};
 use pasta_curves::pallas;
-use sinsemilla::HashDomain;
 
 use ff::{Field, PrimeField, PrimeFieldBits};
 use lazy_static::lazy_static;
@@ -23,10 +29,19 @@ use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
 use subtle::{Choice, ConditionallySelectable, CtOption};
 
+mod consistency;
+mod delta;
+pub use self::consistency::ConsistencyProof;
+pub use self::delta::{TreeDelta, TreeDeltaError};
+
 // The uncommitted leaf is defined as pallas::Base(2).
 // <https://zips.z.cash/protocol/protocol.pdf#thmuncommittedorchard>
 lazy_static! {
     static ref UNCOMMITTED_ORCHARD: pallas::Base = pallas::Base::from(2);
+    /// A hasher in the MerkleCRH domain that has not absorbed any input yet.
+    #[cfg(feature = "sinsemilla-table")]
+    static ref MERKLE_CRH: SinsemillaHasher =
+        SinsemillaHasher::new(ProtocolDomain::ZCASH.merkle_crh);
     pub(crate) static ref EMPTY_ROOTS: Vec<MerkleHashOrchard> = {
         iter::empty()
             .chain(Some(MerkleHashOrchard::empty_leaf()))
@@ -46,6 +61,24 @@ lazy_static! {
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
 pub struct Anchor(pallas::Base);
 
+impl PartialOrd for Anchor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Anchor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for Anchor {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
 impl From<pallas::Base> for Anchor {
     fn from(anchor_field: pallas::Base) -> Anchor {
         Anchor(anchor_field)
@@ -115,6 +148,7 @@ impl From<incrementalmerkletree::MerklePath<MerkleHashOrchard, 32>> for MerklePa
 
 impl MerklePath {
     /// Generates a dummy Merkle path for use in dummy spent notes.
+    #[cfg_attr(feature = "test-dependencies", visibility::make(pub))]
     pub(crate) fn dummy(mut rng: &mut impl RngCore) -> Self {
         MerklePath {
             position: rng.next_u32(),
@@ -144,19 +178,15 @@ impl MerklePath {
     ///      - when hashing two leaves, we produce a node on the layer above the leaves, i.e.
     ///        layer = 31, l = 0
     ///      - when hashing to the final root, we produce the anchor with layer = 0, l = 31.
+    ///
+    /// This does not branch on the position of the leaf; see [`root_of_depth`].
     pub fn root(&self, cmx: ExtractedNoteCommitment) -> Anchor {
-        self.auth_path
-            .iter()
-            .enumerate()
-            .fold(MerkleHashOrchard::from_cmx(&cmx), |node, (l, sibling)| {
-                let l = l as u8;
-                if self.position & (1 << l) == 0 {
-                    MerkleHashOrchard::combine(l.into(), &node, sibling)
-                } else {
-                    MerkleHashOrchard::combine(l.into(), sibling, &node)
-                }
-            })
-            .into()
+        root_of_depth(
+            self.position,
+            &self.auth_path,
+            MerkleHashOrchard::from_cmx(&cmx),
+        )
+        .into()
     }
 
     /// Returns the position of the leaf using this Merkle path.
@@ -170,6 +200,41 @@ impl MerklePath {
     }
 }
 
+/// Computes the root of a tree of depth `DEPTH`, built with the Orchard Merkle hash, from
+/// a `leaf` at `position` and its authentication path.
+///
+/// Only the low `DEPTH` bits of `position` are used. For `DEPTH = MERKLE_DEPTH_ORCHARD`
+/// this is the computation performed by [`MerklePath::root`]; shallower trees are useful
+/// for testing circuits that verify Merkle paths, which are otherwise dominated by the 32
+/// layers of the Orchard note commitment tree.
+///
+/// The computation does not branch on `position`: at each layer, the order of the node
+/// and its sibling is chosen with a constant-time conditional swap, and the same hash is
+/// computed either way. A local observer of timing or branch behaviour therefore cannot
+/// learn which leaf is being authenticated from this function's control flow. The
+/// Sinsemilla hash itself uses table lookups indexed by its input, which this does not
+/// protect against.
+///
+/// # Panics
+///
+/// Panics if `DEPTH` is greater than `MERKLE_DEPTH_ORCHARD`.
+pub fn root_of_depth<const DEPTH: usize>(
+    position: u32,
+    auth_path: &[MerkleHashOrchard; DEPTH],
+    leaf: MerkleHashOrchard,
+) -> MerkleHashOrchard {
+    assert!(DEPTH <= MERKLE_DEPTH_ORCHARD);
+    auth_path
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (l, sibling)| {
+            let is_right = Choice::from(((position >> l) & 1) as u8);
+            let left = MerkleHashOrchard::conditional_select(&node, sibling, is_right);
+            let right = MerkleHashOrchard::conditional_select(sibling, &node, is_right);
+            MerkleHashOrchard::combine((l as u8).into(), &left, &right)
+        })
+}
+
 /// A newtype wrapper for leaves and internal nodes in the Orchard
 /// incremental note commitment tree.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -200,6 +265,34 @@ impl MerkleHashOrchard {
     pub fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
         pallas::Base::from_repr(*bytes).map(MerkleHashOrchard)
     }
+
+    /// Computes the parent of `left` and `right` at `level` with $\mathsf{MerkleCRH}$ in
+    /// the domain of `domain`, for deployments of Orchard that do not use the Zcash
+    /// personalizations.
+    ///
+    /// With [`ProtocolDomain::ZCASH`], this is the same as [`Hashable::combine`]. Note
+    /// that [`Hashable::empty_root`] is only defined for the Zcash domain.
+    pub fn combine_in(domain: &ProtocolDomain, level: Level, left: &Self, right: &Self) -> Self {
+        let message = iter::empty()
+            .chain(i2lebsp_k(level.into()).iter().copied())
+            .chain(left.0.to_le_bits().iter().by_vals().take(L_ORCHARD_MERKLE))
+            .chain(right.0.to_le_bits().iter().by_vals().take(L_ORCHARD_MERKLE));
+
+        #[cfg(not(feature = "sinsemilla-table"))]
+        let hash = domain.merkle_crh_domain().hash(message);
+        #[cfg(feature = "sinsemilla-table")]
+        let hash = {
+            let mut hasher = if *domain == ProtocolDomain::ZCASH {
+                MERKLE_CRH.clone()
+            } else {
+                SinsemillaHasher::new(domain.merkle_crh)
+            };
+            hasher.update(message);
+            hasher.finalize()
+        };
+
+        MerkleHashOrchard(hash.unwrap_or(pallas::Base::zero()))
+    }
 }
 
 impl ConditionallySelectable for MerkleHashOrchard {
@@ -223,20 +316,13 @@ impl Hashable for MerkleHashOrchard {
     ///      - when hashing two leaves, we produce a node on the layer above the leaves, i.e.
     ///        layer = 31, l = 0
     ///      - when hashing to the final root, we produce the anchor with layer = 0, l = 31.
+    ///
+    /// With the `sinsemilla-table` feature flag enabled, the $S$ generators are looked up
+    /// in the table of [`s_generator`] instead of being derived for every chunk.
+    ///
+    /// [`s_generator`]: crate::primitives::sinsemilla::s_generator
     fn combine(level: Level, left: &Self, right: &Self) -> Self {
-        // MerkleCRH Sinsemilla hash domain.
-        let domain = HashDomain::new(MERKLE_CRH_PERSONALIZATION);
-
-        MerkleHashOrchard(
-            domain
-                .hash(
-                    iter::empty()
-                        .chain(i2lebsp_k(level.into()).iter().copied())
-                        .chain(left.0.to_le_bits().iter().by_vals().take(L_ORCHARD_MERKLE))
-                        .chain(right.0.to_le_bits().iter().by_vals().take(L_ORCHARD_MERKLE)),
-                )
-                .unwrap_or(pallas::Base::zero()),
-        )
+        Self::combine_in(&ProtocolDomain::ZCASH, level, left, right)
     }
 
     fn empty_root(level: Level) -> Self {
@@ -261,6 +347,130 @@ impl<'de> Deserialize<'de> for MerkleHashOrchard {
     }
 }
 
+/// The frontier of the Orchard note commitment tree.
+///
+/// This retains only the information required to append new leaves and compute the
+/// current root, which is what indexers need to track the tree as they process blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NoteCommitmentTree(Frontier<MerkleHashOrchard, { MERKLE_DEPTH_ORCHARD as u8 }>);
+
+impl Default for NoteCommitmentTree {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl From<Frontier<MerkleHashOrchard, { MERKLE_DEPTH_ORCHARD as u8 }>> for NoteCommitmentTree {
+    fn from(frontier: Frontier<MerkleHashOrchard, { MERKLE_DEPTH_ORCHARD as u8 }>) -> Self {
+        NoteCommitmentTree(frontier)
+    }
+}
+
+impl NoteCommitmentTree {
+    /// Returns the empty note commitment tree.
+    pub fn empty() -> Self {
+        NoteCommitmentTree(Frontier::empty())
+    }
+
+    /// Returns the underlying frontier.
+    pub fn frontier(&self) -> &Frontier<MerkleHashOrchard, { MERKLE_DEPTH_ORCHARD as u8 }> {
+        &self.0
+    }
+
+    /// Returns the number of leaves that have been appended to the tree.
+    pub fn size(&self) -> u64 {
+        self.0.tree_size()
+    }
+
+    /// Returns the current root of the tree.
+    pub fn root(&self) -> Anchor {
+        self.0.root().into()
+    }
+
+    /// Appends a single extracted note commitment to the tree.
+    ///
+    /// Returns `false` if the tree is full.
+    pub fn append(&mut self, cmx: &ExtractedNoteCommitment) -> bool {
+        self.0.append(MerkleHashOrchard::from_cmx(cmx))
+    }
+
+    /// Appends the extracted note commitments encoded in `cmxs` to the tree, in order.
+    ///
+    /// Each encoding is checked to be a canonical encoding of a Pallas base field
+    /// element before it is appended. If any encoding is invalid, or the tree becomes
+    /// full, an error is returned and the tree is left unchanged.
+    pub fn extend_from_bytes(
+        &mut self,
+        cmxs: impl Iterator<Item = [u8; 32]>,
+    ) -> Result<ExtendStats, ExtendError> {
+        let start = Position::from(self.size());
+        let mut frontier = self.0.clone();
+
+        let mut count = 0;
+        for (index, bytes) in cmxs.enumerate() {
+            let leaf = Option::from(MerkleHashOrchard::from_bytes(&bytes))
+                .ok_or(ExtendError::NonCanonical { index })?;
+            if !frontier.append(leaf) {
+                return Err(ExtendError::TreeFull { index });
+            }
+            count += 1;
+        }
+
+        self.0 = frontier;
+
+        Ok(ExtendStats {
+            count,
+            root: self.root(),
+            positions: start..Position::from(self.size()),
+        })
+    }
+}
+
+/// Statistics about a batch of leaves appended with
+/// [`NoteCommitmentTree::extend_from_bytes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtendStats {
+    /// The number of leaves that were appended.
+    pub count: usize,
+    /// The root of the tree after the leaves were appended.
+    pub root: Anchor,
+    /// The positions at which the leaves were appended.
+    pub positions: Range<Position>,
+}
+
+/// Errors that can occur when appending to a [`NoteCommitmentTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExtendError {
+    /// The leaf at the given index within the batch was not a canonical encoding of an
+    /// extracted note commitment.
+    NonCanonical {
+        /// The index of the invalid leaf within the batch.
+        index: usize,
+    },
+    /// The tree was full, so the leaf at the given index within the batch could not be
+    /// appended.
+    TreeFull {
+        /// The index of the first leaf that could not be appended.
+        index: usize,
+    },
+}
+
+impl fmt::Display for ExtendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtendError::NonCanonical { index } => {
+                write!(f, "Leaf {} is not a canonical note commitment", index)
+            }
+            ExtendError::TreeFull { index } => {
+                write!(f, "Note commitment tree is full at leaf {}", index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ExtendError {}
+
 /// Test utilities available under the `test-dependencies` feature flag.
 #[cfg(feature = "test-dependencies")]
 pub mod testing {
@@ -289,13 +499,47 @@ pub mod testing {
 #[cfg(test)]
 mod tests {
     use {
-        crate::tree::{MerkleHashOrchard, EMPTY_ROOTS},
+        crate::tree::{
+            root_of_depth, ExtendError, MerkleHashOrchard, NoteCommitmentTree, EMPTY_ROOTS,
+        },
+        core::iter,
         group::ff::PrimeField,
-        incrementalmerkletree::{frontier::Frontier, Level, Marking, MerklePath, Retention},
+        incrementalmerkletree::{
+            frontier::Frontier, Level, Marking, MerklePath, Position, Retention,
+        },
         pasta_curves::pallas,
         shardtree::{store::memory::MemoryShardStore, ShardTree},
     };
 
+    #[test]
+    fn root_of_depth_empty() {
+        let auth_path: [_; 4] = core::array::from_fn(|l| EMPTY_ROOTS[l]);
+        for position in [0, 5, 15, 16] {
+            assert_eq!(
+                root_of_depth(position, &auth_path, EMPTY_ROOTS[0]),
+                EMPTY_ROOTS[4],
+            );
+        }
+    }
+
+    #[test]
+    fn root_of_depth_matches_incremental() {
+        let auth_path: [_; 4] = core::array::from_fn(|i| {
+            MerkleHashOrchard::from_bytes(&pallas::Base::from(i as u64 + 1).to_repr()).unwrap()
+        });
+        let leaf = MerkleHashOrchard::from_bytes(&pallas::Base::from(7).to_repr()).unwrap();
+
+        for position in 0..16u32 {
+            let expected = MerklePath::<MerkleHashOrchard, 4>::from_parts(
+                auth_path.to_vec(),
+                Position::from(u64::from(position)),
+            )
+            .unwrap()
+            .root(leaf);
+            assert_eq!(root_of_depth(position, &auth_path, leaf), expected);
+        }
+    }
+
     #[test]
     fn test_vectors() {
         let tv_empty_roots = crate::test_vectors::commitment_tree::test_vectors().empty_roots;
@@ -410,5 +654,49 @@ mod tests {
             frontier.append(cmx);
         }
         assert_eq!(frontier.root().0, pallas::Base::from_repr(anchor).unwrap());
+
+        let mut tree = NoteCommitmentTree::empty();
+        let stats = tree
+            .extend_from_bytes(commitments[..2].iter().copied())
+            .unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.positions, Position::from(0)..Position::from(2));
+
+        // A non-canonical encoding causes the whole batch to be rejected.
+        assert_eq!(
+            tree.extend_from_bytes(
+                commitments[2..]
+                    .iter()
+                    .copied()
+                    .chain(iter::once([0xff; 32]))
+            ),
+            Err(ExtendError::NonCanonical { index: 3 })
+        );
+        assert_eq!(tree.size(), 2);
+
+        let stats = tree
+            .extend_from_bytes(commitments[2..].iter().copied())
+            .unwrap();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.positions, Position::from(2)..Position::from(5));
+        assert_eq!(stats.root.to_bytes(), anchor);
+        assert_eq!(tree.frontier(), &frontier);
+    }
+
+    #[test]
+    fn anchor_ordering() {
+        use alloc::collections::BTreeSet;
+
+        use crate::tree::Anchor;
+
+        let anchors: BTreeSet<_> = [3u64, 1, 2, 1]
+            .into_iter()
+            .map(|v| Anchor::from(pallas::Base::from(v)))
+            .collect();
+        assert_eq!(
+            anchors.into_iter().collect::<alloc::vec::Vec<_>>(),
+            [1u64, 2, 3].map(|v| Anchor::from(pallas::Base::from(v))),
+        );
+        assert!(Anchor::from(pallas::Base::from(256)) > Anchor::from(pallas::Base::from(255)));
     }
 }
@@ -0,0 +1,235 @@
+//! Proofs that an earlier state of the note commitment tree is a prefix of a later one.
+
+use alloc::vec::Vec;
+
+use core2::io::{self, Read, Write};
+use incrementalmerkletree::{Hashable, Level};
+
+use super::{Anchor, MerkleHashOrchard, EMPTY_ROOTS};
+use crate::constants::MERKLE_DEPTH_ORCHARD;
+
+/// The version byte of the encoding written by [`ConsistencyProof::write`].
+const CONSISTENCY_PROOF_VERSION: u8 = 1;
+
+/// A proof that the note commitment tree with some anchor `old` is a prefix of the tree
+/// with a later anchor `new`; that is, that `new` was obtained from `old` by appending
+/// leaves.
+///
+/// A light client that trusts `new` (for example, because it is committed to by a block
+/// header it has validated) can use this to check that a historical anchor provided by a
+/// server belongs to the same tree, without downloading the leaves between the two.
+///
+/// The proof consists of 33 hashes. Consider the path from
+/// the first leaf that is not in the old tree (at position `old_size`) to the root: the
+/// siblings to the left of the path are the same in both trees, and the siblings to the
+/// right of it, and the leaf itself, are empty in the old tree. The proof contains the
+/// left siblings, and the leaf and right siblings of the new tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsistencyProof {
+    old_size: u32,
+    leaf: MerkleHashOrchard,
+    /// The left siblings of the path, ordered from the leaves towards the root.
+    left: Vec<MerkleHashOrchard>,
+    /// The right siblings of the path in the new tree, ordered from the leaves towards
+    /// the root.
+    right: Vec<MerkleHashOrchard>,
+}
+
+impl ConsistencyProof {
+    /// Constructs a proof that the tree containing the first `old_size` of `leaves` is a
+    /// prefix of the tree containing all of `leaves`.
+    ///
+    /// The cost of this is linear in the number of leaves. Returns `None` if `old_size` is
+    /// greater than the number of leaves, or if there are more leaves than fit in the tree.
+    pub fn new(leaves: &[MerkleHashOrchard], old_size: u64) -> Option<Self> {
+        if old_size > leaves.len() as u64 || leaves.len() as u64 > 1 << MERKLE_DEPTH_ORCHARD {
+            return None;
+        }
+        // The full tree has no leaves left to append, so it is only consistent with itself.
+        let old_size = u32::try_from(old_size).ok()?;
+
+        let mut left = vec![];
+        let mut right = vec![];
+        for l in 0..MERKLE_DEPTH_ORCHARD {
+            let index = u64::from(old_size >> l);
+            if index & 1 == 1 {
+                left.push(subtree_root(leaves, l, index - 1));
+            } else {
+                right.push(subtree_root(leaves, l, index + 1));
+            }
+        }
+
+        Some(ConsistencyProof {
+            old_size,
+            leaf: subtree_root(leaves, 0, old_size.into()),
+            left,
+            right,
+        })
+    }
+
+    /// Returns the number of leaves in the old tree.
+    pub fn old_size(&self) -> u64 {
+        self.old_size.into()
+    }
+
+    /// Checks that the tree with root `old`, which contains [`ConsistencyProof::old_size`]
+    /// leaves, is a prefix of the tree with root `new`.
+    pub fn verify(&self, old: &Anchor, new: &Anchor) -> bool {
+        let old_root = self.root(MerkleHashOrchard::empty_leaf(), |l, _| EMPTY_ROOTS[l]);
+        let new_root = self.root(self.leaf, |_, i| self.right[i]);
+        Anchor::from(old_root) == *old && Anchor::from(new_root) == *new
+    }
+
+    /// Computes the root of the tree from the leaf at `old_size`, taking the `i`-th right
+    /// sibling at level `l` from `right(l, i)`.
+    fn root(
+        &self,
+        leaf: MerkleHashOrchard,
+        right: impl Fn(usize, usize) -> MerkleHashOrchard,
+    ) -> MerkleHashOrchard {
+        let mut left = self.left.iter();
+        let mut num_right = 0;
+        (0..MERKLE_DEPTH_ORCHARD).fold(leaf, |node, l| {
+            let level = Level::from(l as u8);
+            if (self.old_size >> l) & 1 == 1 {
+                let sibling = left.next().expect("one left sibling per set bit");
+                MerkleHashOrchard::combine(level, sibling, &node)
+            } else {
+                let sibling = right(l, num_right);
+                num_right += 1;
+                MerkleHashOrchard::combine(level, &node, &sibling)
+            }
+        })
+    }
+
+    /// Writes the binary encoding of this proof.
+    ///
+    /// The encoding consists of a version byte, the old size as a little-endian `u32`,
+    /// the leaf, and then the siblings of the path in order from the leaves towards the
+    /// root. The number of left and right siblings is determined by the old size.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&[CONSISTENCY_PROOF_VERSION])?;
+        writer.write_all(&self.old_size.to_le_bytes())?;
+        writer.write_all(&self.leaf.to_bytes())?;
+        let (mut left, mut right) = (self.left.iter(), self.right.iter());
+        for l in 0..MERKLE_DEPTH_ORCHARD {
+            let sibling = if (self.old_size >> l) & 1 == 1 {
+                left.next()
+            } else {
+                right.next()
+            };
+            writer.write_all(&sibling.expect("proof has a sibling per level").to_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Parses a proof from the encoding described in [`ConsistencyProof::write`].
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != CONSISTENCY_PROOF_VERSION {
+            return Err(invalid("Unsupported consistency proof encoding version"));
+        }
+
+        let mut old_size = [0u8; 4];
+        reader.read_exact(&mut old_size)?;
+        let old_size = u32::from_le_bytes(old_size);
+
+        let mut read_node = || -> io::Result<MerkleHashOrchard> {
+            let mut bytes = [0u8; 32];
+            reader.read_exact(&mut bytes)?;
+            Option::from(MerkleHashOrchard::from_bytes(&bytes))
+                .ok_or_else(|| invalid("Invalid node in consistency proof"))
+        };
+        let leaf = read_node()?;
+        let mut left = vec![];
+        let mut right = vec![];
+        for l in 0..MERKLE_DEPTH_ORCHARD {
+            if (old_size >> l) & 1 == 1 {
+                left.push(read_node()?);
+            } else {
+                right.push(read_node()?);
+            }
+        }
+
+        Ok(ConsistencyProof {
+            old_size,
+            leaf,
+            left,
+            right,
+        })
+    }
+}
+
+/// Returns the root of the subtree at `level` and `index` of the tree containing
+/// `leaves`, where positions beyond the end of `leaves` are empty.
+fn subtree_root(leaves: &[MerkleHashOrchard], level: usize, index: u64) -> MerkleHashOrchard {
+    let start = index << level;
+    if start >= leaves.len() as u64 {
+        EMPTY_ROOTS[level]
+    } else if level == 0 {
+        leaves[start as usize]
+    } else {
+        MerkleHashOrchard::combine(
+            Level::from(level as u8 - 1),
+            &subtree_root(leaves, level - 1, 2 * index),
+            &subtree_root(leaves, level - 1, 2 * index + 1),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use ff::Field;
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    use super::ConsistencyProof;
+    use crate::tree::{MerkleHashOrchard, NoteCommitmentTree};
+
+    #[test]
+    fn consistency_proofs() {
+        let leaves: Vec<_> = (0..37)
+            .map(|_| MerkleHashOrchard(pallas::Base::random(OsRng)))
+            .collect();
+        let roots: Vec<_> = (0..=leaves.len())
+            .map(|n| {
+                let mut tree = NoteCommitmentTree::empty();
+                for leaf in &leaves[..n] {
+                    assert!(tree.0.append(*leaf));
+                }
+                tree.root()
+            })
+            .collect();
+        let new = roots[leaves.len()];
+
+        for old_size in [0, 1, 5, 16, 32, 36, 37] {
+            let proof = ConsistencyProof::new(&leaves, old_size).unwrap();
+            assert_eq!(proof.old_size(), old_size);
+            assert!(proof.verify(&roots[old_size as usize], &new));
+
+            // The proof does not hold for other anchors.
+            assert!(!proof.verify(&roots[old_size as usize], &roots[20]));
+            if old_size > 0 {
+                assert!(!proof.verify(&roots[old_size as usize - 1], &new));
+            }
+
+            let mut encoded = vec![];
+            proof.write(&mut encoded).unwrap();
+            assert_eq!(encoded.len(), 1 + 4 + 33 * 32);
+            assert_eq!(ConsistencyProof::read(&encoded[..]).unwrap(), proof);
+        }
+
+        // A proof for a prefix of a different tree does not verify.
+        let mut other = leaves.clone();
+        other[3] = MerkleHashOrchard(pallas::Base::random(OsRng));
+        let proof = ConsistencyProof::new(&other, 5).unwrap();
+        assert!(!proof.verify(&roots[5], &new));
+
+        assert!(ConsistencyProof::new(&leaves, 38).is_none());
+    }
+}
@@ -0,0 +1,340 @@
+//! A compact description of the leaves appended to the note commitment tree.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use core2::io::{self, Read, Write};
+
+use super::{Anchor, MerkleHashOrchard, NoteCommitmentTree};
+use crate::note::ExtractedNoteCommitment;
+
+/// The version byte of the encoding written by [`TreeDelta::write`].
+const TREE_DELTA_VERSION: u8 = 1;
+
+/// The leaves appended to a [`NoteCommitmentTree`] between two of its states, together
+/// with the checkpoints (typically block boundaries) that fall between them.
+///
+/// A server that tracks the tree can send a `TreeDelta` to a client that knows the tree
+/// as of [`TreeDelta::start_size`] leaves. The client checks that its tree has the
+/// expected root before applying the delta, and that the resulting tree has the
+/// expected root afterwards, so a delta cannot be applied to the wrong tree state.
+///
+/// Clients that maintain witnesses with the `shardtree` crate can instead append
+/// [`TreeDelta::leaves`] to their tree, starting at position [`TreeDelta::start_size`],
+/// and create a checkpoint after each of the leaf counts in [`TreeDelta::checkpoints`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeDelta {
+    start_size: u64,
+    start_root: Anchor,
+    leaves: Vec<MerkleHashOrchard>,
+    checkpoints: Vec<u32>,
+    end_root: Anchor,
+}
+
+impl TreeDelta {
+    /// Constructs the delta that appends `cmxs` to `tree`.
+    ///
+    /// `checkpoints` gives the number of leaves of the delta that precede each checkpoint,
+    /// and must be non-decreasing and no greater than the number of leaves. Two
+    /// checkpoints with the same leaf count describe, for example, a block that contains
+    /// no Orchard outputs.
+    pub fn new(
+        tree: &NoteCommitmentTree,
+        cmxs: impl IntoIterator<Item = ExtractedNoteCommitment>,
+        checkpoints: Vec<u32>,
+    ) -> Result<Self, TreeDeltaError> {
+        let leaves: Vec<_> = cmxs
+            .into_iter()
+            .map(|cmx| MerkleHashOrchard::from_cmx(&cmx))
+            .collect();
+        check_checkpoints(&checkpoints, leaves.len())?;
+
+        let mut end = tree.clone();
+        for leaf in &leaves {
+            if !end.0.append(*leaf) {
+                return Err(TreeDeltaError::TreeFull);
+            }
+        }
+
+        Ok(TreeDelta {
+            start_size: tree.size(),
+            start_root: tree.root(),
+            leaves,
+            checkpoints,
+            end_root: end.root(),
+        })
+    }
+
+    /// Returns the number of leaves in the tree that this delta applies to.
+    pub fn start_size(&self) -> u64 {
+        self.start_size
+    }
+
+    /// Returns the root of the tree that this delta applies to.
+    pub fn start_root(&self) -> Anchor {
+        self.start_root
+    }
+
+    /// Returns the number of leaves in the tree after this delta is applied.
+    pub fn end_size(&self) -> u64 {
+        self.start_size + self.leaves.len() as u64
+    }
+
+    /// Returns the root of the tree after this delta is applied.
+    pub fn end_root(&self) -> Anchor {
+        self.end_root
+    }
+
+    /// Returns the leaves appended by this delta, in order.
+    pub fn leaves(&self) -> &[MerkleHashOrchard] {
+        &self.leaves
+    }
+
+    /// Returns the number of leaves of this delta that precede each checkpoint.
+    pub fn checkpoints(&self) -> &[u32] {
+        &self.checkpoints
+    }
+
+    /// Appends the leaves of this delta to `tree`.
+    ///
+    /// Returns an error, leaving `tree` unchanged, if `tree` is not the state this delta
+    /// applies to, or if the resulting root does not match [`TreeDelta::end_root`].
+    pub fn apply(&self, tree: &mut NoteCommitmentTree) -> Result<(), TreeDeltaError> {
+        if tree.size() != self.start_size {
+            return Err(TreeDeltaError::SizeMismatch {
+                expected: self.start_size,
+                actual: tree.size(),
+            });
+        }
+        if tree.root() != self.start_root {
+            return Err(TreeDeltaError::StartRootMismatch);
+        }
+
+        let mut end = tree.clone();
+        for leaf in &self.leaves {
+            if !end.0.append(*leaf) {
+                return Err(TreeDeltaError::TreeFull);
+            }
+        }
+        if end.root() != self.end_root {
+            return Err(TreeDeltaError::EndRootMismatch);
+        }
+
+        *tree = end;
+        Ok(())
+    }
+
+    /// Writes the binary encoding of this delta.
+    ///
+    /// The encoding consists of a version byte, the start size as a little-endian `u64`,
+    /// the start and end roots, the number of leaves as a little-endian `u32` followed by
+    /// the leaves, and the number of checkpoints as a little-endian `u32` followed by the
+    /// checkpoints, each as a little-endian `u32`.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let len = |n: usize| {
+            u32::try_from(n)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Tree delta too large"))
+        };
+
+        writer.write_all(&[TREE_DELTA_VERSION])?;
+        writer.write_all(&self.start_size.to_le_bytes())?;
+        writer.write_all(&self.start_root.to_bytes())?;
+        writer.write_all(&self.end_root.to_bytes())?;
+        writer.write_all(&len(self.leaves.len())?.to_le_bytes())?;
+        for leaf in &self.leaves {
+            writer.write_all(&leaf.to_bytes())?;
+        }
+        writer.write_all(&len(self.checkpoints.len())?.to_le_bytes())?;
+        for checkpoint in &self.checkpoints {
+            writer.write_all(&checkpoint.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Parses a delta from the encoding described in [`TreeDelta::write`].
+    ///
+    /// The roots are not checked against the leaves here; that happens when the delta is
+    /// applied.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != TREE_DELTA_VERSION {
+            return Err(invalid("Unsupported tree delta encoding version"));
+        }
+
+        let read_u32 = |reader: &mut R| -> io::Result<u32> {
+            let mut bytes = [0u8; 4];
+            reader.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        };
+        let read_32_bytes = |reader: &mut R| -> io::Result<[u8; 32]> {
+            let mut bytes = [0u8; 32];
+            reader.read_exact(&mut bytes)?;
+            Ok(bytes)
+        };
+
+        let mut start_size = [0u8; 8];
+        reader.read_exact(&mut start_size)?;
+        let start_size = u64::from_le_bytes(start_size);
+        let start_root = Option::from(Anchor::from_bytes(read_32_bytes(&mut reader)?))
+            .ok_or_else(|| invalid("Invalid start root in tree delta"))?;
+        let end_root = Option::from(Anchor::from_bytes(read_32_bytes(&mut reader)?))
+            .ok_or_else(|| invalid("Invalid end root in tree delta"))?;
+
+        // The lengths are untrusted, so the vectors are grown as elements are read rather
+        // than allocated up front.
+        let num_leaves = read_u32(&mut reader)?;
+        let mut leaves = vec![];
+        for _ in 0..num_leaves {
+            let leaf = Option::from(MerkleHashOrchard::from_bytes(&read_32_bytes(&mut reader)?))
+                .ok_or_else(|| invalid("Invalid leaf in tree delta"))?;
+            leaves.push(leaf);
+        }
+        let num_checkpoints = read_u32(&mut reader)?;
+        let mut checkpoints = vec![];
+        for _ in 0..num_checkpoints {
+            checkpoints.push(read_u32(&mut reader)?);
+        }
+        check_checkpoints(&checkpoints, leaves.len())
+            .map_err(|_| invalid("Invalid checkpoints in tree delta"))?;
+
+        Ok(TreeDelta {
+            start_size,
+            start_root,
+            leaves,
+            checkpoints,
+            end_root,
+        })
+    }
+}
+
+fn check_checkpoints(checkpoints: &[u32], num_leaves: usize) -> Result<(), TreeDeltaError> {
+    let ordered = checkpoints.windows(2).all(|w| w[0] <= w[1]);
+    let in_range = checkpoints.iter().all(|&c| c as usize <= num_leaves);
+    if ordered && in_range {
+        Ok(())
+    } else {
+        Err(TreeDeltaError::InvalidCheckpoints)
+    }
+}
+
+/// Errors that can occur when constructing or applying a [`TreeDelta`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeDeltaError {
+    /// The tree does not have the number of leaves that the delta applies to.
+    SizeMismatch {
+        /// The number of leaves that the delta applies to.
+        expected: u64,
+        /// The number of leaves in the tree.
+        actual: u64,
+    },
+    /// The root of the tree is not the root that the delta applies to.
+    StartRootMismatch,
+    /// Applying the delta did not produce its end root.
+    EndRootMismatch,
+    /// The tree became full before every leaf of the delta was appended.
+    TreeFull,
+    /// The checkpoints are out of order, or exceed the number of leaves.
+    InvalidCheckpoints,
+}
+
+impl fmt::Display for TreeDeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeDeltaError::SizeMismatch { expected, actual } => write!(
+                f,
+                "Tree delta applies to a tree of {} leaves, but the tree has {}",
+                expected, actual
+            ),
+            TreeDeltaError::StartRootMismatch => {
+                write!(f, "Tree root does not match the start of the tree delta")
+            }
+            TreeDeltaError::EndRootMismatch => {
+                write!(f, "Tree delta did not produce its expected root")
+            }
+            TreeDeltaError::TreeFull => write!(f, "Note commitment tree is full"),
+            TreeDeltaError::InvalidCheckpoints => write!(f, "Tree delta checkpoints are invalid"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TreeDeltaError {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use ff::{Field, PrimeField};
+    use pasta_curves::pallas;
+    use rand::rngs::OsRng;
+
+    use super::{TreeDelta, TreeDeltaError};
+    use crate::{note::ExtractedNoteCommitment, tree::NoteCommitmentTree};
+
+    fn random_cmxs(n: usize) -> Vec<ExtractedNoteCommitment> {
+        (0..n)
+            .map(|_| {
+                ExtractedNoteCommitment::from_bytes(&pallas::Base::random(OsRng).to_repr()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tree_delta() {
+        let mut tree = NoteCommitmentTree::empty();
+        for cmx in random_cmxs(3) {
+            assert!(tree.append(&cmx));
+        }
+
+        let cmxs = random_cmxs(5);
+        let delta = TreeDelta::new(&tree, cmxs.iter().copied(), vec![2, 2, 5]).unwrap();
+        assert_eq!(delta.start_size(), 3);
+        assert_eq!(delta.end_size(), 8);
+
+        let mut expected = tree.clone();
+        for cmx in &cmxs {
+            assert!(expected.append(cmx));
+        }
+        assert_eq!(delta.end_root(), expected.root());
+
+        // The delta round-trips through its encoding.
+        let mut encoded = vec![];
+        delta.write(&mut encoded).unwrap();
+        let decoded = TreeDelta::read(&encoded[..]).unwrap();
+        assert_eq!(decoded, delta);
+
+        // The delta applies to the tree it was constructed from, and only to that tree.
+        let mut applied = tree.clone();
+        decoded.apply(&mut applied).unwrap();
+        assert_eq!(applied, expected);
+        assert_eq!(
+            decoded.apply(&mut applied),
+            Err(TreeDeltaError::SizeMismatch {
+                expected: 3,
+                actual: 8,
+            })
+        );
+        assert_eq!(applied, expected);
+
+        let mut other = NoteCommitmentTree::empty();
+        for cmx in random_cmxs(3) {
+            assert!(other.append(&cmx));
+        }
+        assert_eq!(
+            decoded.apply(&mut other),
+            Err(TreeDeltaError::StartRootMismatch)
+        );
+
+        assert_eq!(
+            TreeDelta::new(&tree, cmxs.iter().copied(), vec![3, 2]),
+            Err(TreeDeltaError::InvalidCheckpoints)
+        );
+        assert_eq!(
+            TreeDelta::new(&tree, cmxs.iter().copied(), vec![6]),
+            Err(TreeDeltaError::InvalidCheckpoints)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! Integration with [ZIP 316] unified addresses and viewing keys.
+//!
+//! A unified address or viewing key is a container of typed items, one per shielded (or
+//! transparent) protocol. The [`UnifiedItem`] trait exposes the typecode and raw encoding
+//! of the Orchard items, so that container implementations can handle Orchard generically
+//! instead of special-casing its byte layouts.
+//!
+//! [ZIP 316]: https://zips.z.cash/zip-0316
+
+use alloc::vec::Vec;
+
+use crate::{
+    keys::{FullViewingKey, IncomingViewingKey},
+    Address,
+};
+
+/// The [ZIP 316] typecode of Orchard items in unified addresses and viewing keys.
+///
+/// [ZIP 316]: https://zips.z.cash/zip-0316#encoding-of-unified-addresses
+pub const ORCHARD_TYPECODE: u32 = 0x03;
+
+/// A type that can be an item of a [ZIP 316] unified container.
+///
+/// [ZIP 316]: https://zips.z.cash/zip-0316
+pub trait UnifiedItem: Sized {
+    /// The typecode of the item.
+    const TYPECODE: u32;
+
+    /// The length in bytes of the item's raw encoding.
+    const ITEM_LEN: usize;
+
+    /// Returns the raw encoding of the item, which is [`Self::ITEM_LEN`] bytes long.
+    fn to_item_bytes(&self) -> Vec<u8>;
+
+    /// Parses an item from its raw encoding.
+    ///
+    /// Returns `None` if `bytes` is not a valid encoding, including if it is not
+    /// [`Self::ITEM_LEN`] bytes long.
+    fn from_item_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+/// The Orchard receiver of a unified address.
+impl UnifiedItem for Address {
+    const TYPECODE: u32 = ORCHARD_TYPECODE;
+    const ITEM_LEN: usize = 43;
+
+    fn to_item_bytes(&self) -> Vec<u8> {
+        self.to_raw_address_bytes().to_vec()
+    }
+
+    fn from_item_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.try_into().ok()?;
+        Address::from_raw_address_bytes(bytes).into()
+    }
+}
+
+/// The Orchard item of a unified full viewing key.
+impl UnifiedItem for FullViewingKey {
+    const TYPECODE: u32 = ORCHARD_TYPECODE;
+    const ITEM_LEN: usize = 96;
+
+    fn to_item_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn from_item_bytes(bytes: &[u8]) -> Option<Self> {
+        FullViewingKey::from_bytes(bytes.try_into().ok()?)
+    }
+}
+
+/// The Orchard item of a unified incoming viewing key.
+impl UnifiedItem for IncomingViewingKey {
+    const TYPECODE: u32 = ORCHARD_TYPECODE;
+    const ITEM_LEN: usize = 64;
+
+    fn to_item_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    fn from_item_bytes(bytes: &[u8]) -> Option<Self> {
+        IncomingViewingKey::from_bytes(bytes.try_into().ok()?).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnifiedItem;
+    use crate::{
+        keys::{FullViewingKey, IncomingViewingKey, Scope, SpendingKey},
+        Address,
+    };
+
+    fn round_trip<T: UnifiedItem + PartialEq + core::fmt::Debug>(item: T) {
+        let bytes = item.to_item_bytes();
+        assert_eq!(bytes.len(), T::ITEM_LEN);
+        assert_eq!(T::from_item_bytes(&bytes), Some(item));
+        assert_eq!(T::from_item_bytes(&bytes[1..]), None);
+    }
+
+    #[test]
+    fn orchard_items() {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([2; 32]).unwrap());
+        assert_eq!(<Address as UnifiedItem>::TYPECODE, 3);
+        round_trip(fvk.address_at(0u32, Scope::External));
+        round_trip(fvk.to_ivk(Scope::External));
+        round_trip(fvk);
+
+        assert_eq!(IncomingViewingKey::from_item_bytes(&[0; 64]), None);
+    }
+}
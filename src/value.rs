@@ -393,6 +393,47 @@ impl ValueCommitment {
     }
 }
 
+/// Derives the binding signing key $\mathsf{bsk}$ for a bundle from the value commitment
+/// trapdoors $\mathsf{rcv}$ of all of its actions.
+///
+/// This allows the actions of a bundle to be constructed separately (for example, by
+/// different parties) with the trapdoors collected afterwards by whoever signs the bundle.
+///
+/// Defined in [Zcash Protocol Spec § 4.14: Balance and Binding Signature (Orchard)][orchardbalance].
+///
+/// [orchardbalance]: https://zips.z.cash/protocol/nu5.pdf#orchardbalance
+pub fn derive_bsk<'a>(
+    rcvs: impl IntoIterator<Item = &'a ValueCommitTrapdoor>,
+) -> redpallas::SigningKey<Binding> {
+    rcvs.into_iter().sum::<ValueCommitTrapdoor>().into_bsk()
+}
+
+/// Derives the binding validating key $\mathsf{bvk}$ for a bundle from the net value
+/// commitments $\mathsf{cv^{net}}$ of all of its actions and its `valueBalanceOrchard`.
+///
+/// The binding signature of a bundle is valid only if this key matches the result of
+/// [`derive_bsk`] on the same actions.
+///
+/// Defined in [Zcash Protocol Spec § 4.14: Balance and Binding Signature (Orchard)][orchardbalance].
+///
+/// [orchardbalance]: https://zips.z.cash/protocol/nu5.pdf#orchardbalance
+pub fn derive_bvk<'a>(
+    cv_nets: impl IntoIterator<Item = &'a ValueCommitment>,
+    value_balance: i64,
+) -> redpallas::VerificationKey<Binding> {
+    derive_bvk_from_sum(cv_nets, ValueSum::from_raw(value_balance))
+}
+
+/// [`derive_bvk`] for a value balance that has not yet been converted to an `i64`.
+pub(crate) fn derive_bvk_from_sum<'a>(
+    cv_nets: impl IntoIterator<Item = &'a ValueCommitment>,
+    value_balance: ValueSum,
+) -> redpallas::VerificationKey<Binding> {
+    (cv_nets.into_iter().sum::<ValueCommitment>()
+        - ValueCommitment::derive(value_balance, ValueCommitTrapdoor::zero()))
+    .into_bvk()
+}
+
 /// Generators for property testing.
 #[cfg(any(test, feature = "test-dependencies"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "test-dependencies")))]
@@ -460,11 +501,14 @@ pub mod testing {
 
 #[cfg(test)]
 mod tests {
+    use alloc::vec::Vec;
+
     use proptest::prelude::*;
 
     use super::{
+        derive_bsk, derive_bvk_from_sum,
         testing::{arb_note_value_bounded, arb_trapdoor, arb_value_sum_bounded},
-        OverflowError, ValueCommitTrapdoor, ValueCommitment, ValueSum, MAX_NOTE_VALUE,
+        OverflowError, ValueCommitment, ValueSum, MAX_NOTE_VALUE,
     };
     use crate::primitives::redpallas;
 
@@ -483,18 +527,13 @@ mod tests {
                 .sum::<Result<ValueSum, OverflowError>>()
                 .expect("we generate values that won't overflow");
 
-            let bsk = values
-                .iter()
-                .map(|(_, rcv)| rcv)
-                .sum::<ValueCommitTrapdoor>()
-                .into_bsk();
+            let bsk = derive_bsk(values.iter().map(|(_, rcv)| rcv));
 
-            let bvk = (values
+            let cv_nets: Vec<_> = values
                 .into_iter()
                 .map(|(value, rcv)| ValueCommitment::derive(value, rcv))
-                .sum::<ValueCommitment>()
-                - ValueCommitment::derive(value_balance, ValueCommitTrapdoor::zero()))
-            .into_bvk();
+                .collect();
+            let bvk = derive_bvk_from_sum(&cv_nets, value_balance);
 
             assert_eq!(redpallas::VerificationKey::from(&bsk), bvk);
         }
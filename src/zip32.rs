@@ -12,11 +12,46 @@ use zip32::{
 
 use crate::{
     keys::{FullViewingKey, SpendingKey},
+    redacted::Redacted,
     spec::PrfExpand,
 };
 
+/// A hardened child index for a derivation step.
+///
+/// Orchard only supports hardened derivation, so a `ChildIndex` can only represent a
+/// hardened index. [`ChildIndex::hardened`] takes the unhardened value (below $2^{31}$) and
+/// panics otherwise, while [`ChildIndex::from_index`] takes the raw, hardened encoding
+/// (at or above $2^{31}$) and returns `None` otherwise. Use [`hardened_child_index`] to
+/// construct an index from an untrusted unhardened value without panicking.
 pub use zip32::ChildIndex;
 
+/// A [ZIP 32 seed fingerprint][seedfingerprints], which identifies the seed from which an
+/// account's keys were derived without revealing it.
+///
+/// [seedfingerprints]: https://zips.z.cash/zip-0032#seed-fingerprints
+pub use zip32::fingerprint::SeedFingerprint;
+
+/// Computes the [ZIP 32 seed fingerprint][seedfingerprints] of `seed`.
+///
+/// Returns [`Error::InvalidSeedLength`] if the seed is not between 32 and 252 bytes long.
+///
+/// [seedfingerprints]: https://zips.z.cash/zip-0032#seed-fingerprints
+pub fn seed_fingerprint(seed: &[u8]) -> Result<SeedFingerprint, Error> {
+    SeedFingerprint::from_seed(seed).ok_or(Error::InvalidSeedLength(seed.len()))
+}
+
+/// Returns the hardened child index for the unhardened value `value`.
+///
+/// Returns [`Error::InvalidChildIndex`] if `value` is not below $2^{31}$, such as when it is
+/// already a hardened index.
+pub fn hardened_child_index(value: u32) -> Result<ChildIndex, Error> {
+    if value < (1 << 31) {
+        Ok(ChildIndex::hardened(value))
+    } else {
+        Err(Error::InvalidChildIndex(value))
+    }
+}
+
 const ZIP32_ORCHARD_PERSONALIZATION: &[u8; 16] = b"ZcashIP32Orchard";
 const ZIP32_ORCHARD_FVFP_PERSONALIZATION: &[u8; 16] = b"ZcashOrchardFVFP";
 
@@ -27,18 +62,39 @@ pub enum Error {
     InvalidSpendingKey,
     /// A child index in a derivation path exceeded 2^31
     InvalidChildIndex(u32),
+    /// A derivation path was longer than the maximum depth of 255
+    MaxDepthExceeded,
+    /// A seed was shorter than 32 bytes or longer than 252 bytes
+    InvalidSeedLength(usize),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Seed produced invalid spending key.")
+        match self {
+            Error::InvalidSpendingKey => write!(f, "Seed produced invalid spending key."),
+            Error::InvalidChildIndex(i) => write!(f, "Child index {} is not below 2^31.", i),
+            Error::MaxDepthExceeded => write!(f, "Derivation path is deeper than 255 levels."),
+            Error::InvalidSeedLength(len) => {
+                write!(f, "Seed length {} is not between 32 and 252 bytes.", len)
+            }
+        }
     }
 }
 
-//impl std::error::Error for Error {}
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
 
-/// An Orchard full viewing key fingerprint
-struct FvkFingerprint([u8; 32]);
+/// An Orchard full viewing key fingerprint.
+///
+/// This is a 32-byte BLAKE2b hash of the encoding of a full viewing key, which uniquely
+/// identifies it and is suitable as a database key. Its first four bytes, the [`FvkTag`],
+/// identify the parent of a derived key in the ZIP 32 extended key encoding.
+///
+/// Defined in [ZIP32: Orchard key identifiers][orchardkeyidentifiers].
+///
+/// [orchardkeyidentifiers]: https://zips.z.cash/zip-0032#orchard-key-identifiers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FvkFingerprint([u8; 32]);
 
 impl From<&FullViewingKey> for FvkFingerprint {
     fn from(fvk: &FullViewingKey) -> Self {
@@ -53,22 +109,35 @@ impl From<&FullViewingKey> for FvkFingerprint {
     }
 }
 
-/// An Orchard full viewing key tag
-#[derive(Clone, Copy, Debug, PartialEq)]
-struct FvkTag([u8; 4]);
+/// An Orchard full viewing key tag, the first four bytes of its [`FvkFingerprint`].
+///
+/// Tags are short, so distinct keys may share a tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FvkTag([u8; 4]);
 
 impl FvkFingerprint {
-    fn tag(&self) -> FvkTag {
+    /// Returns the tag of this fingerprint.
+    pub fn tag(&self) -> FvkTag {
         let mut tag = [0u8; 4];
         tag.copy_from_slice(&self.0[..4]);
         FvkTag(tag)
     }
+
+    /// Returns the bytes of this fingerprint.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
 }
 
 impl FvkTag {
     fn master() -> Self {
         FvkTag([0u8; 4])
     }
+
+    /// Returns the bytes of this tag.
+    pub fn as_bytes(&self) -> &[u8; 4] {
+        &self.0
+    }
 }
 
 /// The derivation index associated with a key.
@@ -133,15 +202,30 @@ impl hardened_only::Context for Orchard {
 ///
 /// Defined in [ZIP32: Orchard extended keys][orchardextendedkeys].
 ///
+/// Most wallets only need [`SpendingKey::from_zip32_seed`], which derives the key at the
+/// standard path `m/32'/coin_type'/account'`. This type supports derivation along any
+/// path; all Orchard derivation is hardened.
+///
 /// [orchardextendedkeys]: https://zips.z.cash/zip-0032#orchard-extended-keys
-#[derive(Debug, Clone)]
-pub(crate) struct ExtendedSpendingKey {
+#[derive(Clone)]
+pub struct ExtendedSpendingKey {
     depth: u8,
     parent_fvk_tag: FvkTag,
     child_index: KeyIndex,
     inner: HardenedOnlyKey<Orchard>,
 }
 
+impl fmt::Debug for ExtendedSpendingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtendedSpendingKey")
+            .field("depth", &self.depth)
+            .field("parent_fvk_tag", &self.parent_fvk_tag)
+            .field("child_index", &self.child_index)
+            .field("inner", &Redacted(&self.inner))
+            .finish()
+    }
+}
+
 impl ConstantTimeEq for ExtendedSpendingKey {
     fn ct_eq(&self, rhs: &Self) -> Choice {
         self.depth.ct_eq(&rhs.depth)
@@ -156,9 +240,9 @@ impl ExtendedSpendingKey {
     /// Returns the spending key of the child key corresponding to
     /// the path derived from the master key
     ///
-    /// # Panics
-    ///
-    /// Panics if seed results in invalid spending key.
+    /// Returns an error if the seed is not between 32 and 252 bytes long, if the seed or
+    /// any index along the path results in an invalid spending key, or if the path is
+    /// longer than 255 indices.
     pub fn from_path(seed: &[u8], path: &[ChildIndex]) -> Result<Self, Error> {
         let mut xsk = Self::master(seed)?;
         for i in path {
@@ -173,10 +257,13 @@ impl ExtendedSpendingKey {
     ///
     /// [orchardmasterkey]: https://zips.z.cash/zip-0032#orchard-master-key-generation
     ///
-    /// # Panics
-    ///
-    /// Panics if the seed is shorter than 32 bytes or longer than 252 bytes.
-    fn master(seed: &[u8]) -> Result<Self, Error> {
+    /// Returns [`Error::InvalidSeedLength`] if the seed is not between 32 and 252 bytes
+    /// long, or [`Error::InvalidSpendingKey`] if it results in an invalid spending key.
+    pub fn master(seed: &[u8]) -> Result<Self, Error> {
+        if !(32..=252).contains(&seed.len()) {
+            return Err(Error::InvalidSeedLength(seed.len()));
+        }
+
         let m_orchard = HardenedOnlyKey::master(&[seed]);
 
         let sk = SpendingKey::from_bytes(*m_orchard.parts().0);
@@ -199,8 +286,10 @@ impl ExtendedSpendingKey {
     ///
     /// [orchardchildkey]: https://zips.z.cash/zip-0032#orchard-child-key-derivation
     ///
-    /// Discards index if it results in an invalid sk
-    fn derive_child(&self, index: ChildIndex) -> Result<Self, Error> {
+    /// Returns an error if `index` results in an invalid spending key, in which case the
+    /// caller should skip to the next index, or if this key is already at depth 255.
+    pub fn derive_child(&self, index: ChildIndex) -> Result<Self, Error> {
+        let depth = self.depth.checked_add(1).ok_or(Error::MaxDepthExceeded)?;
         let child_i = self.inner.derive_child(index);
 
         let sk = SpendingKey::from_bytes(*child_i.parts().0);
@@ -211,24 +300,95 @@ impl ExtendedSpendingKey {
         let fvk: FullViewingKey = self.into();
 
         Ok(Self {
-            depth: self.depth + 1,
+            depth,
             parent_fvk_tag: FvkFingerprint::from(&fvk).tag(),
             child_index: KeyIndex::child(index),
             inner: child_i,
         })
     }
 
+    /// Returns the depth of this key in the derivation tree, which is 0 for the master key.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
     /// Returns sk of this ExtendedSpendingKey.
     pub fn sk(&self) -> SpendingKey {
         SpendingKey::from_bytes(*self.inner.parts().0).expect("checked during derivation")
     }
 
+    /// Serializes this key in the encoding defined by [ZIP 32][orchardextendedkeys]:
+    /// the depth, the tag of the parent's full viewing key, the little-endian child
+    /// index, the chain code, and the spending key.
+    ///
+    /// [orchardextendedkeys]: https://zips.z.cash/zip-0032#orchard-extended-keys
+    pub fn to_bytes(&self) -> [u8; 73] {
+        let (sk, c) = self.inner.parts();
+        let mut bytes = [0; 73];
+        bytes[0] = self.depth;
+        bytes[1..5].copy_from_slice(&self.parent_fvk_tag.0);
+        bytes[5..9].copy_from_slice(&self.child_index.index().to_le_bytes());
+        bytes[9..41].copy_from_slice(c.as_bytes());
+        bytes[41..].copy_from_slice(sk);
+        bytes
+    }
+
+    /// Parses a key from the encoding produced by [`ExtendedSpendingKey::to_bytes`].
+    ///
+    /// Returns `None` if the spending key is invalid, if the child index is not hardened,
+    /// or if a master key (with depth 0) has a non-zero parent tag or child index.
+    pub fn from_bytes(bytes: &[u8; 73]) -> Option<Self> {
+        let depth = bytes[0];
+        let parent_fvk_tag = FvkTag(bytes[1..5].try_into().unwrap());
+        let child_index =
+            KeyIndex::new(depth, u32::from_le_bytes(bytes[5..9].try_into().unwrap()))?;
+        if depth == 0 && parent_fvk_tag != FvkTag::master() {
+            return None;
+        }
+
+        let sk: [u8; 32] = bytes[41..].try_into().unwrap();
+        if SpendingKey::from_bytes(sk).is_none().into() {
+            return None;
+        }
+        let c = ChainCode::new(bytes[9..41].try_into().unwrap());
+
+        Some(Self {
+            depth,
+            parent_fvk_tag,
+            child_index,
+            inner: HardenedOnlyKey::from_parts(sk, c),
+        })
+    }
+
     /// Returns the chain code for this ExtendedSpendingKey.
     fn chain_code(&self) -> &ChainCode {
         self.inner.parts().1
     }
 }
 
+/// Overwrites the spending key and chain code with zeroes.
+///
+/// The intermediate keys derived by [`ExtendedSpendingKey::from_path`] are dropped, and
+/// hence zeroized, as soon as their children have been derived.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for ExtendedSpendingKey {
+    fn zeroize(&mut self) {
+        self.inner = HardenedOnlyKey::from_parts([0; 32], ChainCode::new([0; 32]));
+        // Prevent the write from being elided as a dead store.
+        core::hint::black_box(&self.inner);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for ExtendedSpendingKey {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for ExtendedSpendingKey {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,6 +427,103 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn seed_fingerprints() {
+        let seed: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let expected = Blake2bParams::new()
+            .hash_length(32)
+            .personal(b"Zcash_HD_Seed_FP")
+            .to_state()
+            .update(&[32])
+            .update(&seed)
+            .finalize();
+        assert_eq!(
+            &seed_fingerprint(&seed).unwrap().to_bytes()[..],
+            expected.as_bytes()
+        );
+        assert_eq!(
+            seed_fingerprint(&seed[..31]).unwrap_err(),
+            Error::InvalidSeedLength(31)
+        );
+    }
+
+    #[test]
+    fn invalid_seed_lengths() {
+        assert!(ExtendedSpendingKey::master(&[0; 32]).is_ok());
+        assert!(ExtendedSpendingKey::master(&[0; 252]).is_ok());
+        assert_eq!(
+            ExtendedSpendingKey::master(&[0; 31]).unwrap_err(),
+            Error::InvalidSeedLength(31)
+        );
+        assert_eq!(
+            ExtendedSpendingKey::from_path(&[0; 253], &[ChildIndex::hardened(0)]).unwrap_err(),
+            Error::InvalidSeedLength(253)
+        );
+    }
+
+    #[test]
+    fn hardened_child_indices() {
+        assert_eq!(hardened_child_index(0), Ok(ChildIndex::hardened(0)));
+        assert_eq!(
+            hardened_child_index((1 << 31) - 1),
+            Ok(ChildIndex::hardened((1 << 31) - 1))
+        );
+        // Passing an already-hardened index is rejected rather than double-hardened.
+        let hardened = ChildIndex::hardened(5).index();
+        assert_eq!(
+            hardened_child_index(hardened),
+            Err(Error::InvalidChildIndex(hardened))
+        );
+        assert_eq!(
+            ChildIndex::from_index(hardened),
+            Some(ChildIndex::hardened(5))
+        );
+        assert_eq!(ChildIndex::from_index(5), None);
+    }
+
+    #[test]
+    fn max_depth() {
+        let mut xsk = ExtendedSpendingKey::master(&[0; 32]).unwrap();
+        for _ in 0..u8::MAX {
+            xsk = xsk.derive_child(ChildIndex::hardened(0)).unwrap();
+        }
+        assert_eq!(xsk.depth(), u8::MAX);
+        assert_eq!(
+            xsk.derive_child(ChildIndex::hardened(0)).unwrap_err(),
+            Error::MaxDepthExceeded
+        );
+    }
+
+    #[test]
+    fn parse_rejects_invalid() {
+        let xsk_m = ExtendedSpendingKey::master(&[0; 32]).unwrap();
+        let child = xsk_m.derive_child(ChildIndex::hardened(5)).unwrap();
+
+        // A master key must have a zero parent tag and child index.
+        let mut bytes = xsk_m.to_bytes();
+        bytes[1] = 1;
+        assert!(ExtendedSpendingKey::from_bytes(&bytes).is_none());
+        let mut bytes = xsk_m.to_bytes();
+        bytes[5] = 1;
+        assert!(ExtendedSpendingKey::from_bytes(&bytes).is_none());
+
+        // Child indices must be hardened.
+        let mut bytes = child.to_bytes();
+        bytes[8] = 0;
+        assert!(ExtendedSpendingKey::from_bytes(&bytes).is_none());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize() {
+        use zeroize::Zeroize;
+
+        let mut xsk = ExtendedSpendingKey::master(&[0; 32]).unwrap();
+        xsk.zeroize();
+        assert_eq!(xsk.inner.parts().0, &[0; 32]);
+        assert_eq!(xsk.chain_code().as_bytes(), &[0; 32]);
+    }
+
     #[test]
     fn test_vectors() {
         let test_vectors = crate::test_vectors::zip32::TEST_VECTORS;
@@ -297,9 +554,15 @@ mod tests {
             assert_eq!(&xsk.child_index.index().to_le_bytes(), &tv.xsk[5..9]);
             assert_eq!(xsk.chain_code().as_bytes(), &tv.xsk[9..9 + 32]);
             assert_eq!(xsk.sk().to_bytes(), &tv.xsk[9 + 32..]);
+            assert_eq!(xsk.to_bytes(), tv.xsk);
+            assert!(bool::from(
+                ExtendedSpendingKey::from_bytes(&tv.xsk).unwrap().ct_eq(xsk)
+            ));
 
             let fvk: FullViewingKey = (&xsk.sk()).into();
             assert_eq!(FvkFingerprint::from(&fvk).0, tv.fp);
+            assert_eq!(fvk.fingerprint().as_bytes(), &tv.fp);
+            assert_eq!(fvk.fingerprint().tag().as_bytes(), &tv.fp[..4]);
         }
     }
 }
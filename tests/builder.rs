@@ -8,7 +8,7 @@ use orchard::{
     note_encryption::OrchardDomain,
     tree::MerkleHashOrchard,
     value::NoteValue,
-    Bundle,
+    Bundle, Proof,
 };
 use rand::rngs::OsRng;
 use shardtree::{store::memory::MemoryShardStore, ShardTree};
@@ -126,3 +126,63 @@ fn bundle_chain() {
     // Verify the shielded bundle.
     verify_bundle(&shielded_bundle, &vk);
 }
+
+#[test]
+fn verify_action_membership() {
+    let mut rng = OsRng;
+    let pk = ProvingKey::build();
+    let vk = VerifyingKey::build();
+
+    let sk = SpendingKey::from_bytes([0; 32]).unwrap();
+    let fvk = FullViewingKey::from(&sk);
+    let recipient = fvk.address_at(0u32, Scope::External);
+
+    let bundle: Bundle<_, i64> = {
+        let anchor = MerkleHashOrchard::empty_root(32.into()).into();
+        let mut builder = Builder::new(BundleType::DEFAULT, anchor);
+        assert_eq!(
+            builder.add_output(None, recipient, NoteValue::from_raw(5000), [0u8; 512]),
+            Ok(())
+        );
+        let (unauthorized, _) = builder.build(&mut rng).unwrap().unwrap();
+        let sighash = unauthorized.commitment().into();
+        let proven = unauthorized.create_proof(&pk, &mut rng).unwrap();
+        proven.apply_signatures(rng, sighash, &[]).unwrap()
+    };
+
+    // Each index returns the instance proven for the action at that index.
+    for (index, action) in bundle.actions().iter().enumerate() {
+        let instance = bundle
+            .verify_action_membership(&vk, index)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            instance.to_bytes(),
+            action
+                .to_instance(*bundle.flags(), *bundle.anchor())
+                .to_bytes()
+        );
+    }
+
+    // There is no action past the end of the bundle.
+    assert!(bundle
+        .verify_action_membership(&vk, bundle.actions().len())
+        .is_none());
+
+    // A tampered proof is rejected for every action.
+    let tampered = bundle.map_authorization(
+        &mut (),
+        |_, _, sig| sig,
+        |_, auth| {
+            let mut proof = auth.proof_bytes().to_vec();
+            proof[0] ^= 1;
+            Authorized::from_parts(Proof::new(proof), auth.binding_signature().clone())
+        },
+    );
+    for index in 0..tampered.actions().len() {
+        assert!(matches!(
+            tampered.verify_action_membership(&vk, index),
+            Some(Err(_))
+        ));
+    }
+}